@@ -1,63 +1,319 @@
-use crate::clipboard::{ClipboardManager, Selection};
+use crate::clipboard::{paste_bytes, ClipboardManager, Selection};
 use crate::config::Config;
-use crate::input::{KeyboardHandler, ShortcutHandler, ShortcutAction};
-use crate::pane::{PaneManager, Rect};
-use crate::renderer::backend::{BackendType, CursorInfo, RenderBackend};
+use crate::config::loader::{layout_path, save_theme};
+use crate::config::types::{CursorConfig, ThemeConfig};
+use crate::input::{mouse, mouse::MouseMode, KeyboardHandler, ShortcutHandler, ShortcutAction};
+use crate::pane::{Pane, PaneId, PaneManager, Rect, TabManager};
+use crate::renderer::backend::{BackendType, CursorInfo, PaneRenderOptions, RenderBackend};
 use crate::renderer::softbuffer_backend::SoftbufferBackend;
-use crate::utils::Result;
+use crate::terminal::pty::{OutputWaker, ShellCommand};
+use crate::terminal::{TerminalEmulator, TerminalEvent};
+use crate::utils::hook::SequenceHook;
+use crate::utils::bell::{self as bell_sound, BellMode};
+use crate::utils::{launcher, url, Result, TerbulatorError};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
-use winit::window::Window;
+use winit::event::{MouseButton, MouseScrollDelta};
+use winit::window::{Window, WindowLevel};
+
+/// A mouse button press currently being reported to a pane
+#[derive(Debug, Clone, Copy)]
+struct MouseReport {
+    pane_id: PaneId,
+    rect: Rect,
+    button: u8,
+    cell: (usize, usize),
+}
+
+/// Blink state of the active pane's cursor
+#[derive(Debug, Clone, Copy)]
+struct CursorBlink {
+    /// Time between toggles; None for a steady cursor
+    interval: Option<Duration>,
+    last_toggle: Instant,
+    visible: bool,
+}
+
+impl CursorBlink {
+    fn new(interval: Option<Duration>, now: Instant) -> Self {
+        Self { interval, last_toggle: now, visible: true }
+    }
+
+    /// Toggle the cursor once the interval has passed; a steady cursor stays visible.
+    /// Returns whether the cursor toggled.
+    fn update(&mut self, now: Instant) -> bool {
+        let Some(interval) = self.interval else {
+            self.visible = true;
+            return false;
+        };
+        if now.duration_since(self.last_toggle) >= interval {
+            self.visible = !self.visible;
+            self.last_toggle = now;
+            return true;
+        }
+        false
+    }
+
+    /// When the cursor toggles next, None for a steady cursor
+    fn next_toggle(&self) -> Option<Instant> {
+        self.interval.map(|interval| self.last_toggle + interval)
+    }
+}
+
+/// How long the scrollback position stays on screen after the view was scrolled
+const SCROLL_INDICATOR_DURATION: Duration = Duration::from_millis(1500);
+
+/// Scrollback position shown in the corner of the active pane after scrolling
+#[derive(Debug, Clone, Copy)]
+struct ScrollIndicator {
+    enabled: bool,
+    /// When the view was last scrolled
+    scrolled_at: Option<Instant>,
+}
+
+impl ScrollIndicator {
+    fn new(enabled: bool) -> Self {
+        Self { enabled, scrolled_at: None }
+    }
+
+    fn scrolled(&mut self, now: Instant) {
+        if self.enabled {
+            self.scrolled_at = Some(now);
+        }
+    }
+
+    fn is_active(&self, now: Instant) -> bool {
+        self.scrolled_at.is_some_and(|at| now.duration_since(at) < SCROLL_INDICATOR_DURATION)
+    }
+
+    /// Label for a view scrolled `offset` lines back, None while hidden
+    fn label(&self, offset: usize, now: Instant) -> Option<String> {
+        (offset > 0 && self.is_active(now)).then(|| format!("-{} lines", offset))
+    }
+}
+
+/// How long a notice stays in the corner of the active pane
+const NOTICE_DURATION: Duration = Duration::from_millis(2000);
+
+/// Short message shown in the corner of the active pane, e.g. why a split was refused
+#[derive(Debug, Clone, Default)]
+struct Notice {
+    text: String,
+    shown_at: Option<Instant>,
+}
+
+impl Notice {
+    fn show(&mut self, text: String, now: Instant) {
+        self.text = text;
+        self.shown_at = Some(now);
+    }
+
+    fn is_active(&self, now: Instant) -> bool {
+        self.shown_at.is_some_and(|at| now.duration_since(at) < NOTICE_DURATION)
+    }
+
+    /// The message while it is on screen
+    fn text(&self, now: Instant) -> Option<&str> {
+        self.is_active(now).then_some(self.text.as_str())
+    }
+}
+
+/// Whether the window stays above other windows. The window belongs to the event
+/// loop, which applies each change once.
+#[derive(Debug, Clone, Copy)]
+struct AlwaysOnTop {
+    enabled: bool,
+    changed: bool,
+}
+
+impl AlwaysOnTop {
+    fn new(enabled: bool) -> Self {
+        Self { enabled, changed: false }
+    }
+
+    fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.changed = true;
+    }
+
+    /// Window level to apply after a toggle
+    fn take_change(&mut self) -> Option<WindowLevel> {
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        Some(if self.enabled { WindowLevel::AlwaysOnTop } else { WindowLevel::Normal })
+    }
+}
+
+/// Bells rung by the panes. Bells within `min_interval` of the last notification
+/// (e.g. a full-screen editor beeping on every rejected key) are coalesced into it.
+#[derive(Debug, Clone, Copy)]
+struct Bell {
+    min_interval: Duration,
+    last_notified: Option<Instant>,
+    pending: bool,
+}
+
+impl Bell {
+    fn new(min_interval: Duration) -> Self {
+        Self { min_interval, last_notified: None, pending: false }
+    }
+
+    /// A BEL arrived at `now`; returns whether it gives a notification
+    fn ring(&mut self, now: Instant) -> bool {
+        if self.last_notified.is_some_and(|last| now.duration_since(last) < self.min_interval) {
+            return false;
+        }
+        self.last_notified = Some(now);
+        self.pending = true;
+        true
+    }
+
+    /// Whether a notification is waiting for the event loop
+    fn take_notification(&mut self) -> bool {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Shown once the last pane's process has exited: how it exited and, when the
+/// window closes by itself, a countdown that a keypress cancels
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LastExit {
+    status: String,
+    /// When the window closes; None waits for a keypress to start a new shell
+    close_at: Option<Instant>,
+}
+
+impl LastExit {
+    fn new(status: String, close_after: Option<Duration>, now: Instant) -> Self {
+        Self { status, close_at: close_after.map(|grace| now + grace) }
+    }
+
+    /// Whole seconds left before the window closes (rounded up), None without a countdown
+    fn remaining_secs(&self, now: Instant) -> Option<u64> {
+        self.close_at.map(|close_at| close_at.saturating_duration_since(now).as_millis().div_ceil(1000) as u64)
+    }
+
+    fn should_close(&self, now: Instant) -> bool {
+        self.remaining_secs(now) == Some(0)
+    }
+
+    /// Stop the countdown; the window then stays open
+    fn cancel(&mut self) {
+        self.close_at = None;
+    }
+
+    fn lines(&self, now: Instant) -> Vec<String> {
+        let prompt = match self.remaining_secs(now) {
+            Some(secs) => format!("Closing in {}s - press any key to start a new shell", secs),
+            None => "Press any key to start a new shell".to_string(),
+        };
+        vec![format!("Process finished ({})", self.status), String::new(), prompt]
+    }
+}
+
+/// Everything in a frame apart from the pane contents. While it stays the same the
+/// panes repaint only their changed cells; otherwise the frame is drawn from scratch.
+#[derive(Debug, Clone, PartialEq)]
+struct FrameLayout {
+    panes: Vec<(PaneId, Rect)>,
+    active_pane_id: PaneId,
+    /// Labels in the tab bar (empty while it is hidden) and the active tab; the
+    /// panes of another tab can have the same ids and areas
+    tabs: Vec<String>,
+    active_tab: usize,
+    /// Something is drawn over the panes (selection, help, images, labels); such
+    /// frames are always drawn from scratch, as is the frame after them
+    overlays: bool,
+}
 
 /// Central application state
 pub struct App {
     pub config: Config,
-    pane_manager: PaneManager,
+    config_path: Option<PathBuf>, // Config file given on the command line (None = default path)
+    tabs: TabManager, // Each tab has its own layout of panes
     renderer: Box<dyn RenderBackend>,
     keyboard: KeyboardHandler,
     shortcuts: ShortcutHandler,
     clipboard_manager: ClipboardManager,
     selection: Selection,
     selecting: bool, // Track if user is currently selecting text
+    selection_pressed: bool, // The left button press started or made a selection
     mark_mode: bool, // Track if mark mode is active (keyboard-based selection)
     mark_cursor: Option<(usize, usize)>, // Mark mode cursor position (col, row)
     ime_enabled: bool, // Track if IME is enabled
-    last_cursor_blink: Instant,
-    cursor_visible: bool,
+    cursor_blink: CursorBlink,
+    scroll_indicator: ScrollIndicator,
+    notice: Notice,
     help_visible: bool,
     window_width: u32,
     window_height: u32,
     dragging_border: bool,
+    pane_limit_hit: bool, // A split was refused by pane.max_panes; shown until the next key
+    mouse_report: Option<MouseReport>, // Button press forwarded to the application
+    hover_report: Option<(PaneId, (usize, usize))>, // Cell of the last any-event motion report
+    motion_throttle: mouse::MotionThrottle, // Caps motion reports at `mouse.motion_report_hz`
+    monitor_indicators: (bool, bool), // (activity, silence) indicators shown in the window title
+    dynamic_title: Option<String>, // Title set by the active pane's application (OSC 0/2)
+    last_frame: Option<FrameLayout>, // Layout of the last rendered frame
+    drawn_cursor_visible: bool, // Blink state of the last rendered frame
+    last_exit: Option<LastExit>, // The last pane's process exited
+    close_requested: bool, // A key closed the last pane, which was held open after its process exited
+    sequence_hook: Option<SequenceHook>, // Gets the OSC/DCS sequences the terminal does not handle
+    always_on_top: AlwaysOnTop,
+    fullscreen_toggled: bool, // F11 was pressed; the event loop toggles the window, which knows its own state
+    bell: Bell,
+    bell_mode: BellMode,
+    clicks: mouse::ClickCounter, // Double and triple clicks select a word or a line
 }
 
 impl App {
-    pub fn new(config: Config, window: &Window) -> Result<Self> {
+    /// `command` (from `--command`) runs in the first pane instead of the configured shell or command
+    pub fn new(config: Config, config_path: Option<PathBuf>, command: Option<&[String]>, window: &Window) -> Result<Self> {
         let cols = config.terminal.cols;
         let rows = config.terminal.rows;
         let font_size = config.terminal.font_size;
+        let font_family = config.terminal.font_family.clone();
         let scrollback = config.terminal.scrollback;
-        let shell = config.terminal.shell.clone();
+        let shell = ShellCommand {
+            term: config.terminal.term.clone(),
+            working_directory: config.terminal.working_directory(),
+            env: config.terminal.env.clone(),
+            ..ShellCommand::default()
+        }
+        .with_argv(&config.terminal.pane_argv()?);
+        let initial_shell = match command {
+            Some(argv) => shell.with_argv(argv),
+            None => shell.clone(),
+        };
 
         // Create pane manager with initial pane
-        let pane_manager = PaneManager::new(cols, rows, scrollback, shell)?;
+        let mut pane_manager = PaneManager::new(cols, rows, scrollback, shell, &initial_shell)?;
+        pane_manager.set_max_panes(config.pane.max_panes);
 
         // Create renderer based on config
-        let renderer: Box<dyn RenderBackend> = match config.renderer.backend.as_str() {
+        let mut renderer: Box<dyn RenderBackend> = match config.renderer.backend.as_str() {
             "cpu" => {
                 log::info!("Using CPU rendering backend (softbuffer)");
-                Box::new(SoftbufferBackend::new(window, font_size)?)
+                Box::new(SoftbufferBackend::new(window, font_size, &font_family)?)
             }
             "gpu" => {
                 log::warn!("GPU backend not yet fully implemented, falling back to CPU");
-                Box::new(SoftbufferBackend::new(window, font_size)?)
+                Box::new(SoftbufferBackend::new(window, font_size, &font_family)?)
             }
             "auto" | _ => {
                 log::info!("Auto-selecting rendering backend: using CPU (softbuffer)");
-                Box::new(SoftbufferBackend::new(window, font_size)?)
+                Box::new(SoftbufferBackend::new(window, font_size, &font_family)?)
             }
         };
 
-        let keyboard = KeyboardHandler::new();
-        let shortcuts = ShortcutHandler::new();
+        renderer.set_fallback_fonts(&config.terminal.fallback_fonts)?;
+        renderer.set_font_weights(config.terminal.font_weights()?)?;
+        renderer.set_bold_font_family(config.terminal.bold_font_family.as_deref())?;
+
+        let mut keyboard = KeyboardHandler::new();
+        keyboard.set_option_is_meta(config.keyboard.macos_option_is_meta);
+        let shortcuts = ShortcutHandler::with_bindings(&config.keybindings)?;
         let clipboard_manager = ClipboardManager::new()?;
         let selection = Selection::new();
 
@@ -73,26 +329,70 @@ impl App {
 
         let mut app = Self {
             config: config.clone(),
-            pane_manager,
+            config_path,
+            tabs: TabManager::new(pane_manager),
             renderer,
             keyboard,
             shortcuts,
             clipboard_manager,
             selection,
             selecting: false,
+            selection_pressed: false,
             mark_mode: false,
             mark_cursor: None,
             ime_enabled: false,
-            last_cursor_blink: Instant::now(),
-            cursor_visible: true,
+            cursor_blink: CursorBlink::new(config.terminal.cursor_blink_interval(), Instant::now()),
+            scroll_indicator: ScrollIndicator::new(config.scroll.indicator),
+            notice: Notice::default(),
             help_visible: false,
             window_width: size.width,
             window_height: size.height,
             dragging_border: false,
+            pane_limit_hit: false,
+            mouse_report: None,
+            hover_report: None,
+            motion_throttle: mouse::MotionThrottle::new(config.mouse.motion_report_hz),
+            monitor_indicators: (false, false),
+            dynamic_title: None,
+            last_frame: None,
+            drawn_cursor_visible: true,
+            last_exit: None,
+            close_requested: false,
+            sequence_hook: config
+                .terminal
+                .sequence_hook_argv()?
+                .map(|argv| SequenceHook::new(argv, config.terminal.sequence_hook_prefixes.clone())),
+            always_on_top: AlwaysOnTop::new(config.window.always_on_top),
+            fullscreen_toggled: false,
+            bell: Bell::new(config.window.bell_min_interval()),
+            bell_mode: config.terminal.bell_mode()?,
+            clicks: mouse::ClickCounter::default(),
         };
 
+        // Colors must be set before the startup panes are split off the first one
+        let palette = config.palette()?;
+        app.renderer.set_clear_color(palette.background);
+        app.tabs.active_mut().set_palette(palette);
+        app.tabs.active_mut().set_cursor_style(config.terminal.cursor_style()?);
+        app.tabs.active_mut().set_pin_prompt(config.scroll.pin_prompt);
+        app.tabs.active_mut().set_hold_on_exit(config.terminal.hold_on_exit);
+        let (hold_fg, hold_bg) = config.terminal.hold_message_colors()?;
+        app.tabs.active_mut().set_hold_message_colors(hold_fg, hold_bg);
+        app.tabs.active_mut().set_output_log(config.pane.log_path(), config.pane.log_format()?);
+        app.tabs.active_mut().set_forward_unhandled(app.sequence_hook.is_some());
+        let (cell_width, cell_height) = app.renderer.cell_dimensions();
+        app.tabs.active_mut().set_cell_size(cell_width, cell_height);
+        let (min_cols, min_rows) = config.pane.min_size()?;
+        app.tabs.active_mut().set_min_pane_size(min_cols, min_rows);
+
         // Initialize startup panes according to config
         app.initialize_startup_panes(&config)?;
+        app.apply_pane_tints(&config)?;
+        app.tabs.active_mut().set_focus_flash_enabled(config.pane.focus_flash);
+        app.tabs.active_mut().set_visual_bell_enabled(app.bell_mode == BellMode::Visual);
+        let silence = (config.pane.monitor_silence_secs > 0)
+            .then(|| Duration::from_secs(config.pane.monitor_silence_secs));
+        app.tabs.active_mut().set_monitoring(config.pane.monitor_activity, silence);
 
         Ok(app)
     }
@@ -101,17 +401,91 @@ impl App {
         self.window_width = width;
         self.window_height = height;
 
-        let window_rect = Rect::new(0, 0, width, height);
-        let (cell_width, cell_height) = self.renderer.cell_dimensions();
-
         // Resize all panes based on new window size
-        self.pane_manager.resize_all_panes(window_rect, cell_width, cell_height)?;
+        self.resize_tabs()?;
 
         self.renderer.resize(width, height)?;
         Ok(())
     }
 
-    pub fn handle_keyboard_input(&mut self, key: &winit::keyboard::PhysicalKey, modifiers: winit::keyboard::ModifiersState) -> Result<()> {
+    /// Window area left to the panes: below the tab bar, which is only shown with several tabs
+    fn pane_area(&self) -> Rect {
+        let bar_height = self.tab_bar_height();
+        Rect::new(0, bar_height, self.window_width, self.window_height.saturating_sub(bar_height))
+    }
+
+    fn tab_bar_height(&self) -> u32 {
+        if self.tabs.len() > 1 {
+            self.renderer.cell_dimensions().1.ceil() as u32
+        } else {
+            0
+        }
+    }
+
+    /// Fit the panes of every tab to the pane area, e.g. after the tab bar appeared or went away
+    fn resize_tabs(&mut self) -> Result<()> {
+        let window_rect = self.pane_area();
+        let (cell_width, cell_height) = self.renderer.cell_dimensions();
+        self.tabs.resize_all_tabs(window_rect, cell_width, cell_height)
+    }
+
+    /// Open a tab with a new shell, configured like the active tab, next to it
+    fn open_tab(&mut self) -> Result<()> {
+        let (cell_width, cell_height) = self.renderer.cell_dimensions();
+        let area = self.pane_area();
+        let cols = (area.width as f32 / cell_width).max(1.0) as usize;
+        let rows = (area.height as f32 / cell_height).max(1.0) as usize;
+        self.tabs.open_tab(cols, rows)?;
+        self.tab_changed()
+    }
+
+    /// Drop the selection and mark mode of the previous tab, and fit the panes to
+    /// the pane area (the tab bar appears with the second tab and goes with it)
+    fn tab_changed(&mut self) -> Result<()> {
+        self.selection.clear();
+        self.selecting = false;
+        self.mark_mode = false;
+        self.mark_cursor = None;
+        self.mouse_report = None;
+        self.resize_tabs()
+    }
+
+    /// `unmodified_text` is the key's character without modifiers where Option composes
+    /// characters (macOS), sent instead of `text` while Option acts as Meta
+    pub fn handle_keyboard_input(
+        &mut self,
+        key: &winit::keyboard::PhysicalKey,
+        text: Option<&str>,
+        unmodified_text: Option<&str>,
+        modifiers: winit::keyboard::ModifiersState,
+    ) -> Result<()> {
+        self.pane_limit_hit = false;
+
+        let text = self.keyboard.key_text(text, unmodified_text);
+        let modifier = matches!(
+            key,
+            winit::keyboard::PhysicalKey::Code(
+                winit::keyboard::KeyCode::ShiftLeft
+                    | winit::keyboard::KeyCode::ShiftRight
+                    | winit::keyboard::KeyCode::ControlLeft
+                    | winit::keyboard::KeyCode::ControlRight
+                    | winit::keyboard::KeyCode::AltLeft
+                    | winit::keyboard::KeyCode::AltRight
+                    | winit::keyboard::KeyCode::SuperLeft
+                    | winit::keyboard::KeyCode::SuperRight
+            )
+        );
+
+        // After the last pane exited, any key (other than a modifier) starts a new shell
+        if self.last_exit.is_some() {
+            return if modifier { Ok(()) } else { self.restart_last_pane() };
+        }
+
+        // A pane held open after its process exited closes on any key
+        if self.tabs.active().active_pane().is_some_and(|pane| pane.is_held()) {
+            return if modifier { Ok(()) } else { self.close_held_pane() };
+        }
+
         // Check for F1 (help toggle)
         if let winit::keyboard::PhysicalKey::Code(key_code) = key {
             if *key_code == winit::keyboard::KeyCode::F1 {
@@ -144,16 +518,30 @@ impl App {
         // Check for shortcuts
         if let winit::keyboard::PhysicalKey::Code(key_code) = key {
             log::trace!("Key pressed: {:?}, modifiers: ctrl={}, shift={}", key_code, modifiers.control_key(), modifiers.shift_key());
-            if let Some(action) = self.shortcuts.match_shortcut(*key_code, modifiers) {
+            // The alternate screen has no scrollback: the scroll keys (e.g. Shift+PageUp)
+            // go to the full-screen application instead
+            let alt_screen = self.tabs.active().active_pane().is_some_and(|pane| pane.terminal().is_alt_screen());
+            if let Some(action) = self.shortcuts.match_shortcut(*key_code, modifiers)
+                && !(alt_screen && action.scrolls_view())
+            {
                 log::info!("Shortcut detected: {:?}", action);
                 return self.handle_shortcut_action(action);
             }
         }
 
-        // Regular keyboard input
-        if let Some(bytes) = self.keyboard.handle_key(key) {
+        // Printable text, including dead-key compositions and AltGr characters,
+        // goes to the PTY like an IME commit
+        if let Some(text) = self.keyboard.text_input(text) {
+            return self.handle_ime_commit(text.to_string());
+        }
+
+        // Other keys, encoded for the active pane's cursor key mode
+        if let Some(pane) = self.tabs.active().active_pane() {
+            self.keyboard.set_application_cursor_keys(pane.terminal().application_cursor_keys());
+        }
+        if let Some(bytes) = self.keyboard.handle_key(key, text) {
             log::debug!("Keyboard input: {:?} -> {} bytes", key, bytes.len());
-            self.pane_manager.write_input(&bytes)?;
+            self.tabs.active_mut().write_input(&bytes)?;
             log::debug!("Written to pane(s) successfully");
         } else {
             log::debug!("Keyboard input ignored: {:?}", key);
@@ -164,66 +552,107 @@ impl App {
     fn handle_shortcut_action(&mut self, action: ShortcutAction) -> Result<()> {
         use crate::pane::SplitDirection;
 
-        let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
+        let window_rect = self.pane_area();
         let (cell_width, cell_height) = self.renderer.cell_dimensions();
 
         match action {
             ShortcutAction::SplitHorizontal => {
-                match self.pane_manager.split_active_pane(SplitDirection::Horizontal, window_rect, cell_width, cell_height) {
-                    Ok(new_id) => log::info!("Split pane horizontally, created pane {}", new_id),
+                match self.tabs.active_mut().split_active_pane(SplitDirection::Horizontal, window_rect, cell_width, cell_height) {
+                    Ok(Some(new_id)) => log::info!("Split pane horizontally, created pane {}", new_id),
+                    Ok(None) => self.show_min_size_notice(),
+                    Err(TerbulatorError::PaneLimit(_)) => self.pane_limit_hit = true,
                     Err(e) => log::error!("Failed to split pane horizontally: {}", e),
                 }
             }
             ShortcutAction::SplitVertical => {
-                match self.pane_manager.split_active_pane(SplitDirection::Vertical, window_rect, cell_width, cell_height) {
-                    Ok(new_id) => log::info!("Split pane vertically, created pane {}", new_id),
+                match self.tabs.active_mut().split_active_pane(SplitDirection::Vertical, window_rect, cell_width, cell_height) {
+                    Ok(Some(new_id)) => log::info!("Split pane vertically, created pane {}", new_id),
+                    Ok(None) => self.show_min_size_notice(),
+                    Err(TerbulatorError::PaneLimit(_)) => self.pane_limit_hit = true,
                     Err(e) => log::error!("Failed to split pane vertically: {}", e),
                 }
             }
             ShortcutAction::ClosePane => {
-                match self.pane_manager.close_active_pane(window_rect, cell_width, cell_height) {
-                    Ok(closed) => {
-                        if closed {
-                            log::info!("Closed active pane");
-                        } else {
-                            log::info!("Cannot close last pane");
-                        }
+                // The last pane of a tab closes the tab, unless it is the only one
+                if self.tabs.active().panes().count() == 1 {
+                    if self.tabs.close_active_tab() {
+                        self.tab_changed()?;
+                    } else {
+                        log::info!("Cannot close last pane");
                     }
+                    return Ok(());
+                }
+                match self.tabs.active_mut().close_active_pane(window_rect, cell_width, cell_height) {
+                    Ok(_) => log::info!("Closed active pane"),
                     Err(e) => log::error!("Failed to close pane: {}", e),
                 }
             }
+            ShortcutAction::BalancePanes => {
+                match self.tabs.active_mut().balance_panes(window_rect, cell_width, cell_height) {
+                    Ok(()) => log::info!("Balanced pane sizes"),
+                    Err(e) => log::error!("Failed to balance panes: {}", e),
+                }
+            }
             ShortcutAction::MoveFocusLeft => {
-                if self.pane_manager.focus_left(window_rect) {
-                    log::info!("Moved focus left to pane {}", self.pane_manager.active_pane_id());
+                if self.tabs.active_mut().focus_left(window_rect) {
+                    log::info!("Moved focus left to pane {}", self.tabs.active().active_pane_id());
                 }
             }
             ShortcutAction::MoveFocusDown => {
-                if self.pane_manager.focus_down(window_rect) {
-                    log::info!("Moved focus down to pane {}", self.pane_manager.active_pane_id());
+                if self.tabs.active_mut().focus_down(window_rect) {
+                    log::info!("Moved focus down to pane {}", self.tabs.active().active_pane_id());
                 }
             }
             ShortcutAction::MoveFocusUp => {
-                if self.pane_manager.focus_up(window_rect) {
-                    log::info!("Moved focus up to pane {}", self.pane_manager.active_pane_id());
+                if self.tabs.active_mut().focus_up(window_rect) {
+                    log::info!("Moved focus up to pane {}", self.tabs.active().active_pane_id());
                 }
             }
             ShortcutAction::MoveFocusRight => {
-                if self.pane_manager.focus_right(window_rect) {
-                    log::info!("Moved focus right to pane {}", self.pane_manager.active_pane_id());
+                if self.tabs.active_mut().focus_right(window_rect) {
+                    log::info!("Moved focus right to pane {}", self.tabs.active().active_pane_id());
                 }
             }
             ShortcutAction::MoveFocusNext => {
-                if self.pane_manager.focus_next() {
-                    log::info!("Moved focus to next pane {}", self.pane_manager.active_pane_id());
+                if self.tabs.active_mut().focus_next() {
+                    log::info!("Moved focus to next pane {}", self.tabs.active().active_pane_id());
                 }
             }
             ShortcutAction::MoveFocusPrev => {
-                if self.pane_manager.focus_prev() {
-                    log::info!("Moved focus to previous pane {}", self.pane_manager.active_pane_id());
+                if self.tabs.active_mut().focus_prev() {
+                    log::info!("Moved focus to previous pane {}", self.tabs.active().active_pane_id());
+                }
+            }
+            ShortcutAction::SwapPaneLeft => {
+                match self.tabs.active_mut().swap_left(window_rect, cell_width, cell_height) {
+                    Ok(true) => log::info!("Swapped pane {} left", self.tabs.active().active_pane_id()),
+                    Ok(false) => {}
+                    Err(e) => log::error!("Failed to swap panes: {}", e),
+                }
+            }
+            ShortcutAction::SwapPaneDown => {
+                match self.tabs.active_mut().swap_down(window_rect, cell_width, cell_height) {
+                    Ok(true) => log::info!("Swapped pane {} down", self.tabs.active().active_pane_id()),
+                    Ok(false) => {}
+                    Err(e) => log::error!("Failed to swap panes: {}", e),
+                }
+            }
+            ShortcutAction::SwapPaneUp => {
+                match self.tabs.active_mut().swap_up(window_rect, cell_width, cell_height) {
+                    Ok(true) => log::info!("Swapped pane {} up", self.tabs.active().active_pane_id()),
+                    Ok(false) => {}
+                    Err(e) => log::error!("Failed to swap panes: {}", e),
+                }
+            }
+            ShortcutAction::SwapPaneRight => {
+                match self.tabs.active_mut().swap_right(window_rect, cell_width, cell_height) {
+                    Ok(true) => log::info!("Swapped pane {} right", self.tabs.active().active_pane_id()),
+                    Ok(false) => {}
+                    Err(e) => log::error!("Failed to swap panes: {}", e),
                 }
             }
             ShortcutAction::ToggleBroadcast => {
-                self.pane_manager.toggle_broadcast();
+                self.tabs.active_mut().toggle_broadcast();
                 // Window title will be updated in main event loop
             }
             ShortcutAction::IncreaseFontSize => {
@@ -241,11 +670,88 @@ impl App {
             ShortcutAction::ToggleMarkMode => {
                 self.toggle_mark_mode();
             }
+            ShortcutAction::SaveTheme => {
+                self.save_theme();
+            }
+            ShortcutAction::ScrollPageUp => {
+                let page = self.active_pane_rows();
+                self.scroll_active_view(page);
+            }
+            ShortcutAction::ScrollPageDown => {
+                let page = self.active_pane_rows();
+                self.scroll_active_view(-page);
+            }
+            ShortcutAction::ScrollToTop => {
+                self.scroll_active_view(isize::MAX);
+            }
+            ShortcutAction::ScrollToBottom => {
+                self.scroll_active_view(isize::MIN);
+            }
+            ShortcutAction::ToggleAlwaysOnTop => {
+                self.always_on_top.toggle();
+                log::info!("Always on top: {}", self.always_on_top.enabled);
+            }
+            ShortcutAction::ToggleFullscreen => {
+                self.fullscreen_toggled = true;
+                log::info!("Fullscreen toggled");
+            }
+            ShortcutAction::NewTab => {
+                if let Err(e) = self.open_tab() {
+                    log::error!("Failed to open a tab: {}", e);
+                }
+            }
+            ShortcutAction::CloseTab => {
+                if self.tabs.close_active_tab() {
+                    self.tab_changed()?;
+                } else {
+                    log::info!("Cannot close last tab");
+                }
+            }
+            ShortcutAction::NextTab => {
+                if self.tabs.select_next() {
+                    self.tab_changed()?;
+                }
+            }
+            ShortcutAction::PrevTab => {
+                if self.tabs.select_prev() {
+                    self.tab_changed()?;
+                }
+            }
+            ShortcutAction::TogglePaneLog => {
+                if let Err(e) = self.tabs.active_mut().toggle_active_pane_log() {
+                    log::error!("Failed to start logging the active pane: {}", e);
+                }
+            }
+            ShortcutAction::SoftReset => {
+                // A button press forwarded before the reset has no release to follow
+                self.mouse_report = None;
+                if let Some(pane) = self.tabs.active_mut().active_pane_mut() {
+                    pane.terminal_mut().soft_reset();
+                    pane.mark_dirty();
+                    log::info!("Soft reset pane {}", pane.id());
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Save the active pane's current colors (including OSC 4/10/11 changes) as the config theme
+    fn save_theme(&mut self) {
+        let Some(pane) = self.tabs.active().active_pane() else {
+            return;
+        };
+        let theme = ThemeConfig::from_palette(pane.terminal().palette());
+
+        match save_theme(&theme, self.config_path.clone()) {
+            Ok(_) => {
+                log::info!("Saved theme ({} palette colors)", theme.palette.len());
+                self.config.theme = theme;
+            }
+            Err(e) => log::error!("Failed to save theme: {}", e),
+        }
+    }
+
     pub fn update_modifiers(&mut self, modifiers: winit::keyboard::ModifiersState) {
         self.keyboard.update_modifiers(modifiers);
     }
@@ -255,70 +761,220 @@ impl App {
     /// - has_output: whether any pane had output
     /// - should_exit: whether all panes have exited and app should exit
     pub fn process_pty_output(&mut self) -> Result<(bool, bool)> {
-        let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
+        let now = Instant::now();
+        if self.close_requested {
+            return Ok((false, true));
+        }
+        if let Some(last_exit) = &self.last_exit {
+            return Ok((false, last_exit.should_close(now)));
+        }
+
+        let window_rect = self.pane_area();
         let (cell_width, cell_height) = self.renderer.cell_dimensions();
-        self.pane_manager.process_all_pty_output(window_rect, cell_width, cell_height)
-    }
+        let tab_count = self.tabs.len();
+        let (has_output, last_pane_exited) = self.tabs.process_all_pty_output(window_rect, cell_width, cell_height)?;
+        if self.tabs.len() != tab_count {
+            // A tab closed after its last pane exited
+            self.tab_changed()?;
+        }
+        self.handle_terminal_events();
+        if !last_pane_exited {
+            return Ok((has_output, false));
+        }
 
-    pub fn update_cursor_blink(&mut self) {
-        // Blink cursor every 500ms
-        if self.last_cursor_blink.elapsed() > Duration::from_millis(500) {
-            self.cursor_visible = !self.cursor_visible;
-            self.last_cursor_blink = Instant::now();
+        // Show how the last process exited before closing, or wait for a new shell
+        let window = &self.config.window;
+        let grace = window.exit_grace();
+        if window.close_on_last_exit && grace.is_none() {
+            return Ok((has_output, true));
         }
+        let status = self
+            .tabs
+            .active()
+            .active_pane()
+            .and_then(|pane| pane.exit_status())
+            .map_or_else(|| "unknown status".to_string(), |status| status.to_string());
+        log::info!("Last pane exited ({}), showing exit status", status);
+        let close_after = grace.filter(|_| window.close_on_last_exit);
+        self.last_exit = Some(LastExit::new(status, close_after, now));
+        Ok((has_output, false))
     }
 
-    pub fn render(&mut self) -> Result<()> {
-        self.update_cursor_blink();
+    /// Seconds left on the countdown shown after the last pane exited
+    pub fn exit_countdown_secs(&self) -> Option<u64> {
+        self.last_exit.as_ref().and_then(|last_exit| last_exit.remaining_secs(Instant::now()))
+    }
 
-        // Check if any pane needs redraw
-        let mut any_pane_needs_redraw = false;
-        let active_pane_id = self.pane_manager.active_pane_id();
+    /// A keypress after the last pane exited cancels closing and starts a new shell in it
+    fn restart_last_pane(&mut self) -> Result<()> {
+        if let Some(last_exit) = &mut self.last_exit {
+            last_exit.cancel();
+        }
+        let pane_id = self.tabs.active().active_pane_id();
+        self.tabs.active_mut().respawn_pane(pane_id)?;
+        self.last_exit = None;
+        Ok(())
+    }
 
-        // Active pane with cursor blink or selection always needs redraw
-        let active_needs_redraw = self.selection.active || true; // Always redraw for cursor blink
+    /// Close the active pane, held open after its process exited. Closing the last pane
+    /// closes its tab; in the last tab, it closes the window, or starts a new shell
+    /// without `close_on_last_exit`.
+    fn close_held_pane(&mut self) -> Result<()> {
+        if self.tabs.active().panes().count() > 1 {
+            let window_rect = self.pane_area();
+            let (cell_width, cell_height) = self.renderer.cell_dimensions();
+            self.tabs.active_mut().close_active_pane(window_rect, cell_width, cell_height)?;
+        } else if self.tabs.close_active_tab() {
+            self.tab_changed()?;
+        } else if self.config.window.close_on_last_exit {
+            self.close_requested = true;
+        } else {
+            let pane_id = self.tabs.active().active_pane_id();
+            self.tabs.active_mut().respawn_pane(pane_id)?;
+        }
+        Ok(())
+    }
 
-        for pane_id in 0..100 { // Arbitrary upper limit
-            if let Some(pane) = self.pane_manager.pane(pane_id) {
-                if pane.needs_redraw() || (pane_id == active_pane_id && active_needs_redraw) {
-                    any_pane_needs_redraw = true;
-                    break;
+    /// Handle requests from terminal applications (e.g. OSC 52 clipboard writes, sequences for the hook)
+    fn handle_terminal_events(&mut self) {
+        for (pane_id, event) in self.tabs.take_events() {
+            match event {
+                TerminalEvent::ClipboardStore { kind, text } => {
+                    log::debug!("Pane {} set {:?} selection ({} bytes)", pane_id, kind, text.len());
+                    if let Err(e) = self.clipboard_manager.copy_to(kind, &text) {
+                        log::error!("Failed to store selection from pane {}: {}", pane_id, e);
+                    }
+                }
+                TerminalEvent::Bell => {
+                    if self.bell.ring(Instant::now()) {
+                        log::debug!("Bell in pane {}", pane_id);
+                        self.tabs.ring_visual_bell(pane_id);
+                        if self.bell_mode == BellMode::Audible
+                            && let Err(e) = bell_sound::play_sound()
+                        {
+                            log::warn!("Failed to play the bell sound: {}", e);
+                        }
+                    }
+                }
+                TerminalEvent::UnhandledSequence { kind, payload } => {
+                    let Some(hook) = self.sequence_hook.as_ref().filter(|hook| hook.accepts(&payload)) else {
+                        continue;
+                    };
+                    if let Err(e) = hook.run(pane_id, kind.name(), payload) {
+                        log::error!("Failed to run the sequence hook: {}", e);
+                    }
                 }
-            } else {
-                break;
             }
         }
+    }
+
+    /// Advance the cursor blink. Returns whether the frame has to be redrawn for it,
+    /// i.e. the blink toggled while the active pane shows its cursor.
+    pub fn update_cursor_blink(&mut self) -> bool {
+        self.cursor_blink.update(Instant::now())
+            && self.tabs.active().active_pane().is_some_and(|pane| pane.terminal().cursor_visible())
+    }
+
+    /// When the cursor blinks next, None for a steady cursor
+    pub fn next_cursor_blink(&self) -> Option<Instant> {
+        self.cursor_blink.next_toggle()
+    }
+
+    /// Whether any pane has PTY output that has not been processed yet
+    pub fn has_pending_output(&mut self) -> bool {
+        // A finished last pane stays closed until it is restarted
+        self.last_exit.is_none() && self.tabs.has_pending_output()
+    }
+
+    /// Call `waker` from the PTY reader threads whenever a pane has new output
+    pub fn set_output_waker(&mut self, waker: OutputWaker) {
+        self.tabs.set_output_waker(waker);
+    }
+
+    /// Draw the next frame even if nothing seems to have changed (e.g. the window was exposed again)
+    pub fn invalidate(&mut self) {
+        self.last_frame = None;
+    }
+
+    pub fn render(&mut self) -> Result<()> {
+        let active_pane_id = self.tabs.active().active_pane_id();
+        let window_rect = self.pane_area();
+        let pane_rects = self.tabs.active().layout().calculate_rects(window_rect);
+
+        let focus_flash_active = self.tabs.active().is_focus_flash_active();
+        let visual_bell_active = self.tabs.active().is_visual_bell_active();
+
+        // Clear the buffer only when the panes cannot just repaint their changed cells
+        let has_images = pane_rects
+            .iter()
+            .any(|(pane_id, _)| self.tabs.active().pane(*pane_id).is_some_and(|pane| !pane.terminal().images().is_empty()));
+        let tab_titles = if self.tabs.len() > 1 { self.tabs.titles(&self.config.window.title) } else { Vec::new() };
+        let frame = FrameLayout {
+            panes: pane_rects.clone(),
+            active_pane_id,
+            tabs: tab_titles.clone(),
+            active_tab: self.tabs.active_index(),
+            overlays: self.selection.active
+                || self.help_visible
+                || focus_flash_active
+                || visual_bell_active
+                || self.scroll_indicator.is_active(Instant::now())
+                || self.notice.is_active(Instant::now())
+                || self.last_exit.is_some()
+                || has_images,
+        };
 
         // Only render if something changed
-        if !any_pane_needs_redraw {
+        let any_pane_needs_redraw = pane_rects
+            .iter()
+            .any(|(pane_id, _)| self.tabs.active().pane(*pane_id).is_some_and(Pane::needs_redraw));
+        if !any_pane_needs_redraw
+            && !frame.overlays
+            && self.drawn_cursor_visible == self.cursor_blink.visible
+            && self.last_frame.as_ref() == Some(&frame)
+        {
             return Ok(());
         }
+        let cursor_blinked = self.drawn_cursor_visible != self.cursor_blink.visible;
+        self.drawn_cursor_visible = self.cursor_blink.visible;
 
-        // Clear the buffer before rendering
-        self.renderer.clear()?;
-
-        let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
-        let pane_rects = self.pane_manager.layout().calculate_rects(window_rect);
+        let cleared = frame.overlays || self.last_frame.as_ref() != Some(&frame);
+        if cleared {
+            self.renderer.clear()?;
+        }
+        self.last_frame = Some(frame);
+        let rendered = panes_to_render(&pane_rects, active_pane_id, cleared, cursor_blinked, |pane_id| {
+            self.tabs.active().pane(pane_id).is_some_and(Pane::needs_redraw)
+        });
+
+        if !tab_titles.is_empty() {
+            let bar = Rect::new(0, 0, self.window_width, self.tab_bar_height());
+            self.renderer.draw_tab_bar(&tab_titles, self.tabs.active_index(), bar)?;
+        }
 
-        // Render ALL panes (to avoid black areas from cleared buffer)
-        // But only if at least one pane needs redraw
-        for (pane_id, pane_rect) in &pane_rects {
+        for (pane_id, pane_rect) in &rendered {
             // Get cursor info (immutable borrow)
-            let (cursor_info, is_active) = if let Some(pane) = self.pane_manager.pane(*pane_id) {
-                let (col, row) = pane.terminal().cursor_position();
+            let (cursor_info, render_options, is_active) = if let Some(pane) = self.tabs.active().pane(*pane_id) {
                 let is_active = *pane_id == active_pane_id;
-                let cursor = CursorInfo {
-                    col,
-                    row,
-                    visible: is_active && self.cursor_visible && pane.terminal().cursor_visible(),
+                let cursor = pane_cursor_info(
+                    pane.terminal(),
+                    is_active,
+                    self.cursor_blink.visible,
+                    &self.config.cursor,
+                );
+                let render_options = PaneRenderOptions {
+                    reverse_video: pane.terminal().reverse_video(),
+                    tint: pane.tint(),
+                    show_whitespace: self.config.terminal.show_whitespace,
+                    bold_is_bright: self.config.terminal.bold_is_bright,
                 };
-                (cursor, is_active)
+                (cursor, render_options, is_active)
             } else {
                 continue;
             };
 
             // Now get mutable reference to render
-            if let Some(pane) = self.pane_manager.pane_mut(*pane_id) {
+            if let Some(pane) = self.tabs.active_mut().pane_mut(*pane_id) {
                 let offset_x = pane_rect.x;
                 let offset_y = pane_rect.y;
 
@@ -326,10 +982,8 @@ impl App {
                 self.renderer.render_pane(
                     pane.terminal_mut().grid_mut(),
                     cursor_info,
-                    offset_x as i32,
-                    offset_y as i32,
-                    pane_rect.width,
-                    pane_rect.height,
+                    render_options,
+                    *pane_rect,
                 )?;
 
                 // Clear the redraw flag after rendering
@@ -343,6 +997,15 @@ impl App {
                         pane_rect.width as i32,
                         pane_rect.height as i32,
                     )?;
+
+                    if focus_flash_active {
+                        self.renderer.draw_focus_flash(
+                            offset_x as i32,
+                            offset_y as i32,
+                            pane_rect.width as i32,
+                            pane_rect.height as i32,
+                        )?;
+                    }
                 }
 
                 // Draw selection highlight if active
@@ -350,10 +1013,12 @@ impl App {
                     let (cell_width, cell_height) = self.renderer.cell_dimensions();
                     let grid = pane.terminal().grid();
 
-                    // Render selection highlight for all selected cells
+                    // Render selection highlight for all selected cells; a wide character is
+                    // highlighted as a whole when its first column is selected
                     for row in 0..grid.rows() {
+                        let cells = grid.view_row(row).unwrap_or(&[]);
                         for col in 0..grid.cols() {
-                            if self.selection.contains(col, row) {
+                            if self.selection.contains(mouse::glyph_start(cells, col), row) {
                                 self.renderer.draw_selection_highlight(
                                     col,
                                     row,
@@ -375,13 +1040,33 @@ impl App {
                     let img_width = (image.width_cells as f32 * cell_width) as u32;
                     let img_height = (image.height_cells as f32 * cell_height) as u32;
 
-                    self.renderer.draw_image(
-                        &image.image,
-                        img_x,
-                        img_y,
-                        img_width,
-                        img_height,
-                    )?;
+                    // Only the part inside the pane is scaled and drawn
+                    let available = (
+                        (offset_x + pane_rect.width).saturating_sub(img_x as u32),
+                        (offset_y + pane_rect.height).saturating_sub(img_y as u32),
+                    );
+                    let source = (image.image.width(), image.image.height());
+                    let Some((crop, size)) = visible_image_part(source, (img_width, img_height), available) else {
+                        continue;
+                    };
+                    let visible = if crop == source {
+                        std::borrow::Cow::Borrowed(&image.image)
+                    } else {
+                        std::borrow::Cow::Owned(image.image.crop_imm(0, 0, crop.0, crop.1))
+                    };
+                    self.renderer.draw_image(&visible, img_x, img_y, size.0, size.1)?;
+                }
+
+                if pane.is_visual_bell_active(Instant::now()) {
+                    self.renderer.draw_visual_bell(*pane_rect)?;
+                }
+
+                // How far back the view is, shortly after scrolling
+                let offset = pane.terminal().grid().scroll_offset();
+                if let Some(text) = self.notice.text(Instant::now()).filter(|_| is_active) {
+                    self.renderer.draw_corner_label(text, *pane_rect)?;
+                } else if let Some(label) = self.scroll_indicator.label(offset, Instant::now()).filter(|_| is_active) {
+                    self.renderer.draw_corner_label(&label, *pane_rect)?;
                 }
             }
         }
@@ -391,31 +1076,63 @@ impl App {
             self.render_help_overlay()?;
         }
 
+        if let Some(last_exit) = &self.last_exit {
+            let lines = last_exit.lines(Instant::now());
+            self.renderer.render_help_overlay(&lines.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+
         self.renderer.present()?;
 
         Ok(())
     }
 
+    /// Tell why a split was refused
+    fn show_min_size_notice(&mut self) {
+        let text = format!("Pane would be smaller than {}x{}", self.config.pane.min_cols, self.config.pane.min_rows);
+        self.notice.show(text, Instant::now());
+    }
+
+    /// Save the active tab's layout for the next start (`startup.restore_layout`)
+    pub fn save_layout(&self) {
+        if !self.config.startup.restore_layout {
+            return;
+        }
+        let result = layout_path(self.config_path.clone()).and_then(|path| self.tabs.active().save_layout(&path));
+        if let Err(e) = result {
+            log::error!("Failed to save the pane layout: {}", e);
+        }
+    }
+
     fn initialize_startup_panes(&mut self, config: &Config) -> Result<()> {
-        let num_panes = config.startup.panes;
+        let num_panes = config.startup_panes()?;
         let layout = &config.startup.layout;
         let split_ratio = config.startup.split_ratio;
         let vertical_ratio = config.startup.vertical_ratio;
 
+        // A saved layout replaces the configured one, unless it cannot be used
+        if config.startup.restore_layout {
+            let path = layout_path(self.config_path.clone())?;
+            let window_rect = self.pane_area();
+            let (cell_width, cell_height) = self.renderer.cell_dimensions();
+            if self.tabs.active_mut().load_layout(&path, window_rect, cell_width, cell_height)? {
+                return Ok(());
+            }
+        }
+
         if num_panes <= 1 {
             // Single pane, already created
             log::info!("Starting with single pane");
             return Ok(());
         }
 
-        let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
+        let window_rect = self.pane_area();
         let (cell_width, cell_height) = self.renderer.cell_dimensions();
 
         match layout.as_str() {
             "horizontal" if num_panes == 2 => {
                 // Split horizontally once with specified ratio
                 log::info!("Creating 2 panes with horizontal split ({}:{})", (split_ratio * 10.0) as usize, ((1.0 - split_ratio) * 10.0) as usize);
-                self.pane_manager.split_active_pane_with_ratio(
+                self.tabs.active_mut().split_active_pane_with_ratio(
                     crate::pane::SplitDirection::Horizontal,
                     window_rect,
                     cell_width,
@@ -426,7 +1143,7 @@ impl App {
             "vertical" if num_panes == 2 => {
                 // Split vertically once with specified ratio
                 log::info!("Creating 2 panes with vertical split ({}:{})", (vertical_ratio * 10.0) as usize, ((1.0 - vertical_ratio) * 10.0) as usize);
-                self.pane_manager.split_active_pane_with_ratio(
+                self.tabs.active_mut().split_active_pane_with_ratio(
                     crate::pane::SplitDirection::Vertical,
                     window_rect,
                     cell_width,
@@ -441,7 +1158,7 @@ impl App {
                     (vertical_ratio * 10.0) as usize, ((1.0 - vertical_ratio) * 10.0) as usize);
 
                 // First horizontal split (top and bottom) with horizontal ratio
-                self.pane_manager.split_active_pane_with_ratio(
+                self.tabs.active_mut().split_active_pane_with_ratio(
                     crate::pane::SplitDirection::Horizontal,
                     window_rect,
                     cell_width,
@@ -450,8 +1167,8 @@ impl App {
                 )?;
 
                 // Split top pane vertically with vertical ratio
-                self.pane_manager.set_active_pane(0);
-                self.pane_manager.split_active_pane_with_ratio(
+                self.tabs.active_mut().set_active_pane(0);
+                self.tabs.active_mut().split_active_pane_with_ratio(
                     crate::pane::SplitDirection::Vertical,
                     window_rect,
                     cell_width,
@@ -460,8 +1177,8 @@ impl App {
                 )?;
 
                 // Split bottom pane vertically with vertical ratio
-                self.pane_manager.set_active_pane(1);
-                self.pane_manager.split_active_pane_with_ratio(
+                self.tabs.active_mut().set_active_pane(1);
+                self.tabs.active_mut().split_active_pane_with_ratio(
                     crate::pane::SplitDirection::Vertical,
                     window_rect,
                     cell_width,
@@ -470,7 +1187,7 @@ impl App {
                 )?;
 
                 // Set active pane to first one
-                self.pane_manager.set_active_pane(0);
+                self.tabs.active_mut().set_active_pane(0);
             }
             _ => {
                 log::warn!("Unsupported startup layout: {} with {} panes, using single pane", layout, num_panes);
@@ -480,6 +1197,18 @@ impl App {
         Ok(())
     }
 
+    /// Give the startup panes their `pane.tint` colors, in layout order
+    fn apply_pane_tints(&mut self, config: &Config) -> Result<()> {
+        let tints = config.pane.tint_colors()?;
+        let pane_manager = self.tabs.active_mut();
+        for (pane_id, tint) in pane_manager.layout().all_pane_ids().into_iter().zip(tints) {
+            if let Some(pane) = pane_manager.pane_mut(pane_id) {
+                pane.set_tint(tint);
+            }
+        }
+        Ok(())
+    }
+
     fn render_help_overlay(&mut self) -> Result<()> {
         // Render help text in the center of the screen
         let help_text = vec![
@@ -489,6 +1218,12 @@ impl App {
             "  Ctrl+Shift+S    Split Horizontal",
             "  Ctrl+Shift+V    Split Vertical",
             "  Ctrl+Shift+W    Close Pane",
+            "  Ctrl+Shift+E    Equalize Pane Sizes",
+            "",
+            "Tabs:",
+            "  Ctrl+Shift+T    New Tab",
+            "  Ctrl+Shift+Q    Close Tab",
+            "  Ctrl+PgUp/PgDn  Previous / Next Tab",
             "",
             "Focus Movement:",
             "  Ctrl+Shift+H    Focus Left",
@@ -497,6 +1232,7 @@ impl App {
             "  Ctrl+Shift+L    Focus Right",
             "  Ctrl+Shift+N    Focus Next",
             "  Ctrl+Shift+P    Focus Previous",
+            "  Alt+Shift+H/J/K/L  Swap With Left/Down/Up/Right Pane",
             "",
             "Font Size:",
             "  Ctrl++          Increase Font Size",
@@ -504,14 +1240,34 @@ impl App {
             "",
             "Clipboard:",
             "  Mouse Drag      Select Text",
+            "  Double Click    Select Word",
+            "  Triple Click    Select Line",
+            "  Alt+Drag        Select Block",
             "  Ctrl+Shift+C    Copy Selection",
             "  Ctrl+V          Paste",
+            "  Middle Click    Paste Primary Selection",
+            "",
+            "Theme:",
+            "  Ctrl+Shift+Y    Save Colors to Config",
+            "",
+            "Logging:",
+            "  Ctrl+Shift+O    Toggle Output Log of Pane",
             "",
             "Broadcast Mode:",
             "  Ctrl+Shift+B    Toggle Broadcast",
             "                  (Shows 'Broadcasting' in title)",
             "",
+            "Window:",
+            "  Ctrl+Shift+A    Toggle Always on Top",
+            "  F11             Toggle Fullscreen",
+            "",
+            "Scrollback:",
+            "  Mouse Wheel     Scroll",
+            "  Shift+PgUp/PgDn Scroll by a Page",
+            "  Shift+Home/End  Oldest Line / Live Screen",
+            "",
             "Other:",
+            "  Ctrl+Shift+X    Reset Stuck Modes of Pane",
             "  F1              Toggle Help",
             "  ESC             Close Help",
             "",
@@ -523,7 +1279,7 @@ impl App {
     }
 
     pub fn grid_info(&self) -> (usize, usize) {
-        if let Some(pane) = self.pane_manager.active_pane() {
+        if let Some(pane) = self.tabs.active().active_pane() {
             (pane.terminal().grid().cols(), pane.terminal().grid().rows())
         } else {
             (80, 24) // Default fallback
@@ -534,35 +1290,137 @@ impl App {
         self.renderer.backend_type()
     }
 
+    /// Scroll the active pane's view through scrollback.
+    /// Returns whether the view moved.
+    pub fn handle_mouse_wheel(&mut self, delta: MouseScrollDelta) -> bool {
+        let (_, cell_height) = self.renderer.cell_dimensions();
+        let lines = mouse::wheel_lines(delta, cell_height);
+
+        // The alternate screen keeps no scrollback, so full-screen programs without mouse
+        // reporting get arrow keys instead (alternate scroll)
+        if let Some(pane) = self.tabs.active().active_pane() {
+            let terminal = pane.terminal();
+            if terminal.is_alt_screen() && terminal.mouse_mode() == MouseMode::Off {
+                let keys = mouse::alternate_scroll_keys(lines, terminal.application_cursor_keys());
+                if !keys.is_empty()
+                    && let Err(e) = pane.write_input(&keys)
+                {
+                    log::error!("Failed to send scroll keys: {}", e);
+                }
+                return false;
+            }
+        }
+        self.scroll_active_view(lines as isize)
+    }
+
+    /// Rows of the active pane, the distance of a page scroll
+    fn active_pane_rows(&self) -> isize {
+        self.tabs.active().active_pane().map_or(0, |pane| pane.terminal().grid().rows() as isize)
+    }
+
+    /// Move the active pane's view `lines` back into the scrollback (forward when
+    /// negative), stopping at either end. Typing or new output returns to the live screen.
+    /// Returns whether the view moved.
+    fn scroll_active_view(&mut self, lines: isize) -> bool {
+        let Some(pane) = self.tabs.active_mut().active_pane_mut() else {
+            return false;
+        };
+
+        let grid = pane.terminal_mut().grid_mut();
+        let before = grid.scroll_offset();
+        if lines > 0 {
+            grid.scroll_view_up(lines as usize);
+        } else {
+            grid.scroll_view_down(lines.unsigned_abs());
+        }
+        let offset = grid.scroll_offset();
+        let moved = offset != before;
+        if moved {
+            log::trace!("Scrolled pane {} view to offset {}", pane.id(), offset);
+            pane.mark_dirty();
+            self.scroll_indicator.scrolled(Instant::now());
+        }
+        moved
+    }
+
+    /// Whether the scrollback position label is on screen and has to be cleared later
+    pub fn is_scroll_indicator_active(&self) -> bool {
+        self.scroll_indicator.is_active(Instant::now())
+    }
+
+    /// Whether a notice is on screen and has to be cleared later
+    pub fn is_notice_active(&self) -> bool {
+        self.notice.is_active(Instant::now())
+    }
+
     /// Handle mouse button press
-    pub fn handle_mouse_press(&mut self, x: f64, y: f64) -> Result<()> {
-        let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
+    pub fn handle_mouse_press(&mut self, x: f64, y: f64, button: MouseButton) -> Result<()> {
+        let window_rect = self.pane_area();
         let x_u32 = x as u32;
         let y_u32 = y as u32;
 
+        // A click on the tab bar switches to that tab
+        if y_u32 < window_rect.y {
+            let index = tab_at(x_u32, self.window_width, self.tabs.len());
+            if button == MouseButton::Left && self.tabs.select(index) {
+                self.tab_changed()?;
+            }
+            return Ok(());
+        }
+
         // Check if clicking on a border
-        if self.pane_manager.is_near_border(x_u32, y_u32, window_rect) {
+        if button == MouseButton::Left && self.tabs.active().is_near_border(x_u32, y_u32, window_rect) {
             log::info!("Started dragging border at ({}, {})", x, y);
             self.dragging_border = true;
             return Ok(());
         }
 
         // Check which pane was clicked
-        let rects = self.pane_manager.layout().calculate_rects(window_rect);
+        let rects = self.tabs.active().layout().calculate_rects(window_rect);
         for (pane_id, rect) in rects {
             if x >= rect.x as f64 && x < (rect.x + rect.width) as f64 &&
                y >= rect.y as f64 && y < (rect.y + rect.height) as f64 {
                 log::info!("Mouse clicked on pane {} at ({}, {})", pane_id, x, y);
-                self.pane_manager.set_active_pane(pane_id);
+                self.tabs.active_mut().set_active_pane(pane_id);
 
-                // Start text selection
-                let (cell_width, cell_height) = self.renderer.cell_dimensions();
-                let col = ((x - rect.x as f64) / cell_width as f64) as usize;
-                let row = ((y - rect.y as f64) / cell_height as f64) as usize;
+                // Ctrl-click opens a hyperlink instead of reaching the application
+                if button == MouseButton::Left && self.keyboard.modifiers().control_key() && self.open_link_at(pane_id, rect, x, y) {
+                    return Ok(());
+                }
 
-                self.selection.start_at(col, row);
-                self.selecting = true;
-                log::debug!("Started selection at ({}, {})", col, row);
+                // Forward the click to applications that requested mouse reporting
+                if self.report_mouse_press(pane_id, rect, x, y, button)? {
+                    return Ok(());
+                }
+                // A middle-click pastes the PRIMARY selection into the clicked pane
+                if button == MouseButton::Middle {
+                    return self.handle_paste_primary();
+                }
+                if button != MouseButton::Left {
+                    return Ok(());
+                }
+
+                // Start text selection; a double-click takes the word, a triple-click the line
+                let (col, row) = self.pixel_to_cell(pane_id, x, y, rect);
+                let Some(pane) = self.tabs.active().pane(pane_id) else {
+                    return Ok(());
+                };
+                let grid = pane.terminal().grid();
+                match self.clicks.click(pane_id, (col, row), Instant::now()) {
+                    2 => self.selection.select_word(grid, col, row),
+                    3 => self.selection.select_line(grid, row),
+                    // Alt-drag selects a rectangle
+                    _ if self.keyboard.modifiers().alt_key() => {
+                        self.selection.start_block_at(col, row);
+                        self.selecting = true;
+                    }
+                    _ => {
+                        self.selection.start_at(col, row);
+                        self.selecting = true;
+                    }
+                }
+                self.selection_pressed = true;
+                log::debug!("Started selection at ({}, {}), active: {}", col, row, self.selection.active);
 
                 return Ok(());
             }
@@ -573,7 +1431,24 @@ impl App {
     }
 
     /// Handle mouse button release
-    pub fn handle_mouse_release(&mut self) -> Result<()> {
+    pub fn handle_mouse_release(&mut self, x: f64, y: f64, button: MouseButton) -> Result<()> {
+        if let Some(report) = self.mouse_report {
+            if mouse::button_code(button, winit::keyboard::ModifiersState::empty()) == Some(report.button & 0b11) {
+                self.mouse_report = None;
+                self.motion_throttle.discard();
+                let (col, row) = self.pixel_to_cell(report.pane_id, x, y, report.rect);
+                if let Some(pane) = self.tabs.active().pane(report.pane_id) {
+                    let encoding = pane.terminal().mouse_encoding();
+                    pane.write_input(&mouse::encode_report(report.button, col, row, false, encoding))?;
+                }
+            }
+            return Ok(());
+        }
+
+        if button != MouseButton::Left {
+            return Ok(());
+        }
+
         if self.dragging_border {
             log::info!("Stopped dragging border");
             self.dragging_border = false;
@@ -584,6 +1459,14 @@ impl App {
             self.selecting = false;
             log::debug!("Stopped selection, active: {}", self.selection.active);
         }
+        let pressed = std::mem::take(&mut self.selection_pressed);
+        // The finished selection becomes the PRIMARY selection, pasted with a middle-click
+        if pressed && self.selection.active {
+            self.copy_selection_to_primary();
+        }
+        if copies_on_release(self.config.selection.copy_on_select, pressed, &self.selection) {
+            self.handle_copy()?;
+        }
 
         Ok(())
     }
@@ -592,14 +1475,36 @@ impl App {
     pub fn handle_mouse_move(&mut self, x: f64, y: f64) -> Result<bool> {
         let mut needs_redraw = false;
 
+        // Report drags to applications using button-event or any-event tracking
+        if let Some(report) = self.mouse_report {
+            let cell = self.pixel_to_cell(report.pane_id, x, y, report.rect);
+            if cell != report.cell {
+                let motion = self.tabs.active().pane(report.pane_id).map(|pane| pane.terminal()).filter(|terminal| {
+                    matches!(terminal.mouse_mode(), MouseMode::ButtonEvent | MouseMode::AnyEvent)
+                });
+                if let Some(terminal) = motion {
+                    let code = report.button | mouse::MOTION_FLAG;
+                    let bytes = mouse::encode_report(code, cell.0, cell.1, true, terminal.mouse_encoding());
+                    self.report_motion(report.pane_id, bytes)?;
+                }
+                self.mouse_report = Some(MouseReport { cell, ..report });
+            }
+            return Ok(false);
+        }
+
+        // Report motion without a button to applications using any-event tracking
+        if !self.dragging_border && !self.selecting {
+            self.report_hover(x, y)?;
+        }
+
         // Handle border dragging
         if self.dragging_border {
-            let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
+            let window_rect = self.pane_area();
             let (cell_width, cell_height) = self.renderer.cell_dimensions();
             let x_u32 = x as u32;
             let y_u32 = y as u32;
 
-            if self.pane_manager.update_border_at(x_u32, y_u32, window_rect, cell_width, cell_height)? {
+            if self.tabs.active_mut().update_border_at(x_u32, y_u32, window_rect, cell_width, cell_height)? {
                 log::debug!("Updated border position to ({}, {})", x, y);
                 needs_redraw = true;
             }
@@ -607,18 +1512,16 @@ impl App {
 
         // Handle text selection dragging
         if self.selecting {
-            let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
-            let rects = self.pane_manager.layout().calculate_rects(window_rect);
+            let window_rect = self.pane_area();
+            let rects = self.tabs.active().layout().calculate_rects(window_rect);
 
             // Find which pane the mouse is over
             for (pane_id, rect) in rects {
                 if x >= rect.x as f64 && x < (rect.x + rect.width) as f64 &&
                    y >= rect.y as f64 && y < (rect.y + rect.height) as f64 {
                     // Only update if it's the active pane
-                    if pane_id == self.pane_manager.active_pane_id() {
-                        let (cell_width, cell_height) = self.renderer.cell_dimensions();
-                        let col = ((x - rect.x as f64) / cell_width as f64) as usize;
-                        let row = ((y - rect.y as f64) / cell_height as f64) as usize;
+                    if pane_id == self.tabs.active().active_pane_id() {
+                        let (col, row) = self.pixel_to_cell(pane_id, x, y, rect);
 
                         self.selection.update_end(col, row);
                         needs_redraw = true;
@@ -631,6 +1534,124 @@ impl App {
         Ok(needs_redraw)
     }
 
+    /// Report the pointer's cell to the pane under it if its application uses any-event
+    /// tracking (`?1003`), once per cell. Holding Shift bypasses reporting, as for presses.
+    fn report_hover(&mut self, x: f64, y: f64) -> Result<()> {
+        let rects = self.tabs.active().layout().calculate_rects(self.pane_area());
+        let hovered = rects.into_iter().find(|(_, rect)| {
+            x >= rect.x as f64 && x < (rect.x + rect.width) as f64 && y >= rect.y as f64 && y < (rect.y + rect.height) as f64
+        });
+        let modifiers = self.keyboard.modifiers();
+        let tracking = hovered.filter(|&(pane_id, _)| {
+            !modifiers.shift_key()
+                && self.tabs.active().pane(pane_id).is_some_and(|pane| pane.terminal().mouse_mode() == MouseMode::AnyEvent)
+        });
+        let Some((pane_id, rect)) = tracking else {
+            self.hover_report = None;
+            return Ok(());
+        };
+
+        let cell = self.pixel_to_cell(pane_id, x, y, rect);
+        if self.hover_report == Some((pane_id, cell)) {
+            return Ok(());
+        }
+        self.hover_report = Some((pane_id, cell));
+        let Some(encoding) = self.tabs.active().pane(pane_id).map(|pane| pane.terminal().mouse_encoding()) else {
+            return Ok(());
+        };
+        let bytes = mouse::encode_report(mouse::hover_code(modifiers), cell.0, cell.1, true, encoding);
+        self.report_motion(pane_id, bytes)
+    }
+
+    /// Send a motion report now, or hold it back until `mouse.motion_report_hz` allows another
+    fn report_motion(&mut self, pane_id: PaneId, bytes: Vec<u8>) -> Result<()> {
+        match self.motion_throttle.offer(pane_id, bytes, Instant::now()) {
+            Some((pane_id, bytes)) => self.write_motion(pane_id, &bytes),
+            None => Ok(()),
+        }
+    }
+
+    fn write_motion(&self, pane_id: PaneId, bytes: &[u8]) -> Result<()> {
+        match self.tabs.active().pane(pane_id) {
+            Some(pane) => pane.write_input(bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// Send the motion report held back by the throttle once it is due, so the
+    /// application gets the pointer's final position
+    pub fn flush_mouse_motion(&mut self) -> Result<()> {
+        match self.motion_throttle.take_due(Instant::now()) {
+            Some((pane_id, bytes)) => self.write_motion(pane_id, &bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// When the held-back motion report is due, if there is one
+    pub fn next_mouse_motion(&self) -> Option<Instant> {
+        self.motion_throttle.next_due()
+    }
+
+    /// Send a button press to the pane if its application enabled mouse reporting.
+    /// Holding Shift bypasses reporting so text can always be selected.
+    /// Returns whether the press was reported.
+    fn report_mouse_press(&mut self, pane_id: PaneId, rect: Rect, x: f64, y: f64, button: MouseButton) -> Result<bool> {
+        let modifiers = self.keyboard.modifiers();
+        if modifiers.shift_key() {
+            return Ok(false);
+        }
+        let Some(code) = mouse::button_code(button, modifiers) else {
+            return Ok(false);
+        };
+
+        let cell = self.pixel_to_cell(pane_id, x, y, rect);
+        let Some(pane) = self.tabs.active().pane(pane_id) else {
+            return Ok(false);
+        };
+        let terminal = pane.terminal();
+        if terminal.mouse_mode() == MouseMode::Off {
+            return Ok(false);
+        }
+
+        pane.write_input(&mouse::encode_report(code, cell.0, cell.1, true, terminal.mouse_encoding()))?;
+        self.motion_throttle.discard();
+        self.mouse_report = Some(MouseReport { pane_id, rect, button: code, cell });
+        log::debug!("Reported mouse press {} at {:?} to pane {}", code, cell, pane_id);
+        Ok(true)
+    }
+
+    /// Open the hyperlink (OSC 8) or plain-text URL under a pixel position, if there is one
+    fn open_link_at(&self, pane_id: PaneId, rect: Rect, x: f64, y: f64) -> bool {
+        let (col, row) = self.pixel_to_cell(pane_id, x, y, rect);
+        let Some(terminal) = self.tabs.active().pane(pane_id).map(|pane| pane.terminal()) else {
+            return false;
+        };
+        // Plain-text URLs are only looked for in the clicked row, and only on click
+        let url = terminal
+            .link_at(col, row)
+            .map(str::to_string)
+            .or_else(|| url::url_at(terminal.grid().view_row(row)?, col));
+        let Some(url) = url else {
+            return false;
+        };
+        if let Err(e) = launcher::open_url(&url) {
+            log::warn!("Failed to open link {:?}: {}", url, e);
+        }
+        true
+    }
+
+    /// Convert a pixel position inside a pane to a cell clamped to that pane's grid.
+    /// Either half of a wide character maps to its first column.
+    fn pixel_to_cell(&self, pane_id: PaneId, x: f64, y: f64, rect: Rect) -> (usize, usize) {
+        let (cell_width, cell_height) = self.renderer.cell_dimensions();
+        let Some(grid) = self.tabs.active().pane(pane_id).map(|pane| pane.terminal().grid()) else {
+            return mouse::pixel_to_cell(x, y, rect, cell_width, cell_height, 1, 1);
+        };
+        let (col, row) = mouse::pixel_to_cell(x, y, rect, cell_width, cell_height, grid.cols(), grid.rows());
+        let col = grid.view_row(row).map_or(col, |cells| mouse::glyph_start(cells, col));
+        (col, row)
+    }
+
     /// Change font size by delta
     fn change_font_size(&mut self, delta: f32) -> Result<()> {
         let current_size = self.renderer.font_size();
@@ -639,24 +1660,83 @@ impl App {
         if new_size != current_size {
             log::info!("Changing font size from {} to {}", current_size, new_size);
             self.renderer.set_font_size(new_size)?;
+            self.invalidate();
 
-            // Recalculate all pane sizes with new cell dimensions
-            let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
-            let (cell_width, cell_height) = self.renderer.cell_dimensions();
-            self.pane_manager.resize_all_panes(window_rect, cell_width, cell_height)?;
+            // Recalculate all pane sizes with new cell dimensions (the tab bar is a cell high)
+            self.resize_tabs()?;
         }
 
         Ok(())
     }
 
+    /// Check if the focus-change flash is still being shown
+    pub fn is_focus_flash_active(&self) -> bool {
+        self.tabs.active().is_focus_flash_active()
+    }
+
+    /// Check if a pane of the active tab is flashing for the visual bell
+    pub fn is_visual_bell_active(&self) -> bool {
+        self.tabs.active().is_visual_bell_active()
+    }
+
+    /// Re-check the activity/silence monitors of unfocused panes.
+    /// Returns true if the indicators changed and the window title needs updating.
+    pub fn update_monitor_indicators(&mut self) -> bool {
+        let activity = self.tabs.active().activity_panes();
+        let silence = self.tabs.active().silent_panes();
+        let indicators = (!activity.is_empty(), !silence.is_empty());
+        if indicators == self.monitor_indicators {
+            return false;
+        }
+        log::debug!("Pane monitor: activity in {:?}, silence in {:?}", activity, silence);
+        self.monitor_indicators = indicators;
+        true
+    }
+
+    /// Write out pane log output that has been buffered for long enough
+    pub fn flush_pane_logs(&mut self) {
+        self.tabs.flush_logs_if_due();
+    }
+
+    /// When buffered pane log output has to be written out next
+    pub fn next_log_flush(&self) -> Option<Instant> {
+        self.tabs.next_log_flush()
+    }
+
+    /// When an unfocused pane reaches the silence threshold next, if one is being timed
+    pub fn next_monitor_check(&self) -> Option<Instant> {
+        self.tabs.active().next_silence()
+    }
+
+    /// Pick up the title set by the active pane's application.
+    /// Returns true if it changed and the window title needs updating.
+    pub fn update_dynamic_title(&mut self) -> bool {
+        let title = self.tabs.active().active_pane_title();
+        if title == self.dynamic_title.as_deref() {
+            return false;
+        }
+        self.dynamic_title = title.map(str::to_string);
+        true
+    }
+
+    /// Check if an unfocused pane has produced output since it was last focused
+    pub fn has_pane_activity(&self) -> bool {
+        self.monitor_indicators.0
+    }
+
+    /// Check if an unfocused pane has been silent for the configured time
+    pub fn has_pane_silence(&self) -> bool {
+        self.monitor_indicators.1
+    }
+
     /// Check if broadcast mode is enabled
     pub fn is_broadcast_enabled(&self) -> bool {
-        self.pane_manager.is_broadcast_enabled()
+        self.tabs.active().is_broadcast_enabled()
     }
 
-    /// Get the base window title
+    /// Get the base window title: the active pane's dynamic title, or the configured one
     pub fn base_title(&self) -> &str {
-        &self.config.window.title
+        self.dynamic_title.as_deref().unwrap_or(&self.config.window.title)
     }
 
     /// Handle copy operation
@@ -667,7 +1747,7 @@ impl App {
         }
 
         // Get text from active pane's grid
-        if let Some(pane) = self.pane_manager.active_pane() {
+        if let Some(pane) = self.tabs.active().active_pane() {
             let text = self.selection.get_text(pane.terminal().grid());
 
             if !text.is_empty() {
@@ -678,22 +1758,50 @@ impl App {
             }
         }
 
-        // Clear selection after copy
-        self.selection.clear();
+        // Clear selection after copy; copy-on-select leaves it highlighted
+        if !self.config.selection.copy_on_select {
+            self.selection.clear();
+        }
 
         Ok(())
     }
 
+    /// Copy the selected text of the active pane to the PRIMARY selection (Linux only)
+    fn copy_selection_to_primary(&mut self) {
+        let Some(pane) = self.tabs.active().active_pane() else {
+            return;
+        };
+        let text = self.selection.get_text(pane.terminal().grid());
+        if !text.is_empty()
+            && let Err(e) = self.clipboard_manager.copy_primary(&text)
+        {
+            log::warn!("Failed to set the primary selection: {}", e);
+        }
+    }
+
     /// Handle paste operation
     fn handle_paste(&mut self) -> Result<()> {
-        match self.clipboard_manager.paste() {
+        let text = self.clipboard_manager.paste();
+        self.paste_text(text, "clipboard")
+    }
+
+    /// Paste the PRIMARY selection (middle-click); nothing happens where there is none
+    fn handle_paste_primary(&mut self) -> Result<()> {
+        let text = self.clipboard_manager.paste_primary();
+        self.paste_text(text, "primary selection")
+    }
+
+    fn paste_text(&mut self, text: Result<String>, source: &str) -> Result<()> {
+        match text {
             Ok(text) => {
                 if !text.is_empty() {
-                    // Write pasted text to active pane(s)
-                    self.pane_manager.write_input(text.as_bytes())?;
-                    log::info!("Pasted {} bytes from clipboard", text.len());
+                    // Write pasted text to active pane(s), bracketed if the application asked for it
+                    let bracketed = self.tabs.active().active_pane()
+                        .is_some_and(|pane| pane.terminal().bracketed_paste());
+                    self.tabs.active_mut().write_input(&paste_bytes(&text, bracketed))?;
+                    log::info!("Pasted {} bytes from {}", text.len(), source);
                 } else {
-                    log::debug!("Clipboard is empty, nothing to paste");
+                    log::debug!("The {} is empty, nothing to paste", source);
                 }
             }
             Err(e) => {
@@ -718,7 +1826,7 @@ impl App {
             self.mark_mode = true;
 
             // Initialize mark cursor at current terminal cursor position
-            if let Some(pane) = self.pane_manager.active_pane() {
+            if let Some(pane) = self.tabs.active().active_pane() {
                 let (col, row) = pane.terminal().cursor_position();
                 self.mark_cursor = Some((col, row));
                 self.selection.start_at(col, row);
@@ -737,7 +1845,7 @@ impl App {
             return false;
         };
 
-        let Some(pane) = self.pane_manager.active_pane() else {
+        let Some(pane) = self.tabs.active().active_pane() else {
             return false;
         };
 
@@ -791,6 +1899,11 @@ impl App {
         self.mark_mode
     }
 
+    /// Title notice after a split was refused by `pane.max_panes`, until the next key
+    pub fn pane_limit_notice(&self) -> Option<String> {
+        self.pane_limit_hit.then(|| format!("Max {} panes", self.config.pane.max_panes))
+    }
+
     /// Set IME (Input Method Editor) enabled/disabled (called by OS events)
     pub fn set_ime_enabled(&mut self, enabled: bool) {
         self.ime_enabled = enabled;
@@ -798,6 +1911,21 @@ impl App {
     }
 
     /// Check if IME is enabled
+    /// Window level the event loop should set after Ctrl-Shift-A, once per toggle
+    pub fn take_window_level_change(&mut self) -> Option<WindowLevel> {
+        self.always_on_top.take_change()
+    }
+
+    /// Whether F11 asked the event loop to toggle fullscreen, once per press
+    pub fn take_fullscreen_toggle(&mut self) -> bool {
+        std::mem::take(&mut self.fullscreen_toggled)
+    }
+
+    /// Whether a bell rang since the last call (at most one per `window.bell_min_interval_ms`)
+    pub fn take_bell(&mut self) -> bool {
+        self.bell.take_notification()
+    }
+
     pub fn is_ime_enabled(&self) -> bool {
         self.ime_enabled
     }
@@ -806,7 +1934,7 @@ impl App {
     pub fn handle_ime_commit(&mut self, text: String) -> Result<()> {
         if !text.is_empty() {
             log::debug!("Writing IME commit to PTY: {:?} ({} bytes)", text, text.len());
-            self.pane_manager.write_input(text.as_bytes())?;
+            self.tabs.active_mut().write_input(text.as_bytes())?;
             log::debug!("Successfully wrote IME commit to PTY");
         } else {
             log::debug!("Empty IME commit, ignoring");
@@ -818,10 +1946,10 @@ impl App {
     /// Returns (x, y) in physical pixels
     pub fn get_ime_cursor_position(&self) -> (f32, f32) {
         let (cell_width, cell_height) = self.renderer.cell_dimensions();
-        let window_rect = Rect::new(0, 0, self.window_width, self.window_height);
+        let window_rect = self.pane_area();
 
-        if let Some(pane) = self.pane_manager.active_pane() {
-            if let Some(rect) = self.pane_manager.active_pane_rect(window_rect) {
+        if let Some(pane) = self.tabs.active().active_pane() {
+            if let Some(rect) = self.tabs.active().active_pane_rect(window_rect) {
                 let (col, row) = pane.terminal().cursor_position();
 
                 // Calculate pixel position
@@ -843,3 +1971,307 @@ impl App {
     }
 
 }
+
+/// Part of a `source` sized image drawn `size` pixels large that fits in `available`
+/// pixels: the source pixels to take from its top-left corner and the size to scale
+/// them to. None when none of it is visible.
+fn visible_image_part(source: (u32, u32), size: (u32, u32), available: (u32, u32)) -> Option<((u32, u32), (u32, u32))> {
+    let visible = (size.0.min(available.0), size.1.min(available.1));
+    if visible.0 == 0 || visible.1 == 0 || source.0 == 0 || source.1 == 0 {
+        return None;
+    }
+    let crop = |source: u32, visible: u32, size: u32| (u64::from(source) * u64::from(visible)).div_ceil(u64::from(size)) as u32;
+    Some(((crop(source.0, visible.0, size.0), crop(source.1, visible.1, size.1)), visible))
+}
+
+/// Index of the tab under column `x` of the tab bar, where the tabs share the width equally
+fn tab_at(x: u32, window_width: u32, tab_count: usize) -> usize {
+    let index = x as u64 * tab_count as u64 / window_width.max(1) as u64;
+    (index as usize).min(tab_count.saturating_sub(1))
+}
+
+/// Whether releasing the left button copies the selection: with `selection.copy_on_select`,
+/// once a press has dragged out a selection or picked a word or line
+fn copies_on_release(copy_on_select: bool, pressed: bool, selection: &Selection) -> bool {
+    copy_on_select && pressed && selection.active
+}
+
+/// Panes repainted in a frame: all of them after the buffer was cleared, otherwise only
+/// those with changes, plus the active pane when its cursor blinked. The others keep
+/// the pixels of the previous frame.
+fn panes_to_render(
+    panes: &[(PaneId, Rect)],
+    active_pane_id: PaneId,
+    cleared: bool,
+    cursor_blinked: bool,
+    needs_redraw: impl Fn(PaneId) -> bool,
+) -> Vec<(PaneId, Rect)> {
+    panes
+        .iter()
+        .filter(|&&(pane_id, _)| cleared || (cursor_blinked && pane_id == active_pane_id) || needs_redraw(pane_id))
+        .copied()
+        .collect()
+}
+
+/// Cursor to draw for a pane.
+/// The active pane gets the blinking cursor; inactive panes get a steady hollow cursor
+/// when `inactive_outline` is enabled, and none otherwise.
+fn pane_cursor_info(terminal: &TerminalEmulator, is_active: bool, blink_on: bool, config: &CursorConfig) -> CursorInfo {
+    let (col, row) = terminal.cursor_position();
+    let visible = terminal.cursor_visible() && if is_active { blink_on } else { config.inactive_outline };
+    CursorInfo {
+        col,
+        row,
+        visible,
+        hollow: !is_active,
+        auto_contrast: config.auto_contrast,
+        solid_block: config.solid_block,
+        style: terminal.cursor_style(),
+        color: terminal.palette().cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_changed_panes_render() {
+        let panes = [
+            (0, Rect::new(0, 0, 400, 300)),
+            (1, Rect::new(400, 0, 400, 300)),
+            (2, Rect::new(0, 300, 800, 300)),
+        ];
+        let ids = |rendered: Vec<(PaneId, Rect)>| rendered.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+
+        // Output in one inactive pane repaints only that pane
+        assert_eq!(ids(panes_to_render(&panes, 0, false, false, |id| id == 2)), [2]);
+        // A blinking cursor repaints the active pane as well
+        assert_eq!(ids(panes_to_render(&panes, 0, false, true, |id| id == 2)), [0, 2]);
+        assert!(panes_to_render(&panes, 0, false, false, |_| false).is_empty());
+        // A cleared buffer has to be painted entirely
+        assert_eq!(ids(panes_to_render(&panes, 0, true, false, |_| false)), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_inactive_pane_hollow_cursor() {
+        let mut terminal = TerminalEmulator::new(80, 24, 100);
+        terminal.process_bytes(b"abc");
+        let outline = CursorConfig { inactive_outline: true, ..CursorConfig::default() };
+        let no_outline = CursorConfig { inactive_outline: false, ..CursorConfig::default() };
+
+        let cursor = pane_cursor_info(&terminal, false, true, &outline);
+        assert!(cursor.visible);
+        assert!(cursor.hollow);
+        assert_eq!((cursor.col, cursor.row), (3, 0));
+
+        // Disabled by default: inactive panes have no cursor
+        assert!(!pane_cursor_info(&terminal, false, true, &no_outline).visible);
+
+        // The inactive outline does not blink
+        assert!(pane_cursor_info(&terminal, false, false, &outline).visible);
+
+        let active = pane_cursor_info(&terminal, true, true, &outline);
+        assert!(active.visible);
+        assert!(!active.hollow);
+        assert!(!pane_cursor_info(&terminal, true, false, &outline).visible);
+    }
+
+    #[test]
+    fn test_copy_on_select_release() {
+        let mut selection = Selection::new();
+        // A click without a drag selects nothing
+        selection.start_at(3, 1);
+        assert!(!copies_on_release(true, true, &selection));
+
+        selection.update_end(8, 1);
+        assert!(copies_on_release(true, true, &selection));
+        assert!(!copies_on_release(false, true, &selection));
+        // Releases that did not make the selection (e.g. after dragging a border) leave it alone
+        assert!(!copies_on_release(true, false, &selection));
+    }
+
+    #[test]
+    fn test_drag_release_copies_the_dragged_text() {
+        let mut terminal = TerminalEmulator::new(20, 3, 0);
+        terminal.process_bytes(b"$ ls\r\nsrc  target\r\n");
+        let grid = terminal.grid();
+        let rect = Rect::new(0, 0, 200, 60);
+        let cell = |x: f64, y: f64| mouse::pixel_to_cell(x, y, rect, 10.0, 20.0, grid.cols(), grid.rows());
+
+        // Press on "src", drag to the end of "target" and release
+        let mut selection = Selection::new();
+        let (col, row) = cell(2.0, 25.0);
+        selection.start_at(col, row);
+        let (col, row) = cell(105.0, 30.0);
+        selection.update_end(col, row);
+
+        assert!(copies_on_release(true, true, &selection));
+        assert_eq!(selection.get_text(grid), "src  target");
+    }
+
+    #[test]
+    fn test_images_clipped_to_the_pane() {
+        // Fits: drawn whole
+        assert_eq!(visible_image_part((100, 50), (200, 100), (640, 480)), Some(((100, 50), (200, 100))));
+        // Runs past the right and bottom edges: only the visible part is scaled
+        assert_eq!(visible_image_part((100, 50), (200, 100), (50, 25)), Some(((25, 13), (50, 25))));
+        // A huge requested size costs no more than the pane
+        assert_eq!(visible_image_part((10, 10), (u32::MAX, u32::MAX), (800, 600)), Some(((1, 1), (800, 600))));
+        assert_eq!(visible_image_part((100, 50), (200, 100), (0, 480)), None);
+    }
+
+    #[test]
+    fn test_tab_bar_clicks() {
+        // Three tabs share a 300 pixel wide bar
+        assert_eq!(tab_at(0, 300, 3), 0);
+        assert_eq!(tab_at(99, 300, 3), 0);
+        assert_eq!(tab_at(100, 300, 3), 1);
+        assert_eq!(tab_at(299, 300, 3), 2);
+        // Past the right edge (e.g. while the window is resized) picks the last tab
+        assert_eq!(tab_at(400, 300, 3), 2);
+    }
+
+    #[test]
+    fn test_cursor_blink_intervals() {
+        let start = Instant::now();
+        let mut blink = CursorBlink::new(Some(Duration::from_millis(500)), start);
+        blink.update(start + Duration::from_millis(499));
+        assert!(blink.visible);
+        blink.update(start + Duration::from_millis(500));
+        assert!(!blink.visible);
+        blink.update(start + Duration::from_millis(1000));
+        assert!(blink.visible);
+
+        // A faster rate toggles on its own interval
+        let mut fast = CursorBlink::new(Some(Duration::from_millis(200)), start);
+        fast.update(start + Duration::from_millis(200));
+        assert!(!fast.visible);
+        fast.update(start + Duration::from_millis(300));
+        assert!(!fast.visible);
+
+        // A steady cursor never turns off
+        let mut steady = CursorBlink::new(None, start);
+        for ms in [500, 1000, 1500] {
+            steady.update(start + Duration::from_millis(ms));
+            assert!(steady.visible);
+        }
+    }
+
+    #[test]
+    fn test_cursor_blink_wakeups() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(500);
+        let mut blink = CursorBlink::new(Some(interval), start);
+        assert_eq!(blink.next_toggle(), Some(start + interval));
+
+        // Only the update at the boundary reports a toggle
+        assert!(!blink.update(start + Duration::from_millis(250)));
+        assert!(blink.update(start + interval));
+        assert!(!blink.update(start + interval));
+        assert_eq!(blink.next_toggle(), Some(start + interval * 2));
+
+        // A steady cursor never needs a wakeup
+        let mut steady = CursorBlink::new(None, start);
+        assert!(!steady.update(start + interval));
+        assert_eq!(steady.next_toggle(), None);
+    }
+
+    #[test]
+    fn test_scroll_indicator_label() {
+        let start = Instant::now();
+        let mut indicator = ScrollIndicator::new(true);
+        assert_eq!(indicator.label(10, start), None);
+
+        indicator.scrolled(start);
+        assert_eq!(indicator.label(1234, start).as_deref(), Some("-1234 lines"));
+        assert_eq!(indicator.label(3, start + Duration::from_millis(100)).as_deref(), Some("-3 lines"));
+        // Back at the live screen, then hidden after a moment
+        assert_eq!(indicator.label(0, start), None);
+        assert_eq!(indicator.label(1234, start + SCROLL_INDICATOR_DURATION), None);
+
+        let mut disabled = ScrollIndicator::new(false);
+        disabled.scrolled(start);
+        assert_eq!(disabled.label(1234, start), None);
+    }
+
+    #[test]
+    fn test_last_exit_countdown() {
+        let start = Instant::now();
+        let last_exit = LastExit::new("Exited with code 1".to_string(), Some(Duration::from_secs(3)), start);
+        assert_eq!(last_exit.remaining_secs(start), Some(3));
+        assert_eq!(last_exit.remaining_secs(start + Duration::from_millis(2100)), Some(1));
+        assert!(!last_exit.should_close(start + Duration::from_millis(2999)));
+        assert!(last_exit.should_close(start + Duration::from_secs(3)));
+        assert_eq!(last_exit.lines(start), [
+            "Process finished (Exited with code 1)",
+            "",
+            "Closing in 3s - press any key to start a new shell",
+        ]);
+
+        // Without close_on_last_exit the window waits for a keypress
+        let waiting = LastExit::new("Success".to_string(), None, start);
+        assert_eq!(waiting.remaining_secs(start), None);
+        assert!(!waiting.should_close(start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_keypress_cancels_exit_countdown() {
+        let start = Instant::now();
+        let mut last_exit = LastExit::new("Success".to_string(), Some(Duration::from_secs(3)), start);
+        last_exit.cancel();
+        assert!(!last_exit.should_close(start + Duration::from_secs(10)));
+        assert_eq!(last_exit.lines(start)[2], "Press any key to start a new shell");
+    }
+
+    #[test]
+    fn test_rapid_bells_coalesce() {
+        let start = Instant::now();
+        let mut bell = Bell::new(Duration::from_millis(200));
+        assert!(!bell.take_notification());
+
+        // A burst of bells gives one notification
+        let burst: Vec<bool> = (0..10).map(|ms| bell.ring(start + Duration::from_millis(ms * 15))).collect();
+        assert_eq!(burst.iter().filter(|&&notified| notified).count(), 1);
+        assert!(bell.take_notification());
+        assert!(!bell.take_notification());
+
+        // The interval counts from the last notification
+        assert!(bell.ring(start + Duration::from_millis(200)));
+        assert!(!bell.ring(start + Duration::from_millis(399)));
+        assert!(bell.ring(start + Duration::from_millis(400)));
+
+        // 0 turns the limit off
+        let mut unlimited = Bell::new(Duration::ZERO);
+        assert!(unlimited.ring(start) && unlimited.ring(start));
+    }
+
+    #[test]
+    fn test_always_on_top_toggle() {
+        let mut modifiers = winit::keyboard::ModifiersState::empty();
+        modifiers.set(winit::keyboard::ModifiersState::CONTROL, true);
+        modifiers.set(winit::keyboard::ModifiersState::SHIFT, true);
+        let action = ShortcutHandler::new().match_shortcut(winit::keyboard::KeyCode::KeyA, modifiers);
+        assert_eq!(action, Some(ShortcutAction::ToggleAlwaysOnTop));
+
+        // The configured level is set when the window is created, not requested again
+        let mut always_on_top = AlwaysOnTop::new(false);
+        assert_eq!(always_on_top.take_change(), None);
+
+        // Each toggle asks the event loop for one window level change
+        always_on_top.toggle();
+        assert_eq!(always_on_top.take_change(), Some(WindowLevel::AlwaysOnTop));
+        assert_eq!(always_on_top.take_change(), None);
+        always_on_top.toggle();
+        assert_eq!(always_on_top.take_change(), Some(WindowLevel::Normal));
+    }
+
+    #[test]
+    fn test_fullscreen_toggle() {
+        let action = ShortcutHandler::new().match_shortcut(winit::keyboard::KeyCode::F11, winit::keyboard::ModifiersState::empty());
+        assert_eq!(action, Some(ShortcutAction::ToggleFullscreen));
+        // Modified F11 still reaches the shell
+        let action = ShortcutHandler::new().match_shortcut(winit::keyboard::KeyCode::F11, winit::keyboard::ModifiersState::SHIFT);
+        assert_eq!(action, None);
+    }
+}
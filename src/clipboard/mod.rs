@@ -6,6 +6,49 @@ use crate::utils::Result;
 use arboard::Clipboard;
 use log;
 
+/// Which system selection to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardKind {
+    /// The regular clipboard (Ctrl-C / Ctrl-V)
+    Clipboard,
+    /// The PRIMARY selection (X11/Wayland only)
+    Primary,
+}
+
+impl ClipboardKind {
+    /// The selection actually used on this platform.
+    /// Platforms without a primary selection use the regular clipboard instead.
+    pub fn effective(self) -> Self {
+        if cfg!(target_os = "linux") {
+            self
+        } else {
+            ClipboardKind::Clipboard
+        }
+    }
+}
+
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Bytes to send to the PTY for pasted text.
+///
+/// With bracketed paste (`?2004`) the text is wrapped in `ESC [200~` ... `ESC [201~`.
+/// Markers already in the text are removed first so the paste cannot end the bracket early
+/// and have the rest run as typed input.
+pub fn paste_bytes(text: &str, bracketed: bool) -> Vec<u8> {
+    if !bracketed {
+        return text.as_bytes().to_vec();
+    }
+
+    // Removing one marker can join the pieces of another, so repeat until none are left
+    let mut text = text.to_string();
+    while text.contains(PASTE_START) || text.contains(PASTE_END) {
+        text = text.replace(PASTE_START, "").replace(PASTE_END, "");
+    }
+
+    format!("{}{}{}", PASTE_START, text, PASTE_END).into_bytes()
+}
+
 /// Clipboard manager for Copy/Paste operations
 pub struct ClipboardManager {
     clipboard: Clipboard,
@@ -33,6 +76,58 @@ impl ClipboardManager {
         Ok(())
     }
 
+    /// Copy text to the given selection
+    pub fn copy_to(&mut self, kind: ClipboardKind, text: &str) -> Result<()> {
+        match kind.effective() {
+            ClipboardKind::Clipboard => self.copy(text),
+            ClipboardKind::Primary => self.copy_primary(text),
+        }
+    }
+
+    /// Copy text to the PRIMARY selection (X11/Wayland)
+    #[cfg(target_os = "linux")]
+    pub fn copy_primary(&mut self, text: &str) -> Result<()> {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+
+        self.clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+            .map_err(|e| crate::utils::TerbulatorError::io(format!("Failed to copy to primary selection: {}", e)))?;
+
+        log::debug!("Copied {} bytes to primary selection", text.len());
+
+        Ok(())
+    }
+
+    /// Other platforms have no primary selection; nothing is copied
+    #[cfg(not(target_os = "linux"))]
+    pub fn copy_primary(&mut self, _text: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Paste text from the PRIMARY selection (X11/Wayland)
+    #[cfg(target_os = "linux")]
+    pub fn paste_primary(&mut self) -> Result<String> {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+
+        let text = self.clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            .map_err(|e| crate::utils::TerbulatorError::io(format!("Failed to paste from primary selection: {}", e)))?;
+
+        log::debug!("Pasted {} bytes from primary selection", text.len());
+
+        Ok(text)
+    }
+
+    /// Other platforms have no primary selection; it is always empty
+    #[cfg(not(target_os = "linux"))]
+    pub fn paste_primary(&mut self) -> Result<String> {
+        Ok(String::new())
+    }
+
     /// Paste text from clipboard
     pub fn paste(&mut self) -> Result<String> {
         let text = self.clipboard
@@ -44,3 +139,36 @@ impl ClipboardManager {
         Ok(text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_primary_selection_kept_on_linux() {
+        assert_eq!(ClipboardKind::Primary.effective(), ClipboardKind::Primary);
+        assert_eq!(ClipboardKind::Clipboard.effective(), ClipboardKind::Clipboard);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "linux"))]
+    fn test_primary_selection_falls_back_to_clipboard() {
+        assert_eq!(ClipboardKind::Primary.effective(), ClipboardKind::Clipboard);
+    }
+
+    #[test]
+    fn test_paste_bytes_wraps_only_when_bracketed() {
+        assert_eq!(paste_bytes("ls\nrm -rf x\n", false), b"ls\nrm -rf x\n");
+        assert_eq!(paste_bytes("ls\n", true), b"\x1b[200~ls\n\x1b[201~");
+    }
+
+    #[test]
+    fn test_paste_bytes_strips_embedded_markers() {
+        assert_eq!(paste_bytes("a\x1b[201~\nrm x\n", true), b"\x1b[200~a\nrm x\n\x1b[201~");
+        // A marker reassembled after stripping another one is removed too
+        assert_eq!(paste_bytes("\x1b[20\x1b[201~1~b", true), b"\x1b[200~b\x1b[201~");
+        // Without bracketing the text is sent untouched
+        assert_eq!(paste_bytes("a\x1b[201~", false), b"a\x1b[201~");
+    }
+}
@@ -1,4 +1,18 @@
+use crate::terminal::grid::Cell;
 use crate::terminal::Grid;
+use crate::terminal::grid::Whitespace;
+
+/// Characters selected together by a double-click; whitespace and other
+/// punctuation end the word
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Whether a cell belongs to a word (the second column of a wide character goes with the first)
+fn is_word_cell(row: &[Cell], col: usize) -> bool {
+    let col = (0..=col).rev().find(|&col| !row[col].is_continuation()).unwrap_or(col);
+    is_word_char(row[col].ch)
+}
 
 /// Text selection state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -9,6 +23,9 @@ pub struct Selection {
     pub end: (usize, usize),
     /// Whether selection is active
     pub active: bool,
+    /// Rectangular (block) selection of the columns between start and end on every row,
+    /// instead of the text stream between them
+    pub block: bool,
 }
 
 impl Selection {
@@ -18,6 +35,7 @@ impl Selection {
             start: (0, 0),
             end: (0, 0),
             active: false,
+            block: false,
         }
     }
 
@@ -26,6 +44,13 @@ impl Selection {
         self.start = (col, row);
         self.end = (col, row);
         self.active = false; // Don't activate until actual drag occurs
+        self.block = false;
+    }
+
+    /// Start a rectangular selection at the given position (Alt-drag)
+    pub fn start_block_at(&mut self, col: usize, row: usize) {
+        self.start_at(col, row);
+        self.block = true;
     }
 
     /// Update the end position of the selection (activates on first drag)
@@ -37,9 +62,48 @@ impl Selection {
         }
     }
 
+    /// Select the word around a cell (double-click) of the displayed view. A cell that is
+    /// not part of a word is selected on its own, with the second column of a wide character.
+    pub fn select_word(&mut self, grid: &Grid, col: usize, row: usize) {
+        let Some(cells) = grid.view_row(row) else {
+            return;
+        };
+        if col >= cells.len() {
+            return;
+        }
+
+        let (start, end) = if is_word_cell(cells, col) {
+            let start = (0..col).rev().take_while(|&col| is_word_cell(cells, col)).last().unwrap_or(col);
+            let end = (col + 1..cells.len()).take_while(|&col| is_word_cell(cells, col)).last().unwrap_or(col);
+            (start, end)
+        } else {
+            let start = (0..=col).rev().find(|&col| !cells[col].is_continuation()).unwrap_or(col);
+            (start, start + cells[start].width.max(1) as usize - 1)
+        };
+        self.start = (start, row);
+        self.end = (end.min(cells.len() - 1), row);
+        self.active = true;
+        self.block = false;
+    }
+
+    /// Select the whole logical line through a row of the displayed view (triple-click),
+    /// including the rows it was auto-wrapped over
+    pub fn select_line(&mut self, grid: &Grid, row: usize) {
+        if row >= grid.rows() || grid.cols() == 0 {
+            return;
+        }
+        let first = (0..row).rev().take_while(|&row| grid.view_row_wrapped(row)).last().unwrap_or(row);
+        let last = (row..grid.rows()).find(|&row| !grid.view_row_wrapped(row)).unwrap_or(grid.rows() - 1);
+        self.start = (0, first);
+        self.end = (grid.cols() - 1, last);
+        self.active = true;
+        self.block = false;
+    }
+
     /// Clear the selection
     pub fn clear(&mut self) {
         self.active = false;
+        self.block = false;
         self.start = (0, 0);
         self.end = (0, 0);
     }
@@ -50,6 +114,11 @@ impl Selection {
             return false;
         }
 
+        if self.block {
+            let ((left, top), (right, bottom)) = self.block_corners();
+            return (left..=right).contains(&col) && (top..=bottom).contains(&row);
+        }
+
         let (start_col, start_row) = self.normalized_start();
         let (end_col, end_row) = self.normalized_end();
 
@@ -91,6 +160,12 @@ impl Selection {
         }
     }
 
+    /// Top-left and bottom-right corners of a block selection
+    fn block_corners(&self) -> ((usize, usize), (usize, usize)) {
+        let (start, end) = (self.start, self.end);
+        ((start.0.min(end.0), start.1.min(end.1)), (start.0.max(end.0), start.1.max(end.1)))
+    }
+
     /// Extract selected text from the grid
     pub fn get_text(&self, grid: &Grid) -> String {
         if !self.active {
@@ -99,6 +174,8 @@ impl Selection {
 
         let (start_col, start_row) = self.normalized_start();
         let (end_col, end_row) = self.normalized_end();
+        let ((left, top), (right, bottom)) = self.block_corners();
+        let (start_row, end_row) = if self.block { (top, bottom) } else { (start_row, end_row) };
 
         let mut text = String::new();
 
@@ -107,23 +184,40 @@ impl Selection {
                 break;
             }
 
-            let row_start = if row == start_row { start_col } else { 0 };
-            let row_end = if row == end_row { end_col } else { grid.cols() - 1 };
+            // A block takes the same columns from every row
+            let (row_start, row_end) = if self.block {
+                (left, right)
+            } else {
+                (
+                    if row == start_row { start_col } else { 0 },
+                    if row == end_row { end_col } else { grid.cols() - 1 },
+                )
+            };
 
+            // Copy what is on screen, which may be scrollback while the view is scrolled back
+            let cells = grid.view_row(row).unwrap_or(&[]);
             for col in row_start..=row_end {
                 if col >= grid.cols() {
                     break;
                 }
 
-                if let Some(cell) = grid.get(col, row) {
-                    if cell.ch != '\0' {
+                if let Some(cell) = cells.get(col) {
+                    if matches!(cell.whitespace, Whitespace::TabStart | Whitespace::Tab) {
+                        // The cells one tab skipped copy as a single tab
+                        let continues_tab = cell.whitespace == Whitespace::Tab
+                            && col > row_start
+                            && matches!(cells[col - 1].whitespace, Whitespace::TabStart | Whitespace::Tab);
+                        if !continues_tab {
+                            text.push('\t');
+                        }
+                    } else if cell.ch != '\0' {
                         text.push(cell.ch);
                     }
                 }
             }
 
-            // Add newline if not the last row
-            if row < end_row {
+            // Rows the line was auto-wrapped over join back into one line
+            if row < end_row && (self.block || !grid.view_row_wrapped(row)) {
                 text.push('\n');
             }
         }
@@ -137,3 +231,116 @@ impl Default for Selection {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_with(lines: &[&str], cols: usize) -> Grid {
+        let mut grid = Grid::new(cols, lines.len(), 10);
+        for (row, line) in lines.iter().enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                grid.set(col, row, Cell::new(ch));
+            }
+        }
+        grid
+    }
+
+    fn word_at(grid: &Grid, col: usize) -> String {
+        let mut selection = Selection::new();
+        selection.select_word(grid, col, 0);
+        selection.get_text(grid)
+    }
+
+    #[test]
+    fn test_select_word_boundaries() {
+        let grid = grid_with(&["let x_1 = foo.bar(baz, \"qux\");"], 32);
+        assert_eq!(word_at(&grid, 0), "let");
+        assert_eq!(word_at(&grid, 5), "x_1");
+        assert_eq!(word_at(&grid, 14), "bar");
+        assert_eq!(word_at(&grid, 18), "baz");
+        assert_eq!(word_at(&grid, 26), "qux");
+        // Punctuation and spaces are selected on their own
+        assert_eq!(word_at(&grid, 13), ".");
+        assert_eq!(word_at(&grid, 3), " ");
+        assert_eq!(word_at(&grid, 23), "\"");
+    }
+
+    #[test]
+    fn test_select_word_with_wide_characters() {
+        let mut grid = Grid::new(8, 1, 0);
+        let mut col = 0;
+        for ch in "漢字、ab".chars() {
+            let cell = Cell { width: if ch.is_ascii() { 1 } else { 2 }, ..Cell::new(ch) };
+            grid.set(col, 0, cell);
+            if cell.width == 2 {
+                grid.set(col + 1, 0, Cell::continuation(&cell));
+            }
+            col += cell.width as usize;
+        }
+        // Clicking the second column of a wide character selects its word
+        assert_eq!(word_at(&grid, 3), "漢字");
+        assert_eq!(word_at(&grid, 5), "、");
+        assert_eq!(word_at(&grid, 6), "ab");
+    }
+
+    #[test]
+    fn test_stream_and_block_selection() {
+        let grid = grid_with(&["name  size", "a.rs   120", "b.rs  4096"], 10);
+        // Drag from the start of "size" to the end of "b.rs"
+        let mut stream = Selection::new();
+        stream.start_at(6, 0);
+        stream.update_end(3, 2);
+        let mut block = Selection::new();
+        block.start_block_at(6, 0);
+        block.update_end(3, 2);
+
+        assert!(stream.contains(9, 0) && stream.contains(0, 1) && stream.contains(9, 1));
+        assert!(!stream.contains(5, 0) && !stream.contains(4, 2));
+        assert!(block.contains(3, 0) && block.contains(6, 2) && block.contains(5, 1));
+        assert!(!block.contains(2, 1) && !block.contains(7, 1) && !block.contains(9, 0));
+
+        assert_eq!(stream.get_text(&grid), "size\na.rs   120\nb.rs");
+        assert_eq!(block.get_text(&grid), "e  s\ns   \ns  4");
+        // Dragging towards the top-left selects the same rectangle
+        block.start_block_at(3, 2);
+        block.update_end(6, 0);
+        assert_eq!(block.get_text(&grid), "e  s\ns   \ns  4");
+    }
+
+    #[test]
+    fn test_copy_from_scrollback() {
+        let mut grid = grid_with(&["old", "new"], 3);
+        grid.scroll_up(1);
+        for (col, ch) in "now".chars().enumerate() {
+            grid.set(col, 1, Cell::new(ch));
+        }
+        grid.scroll_view_up(1);
+
+        // The rows on screen are "old" (scrollback) and "new", not the live "new" and "now"
+        let mut selection = Selection::new();
+        selection.start_at(0, 0);
+        selection.update_end(2, 1);
+        assert_eq!(selection.get_text(&grid), "old\nnew");
+    }
+
+    #[test]
+    fn test_select_wrapped_line() {
+        let mut grid = grid_with(&["$ echo", "abcdef", "ghi", "next"], 6);
+        grid.set_wrapped(1, true);
+
+        let mut selection = Selection::new();
+        selection.select_line(&grid, 2);
+        assert_eq!(selection.get_text(&grid).trim_end(), "abcdefghi");
+        selection.select_line(&grid, 0);
+        assert_eq!(selection.get_text(&grid), "$ echo");
+
+        // The same line scrolled back: the wrap is remembered in scrollback
+        grid.scroll_up(2);
+        grid.scroll_view_up(2);
+        selection.select_line(&grid, 2);
+        assert_eq!(selection.get_text(&grid).trim_end(), "abcdefghi");
+        selection.select_word(&grid, 2, 1);
+        assert_eq!(selection.get_text(&grid), "abcdef");
+    }
+}
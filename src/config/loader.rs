@@ -1,7 +1,9 @@
 use crate::config::Config;
+use crate::config::types::ThemeConfig;
+use crate::input::ShortcutHandler;
 use crate::utils::{Result, TerbulatorError};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Get the default config file path: ~/.config/terbulator/config.yaml
 pub fn default_config_path() -> Result<PathBuf> {
@@ -16,6 +18,11 @@ pub fn default_config_path() -> Result<PathBuf> {
     Ok(path)
 }
 
+/// File the pane layout is saved to (`startup.restore_layout`): layout.yaml next to the config file
+pub fn layout_path(config_path: Option<PathBuf>) -> Result<PathBuf> {
+    Ok(config_path.unwrap_or(default_config_path()?).with_file_name("layout.yaml"))
+}
+
 /// Load configuration from file, or return default if file doesn't exist
 pub fn load_config(path: Option<PathBuf>) -> Result<Config> {
     let config_path = path.unwrap_or(default_config_path()?);
@@ -24,6 +31,16 @@ pub fn load_config(path: Option<PathBuf>) -> Result<Config> {
         log::info!("Loading config from: {}", config_path.display());
         let content = fs::read_to_string(&config_path)?;
         let config: Config = serde_yaml::from_str(&content)?;
+        config.palette()?;
+        config.startup_panes()?;
+        config.terminal.sequence_hook_argv()?;
+        config.terminal.cursor_style()?;
+        config.terminal.bell_mode()?;
+        config.terminal.font_weights()?;
+        config.terminal.hold_message_colors()?;
+        config.pane.log_format()?;
+        config.pane.min_size()?;
+        ShortcutHandler::with_bindings(&config.keybindings)?;
         Ok(config)
     } else {
         log::info!("Config file not found at {}, using defaults", config_path.display());
@@ -33,20 +50,33 @@ pub fn load_config(path: Option<PathBuf>) -> Result<Config> {
 
 /// Save configuration to file
 pub fn save_config(config: &Config) -> Result<()> {
-    let config_path = default_config_path()?;
+    save_config_to(config, &default_config_path()?)
+}
 
+/// Save configuration to the given path
+pub fn save_config_to(config: &Config, config_path: &Path) -> Result<()> {
     // Create parent directory if it doesn't exist
     if let Some(parent) = config_path.parent() {
         fs::create_dir_all(parent)?;
     }
 
     let yaml = serde_yaml::to_string(config)?;
-    fs::write(&config_path, yaml)?;
+    fs::write(config_path, yaml)?;
 
     log::info!("Config saved to: {}", config_path.display());
     Ok(())
 }
 
+/// Replace the theme section of the config file, keeping every other section as it is on disk
+pub fn save_theme(theme: &ThemeConfig, path: Option<PathBuf>) -> Result<Config> {
+    let config_path = path.unwrap_or(default_config_path()?);
+
+    let mut config = load_config(Some(config_path.clone()))?;
+    config.theme = theme.clone();
+    save_config_to(&config, &config_path)?;
+    Ok(config)
+}
+
 /// Initialize config directory and create default config if it doesn't exist
 pub fn init_config(path: Option<PathBuf>) -> Result<Config> {
     let config_path = if let Some(p) = path.clone() {
@@ -73,3 +103,33 @@ pub fn init_config(path: Option<PathBuf>) -> Result<Config> {
         load_config(path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::TerminalEmulator;
+
+    #[test]
+    fn test_save_theme_after_osc_color_changes() {
+        let dir = std::env::temp_dir().join(format!("terbulator-theme-{}", std::process::id()));
+        let path = dir.join("config.yaml");
+        let mut existing = Config::default();
+        existing.terminal.font_size = 20.0;
+        save_config_to(&existing, &path).unwrap();
+
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b]4;1;rgb:cc/24/1d;2;#98971a\x07\x1b]11;#282828\x07");
+        save_theme(&ThemeConfig::from_palette(term.palette()), Some(path.clone())).unwrap();
+
+        let saved = load_config(Some(path)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(saved.theme.foreground, None);
+        assert_eq!(saved.theme.background.as_deref(), Some("#282828"));
+        assert_eq!(saved.theme.palette.len(), 2);
+        assert_eq!(saved.theme.palette[&1], "#cc241d");
+        assert_eq!(saved.theme.palette[&2], "#98971a");
+        // Other sections are preserved
+        assert_eq!(saved.terminal.font_size, 20.0);
+    }
+}
@@ -1,5 +1,6 @@
 pub mod types;
 pub mod loader;
+pub mod presets;
 
 pub use types::Config;
 pub use loader::init_config;
@@ -0,0 +1,113 @@
+use crate::renderer::backend::Color;
+use crate::terminal::palette::Palette;
+
+/// A built-in color scheme (`theme.preset`): the 16 ANSI colors and the default colors
+#[derive(Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    pub foreground: Color,
+    pub background: Color,
+    pub cursor: Color,
+    pub ansi: [Color; 16],
+}
+
+impl Preset {
+    /// Replace the default colors and the 16 ANSI colors of `palette`; the 256-color cube
+    /// and grayscale ramp stay as they are
+    pub fn apply_to(&self, palette: &mut Palette) {
+        palette.foreground = self.foreground;
+        palette.background = self.background;
+        palette.cursor = self.cursor;
+        for (index, &color) in self.ansi.iter().enumerate() {
+            palette.set(index as u8, color);
+        }
+    }
+}
+
+/// The preset called `name`
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// Names of the built-in presets, for error messages
+pub fn names() -> Vec<&'static str> {
+    PRESETS.iter().map(|preset| preset.name).collect()
+}
+
+const fn hex(value: u32) -> Color {
+    Color::rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+}
+
+const fn hex_colors(values: [u32; 16]) -> [Color; 16] {
+    let mut colors = [Color::BLACK; 16];
+    let mut index = 0;
+    while index < 16 {
+        colors[index] = hex(values[index]);
+        index += 1;
+    }
+    colors
+}
+
+/// Solarized's accent colors and the base tones in the usual ANSI slots
+/// (the bright slots hold the base tones, so the light and dark variants share them)
+const SOLARIZED: [u32; 16] = [
+    0x073642, 0xdc322f, 0x859900, 0xb58900, 0x268bd2, 0xd33682, 0x2aa198, 0xeee8d5,
+    0x002b36, 0xcb4b16, 0x586e75, 0x657b83, 0x839496, 0x6c71c4, 0x93a1a1, 0xfdf6e3,
+];
+
+const PRESETS: &[Preset] = &[
+    Preset {
+        name: "solarized-dark",
+        foreground: hex(0x839496),
+        background: hex(0x002b36),
+        cursor: hex(0x93a1a1),
+        ansi: hex_colors(SOLARIZED),
+    },
+    Preset {
+        name: "solarized-light",
+        foreground: hex(0x657b83),
+        background: hex(0xfdf6e3),
+        cursor: hex(0x586e75),
+        ansi: hex_colors(SOLARIZED),
+    },
+    Preset {
+        name: "gruvbox-dark",
+        foreground: hex(0xebdbb2),
+        background: hex(0x282828),
+        cursor: hex(0xebdbb2),
+        ansi: hex_colors([
+            0x282828, 0xcc241d, 0x98971a, 0xd79921, 0x458588, 0xb16286, 0x689d6a, 0xa89984,
+            0x928374, 0xfb4934, 0xb8bb26, 0xfabd2f, 0x83a598, 0xd3869b, 0x8ec07c, 0xebdbb2,
+        ]),
+    },
+    Preset {
+        name: "gruvbox-light",
+        foreground: hex(0x3c3836),
+        background: hex(0xfbf1c7),
+        cursor: hex(0x3c3836),
+        ansi: hex_colors([
+            0xfbf1c7, 0xcc241d, 0x98971a, 0xd79921, 0x458588, 0xb16286, 0x689d6a, 0x7c6f64,
+            0x928374, 0x9d0006, 0x79740e, 0xb57614, 0x076678, 0x8f3f71, 0x427b58, 0x3c3836,
+        ]),
+    },
+    Preset {
+        name: "dracula",
+        foreground: hex(0xf8f8f2),
+        background: hex(0x282a36),
+        cursor: hex(0xf8f8f2),
+        ansi: hex_colors([
+            0x21222c, 0xff5555, 0x50fa7b, 0xf1fa8c, 0xbd93f9, 0xff79c6, 0x8be9fd, 0xf8f8f2,
+            0x6272a4, 0xff6e6e, 0x69ff94, 0xffffa5, 0xd6acff, 0xff92df, 0xa4ffff, 0xffffff,
+        ]),
+    },
+    Preset {
+        name: "nord",
+        foreground: hex(0xd8dee9),
+        background: hex(0x2e3440),
+        cursor: hex(0xd8dee9),
+        ansi: hex_colors([
+            0x3b4252, 0xbf616a, 0xa3be8c, 0xebcb8b, 0x81a1c1, 0xb48ead, 0x88c0d0, 0xe5e9f0,
+            0x4c566a, 0xbf616a, 0xa3be8c, 0xebcb8b, 0x81a1c1, 0xb48ead, 0x8fbcbb, 0xeceff4,
+        ]),
+    },
+];
@@ -1,4 +1,14 @@
+use crate::config::presets;
+use crate::pane::LogFormat;
+use crate::renderer::backend::{Color, CursorStyle, FontWeights};
+use crate::terminal::palette::{parse_color_spec, to_hex, Palette};
+use crate::utils::bell::BellMode;
+use crate::utils::path::expand_path;
+use crate::utils::{Result, TerbulatorError};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -13,6 +23,32 @@ pub struct Config {
 
     #[serde(default)]
     pub startup: StartupConfig,
+
+    #[serde(default)]
+    pub cursor: CursorConfig,
+
+    #[serde(default)]
+    pub pane: PaneConfig,
+
+    #[serde(default)]
+    pub scroll: ScrollConfig,
+
+    #[serde(default)]
+    pub selection: SelectionConfig,
+
+    #[serde(default)]
+    pub mouse: MouseConfig,
+
+    #[serde(default)]
+    pub keyboard: KeyboardConfig,
+
+    #[serde(default)]
+    pub theme: ThemeConfig,
+
+    /// Shortcut action name (e.g. `SplitHorizontal`) -> key such as `Ctrl+Shift+S`,
+    /// or `none` to unbind; unlisted actions keep their default keys
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,13 +80,210 @@ pub struct TerminalConfig {
     #[serde(default = "default_font_family")]
     pub font_family: String,
 
+    /// Fonts tried in order for characters the primary font has no glyph for (emoji, CJK, ...)
+    #[serde(default)]
+    pub fallback_fonts: Vec<String>,
+
+    /// Weight of normal text: "light", "regular", "medium", ... or a number from 100 to 900
+    #[serde(default = "default_font_weight")]
+    pub font_weight: String,
+
+    /// Font for bold text (default: `font_family` in `bold_font_weight`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold_font_family: Option<String>,
+
+    /// Weight of bold text, in the same form as `font_weight`
+    #[serde(default = "default_bold_font_weight")]
+    pub bold_font_weight: String,
+
     /// Scrollback buffer size
     #[serde(default = "default_scrollback")]
     pub scrollback: usize,
 
-    /// Shell command to execute
+    /// Shell to execute: a program, or a list of the program and its arguments
     #[serde(default = "default_shell")]
-    pub shell: String,
+    pub shell: CommandLine,
+
+    /// Command run in new panes instead of the shell (same forms as `shell`)
+    #[serde(default)]
+    pub command: Option<CommandLine>,
+
+    /// TERM value for the shell (COLORTERM is always "truecolor")
+    #[serde(default = "default_term")]
+    pub term: String,
+
+    /// Directory shells start in (`~` and `$VAR` are expanded); None for the home directory
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+
+    /// Extra environment variables for the shell
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Keep a pane whose process exited open, showing its exit status until a key is pressed
+    #[serde(default)]
+    pub hold_on_exit: bool,
+
+    /// Colors ("#rrggbb") of the exit status line of a held pane; None uses the
+    /// default foreground or background
+    #[serde(default)]
+    pub hold_message_foreground: Option<String>,
+
+    #[serde(default)]
+    pub hold_message_background: Option<String>,
+
+    /// Mark trailing spaces with faint dots and tabs with arrows
+    #[serde(default)]
+    pub show_whitespace: bool,
+
+    /// Draw bold text in the 8 basic ANSI colors with the bright colors (8-15)
+    #[serde(default)]
+    pub bold_is_bright: bool,
+
+    /// Command that gets the OSC/DCS sequences terbulator does not handle on stdin;
+    /// None (the default) drops them
+    #[serde(default)]
+    pub sequence_hook: Option<CommandLine>,
+
+    /// Only sequences whose payload starts with one of these are given to the hook
+    /// (e.g. "1337;"); required with `sequence_hook`
+    #[serde(default)]
+    pub sequence_hook_prefixes: Vec<String>,
+
+    /// Default foreground color ("#rrggbb")
+    #[serde(default = "default_foreground")]
+    pub foreground: String,
+
+    /// Default background color ("#rrggbb")
+    #[serde(default = "default_background")]
+    pub background: String,
+
+    /// Cursor shape until an application changes it: "block", "underline" or "bar"
+    #[serde(default = "default_cursor_style")]
+    pub cursor_style: String,
+
+    /// Cursor blink interval in milliseconds; null or 0 for a steady cursor
+    #[serde(default = "default_cursor_blink_ms")]
+    pub cursor_blink_ms: Option<u64>,
+
+    /// What a bell does: "none", "visual" (flash the pane) or "audible" (system sound)
+    #[serde(default = "default_bell")]
+    pub bell: String,
+}
+
+/// A program to run, given as a single string (no arguments) or as a list of the
+/// program and its arguments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CommandLine {
+    Program(String),
+    Argv(Vec<String>),
+}
+
+impl CommandLine {
+    pub fn argv(&self) -> &[String] {
+        match self {
+            CommandLine::Program(program) => std::slice::from_ref(program),
+            CommandLine::Argv(argv) => argv,
+        }
+    }
+}
+
+impl TerminalConfig {
+    /// Program and arguments new panes run: `command` if set, otherwise `shell`
+    pub fn pane_argv(&self) -> Result<Vec<String>> {
+        let (field, command) = match &self.command {
+            Some(command) => ("terminal.command", command),
+            None => ("terminal.shell", &self.shell),
+        };
+        match command.argv() {
+            [program, ..] if !program.is_empty() => Ok(command.argv().to_vec()),
+            _ => Err(TerbulatorError::config(format!("{} must name a program", field))),
+        }
+    }
+
+    /// Program and arguments of `sequence_hook`, if one is set. The hook must also list
+    /// the payload prefixes it handles, so shells printing e.g. OSC 7 at every prompt do
+    /// not start it each time
+    pub fn sequence_hook_argv(&self) -> Result<Option<Vec<String>>> {
+        let Some(hook) = &self.sequence_hook else {
+            return Ok(None);
+        };
+        if self.sequence_hook_prefixes.is_empty() || self.sequence_hook_prefixes.iter().any(String::is_empty) {
+            return Err(TerbulatorError::config(
+                "terminal.sequence_hook_prefixes must list the payload prefixes to forward, e.g. [\"1337;\"]",
+            ));
+        }
+        match hook.argv() {
+            [program, ..] if !program.is_empty() => Ok(Some(hook.argv().to_vec())),
+            _ => Err(TerbulatorError::config("terminal.sequence_hook must name a program")),
+        }
+    }
+
+    /// Default (foreground, background) colors
+    pub fn colors(&self) -> Result<(Color, Color)> {
+        Ok((
+            parse_hex_color("terminal.foreground", &self.foreground)?,
+            parse_hex_color("terminal.background", &self.background)?,
+        ))
+    }
+
+    /// (foreground, background) of the exit status line of a held pane, None for the defaults
+    pub fn hold_message_colors(&self) -> Result<(Option<Color>, Option<Color>)> {
+        let parse = |field, value: &Option<String>| value.as_deref().map(|value| parse_hex_color(field, value)).transpose();
+        Ok((
+            parse("terminal.hold_message_foreground", &self.hold_message_foreground)?,
+            parse("terminal.hold_message_background", &self.hold_message_background)?,
+        ))
+    }
+
+    /// Time between cursor blinks, None when the cursor does not blink
+    pub fn cursor_blink_interval(&self) -> Option<Duration> {
+        self.cursor_blink_ms.filter(|&ms| ms > 0).map(Duration::from_millis)
+    }
+
+    /// `working_directory` with `~` and environment variables expanded
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.working_directory.as_deref().map(expand_path)
+    }
+
+    /// `font_weight` and `bold_font_weight`
+    pub fn font_weights(&self) -> Result<FontWeights> {
+        let parse = |field, value: &str| FontWeights::parse_weight(value).ok_or_else(|| TerbulatorError::config(format!(
+            "Invalid terminal.{}: {:?} (expected a name such as \"regular\", \"medium\" or \"bold\", or 100-900)",
+            field, value
+        )));
+        Ok(FontWeights {
+            regular: parse("font_weight", &self.font_weight)?,
+            bold: parse("bold_font_weight", &self.bold_font_weight)?,
+        })
+    }
+
+    pub fn cursor_style(&self) -> Result<CursorStyle> {
+        CursorStyle::from_name(&self.cursor_style).ok_or_else(|| TerbulatorError::config(format!(
+            "Invalid terminal.cursor_style: {:?} (expected \"block\", \"underline\" or \"bar\")",
+            self.cursor_style
+        )))
+    }
+
+    pub fn bell_mode(&self) -> Result<BellMode> {
+        BellMode::from_name(&self.bell).ok_or_else(|| TerbulatorError::config(format!(
+            "Invalid terminal.bell: {:?} (expected \"none\", \"visual\" or \"audible\")",
+            self.bell
+        )))
+    }
+}
+
+/// Parse a `#rrggbb` color of the config field `field`
+fn parse_hex_color(field: &str, value: &str) -> Result<Color> {
+    value
+        .starts_with('#')
+        .then(|| parse_color_spec(value.as_bytes()))
+        .flatten()
+        .ok_or_else(|| TerbulatorError::config(format!(
+            "Invalid color for {}: {:?} (expected \"#rrggbb\")",
+            field, value
+        )))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +303,34 @@ pub struct WindowConfig {
     /// Maximize window on startup
     #[serde(default = "default_maximize")]
     pub maximize: bool,
+
+    /// Close the window when the last pane's process exits; otherwise a keypress
+    /// starts a new shell
+    #[serde(default = "default_close_on_last_exit")]
+    pub close_on_last_exit: bool,
+
+    /// How long the exit status is shown before the window closes (0 = close at once)
+    #[serde(default = "default_exit_grace_ms")]
+    pub exit_grace_ms: u64,
+
+    /// Keep the window above other windows; Ctrl-Shift-A toggles it at runtime
+    #[serde(default)]
+    pub always_on_top: bool,
+
+    /// Bells closer together than this (in milliseconds) give a single notification
+    #[serde(default = "default_bell_min_interval_ms")]
+    pub bell_min_interval_ms: u64,
+}
+
+impl WindowConfig {
+    pub fn bell_min_interval(&self) -> Duration {
+        Duration::from_millis(self.bell_min_interval_ms)
+    }
+
+    /// Countdown before the window closes after the last pane exited, None to close at once
+    pub fn exit_grace(&self) -> Option<Duration> {
+        (self.exit_grace_ms > 0).then(|| Duration::from_millis(self.exit_grace_ms))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +350,267 @@ pub struct StartupConfig {
     /// Split ratio for vertical splits (e.g., 0.5 for 5:5 ratio)
     #[serde(default = "default_vertical_ratio")]
     pub vertical_ratio: f32,
+
+    /// Save the layout of the active tab when the window is closed and start with it
+    /// next time instead of `panes`/`layout`
+    #[serde(default)]
+    pub restore_layout: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorConfig {
+    /// Draw a hollow cursor in inactive panes
+    #[serde(default)]
+    pub inactive_outline: bool,
+
+    /// Invert the cell under the cursor when the cursor color is too close to its background
+    #[serde(default = "default_auto_contrast")]
+    pub auto_contrast: bool,
+
+    /// Draw the block cursor as a solid box in the cursor color instead of inverting
+    /// the cell under it (which keeps its character readable)
+    #[serde(default)]
+    pub solid_block: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaneConfig {
+    /// Background tint of each startup pane in layout order, as "#rrggbb" ("" for none)
+    #[serde(default)]
+    pub tint: Vec<String>,
+
+    /// Most panes that can be open at once (0 for no limit)
+    #[serde(default = "default_max_panes")]
+    pub max_panes: usize,
+
+    /// Briefly flash the border of the newly focused pane
+    #[serde(default)]
+    pub focus_flash: bool,
+
+    /// Flag unfocused panes that produce output
+    #[serde(default)]
+    pub monitor_activity: bool,
+
+    /// Flag unfocused panes that have been silent for this many seconds (0 = off)
+    #[serde(default)]
+    pub monitor_silence_secs: u64,
+
+    /// Log every pane's output to this file from the start; `{pane}` is replaced
+    /// with the pane id. Ctrl-Shift-O toggles logging of the active pane either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<PathBuf>,
+
+    /// "raw" keeps the escape sequences in the log, "plain" writes only the text
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
+
+    /// Smallest pane in columns; splits that would make a narrower pane are refused
+    #[serde(default = "default_min_pane_size")]
+    pub min_cols: usize,
+
+    /// Smallest pane in rows; splits that would make a shorter pane are refused
+    #[serde(default = "default_min_pane_size")]
+    pub min_rows: usize,
+}
+
+impl Default for PaneConfig {
+    fn default() -> Self {
+        Self {
+            tint: Vec::new(),
+            max_panes: default_max_panes(),
+            focus_flash: false,
+            monitor_activity: false,
+            monitor_silence_secs: 0,
+            log_path: None,
+            log_format: default_log_format(),
+            min_cols: default_min_pane_size(),
+            min_rows: default_min_pane_size(),
+        }
+    }
+}
+
+impl PaneConfig {
+    /// `log_path` with `~` and environment variables expanded
+    pub fn log_path(&self) -> Option<PathBuf> {
+        self.log_path.as_deref().map(expand_path)
+    }
+
+    pub fn log_format(&self) -> Result<LogFormat> {
+        LogFormat::from_name(&self.log_format).ok_or_else(|| TerbulatorError::config(format!(
+            "Invalid pane.log_format: {:?} (expected \"raw\" or \"plain\")",
+            self.log_format
+        )))
+    }
+
+    /// `(min_cols, min_rows)`, each at least 1
+    pub fn min_size(&self) -> Result<(usize, usize)> {
+        if self.min_cols == 0 || self.min_rows == 0 {
+            return Err(TerbulatorError::config("pane.min_cols and pane.min_rows must be at least 1"));
+        }
+        Ok((self.min_cols, self.min_rows))
+    }
+}
+
+impl PaneConfig {
+    /// The tint colors of the startup panes, or an error naming the bad entry
+    pub fn tint_colors(&self) -> Result<Vec<Option<Color>>> {
+        self.tint
+            .iter()
+            .map(|value| match value.as_str() {
+                "" => Ok(None),
+                value => parse_hex_color("pane.tint", value).map(Some),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollConfig {
+    /// Keep the command line of a running command on the bottom row while its
+    /// output scrolls (needs OSC 133 shell integration)
+    #[serde(default)]
+    pub pin_prompt: bool,
+
+    /// Briefly show how many lines back the view is after scrolling into the scrollback
+    #[serde(default = "default_scroll_indicator")]
+    pub indicator: bool,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            pin_prompt: false,
+            indicator: default_scroll_indicator(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectionConfig {
+    /// Copy text to the clipboard as soon as it is selected with the mouse, keeping
+    /// it highlighted
+    #[serde(default)]
+    pub copy_on_select: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseConfig {
+    /// Most mouse motion reports a second sent to applications that track the mouse
+    /// (`?1002`/`?1003`); 0 for no limit
+    #[serde(default = "default_motion_report_hz")]
+    pub motion_report_hz: u32,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            motion_report_hz: default_motion_report_hz(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardConfig {
+    /// On macOS, send Option combinations as Meta (ESC prefix, Option+b is `ESC b`)
+    /// instead of the character the system composes (Option+b is `∫`)
+    #[serde(default = "default_macos_option_is_meta")]
+    pub macos_option_is_meta: bool,
+}
+
+impl Default for KeyboardConfig {
+    fn default() -> Self {
+        Self {
+            macos_option_is_meta: default_macos_option_is_meta(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    /// Built-in color scheme (e.g. "solarized-dark") the other theme colors are applied on top of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+
+    /// Default foreground color ("#rrggbb")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+
+    /// Default background color ("#rrggbb")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+
+    /// Cursor color ("#rrggbb")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+
+    /// Indexed color overrides ("#rrggbb"), keyed by palette index
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub palette: BTreeMap<u8, String>,
+}
+
+impl Config {
+    /// Palette terminals start with: the built-in colors, the `terminal` default colors
+    /// and the `theme` overrides on top
+    pub fn palette(&self) -> Result<Palette> {
+        let (foreground, background) = self.terminal.colors()?;
+        let mut palette = Palette::new(foreground, background);
+        self.theme.apply_to(&mut palette)?;
+        Ok(palette)
+    }
+
+    /// Number of panes to start with; more than `pane.max_panes` could never be opened
+    pub fn startup_panes(&self) -> Result<usize> {
+        let max_panes = self.pane.max_panes;
+        if max_panes > 0 && self.startup.panes > max_panes {
+            return Err(TerbulatorError::config(format!(
+                "startup.panes is {} but pane.max_panes only allows {}",
+                self.startup.panes, max_panes
+            )));
+        }
+        Ok(self.startup.panes)
+    }
+}
+
+impl ThemeConfig {
+    /// Override the colors of `palette` with the preset, then with the colors set in this theme
+    pub fn apply_to(&self, palette: &mut Palette) -> Result<()> {
+        if let Some(name) = &self.preset {
+            let preset = presets::find(name).ok_or_else(|| TerbulatorError::config(format!(
+                "Unknown theme.preset: {:?} (available: {})",
+                name,
+                presets::names().join(", ")
+            )))?;
+            preset.apply_to(palette);
+        }
+        if let Some(foreground) = &self.foreground {
+            palette.foreground = parse_hex_color("theme.foreground", foreground)?;
+        }
+        if let Some(background) = &self.background {
+            palette.background = parse_hex_color("theme.background", background)?;
+        }
+        if let Some(cursor) = &self.cursor {
+            palette.cursor = parse_hex_color("theme.cursor", cursor)?;
+        }
+        for (&index, color) in &self.palette {
+            palette.set(index, parse_hex_color(&format!("theme.palette.{}", index), color)?);
+        }
+        Ok(())
+    }
+
+    /// Capture a runtime palette; only colors that differ from the built-in ones are recorded,
+    /// so the colors of a preset are written out in full
+    pub fn from_palette(palette: &Palette) -> Self {
+        let defaults = Palette::default();
+        let changed = |color, default| (color != default).then(|| to_hex(color));
+
+        Self {
+            preset: None,
+            foreground: changed(palette.foreground, defaults.foreground),
+            background: changed(palette.background, defaults.background),
+            cursor: changed(palette.cursor, defaults.cursor),
+            palette: palette.changed_colors().map(|(index, color)| (index, to_hex(color))).collect(),
+        }
+    }
 }
 
 // Default functions
@@ -112,6 +634,14 @@ fn default_font_size() -> f32 {
     14.0
 }
 
+fn default_font_weight() -> String {
+    "regular".to_string()
+}
+
+fn default_bold_font_weight() -> String {
+    "bold".to_string()
+}
+
 fn default_font_family() -> String {
     "monospace".to_string()
 }
@@ -120,8 +650,56 @@ fn default_scrollback() -> usize {
     10000
 }
 
-fn default_shell() -> String {
-    std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string())
+fn default_shell() -> CommandLine {
+    CommandLine::Program(std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string()))
+}
+
+fn default_term() -> String {
+    "xterm-256color".to_string()
+}
+
+fn default_foreground() -> String {
+    "#ffffff".to_string()
+}
+
+fn default_background() -> String {
+    "#000000".to_string()
+}
+
+fn default_cursor_style() -> String {
+    "underline".to_string()
+}
+
+fn default_bell() -> String {
+    "visual".to_string()
+}
+
+fn default_cursor_blink_ms() -> Option<u64> {
+    Some(500)
+}
+
+fn default_log_format() -> String {
+    "raw".to_string()
+}
+
+fn default_min_pane_size() -> usize {
+    1
+}
+
+fn default_scroll_indicator() -> bool {
+    true
+}
+
+fn default_motion_report_hz() -> u32 {
+    60
+}
+
+fn default_macos_option_is_meta() -> bool {
+    true
+}
+
+fn default_auto_contrast() -> bool {
+    true
 }
 
 fn default_title() -> String {
@@ -140,6 +718,18 @@ fn default_maximize() -> bool {
     true
 }
 
+fn default_close_on_last_exit() -> bool {
+    true
+}
+
+fn default_exit_grace_ms() -> u64 {
+    3000
+}
+
+fn default_bell_min_interval_ms() -> u64 {
+    200
+}
+
 fn default_panes() -> usize {
     4
 }
@@ -156,6 +746,10 @@ fn default_vertical_ratio() -> f32 {
     0.5
 }
 
+fn default_max_panes() -> usize {
+    16
+}
+
 impl Default for RendererConfig {
     fn default() -> Self {
         Self {
@@ -172,8 +766,28 @@ impl Default for TerminalConfig {
             rows: default_rows(),
             font_size: default_font_size(),
             font_family: default_font_family(),
+            fallback_fonts: Vec::new(),
+            font_weight: default_font_weight(),
+            bold_font_family: None,
+            bold_font_weight: default_bold_font_weight(),
             scrollback: default_scrollback(),
             shell: default_shell(),
+            command: None,
+            term: default_term(),
+            working_directory: None,
+            env: BTreeMap::new(),
+            hold_on_exit: false,
+            hold_message_foreground: None,
+            hold_message_background: None,
+            show_whitespace: false,
+            bold_is_bright: false,
+            sequence_hook: None,
+            sequence_hook_prefixes: Vec::new(),
+            foreground: default_foreground(),
+            background: default_background(),
+            cursor_style: default_cursor_style(),
+            cursor_blink_ms: default_cursor_blink_ms(),
+            bell: default_bell(),
         }
     }
 }
@@ -185,6 +799,10 @@ impl Default for WindowConfig {
             width: default_width(),
             height: default_height(),
             maximize: default_maximize(),
+            close_on_last_exit: default_close_on_last_exit(),
+            exit_grace_ms: default_exit_grace_ms(),
+            always_on_top: false,
+            bell_min_interval_ms: default_bell_min_interval_ms(),
         }
     }
 }
@@ -196,6 +814,17 @@ impl Default for StartupConfig {
             layout: default_layout(),
             split_ratio: default_split_ratio(),
             vertical_ratio: default_vertical_ratio(),
+            restore_layout: false,
+        }
+    }
+}
+
+impl Default for CursorConfig {
+    fn default() -> Self {
+        Self {
+            inactive_outline: false,
+            auto_contrast: default_auto_contrast(),
+            solid_block: false,
         }
     }
 }
@@ -207,6 +836,194 @@ impl Default for Config {
             terminal: TerminalConfig::default(),
             window: WindowConfig::default(),
             startup: StartupConfig::default(),
+            cursor: CursorConfig::default(),
+            pane: PaneConfig::default(),
+            scroll: ScrollConfig::default(),
+            selection: SelectionConfig::default(),
+            mouse: MouseConfig::default(),
+            keyboard: KeyboardConfig::default(),
+            theme: ThemeConfig::default(),
+            keybindings: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_panes_within_max_panes() {
+        let mut config: Config = serde_yaml::from_str("startup: {panes: 4, layout: grid}\npane: {max_panes: 2}").unwrap();
+        let err = config.startup_panes().unwrap_err().to_string();
+        assert!(err.contains("startup.panes") && err.contains("pane.max_panes"), "{}", err);
+
+        config.pane.max_panes = 4;
+        assert_eq!(config.startup_panes().unwrap(), 4);
+        config.pane.max_panes = 0;
+        assert_eq!(config.startup_panes().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_hold_message_colors() {
+        let mut config = TerminalConfig::default();
+        assert_eq!(config.hold_message_colors().unwrap(), (None, None));
+
+        config.hold_message_foreground = Some("#000000".to_string());
+        config.hold_message_background = Some("#fabd2f".to_string());
+        assert_eq!(config.hold_message_colors().unwrap(), (Some(Color::BLACK), Some(Color::rgb(0xfa, 0xbd, 0x2f))));
+
+        config.hold_message_background = Some("yellow".to_string());
+        let err = config.hold_message_colors().unwrap_err().to_string();
+        assert!(err.contains("terminal.hold_message_background"), "{}", err);
+    }
+
+    #[test]
+    fn test_terminal_colors() {
+        let mut config = TerminalConfig::default();
+        assert_eq!(config.colors().unwrap(), (Color::WHITE, Color::BLACK));
+
+        config.foreground = "#282828".to_string();
+        config.background = "#fbf1c7".to_string();
+        assert_eq!(config.colors().unwrap(), (Color::rgb(0x28, 0x28, 0x28), Color::rgb(0xfb, 0xf1, 0xc7)));
+
+        for invalid in ["282828", "#28282", "#gggggg", "rgb:28/28/28", "white"] {
+            config.background = invalid.to_string();
+            let err = config.colors().unwrap_err().to_string();
+            assert!(err.contains("terminal.background"), "{}", err);
         }
     }
+
+    #[test]
+    fn test_sequence_hook_needs_prefixes() {
+        let mut config: TerminalConfig = serde_yaml::from_str("sequence_hook: [handler, --stdin]").unwrap();
+        let err = config.sequence_hook_argv().unwrap_err().to_string();
+        assert!(err.contains("terminal.sequence_hook_prefixes"), "{}", err);
+        config.sequence_hook_prefixes = vec![String::new()];
+        assert!(config.sequence_hook_argv().is_err());
+
+        config.sequence_hook_prefixes = vec!["1337;".to_string()];
+        assert_eq!(config.sequence_hook_argv().unwrap().unwrap(), ["handler", "--stdin"]);
+    }
+
+    #[test]
+    fn test_shell_and_command_forms() {
+        let config: TerminalConfig = serde_yaml::from_str("shell: /bin/zsh").unwrap();
+        assert_eq!(config.pane_argv().unwrap(), ["/bin/zsh"]);
+
+        let config: TerminalConfig = serde_yaml::from_str("shell: [bash, -l]").unwrap();
+        assert_eq!(config.pane_argv().unwrap(), ["bash", "-l"]);
+
+        // A command replaces the shell
+        let config: TerminalConfig = serde_yaml::from_str("shell: bash\ncommand: [htop, -d, '5']").unwrap();
+        assert_eq!(config.pane_argv().unwrap(), ["htop", "-d", "5"]);
+
+        let config: TerminalConfig = serde_yaml::from_str("command: []").unwrap();
+        assert!(config.pane_argv().unwrap_err().to_string().contains("terminal.command"));
+    }
+
+    #[test]
+    fn test_cursor_style() {
+        let mut config = TerminalConfig::default();
+        assert_eq!(config.cursor_style().unwrap(), CursorStyle::Underline);
+
+        config.cursor_style = "bar".to_string();
+        assert_eq!(config.cursor_style().unwrap(), CursorStyle::Bar);
+
+        config.cursor_style = "Block".to_string();
+        let err = config.cursor_style().unwrap_err().to_string();
+        assert!(err.contains("terminal.cursor_style"), "{}", err);
+    }
+
+    #[test]
+    fn test_bell_mode() {
+        let mut config = TerminalConfig::default();
+        assert_eq!(config.bell_mode().unwrap(), BellMode::Visual);
+
+        config.bell = "audible".to_string();
+        assert_eq!(config.bell_mode().unwrap(), BellMode::Audible);
+
+        config.bell = "beep".to_string();
+        let err = config.bell_mode().unwrap_err().to_string();
+        assert!(err.contains("terminal.bell"), "{}", err);
+    }
+
+    #[test]
+    fn test_font_weights() {
+        let config = TerminalConfig::default();
+        assert_eq!(config.font_weights().unwrap(), FontWeights { regular: 400, bold: 700 });
+
+        let yaml = "font_weight: medium\nbold_font_family: Iosevka Heavy\nbold_font_weight: 800\n";
+        let config: TerminalConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.font_weights().unwrap(), FontWeights { regular: 500, bold: 800 });
+        assert_eq!(config.bold_font_family.as_deref(), Some("Iosevka Heavy"));
+
+        let config = TerminalConfig { font_weight: "chunky".to_string(), ..TerminalConfig::default() };
+        assert!(config.font_weights().is_err());
+    }
+
+    #[test]
+    fn test_cursor_blink_interval() {
+        let mut config = TerminalConfig::default();
+        assert_eq!(config.cursor_blink_interval(), Some(Duration::from_millis(500)));
+
+        config.cursor_blink_ms = Some(0);
+        assert_eq!(config.cursor_blink_interval(), None);
+        let config: TerminalConfig = serde_yaml::from_str("cursor_blink_ms: null").unwrap();
+        assert_eq!(config.cursor_blink_interval(), None);
+    }
+
+    #[test]
+    fn test_theme_palette() {
+        let yaml = "theme:\n  background: \"#282828\"\n  palette:\n    1: \"#cc241d\"\n    9: \"#fb4934\"\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let palette = config.palette().unwrap();
+
+        assert_eq!(palette.get(1), Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(palette.get(9), Color::rgb(0xfb, 0x49, 0x34));
+        assert_eq!(palette.background, Color::rgb(0x28, 0x28, 0x28));
+
+        // Colors without an override, including the cube and grayscale ranges, are built in
+        assert_eq!(palette.foreground, Color::WHITE);
+        for index in [0, 2, 15, 16, 196, 232, 255] {
+            assert_eq!(palette.get(index), Color::from_ansi_256(index));
+        }
+
+        // Without a theme the palette is the built-in one
+        assert_eq!(Config::default().palette().unwrap(), Palette::default());
+    }
+
+    #[test]
+    fn test_theme_preset() {
+        let config: Config = serde_yaml::from_str("theme:\n  preset: gruvbox-dark\n").unwrap();
+        let palette = config.palette().unwrap();
+        assert_eq!(palette.foreground, Color::rgb(0xeb, 0xdb, 0xb2));
+        assert_eq!(palette.background, Color::rgb(0x28, 0x28, 0x28));
+        assert_eq!(palette.cursor, Color::rgb(0xeb, 0xdb, 0xb2));
+        assert_eq!(palette.get(1), Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(palette.get(12), Color::rgb(0x83, 0xa5, 0x98));
+        // The 256-color cube and grayscale ramp are not part of a preset
+        assert_eq!(palette.get(196), Color::from_ansi_256(196));
+
+        // Explicit colors win over the preset, which still fills in the rest
+        let yaml = "theme:\n  preset: solarized-dark\n  background: \"#000000\"\n  cursor: \"#dc322f\"\n  palette:\n    4: \"#0000ff\"\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let palette = config.palette().unwrap();
+        assert_eq!(palette.background, Color::BLACK);
+        assert_eq!(palette.cursor, Color::rgb(0xdc, 0x32, 0x2f));
+        assert_eq!(palette.get(4), Color::rgb(0, 0, 0xff));
+        assert_eq!(palette.foreground, Color::rgb(0x83, 0x94, 0x96));
+        assert_eq!(palette.get(2), Color::rgb(0x85, 0x99, 0x00));
+
+        let config: Config = serde_yaml::from_str("theme:\n  preset: solarized\n").unwrap();
+        let err = config.palette().unwrap_err().to_string();
+        assert!(err.contains("\"solarized\"") && err.contains("solarized-dark"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_theme_color() {
+        let config: Config = serde_yaml::from_str("theme:\n  palette:\n    3: \"yellow\"\n").unwrap();
+        let err = config.palette().unwrap_err().to_string();
+        assert!(err.contains("theme.palette.3"), "{}", err);
+    }
 }
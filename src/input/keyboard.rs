@@ -1,13 +1,23 @@
 use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
 
+/// Whether the system composes characters with Alt (Option on macOS: Option+b types `∫`)
+const ALT_COMPOSES: bool = cfg!(target_os = "macos");
+
 pub struct KeyboardHandler {
     modifiers: ModifiersState,
+    /// DECCKM - arrow keys send SS3 (`ESC O`) instead of CSI (`ESC [`)
+    application_cursor_keys: bool,
+    /// Option acts as Meta on macOS (`keyboard.macos_option_is_meta`); otherwise the
+    /// character composed with it is typed
+    option_is_meta: bool,
 }
 
 impl KeyboardHandler {
     pub fn new() -> Self {
         Self {
             modifiers: ModifiersState::empty(),
+            application_cursor_keys: false,
+            option_is_meta: true,
         }
     }
 
@@ -15,16 +25,73 @@ impl KeyboardHandler {
         self.modifiers = modifiers;
     }
 
-    pub fn handle_key(&self, key: &PhysicalKey) -> Option<Vec<u8>> {
-        match key {
-            PhysicalKey::Code(code) => self.handle_keycode(*code),
-            _ => None,
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    pub fn set_application_cursor_keys(&mut self, enabled: bool) {
+        self.application_cursor_keys = enabled;
+    }
+
+    pub fn set_option_is_meta(&mut self, enabled: bool) {
+        self.option_is_meta = enabled;
+    }
+
+    /// Alt without Ctrl acts as Meta: the key is sent prefixed with ESC, as readline's
+    /// `M-b` / `M-f` expect (Ctrl+Alt is how Windows reports AltGr). On macOS only
+    /// while Option is Meta.
+    fn meta(&self) -> bool {
+        self.modifiers.alt_key() && !self.modifiers.control_key() && (self.option_is_meta || !ALT_COMPOSES)
+    }
+
+    /// The text a key press stands for: the layout's text, or, where Alt composes
+    /// characters and acts as Meta, the key's own character (`unmodified`, e.g. `b`
+    /// rather than `∫`) so that it gets the ESC prefix
+    pub fn key_text<'a>(&self, text: Option<&'a str>, unmodified: Option<&'a str>) -> Option<&'a str> {
+        if ALT_COMPOSES && self.meta() && unmodified.is_some() {
+            unmodified
+        } else {
+            text
+        }
+    }
+
+    /// Text the key types in the current keyboard layout, unless Ctrl maps it to a control code
+    fn layout_text<'a>(&self, text: Option<&'a str>) -> Option<&'a str> {
+        let altgr = self.modifiers.control_key() && self.modifiers.alt_key();
+        let text = text.filter(|text| !text.is_empty() && !text.chars().any(char::is_control))?;
+        (!self.modifiers.control_key() || altgr).then_some(text)
+    }
+
+    /// Text typed by a key press, if it should be sent as-is.
+    ///
+    /// `text` is what the key types in the current keyboard layout (winit's
+    /// `KeyEvent::text`), which already includes dead-key compositions (`´` then `e`
+    /// gives `é`) and AltGr characters. With Ctrl held the text is ignored so control
+    /// combinations are mapped from the physical key, except Ctrl+Alt, which is how
+    /// Windows reports AltGr. Alt (Meta) combinations are not plain text either.
+    pub fn text_input<'a>(&self, text: Option<&'a str>) -> Option<&'a str> {
+        self.layout_text(text).filter(|_| !self.meta())
+    }
+
+    /// Bytes to send for a key press: the layout's text for printable input, so any
+    /// layout (Dvorak, Colemak, AZERTY...) types what its keys show, and the
+    /// physical key for control combinations and special keys. With Alt held,
+    /// characters and single-byte keys (Backspace, Enter...) get an ESC prefix.
+    pub fn handle_key(&self, key: &PhysicalKey, text: Option<&str>) -> Option<Vec<u8>> {
+        let (bytes, is_text) = match (self.layout_text(text), key) {
+            (Some(text), _) => (text.as_bytes().to_vec(), true),
+            (None, PhysicalKey::Code(code)) => (self.handle_keycode(*code)?, false),
+            _ => return None,
+        };
+
+        if self.meta() && (is_text || bytes.len() == 1) {
+            return Some([&[0x1B], bytes.as_slice()].concat());
         }
+        Some(bytes)
     }
 
     fn handle_keycode(&self, code: KeyCode) -> Option<Vec<u8>> {
         let ctrl = self.modifiers.control_key();
-        let shift = self.modifiers.shift_key();
 
         match code {
             // Control characters
@@ -68,22 +135,22 @@ impl KeyboardHandler {
             KeyCode::Escape => Some(vec![0x1B]),
 
             // Arrow keys
-            KeyCode::ArrowUp => Some(b"\x1b[A".to_vec()),
-            KeyCode::ArrowDown => Some(b"\x1b[B".to_vec()),
-            KeyCode::ArrowRight => Some(b"\x1b[C".to_vec()),
-            KeyCode::ArrowLeft => Some(b"\x1b[D".to_vec()),
+            KeyCode::ArrowUp => Some(self.cursor_key(b'A')),
+            KeyCode::ArrowDown => Some(self.cursor_key(b'B')),
+            KeyCode::ArrowRight => Some(self.cursor_key(b'C')),
+            KeyCode::ArrowLeft => Some(self.cursor_key(b'D')),
 
             // Home/End
-            KeyCode::Home => Some(b"\x1b[H".to_vec()),
-            KeyCode::End => Some(b"\x1b[F".to_vec()),
+            KeyCode::Home => Some(self.modified_key(b'H', || b"\x1b[H".to_vec())),
+            KeyCode::End => Some(self.modified_key(b'F', || b"\x1b[F".to_vec())),
 
             // Page Up/Down
-            KeyCode::PageUp => Some(b"\x1b[5~".to_vec()),
-            KeyCode::PageDown => Some(b"\x1b[6~".to_vec()),
+            KeyCode::PageUp => Some(self.tilde_key(5)),
+            KeyCode::PageDown => Some(self.tilde_key(6)),
 
             // Insert/Delete
-            KeyCode::Insert => Some(b"\x1b[2~".to_vec()),
-            KeyCode::Delete => Some(b"\x1b[3~".to_vec()),
+            KeyCode::Insert => Some(self.tilde_key(2)),
+            KeyCode::Delete => Some(self.tilde_key(3)),
 
             // Function keys
             KeyCode::F1 => Some(b"\x1bOP".to_vec()),
@@ -108,83 +175,41 @@ impl KeyboardHandler {
                 }
             }
 
-            // Alphanumeric keys
-            KeyCode::Digit0 if !ctrl && !shift => Some(vec![b'0']),
-            KeyCode::Digit1 if !ctrl && !shift => Some(vec![b'1']),
-            KeyCode::Digit2 if !ctrl && !shift => Some(vec![b'2']),
-            KeyCode::Digit3 if !ctrl && !shift => Some(vec![b'3']),
-            KeyCode::Digit4 if !ctrl && !shift => Some(vec![b'4']),
-            KeyCode::Digit5 if !ctrl && !shift => Some(vec![b'5']),
-            KeyCode::Digit6 if !ctrl && !shift => Some(vec![b'6']),
-            KeyCode::Digit7 if !ctrl && !shift => Some(vec![b'7']),
-            KeyCode::Digit8 if !ctrl && !shift => Some(vec![b'8']),
-            KeyCode::Digit9 if !ctrl && !shift => Some(vec![b'9']),
-
-            KeyCode::Digit0 if !ctrl && shift => Some(vec![b')']),
-            KeyCode::Digit1 if !ctrl && shift => Some(vec![b'!']),
-            KeyCode::Digit2 if !ctrl && shift => Some(vec![b'@']),
-            KeyCode::Digit3 if !ctrl && shift => Some(vec![b'#']),
-            KeyCode::Digit4 if !ctrl && shift => Some(vec![b'$']),
-            KeyCode::Digit5 if !ctrl && shift => Some(vec![b'%']),
-            KeyCode::Digit6 if !ctrl && shift => Some(vec![b'^']),
-            KeyCode::Digit7 if !ctrl && shift => Some(vec![b'&']),
-            KeyCode::Digit8 if !ctrl && shift => Some(vec![b'*']),
-            KeyCode::Digit9 if !ctrl && shift => Some(vec![b'(']),
-
-            KeyCode::KeyA if !ctrl => Some(vec![if shift { b'A' } else { b'a' }]),
-            KeyCode::KeyB if !ctrl => Some(vec![if shift { b'B' } else { b'b' }]),
-            KeyCode::KeyC if !ctrl => Some(vec![if shift { b'C' } else { b'c' }]),
-            KeyCode::KeyD if !ctrl => Some(vec![if shift { b'D' } else { b'd' }]),
-            KeyCode::KeyE if !ctrl => Some(vec![if shift { b'E' } else { b'e' }]),
-            KeyCode::KeyF if !ctrl => Some(vec![if shift { b'F' } else { b'f' }]),
-            KeyCode::KeyG if !ctrl => Some(vec![if shift { b'G' } else { b'g' }]),
-            KeyCode::KeyH if !ctrl => Some(vec![if shift { b'H' } else { b'h' }]),
-            KeyCode::KeyI if !ctrl => Some(vec![if shift { b'I' } else { b'i' }]),
-            KeyCode::KeyJ if !ctrl => Some(vec![if shift { b'J' } else { b'j' }]),
-            KeyCode::KeyK if !ctrl => Some(vec![if shift { b'K' } else { b'k' }]),
-            KeyCode::KeyL if !ctrl => Some(vec![if shift { b'L' } else { b'l' }]),
-            KeyCode::KeyM if !ctrl => Some(vec![if shift { b'M' } else { b'm' }]),
-            KeyCode::KeyN if !ctrl => Some(vec![if shift { b'N' } else { b'n' }]),
-            KeyCode::KeyO if !ctrl => Some(vec![if shift { b'O' } else { b'o' }]),
-            KeyCode::KeyP if !ctrl => Some(vec![if shift { b'P' } else { b'p' }]),
-            KeyCode::KeyQ if !ctrl => Some(vec![if shift { b'Q' } else { b'q' }]),
-            KeyCode::KeyR if !ctrl => Some(vec![if shift { b'R' } else { b'r' }]),
-            KeyCode::KeyS if !ctrl => Some(vec![if shift { b'S' } else { b's' }]),
-            KeyCode::KeyT if !ctrl => Some(vec![if shift { b'T' } else { b't' }]),
-            KeyCode::KeyU if !ctrl => Some(vec![if shift { b'U' } else { b'u' }]),
-            KeyCode::KeyV if !ctrl => Some(vec![if shift { b'V' } else { b'v' }]),
-            KeyCode::KeyW if !ctrl => Some(vec![if shift { b'W' } else { b'w' }]),
-            KeyCode::KeyX if !ctrl => Some(vec![if shift { b'X' } else { b'x' }]),
-            KeyCode::KeyY if !ctrl => Some(vec![if shift { b'Y' } else { b'y' }]),
-            KeyCode::KeyZ if !ctrl => Some(vec![if shift { b'Z' } else { b'z' }]),
-
-            // Punctuation
-            KeyCode::Minus if !shift => Some(vec![b'-']),
-            KeyCode::Minus if shift => Some(vec![b'_']),
-            KeyCode::Equal if !shift => Some(vec![b'=']),
-            KeyCode::Equal if shift => Some(vec![b'+']),
-            KeyCode::BracketLeft if !shift => Some(vec![b'[']),
-            KeyCode::BracketLeft if shift => Some(vec![b'{']),
-            KeyCode::BracketRight if !shift => Some(vec![b']']),
-            KeyCode::BracketRight if shift => Some(vec![b'}']),
-            KeyCode::Backslash if !shift => Some(vec![b'\\']),
-            KeyCode::Backslash if shift => Some(vec![b'|']),
-            KeyCode::Semicolon if !shift => Some(vec![b';']),
-            KeyCode::Semicolon if shift => Some(vec![b':']),
-            KeyCode::Quote if !shift => Some(vec![b'\'']),
-            KeyCode::Quote if shift => Some(vec![b'"']),
-            KeyCode::Comma if !shift => Some(vec![b',']),
-            KeyCode::Comma if shift => Some(vec![b'<']),
-            KeyCode::Period if !shift => Some(vec![b'.']),
-            KeyCode::Period if shift => Some(vec![b'>']),
-            KeyCode::Slash if !shift => Some(vec![b'/']),
-            KeyCode::Slash if shift => Some(vec![b'?']),
-            KeyCode::Backquote if !shift => Some(vec![b'`']),
-            KeyCode::Backquote if shift => Some(vec![b'~']),
-
             _ => None,
         }
     }
+
+    /// xterm modifier parameter: 1 + Shift (1) + Alt (2) + Ctrl (4), or None without modifiers
+    fn modifier_param(&self) -> Option<u8> {
+        let param = 1
+            + u8::from(self.modifiers.shift_key())
+            + 2 * u8::from(self.modifiers.alt_key())
+            + 4 * u8::from(self.modifiers.control_key());
+        (param > 1).then_some(param)
+    }
+
+    /// `ESC [ 1 ; <modifiers> <final>` when a modifier is held, otherwise the bare sequence
+    fn modified_key(&self, final_byte: u8, bare: impl FnOnce() -> Vec<u8>) -> Vec<u8> {
+        match self.modifier_param() {
+            Some(param) => format!("\x1b[1;{}{}", param, final_byte as char).into_bytes(),
+            None => bare(),
+        }
+    }
+
+    /// `ESC [ <number> ~` keys, with `; <modifiers>` when a modifier is held
+    fn tilde_key(&self, number: u8) -> Vec<u8> {
+        match self.modifier_param() {
+            Some(param) => format!("\x1b[{};{}~", number, param).into_bytes(),
+            None => format!("\x1b[{}~", number).into_bytes(),
+        }
+    }
+
+    /// Arrow key sequence for the current cursor key mode. With modifiers it is always
+    /// the CSI form, as in xterm.
+    fn cursor_key(&self, final_byte: u8) -> Vec<u8> {
+        let intro = if self.application_cursor_keys { b'O' } else { b'[' };
+        self.modified_key(final_byte, || vec![0x1B, intro, final_byte])
+    }
 }
 
 impl Default for KeyboardHandler {
@@ -192,3 +217,140 @@ impl Default for KeyboardHandler {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARROWS: [(KeyCode, u8); 4] = [
+        (KeyCode::ArrowUp, b'A'),
+        (KeyCode::ArrowDown, b'B'),
+        (KeyCode::ArrowRight, b'C'),
+        (KeyCode::ArrowLeft, b'D'),
+    ];
+
+    #[test]
+    fn test_arrow_keys_normal_mode() {
+        let keyboard = KeyboardHandler::new();
+        for (code, final_byte) in ARROWS {
+            assert_eq!(keyboard.handle_key(&PhysicalKey::Code(code), None), Some(vec![0x1B, b'[', final_byte]));
+        }
+    }
+
+    #[test]
+    fn test_arrow_keys_application_mode() {
+        let mut keyboard = KeyboardHandler::new();
+        keyboard.set_application_cursor_keys(true);
+        for (code, final_byte) in ARROWS {
+            assert_eq!(keyboard.handle_key(&PhysicalKey::Code(code), None), Some(vec![0x1B, b'O', final_byte]));
+        }
+
+        keyboard.set_application_cursor_keys(false);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::ArrowUp), None), Some(b"\x1b[A".to_vec()));
+    }
+
+    #[test]
+    fn test_modified_navigation_keys() {
+        let shift = ModifiersState::SHIFT;
+        let alt = ModifiersState::ALT;
+        let ctrl = ModifiersState::CONTROL;
+        let cases: [(ModifiersState, KeyCode, &[u8]); 12] = [
+            (ModifiersState::empty(), KeyCode::ArrowRight, b"\x1b[C"),
+            (ModifiersState::empty(), KeyCode::Home, b"\x1b[H"),
+            (ModifiersState::empty(), KeyCode::Delete, b"\x1b[3~"),
+            (ctrl, KeyCode::ArrowRight, b"\x1b[1;5C"),
+            (shift, KeyCode::ArrowUp, b"\x1b[1;2A"),
+            (alt, KeyCode::ArrowLeft, b"\x1b[1;3D"),
+            (ctrl | shift, KeyCode::ArrowDown, b"\x1b[1;6B"),
+            (ctrl | alt | shift, KeyCode::End, b"\x1b[1;8F"),
+            (ctrl, KeyCode::Home, b"\x1b[1;5H"),
+            (ctrl, KeyCode::PageUp, b"\x1b[5;5~"),
+            (alt, KeyCode::Delete, b"\x1b[3;3~"),
+            (shift, KeyCode::Insert, b"\x1b[2;2~"),
+        ];
+
+        let mut keyboard = KeyboardHandler::new();
+        for (modifiers, code, expected) in cases {
+            keyboard.update_modifiers(modifiers);
+            assert_eq!(keyboard.handle_key(&PhysicalKey::Code(code), None).as_deref(), Some(expected), "{:?} {:?}", modifiers, code);
+        }
+
+        // Application cursor mode only changes the unmodified arrows
+        keyboard.set_application_cursor_keys(true);
+        keyboard.update_modifiers(ctrl);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::ArrowUp), None), Some(b"\x1b[1;5A".to_vec()));
+        keyboard.update_modifiers(ModifiersState::empty());
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::ArrowUp), None), Some(b"\x1bOA".to_vec()));
+    }
+
+    #[test]
+    fn test_text_follows_keyboard_layout() {
+        let mut keyboard = KeyboardHandler::new();
+        // The physical Q key types `'` on Dvorak and `a` on AZERTY
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyQ), Some("'")), Some(b"'".to_vec()));
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyQ), Some("A")), Some(b"A".to_vec()));
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::Semicolon), Some("ö")), Some("ö".as_bytes().to_vec()));
+        // A dead key types nothing until it is combined
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::Quote), None), None);
+
+        // Keys whose text is a control character keep their terminal encoding
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::Backspace), Some("\x08")), Some(vec![0x7F]));
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::Enter), Some("\r")), Some(vec![b'\r']));
+
+        // Control combinations use the physical key
+        keyboard.update_modifiers(ModifiersState::CONTROL);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyC), Some("j")), Some(vec![0x03]));
+    }
+
+    #[test]
+    fn test_alt_sends_escape_prefix() {
+        let mut keyboard = KeyboardHandler::new();
+        let key_a = PhysicalKey::Code(KeyCode::KeyA);
+        assert_eq!(keyboard.handle_key(&key_a, Some("a")), Some(vec![b'a']));
+
+        keyboard.update_modifiers(ModifiersState::ALT);
+        assert_eq!(keyboard.text_input(Some("a")), None);
+        assert_eq!(keyboard.handle_key(&key_a, Some("a")), Some(vec![0x1B, b'a']));
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyB), Some("b")), Some(b"\x1bb".to_vec()));
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::Backspace), None), Some(vec![0x1B, 0x7F]));
+        // Escape sequences carry Alt in their modifier parameter instead of a prefix
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::ArrowUp), None), Some(b"\x1b[1;3A".to_vec()));
+
+        keyboard.update_modifiers(ModifiersState::ALT | ModifiersState::SHIFT);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyF), Some("F")), Some(b"\x1bF".to_vec()));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_macos_option_is_meta() {
+        let mut keyboard = KeyboardHandler::new();
+        let key_b = PhysicalKey::Code(KeyCode::KeyB);
+        keyboard.update_modifiers(ModifiersState::ALT);
+
+        // Option+b composes `∫`; as Meta the key's own character is sent after ESC
+        let text = keyboard.key_text(Some("∫"), Some("b"));
+        assert_eq!(keyboard.text_input(text), None);
+        assert_eq!(keyboard.handle_key(&key_b, text), Some(b"\x1bb".to_vec()));
+
+        // Otherwise the composed character is typed
+        keyboard.set_option_is_meta(false);
+        let text = keyboard.key_text(Some("∫"), Some("b"));
+        assert_eq!(keyboard.text_input(text), Some("∫"));
+        assert_eq!(keyboard.handle_key(&key_b, text), Some("∫".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_dead_keys_and_altgr() {
+        let mut keyboard = KeyboardHandler::new();
+        // `´` (dead) then `e`: the dead key types nothing, the next key the composed character
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::Equal), None), None);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyE), Some("é")), Some(vec![0xC3, 0xA9]));
+
+        // AltGr+e, reported as Ctrl+Alt on Windows
+        keyboard.update_modifiers(ModifiersState::CONTROL | ModifiersState::ALT);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyE), Some("€")), Some(vec![0xE2, 0x82, 0xAC]));
+        // Ctrl+Alt without a character still sends the control code
+        assert_eq!(keyboard.text_input(Some("\x05")), None);
+        assert_eq!(keyboard.handle_key(&PhysicalKey::Code(KeyCode::KeyE), None), Some(vec![0x05]));
+    }
+}
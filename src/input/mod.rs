@@ -1,4 +1,5 @@
 pub mod keyboard;
+pub mod mouse;
 pub mod shortcuts;
 
 pub use keyboard::KeyboardHandler;
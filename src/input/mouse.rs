@@ -0,0 +1,403 @@
+use crate::pane::{PaneId, Rect};
+use crate::terminal::grid::Cell;
+use std::time::{Duration, Instant};
+use winit::event::{MouseButton, MouseScrollDelta};
+use winit::keyboard::ModifiersState;
+
+/// Mouse tracking mode requested by the application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseMode {
+    #[default]
+    Off,
+    /// `?1000` - report button presses and releases
+    Normal,
+    /// `?1002` - also report motion while a button is held
+    ButtonEvent,
+    /// `?1003` - also report motion with no button held
+    AnyEvent,
+}
+
+/// Mouse report encoding selected by the application
+///
+/// Coordinate limits (1-based):
+/// - `Default` (X10/normal, `CSI M Cb Cx Cy`): each value is a single byte offset by 32,
+///   so coordinates above 223 cannot be represented and are clamped to 223.
+/// - `Utf8` (`?1005`): values are UTF-8 encoded code points offset by 32,
+///   so coordinates are clamped to 2015 (the largest two-byte code point minus 32).
+/// - `Sgr` (`?1006`, `CSI < Cb ; Cx ; Cy M/m`): decimal parameters with no practical limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseEncoding {
+    #[default]
+    Default,
+    Utf8,
+    Sgr,
+}
+
+/// Largest coordinate representable by the default encoding (255 - 32)
+const DEFAULT_MAX_COORD: usize = 223;
+/// Largest coordinate representable by the UTF-8 encoding (0x7FF - 32)
+const UTF8_MAX_COORD: usize = 2015;
+/// Button code used by the non-SGR encodings for any release
+const RELEASE_BUTTON: u8 = 3;
+
+/// Flag added to the button code of motion reports
+pub const MOTION_FLAG: u8 = 32;
+
+/// Longest pause between the clicks of a double- or triple-click
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Counts quick successive left clicks on the same cell: 1 for a single click,
+/// 2 for a double-click, 3 for a triple-click. The next click starts over at 1.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClickCounter {
+    /// Time and cell of the previous click
+    last: Option<(Instant, PaneId, (usize, usize))>,
+    count: u8,
+}
+
+impl ClickCounter {
+    pub fn click(&mut self, pane_id: PaneId, cell: (usize, usize), now: Instant) -> u8 {
+        let repeated = self.last.is_some_and(|(at, last_pane, last_cell)| {
+            last_pane == pane_id && last_cell == cell && now.duration_since(at) <= MULTI_CLICK_INTERVAL
+        });
+        self.count = if repeated && self.count < 3 { self.count + 1 } else { 1 };
+        self.last = Some((now, pane_id, cell));
+        self.count
+    }
+}
+
+/// Lines scrolled per wheel notch
+const WHEEL_SCROLL_LINES: f32 = 3.0;
+
+/// Lines to scroll for a wheel event; positive scrolls back (up) into history
+pub fn wheel_lines(delta: MouseScrollDelta, cell_height: f32) -> i32 {
+    let lines = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y * WHEEL_SCROLL_LINES,
+        MouseScrollDelta::PixelDelta(position) if cell_height > 0.0 => position.y as f32 / cell_height,
+        MouseScrollDelta::PixelDelta(_) => 0.0,
+    };
+    lines.round() as i32
+}
+
+/// Arrow keys sent for a wheel event on the alternate screen, which has no scrollback:
+/// Up for each line scrolled back, Down for each line scrolled forward
+pub fn alternate_scroll_keys(lines: i32, application_cursor_keys: bool) -> Vec<u8> {
+    let intro = if application_cursor_keys { b'O' } else { b'[' };
+    let final_byte = if lines > 0 { b'A' } else { b'B' };
+    [0x1B, intro, final_byte].repeat(lines.unsigned_abs() as usize)
+}
+
+/// xterm button code for a press, with the Alt (8) and Ctrl (16) modifier bits.
+/// Shift is not encoded because Shift-click bypasses mouse reporting.
+pub fn button_code(button: MouseButton, modifiers: ModifiersState) -> Option<u8> {
+    let mut code = match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+        _ => return None,
+    };
+    if modifiers.alt_key() {
+        code |= 8;
+    }
+    if modifiers.control_key() {
+        code |= 16;
+    }
+    Some(code)
+}
+
+/// xterm button code for motion with no button held (any-event tracking), with the
+/// same modifier bits as [`button_code`]
+pub fn hover_code(modifiers: ModifiersState) -> u8 {
+    let mut code = RELEASE_BUTTON | MOTION_FLAG;
+    if modifiers.alt_key() {
+        code |= 8;
+    }
+    if modifiers.control_key() {
+        code |= 16;
+    }
+    code
+}
+
+/// Limits motion reports to `mouse.motion_report_hz`. A report that comes too soon
+/// after the last one is held back, replacing any held before it, and sent once the
+/// interval has passed, so the application still learns the final position.
+/// Presses and releases are never throttled.
+#[derive(Debug, Clone, Default)]
+pub struct MotionThrottle {
+    /// Shortest time between reports; None sends every report
+    interval: Option<Duration>,
+    last_sent: Option<Instant>,
+    pending: Option<(PaneId, Vec<u8>)>,
+}
+
+impl MotionThrottle {
+    /// At most `max_hz` reports a second; 0 for no limit
+    pub fn new(max_hz: u32) -> Self {
+        Self {
+            interval: (max_hz > 0).then(|| Duration::from_secs(1) / max_hz),
+            ..Self::default()
+        }
+    }
+
+    /// When the held-back report can be sent
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.as_ref()?;
+        Some(self.last_sent? + self.interval?)
+    }
+
+    /// The report to send now for a motion to `report`, or None if it is held back
+    pub fn offer(&mut self, pane_id: PaneId, report: Vec<u8>, now: Instant) -> Option<(PaneId, Vec<u8>)> {
+        self.pending = Some((pane_id, report));
+        self.take_due(now)
+    }
+
+    /// The held-back report, once the interval since the last report has passed
+    pub fn take_due(&mut self, now: Instant) -> Option<(PaneId, Vec<u8>)> {
+        let due = match (self.last_sent, self.interval) {
+            (Some(last), Some(interval)) => now.saturating_duration_since(last) >= interval,
+            _ => true,
+        };
+        if !due {
+            return None;
+        }
+        let report = self.pending.take()?;
+        self.last_sent = Some(now);
+        Some(report)
+    }
+
+    /// Drop the held-back report (a press or release reports the position itself)
+    pub fn discard(&mut self) {
+        self.pending = None;
+    }
+}
+
+/// Convert a pixel position to a 0-based cell inside `rect`, clamped to the pane grid.
+/// Positions left of/above the pane (or NaN) map to the first column/row.
+pub fn pixel_to_cell(
+    x: f64,
+    y: f64,
+    rect: Rect,
+    cell_width: f32,
+    cell_height: f32,
+    cols: usize,
+    rows: usize,
+) -> (usize, usize) {
+    let to_index = |pos: f64, origin: u32, size: f32, count: usize| -> usize {
+        if size <= 0.0 || count == 0 {
+            return 0;
+        }
+        let cell = ((pos - origin as f64) / size as f64).floor();
+        // `as usize` saturates negatives and NaN to 0
+        (cell as usize).min(count - 1)
+    };
+
+    (
+        to_index(x, rect.x, cell_width, cols),
+        to_index(y, rect.y, cell_height, rows),
+    )
+}
+
+/// Column where the glyph covering `col` starts: the second half of a wide character
+/// maps to its first column, so clicks and selections take the whole glyph
+pub fn glyph_start(row: &[Cell], col: usize) -> usize {
+    match row.get(col) {
+        Some(cell) if cell.is_continuation() && col > 0 => col - 1,
+        _ => col,
+    }
+}
+
+/// Encode a mouse report for a 0-based cell.
+///
+/// `button` is the xterm button code (0-2 for left/middle/right, plus 32 for motion and
+/// 64 for the wheel, plus modifier bits). Releases are reported as button 3 by the
+/// non-SGR encodings, which cannot tell which button was released.
+pub fn encode_report(button: u8, col: usize, row: usize, pressed: bool, encoding: MouseEncoding) -> Vec<u8> {
+    // Reports use 1-based coordinates
+    let x = col.saturating_add(1);
+    let y = row.saturating_add(1);
+
+    match encoding {
+        MouseEncoding::Sgr => {
+            let action = if pressed { 'M' } else { 'm' };
+            format!("\x1b[<{};{};{}{}", button, x, y, action).into_bytes()
+        }
+        MouseEncoding::Default => {
+            let cb = if pressed { button } else { RELEASE_BUTTON | (button & !0b11) };
+            vec![
+                0x1b,
+                b'[',
+                b'M',
+                cb.wrapping_add(32),
+                (x.min(DEFAULT_MAX_COORD) + 32) as u8,
+                (y.min(DEFAULT_MAX_COORD) + 32) as u8,
+            ]
+        }
+        MouseEncoding::Utf8 => {
+            let cb = if pressed { button } else { RELEASE_BUTTON | (button & !0b11) };
+            let mut report = vec![0x1b, b'[', b'M'];
+            for value in [cb as usize, x.min(UTF8_MAX_COORD), y.min(UTF8_MAX_COORD)] {
+                // Values stay below 0x800, so they are always valid chars
+                let ch = char::from_u32((value + 32) as u32).unwrap_or(' ');
+                let mut buf = [0u8; 4];
+                report.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            }
+            report
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_to_cell_clamps_to_grid() {
+        let rect = Rect::new(100, 50, 800, 480);
+        assert_eq!(pixel_to_cell(100.0, 50.0, rect, 10.0, 20.0, 80, 24), (0, 0));
+        assert_eq!(pixel_to_cell(895.0, 525.0, rect, 10.0, 20.0, 80, 24), (79, 23));
+        // Outside the pane on either side
+        assert_eq!(pixel_to_cell(10.0, 0.0, rect, 10.0, 20.0, 80, 24), (0, 0));
+        assert_eq!(pixel_to_cell(5000.0, 5000.0, rect, 10.0, 20.0, 80, 24), (79, 23));
+        // Degenerate input does not panic
+        assert_eq!(pixel_to_cell(f64::NAN, 60.0, rect, 0.0, 20.0, 0, 24), (0, 0));
+    }
+
+    #[test]
+    fn test_alternate_scroll_keys() {
+        assert_eq!(alternate_scroll_keys(2, false), b"\x1b[A\x1b[A");
+        assert_eq!(alternate_scroll_keys(-1, false), b"\x1b[B");
+        assert_eq!(alternate_scroll_keys(-3, true), b"\x1bOB\x1bOB\x1bOB");
+        assert!(alternate_scroll_keys(0, true).is_empty());
+    }
+
+    #[test]
+    fn test_click_on_wide_character() {
+        // "a漢b": the wide character takes columns 1 and 2
+        let wide = Cell { width: 2, ..Cell::new('漢') };
+        let row = [Cell::new('a'), wide, Cell::continuation(&wide), Cell::new('b')];
+        let rect = Rect::new(0, 0, 40, 20);
+
+        // Left and right half of the wide character both map to its first column
+        for x in [12.0, 25.0] {
+            let (col, _) = pixel_to_cell(x, 5.0, rect, 10.0, 20.0, 4, 1);
+            assert_eq!(glyph_start(&row, col), 1);
+        }
+        assert_eq!(glyph_start(&row, 0), 0);
+        assert_eq!(glyph_start(&row, 3), 3);
+        // Past the end of a short scrollback row
+        assert_eq!(glyph_start(&row, 10), 10);
+    }
+
+    #[test]
+    fn test_encode_first_column() {
+        assert_eq!(encode_report(0, 0, 0, true, MouseEncoding::Default), b"\x1b[M !!");
+        assert_eq!(encode_report(0, 0, 0, true, MouseEncoding::Utf8), b"\x1b[M !!");
+        assert_eq!(encode_report(0, 0, 0, true, MouseEncoding::Sgr), b"\x1b[<0;1;1M");
+    }
+
+    #[test]
+    fn test_encode_last_column() {
+        // 80x24 grid, bottom-right cell
+        assert_eq!(encode_report(0, 79, 23, true, MouseEncoding::Default), b"\x1b[M p8");
+        assert_eq!(encode_report(0, 79, 23, true, MouseEncoding::Utf8), b"\x1b[M p8");
+        assert_eq!(encode_report(0, 79, 23, true, MouseEncoding::Sgr), b"\x1b[<0;80;24M");
+    }
+
+    #[test]
+    fn test_encode_beyond_column_223() {
+        // Default encoding clamps to 223 (byte 255) instead of overflowing
+        assert_eq!(encode_report(0, 222, 0, true, MouseEncoding::Default), b"\x1b[M \xff!");
+        assert_eq!(encode_report(0, 300, 0, true, MouseEncoding::Default), b"\x1b[M \xff!");
+        assert_eq!(encode_report(0, usize::MAX, usize::MAX, true, MouseEncoding::Default), b"\x1b[M \xff\xff");
+
+        // UTF-8 encoding uses two-byte sequences past 95
+        let mut expected = b"\x1b[M ".to_vec();
+        expected.extend_from_slice("\u{14c}".as_bytes()); // 300 + 32
+        expected.push(b'!');
+        assert_eq!(encode_report(0, 299, 0, true, MouseEncoding::Utf8), expected);
+        let mut expected = b"\x1b[M ".to_vec();
+        expected.extend_from_slice("\u{7ff}\u{7ff}".as_bytes());
+        assert_eq!(encode_report(0, usize::MAX, 5000, true, MouseEncoding::Utf8), expected);
+
+        // SGR has no limit
+        assert_eq!(encode_report(0, 299, 0, true, MouseEncoding::Sgr), b"\x1b[<0;300;1M");
+    }
+
+    #[test]
+    fn test_button_code_modifiers() {
+        assert_eq!(button_code(MouseButton::Left, ModifiersState::empty()), Some(0));
+        assert_eq!(button_code(MouseButton::Right, ModifiersState::CONTROL), Some(18));
+        assert_eq!(button_code(MouseButton::Middle, ModifiersState::ALT | ModifiersState::CONTROL), Some(25));
+        assert_eq!(button_code(MouseButton::Back, ModifiersState::empty()), None);
+    }
+
+    #[test]
+    fn test_wheel_lines() {
+        use winit::dpi::PhysicalPosition;
+
+        assert_eq!(wheel_lines(MouseScrollDelta::LineDelta(0.0, 1.0), 20.0), 3);
+        assert_eq!(wheel_lines(MouseScrollDelta::LineDelta(0.0, -2.0), 20.0), -6);
+        assert_eq!(wheel_lines(MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 60.0)), 20.0), 3);
+        assert_eq!(wheel_lines(MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 60.0)), 0.0), 0);
+    }
+
+    #[test]
+    fn test_click_counter() {
+        let start = Instant::now();
+        let quick = |n: u32| start + Duration::from_millis(100) * n;
+        let mut clicks = ClickCounter::default();
+
+        assert_eq!(clicks.click(0, (3, 1), start), 1);
+        assert_eq!(clicks.click(0, (3, 1), quick(1)), 2);
+        assert_eq!(clicks.click(0, (3, 1), quick(2)), 3);
+        assert_eq!(clicks.click(0, (3, 1), quick(3)), 1);
+
+        // A slow click, another cell or another pane starts over
+        assert_eq!(clicks.click(0, (3, 1), quick(3) + MULTI_CLICK_INTERVAL * 2), 1);
+        assert_eq!(clicks.click(0, (4, 1), quick(8)), 1);
+        assert_eq!(clicks.click(1, (4, 1), quick(9)), 1);
+        assert_eq!(clicks.click(1, (4, 1), quick(10)), 2);
+    }
+
+    #[test]
+    fn test_encode_release() {
+        assert_eq!(encode_report(2, 0, 0, false, MouseEncoding::Default), b"\x1b[M#!!");
+        assert_eq!(encode_report(2, 0, 0, false, MouseEncoding::Sgr), b"\x1b[<2;1;1m");
+    }
+
+    #[test]
+    fn test_hover_code() {
+        assert_eq!(encode_report(hover_code(ModifiersState::empty()), 4, 2, true, MouseEncoding::Sgr), b"\x1b[<35;5;3M");
+        assert_eq!(hover_code(ModifiersState::CONTROL), 35 | 16);
+    }
+
+    #[test]
+    fn test_motion_throttle() {
+        let start = Instant::now();
+        let ms = |n: u64| start + Duration::from_millis(n);
+        // 20 reports a second: one every 50ms
+        let mut throttle = MotionThrottle::new(20);
+
+        // Motion every 5ms for 200ms gives a report at 0, 50, 100 and 150ms
+        let sent: Vec<u64> = (0..40)
+            .filter(|&n| throttle.offer(0, vec![n as u8], ms(n * 5)).is_some())
+            .map(|n| n * 5)
+            .collect();
+        assert_eq!(sent, [0, 50, 100, 150]);
+
+        // The last position is held back until the interval has passed
+        assert_eq!(throttle.next_due(), Some(ms(200)));
+        assert_eq!(throttle.take_due(ms(199)), None);
+        assert_eq!(throttle.take_due(ms(200)), Some((0, vec![39])));
+        assert_eq!(throttle.next_due(), None);
+
+        // A press or release makes the held-back report obsolete
+        assert_eq!(throttle.offer(1, vec![1], ms(210)), None);
+        throttle.discard();
+        assert_eq!(throttle.take_due(ms(300)), None);
+
+        // 0 sends everything
+        let mut unlimited = MotionThrottle::new(0);
+        assert!((0..10).all(|n| unlimited.offer(0, vec![n], start).is_some()));
+    }
+}
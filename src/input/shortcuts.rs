@@ -1,7 +1,9 @@
+use crate::utils::{Result, TerbulatorError};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use winit::keyboard::{KeyCode, ModifiersState};
 
 /// ショートカットアクション
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ShortcutAction {
     /// ペイン分割（水平）
     SplitHorizontal,
@@ -9,6 +11,8 @@ pub enum ShortcutAction {
     SplitVertical,
     /// ペイン閉じる
     ClosePane,
+    /// 全ペインの大きさを均等にする
+    BalancePanes,
     /// 左のペインに移動
     MoveFocusLeft,
     /// 下のペインに移動
@@ -21,6 +25,14 @@ pub enum ShortcutAction {
     MoveFocusNext,
     /// 前のペインに移動
     MoveFocusPrev,
+    /// 左のペインと入れ替える
+    SwapPaneLeft,
+    /// 下のペインと入れ替える
+    SwapPaneDown,
+    /// 上のペインと入れ替える
+    SwapPaneUp,
+    /// 右のペインと入れ替える
+    SwapPaneRight,
     /// Broadcastモード切り替え
     ToggleBroadcast,
     /// Copy（選択範囲をクリップボードにコピー）
@@ -33,14 +45,203 @@ pub enum ShortcutAction {
     DecreaseFontSize,
     /// マークモード切り替え
     ToggleMarkMode,
+    /// 現在のカラーパレットを設定ファイルのthemeに保存
+    SaveTheme,
+    /// アクティブなペインの出力ログを切り替え
+    TogglePaneLog,
+    /// アクティブなペインのモードと文字属性を初期状態に戻す（画面の内容は残す）
+    SoftReset,
+    /// スクロールバックを1画面分さかのぼる
+    ScrollPageUp,
+    /// スクロールバックを1画面分進める
+    ScrollPageDown,
+    /// スクロールバックの先頭に移動
+    ScrollToTop,
+    /// 最新の画面に戻る
+    ScrollToBottom,
+    /// ウィンドウを常に最前面に表示するか切り替え
+    ToggleAlwaysOnTop,
+    /// ウィンドウの全画面表示を切り替え
+    ToggleFullscreen,
+    /// 新しいタブを開く
+    NewTab,
+    /// アクティブなタブを閉じる
+    CloseTab,
+    /// 次のタブに移動
+    NextTab,
+    /// 前のタブに移動
+    PrevTab,
+}
+
+impl ShortcutAction {
+    /// 設定ファイルで指定できる全アクション
+    pub const ALL: [ShortcutAction; 33] = [
+        ShortcutAction::SplitHorizontal,
+        ShortcutAction::SplitVertical,
+        ShortcutAction::ClosePane,
+        ShortcutAction::BalancePanes,
+        ShortcutAction::MoveFocusLeft,
+        ShortcutAction::MoveFocusDown,
+        ShortcutAction::MoveFocusUp,
+        ShortcutAction::MoveFocusRight,
+        ShortcutAction::MoveFocusNext,
+        ShortcutAction::MoveFocusPrev,
+        ShortcutAction::SwapPaneLeft,
+        ShortcutAction::SwapPaneDown,
+        ShortcutAction::SwapPaneUp,
+        ShortcutAction::SwapPaneRight,
+        ShortcutAction::ToggleBroadcast,
+        ShortcutAction::Copy,
+        ShortcutAction::Paste,
+        ShortcutAction::IncreaseFontSize,
+        ShortcutAction::DecreaseFontSize,
+        ShortcutAction::ToggleMarkMode,
+        ShortcutAction::SaveTheme,
+        ShortcutAction::TogglePaneLog,
+        ShortcutAction::SoftReset,
+        ShortcutAction::ScrollPageUp,
+        ShortcutAction::ScrollPageDown,
+        ShortcutAction::ScrollToTop,
+        ShortcutAction::ScrollToBottom,
+        ShortcutAction::ToggleAlwaysOnTop,
+        ShortcutAction::ToggleFullscreen,
+        ShortcutAction::NewTab,
+        ShortcutAction::CloseTab,
+        ShortcutAction::NextTab,
+        ShortcutAction::PrevTab,
+    ];
+
+    /// スクロールバックを動かすアクションか（スクロールバックのない代替画面ではキーをアプリケーションに送る）
+    pub fn scrolls_view(self) -> bool {
+        matches!(
+            self,
+            ShortcutAction::ScrollPageUp
+                | ShortcutAction::ScrollPageDown
+                | ShortcutAction::ScrollToTop
+                | ShortcutAction::ScrollToBottom
+        )
+    }
+
+    /// アクション名（バリアント名、例: `SplitHorizontal`）から取得
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| format!("{:?}", action) == name)
+    }
+}
+
+/// 設定ファイルのキー指定（例: `Ctrl+Shift+S`、`Shift+PageUp`、`Alt+F5`）を解析
+///
+/// 修飾キーは `Ctrl`/`Shift`/`Alt`/`Super` で、大文字小文字は区別しない。`+` キーは `Plus` と書く
+pub fn parse_key_combo(combo: &str) -> Option<(KeyCode, ModifiersState)> {
+    let parts: Vec<&str> = combo.split('+').map(str::trim).collect();
+    let (key, modifier_names) = parts.split_last()?;
+
+    let mut modifiers = ModifiersState::empty();
+    for name in modifier_names {
+        let modifier = match name.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => ModifiersState::CONTROL,
+            "shift" => ModifiersState::SHIFT,
+            "alt" | "option" => ModifiersState::ALT,
+            "super" | "cmd" | "command" | "meta" => ModifiersState::SUPER,
+            _ => return None,
+        };
+        modifiers |= modifier;
+    }
+    Some((parse_key(key)?, modifiers))
+}
+
+fn parse_key(key: &str) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+        KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+        KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+        KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+        KeyCode::KeyY, KeyCode::KeyZ,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+        KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+    const FUNCTION_KEYS: [KeyCode; 12] = [
+        KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+        KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    ];
+
+    let lower = key.to_ascii_lowercase();
+    let mut chars = lower.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        match ch {
+            'a'..='z' => return Some(LETTERS[ch as usize - 'a' as usize]),
+            '0'..='9' => return Some(DIGITS[ch as usize - '0' as usize]),
+            _ => {}
+        }
+    }
+    if let Some(number) = lower.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(number.checked_sub(1)?).copied();
+    }
+
+    Some(match lower.as_str() {
+        "=" | "equal" | "plus" => KeyCode::Equal,
+        "-" | "minus" => KeyCode::Minus,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        "tab" => KeyCode::Tab,
+        "enter" | "return" => KeyCode::Enter,
+        "space" => KeyCode::Space,
+        "escape" | "esc" => KeyCode::Escape,
+        "backspace" => KeyCode::Backspace,
+        _ => return None,
+    })
 }
 
 /// ショートカットハンドラー
-pub struct ShortcutHandler;
+pub struct ShortcutHandler {
+    /// 設定ファイル（`keybindings`）で割り当てたキー
+    bindings: HashMap<(KeyCode, ModifiersState), ShortcutAction>,
+    /// 既定のキーを使わないアクション（別のキーに割り当てたか `none` にしたもの）
+    overridden: HashSet<ShortcutAction>,
+}
 
 impl ShortcutHandler {
     pub fn new() -> Self {
-        Self
+        Self {
+            bindings: HashMap::new(),
+            overridden: HashSet::new(),
+        }
+    }
+
+    /// 設定ファイルの `keybindings`（アクション名 → キー指定）から作成
+    ///
+    /// 割り当てたアクションは既定のキーでは動かなくなる。`none` は割り当てを外す。
+    /// 割り当てのないアクションは既定のキーのまま
+    pub fn with_bindings(keybindings: &BTreeMap<String, String>) -> Result<Self> {
+        let mut handler = Self::new();
+        for (name, combo) in keybindings {
+            let action = ShortcutAction::from_name(name).ok_or_else(|| {
+                TerbulatorError::config(format!("Unknown action in keybindings: {}", name))
+            })?;
+            handler.overridden.insert(action);
+            if combo.eq_ignore_ascii_case("none") {
+                continue;
+            }
+            let key = parse_key_combo(combo).ok_or_else(|| {
+                TerbulatorError::config(format!("Invalid key for {} in keybindings: {}", name, combo))
+            })?;
+            if let Some(previous) = handler.bindings.insert(key, action) {
+                return Err(TerbulatorError::config(format!(
+                    "{} is bound to both {:?} and {:?} in keybindings",
+                    combo, previous, action
+                )));
+            }
+        }
+        Ok(handler)
     }
 
     /// キー入力がショートカットに一致するか判定
@@ -49,6 +250,14 @@ impl ShortcutHandler {
         key_code: KeyCode,
         modifiers: ModifiersState,
     ) -> Option<ShortcutAction> {
+        if let Some(&action) = self.bindings.get(&(key_code, modifiers)) {
+            return Some(action);
+        }
+        Self::default_shortcut(key_code, modifiers).filter(|action| !self.overridden.contains(action))
+    }
+
+    /// 既定のショートカット
+    fn default_shortcut(key_code: KeyCode, modifiers: ModifiersState) -> Option<ShortcutAction> {
         // Ctrl+Shift が押されているか確認
         let ctrl_shift = modifiers.control_key() && modifiers.shift_key();
 
@@ -63,8 +272,15 @@ impl ShortcutHandler {
                 KeyCode::KeyV => Some(ShortcutAction::SplitVertical),
                 KeyCode::KeyS => Some(ShortcutAction::SplitHorizontal),
                 KeyCode::KeyW => Some(ShortcutAction::ClosePane),
+                KeyCode::KeyE => Some(ShortcutAction::BalancePanes),
                 KeyCode::KeyB => Some(ShortcutAction::ToggleBroadcast),
                 KeyCode::KeyC => Some(ShortcutAction::Copy),
+                KeyCode::KeyT => Some(ShortcutAction::NewTab),
+                KeyCode::KeyQ => Some(ShortcutAction::CloseTab),
+                KeyCode::KeyY => Some(ShortcutAction::SaveTheme),
+                KeyCode::KeyO => Some(ShortcutAction::TogglePaneLog),
+                KeyCode::KeyX => Some(ShortcutAction::SoftReset),
+                KeyCode::KeyA => Some(ShortcutAction::ToggleAlwaysOnTop),
                 _ => None,
             }
         } else if modifiers.control_key() && modifiers.shift_key() {
@@ -77,6 +293,19 @@ impl ShortcutHandler {
             // Alt+Shift
             match key_code {
                 KeyCode::KeyM => Some(ShortcutAction::ToggleMarkMode),
+                KeyCode::KeyH => Some(ShortcutAction::SwapPaneLeft),
+                KeyCode::KeyJ => Some(ShortcutAction::SwapPaneDown),
+                KeyCode::KeyK => Some(ShortcutAction::SwapPaneUp),
+                KeyCode::KeyL => Some(ShortcutAction::SwapPaneRight),
+                _ => None,
+            }
+        } else if modifiers.shift_key() && !modifiers.alt_key() && !modifiers.super_key() {
+            // Shift のみ（スクロールバックの表示、PTYには送らない）
+            match key_code {
+                KeyCode::PageUp => Some(ShortcutAction::ScrollPageUp),
+                KeyCode::PageDown => Some(ShortcutAction::ScrollPageDown),
+                KeyCode::Home => Some(ShortcutAction::ScrollToTop),
+                KeyCode::End => Some(ShortcutAction::ScrollToBottom),
                 _ => None,
             }
         } else if modifiers.control_key() {
@@ -84,8 +313,13 @@ impl ShortcutHandler {
             match key_code {
                 KeyCode::Equal | KeyCode::NumpadAdd => Some(ShortcutAction::IncreaseFontSize),
                 KeyCode::Minus | KeyCode::NumpadSubtract => Some(ShortcutAction::DecreaseFontSize),
+                KeyCode::PageDown => Some(ShortcutAction::NextTab),
+                KeyCode::PageUp => Some(ShortcutAction::PrevTab),
                 _ => None,
             }
+        } else if modifiers.is_empty() && key_code == KeyCode::F11 {
+            // F11 のみ（修飾キー付きの F11 はPTYに送る）
+            Some(ShortcutAction::ToggleFullscreen)
         } else {
             None
         }
@@ -133,6 +367,34 @@ mod tests {
             handler.match_shortcut(KeyCode::KeyP, modifiers),
             Some(ShortcutAction::MoveFocusPrev)
         );
+
+        // Alt+Shift+h/j/k/l はペインの入れ替え
+        let alt_shift = ModifiersState::ALT | ModifiersState::SHIFT;
+        assert_eq!(handler.match_shortcut(KeyCode::KeyH, alt_shift), Some(ShortcutAction::SwapPaneLeft));
+        assert_eq!(handler.match_shortcut(KeyCode::KeyJ, alt_shift), Some(ShortcutAction::SwapPaneDown));
+        assert_eq!(handler.match_shortcut(KeyCode::KeyK, alt_shift), Some(ShortcutAction::SwapPaneUp));
+        assert_eq!(handler.match_shortcut(KeyCode::KeyL, alt_shift), Some(ShortcutAction::SwapPaneRight));
+    }
+
+    #[test]
+    fn test_alt_keys_reach_the_shell() {
+        let handler = ShortcutHandler::new();
+        // Alt+Shift+M（マークモード）と Alt+Shift+H/J/K/L（ペインの入れ替え）は予約済み、
+        // それ以外のAltの組み合わせはPTYに送る
+        let alt_shift = ModifiersState::ALT | ModifiersState::SHIFT;
+        for (key_code, action) in [
+            (KeyCode::KeyM, ShortcutAction::ToggleMarkMode),
+            (KeyCode::KeyH, ShortcutAction::SwapPaneLeft),
+            (KeyCode::KeyJ, ShortcutAction::SwapPaneDown),
+            (KeyCode::KeyK, ShortcutAction::SwapPaneUp),
+            (KeyCode::KeyL, ShortcutAction::SwapPaneRight),
+        ] {
+            assert_eq!(handler.match_shortcut(key_code, alt_shift), Some(action));
+        }
+        for key_code in [KeyCode::KeyB, KeyCode::KeyF, KeyCode::KeyM, KeyCode::KeyH, KeyCode::KeyL, KeyCode::Backspace] {
+            assert_eq!(handler.match_shortcut(key_code, ModifiersState::ALT), None);
+        }
+        assert_eq!(handler.match_shortcut(KeyCode::KeyB, alt_shift), None);
     }
 
     #[test]
@@ -154,9 +416,111 @@ mod tests {
             handler.match_shortcut(KeyCode::KeyW, modifiers),
             Some(ShortcutAction::ClosePane)
         );
+        assert_eq!(
+            handler.match_shortcut(KeyCode::KeyE, modifiers),
+            Some(ShortcutAction::BalancePanes)
+        );
         assert_eq!(
             handler.match_shortcut(KeyCode::KeyB, modifiers),
             Some(ShortcutAction::ToggleBroadcast)
         );
+        assert_eq!(
+            handler.match_shortcut(KeyCode::KeyY, modifiers),
+            Some(ShortcutAction::SaveTheme)
+        );
+        assert_eq!(
+            handler.match_shortcut(KeyCode::KeyX, modifiers),
+            Some(ShortcutAction::SoftReset)
+        );
+    }
+
+    #[test]
+    fn test_tab_shortcuts() {
+        let handler = ShortcutHandler::new();
+        let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+
+        assert_eq!(handler.match_shortcut(KeyCode::KeyT, ctrl_shift), Some(ShortcutAction::NewTab));
+        assert_eq!(handler.match_shortcut(KeyCode::KeyQ, ctrl_shift), Some(ShortcutAction::CloseTab));
+        assert_eq!(handler.match_shortcut(KeyCode::PageDown, ModifiersState::CONTROL), Some(ShortcutAction::NextTab));
+        assert_eq!(handler.match_shortcut(KeyCode::PageUp, ModifiersState::CONTROL), Some(ShortcutAction::PrevTab));
+    }
+
+    #[test]
+    fn test_scrollback_shortcuts() {
+        let handler = ShortcutHandler::new();
+        let shift = ModifiersState::SHIFT;
+
+        assert_eq!(handler.match_shortcut(KeyCode::PageUp, shift), Some(ShortcutAction::ScrollPageUp));
+        assert_eq!(handler.match_shortcut(KeyCode::PageDown, shift), Some(ShortcutAction::ScrollPageDown));
+        assert_eq!(handler.match_shortcut(KeyCode::Home, shift), Some(ShortcutAction::ScrollToTop));
+        assert_eq!(handler.match_shortcut(KeyCode::End, shift), Some(ShortcutAction::ScrollToBottom));
+
+        // Shift なしや他の修飾キー付きはアプリケーションに送る
+        assert_eq!(handler.match_shortcut(KeyCode::PageUp, ModifiersState::empty()), None);
+
+        // スクロールのアクションだけが代替画面でアプリケーションに送られる
+        let scrolling: Vec<_> = ShortcutAction::ALL.into_iter().filter(|action| action.scrolls_view()).collect();
+        assert_eq!(
+            scrolling,
+            [ShortcutAction::ScrollPageUp, ShortcutAction::ScrollPageDown, ShortcutAction::ScrollToTop, ShortcutAction::ScrollToBottom]
+        );
+        assert_eq!(handler.match_shortcut(KeyCode::Home, shift | ModifiersState::ALT), None);
+        assert_eq!(handler.match_shortcut(KeyCode::KeyA, shift), None);
+    }
+
+    #[test]
+    fn test_parse_key_combo() {
+        assert_eq!(
+            parse_key_combo("Ctrl+Shift+S"),
+            Some((KeyCode::KeyS, ModifiersState::CONTROL | ModifiersState::SHIFT))
+        );
+        assert_eq!(parse_key_combo("shift + pageup"), Some((KeyCode::PageUp, ModifiersState::SHIFT)));
+        assert_eq!(parse_key_combo("Alt+F5"), Some((KeyCode::F5, ModifiersState::ALT)));
+        assert_eq!(parse_key_combo("Ctrl+="), Some((KeyCode::Equal, ModifiersState::CONTROL)));
+        assert_eq!(parse_key_combo("F2"), Some((KeyCode::F2, ModifiersState::empty())));
+
+        assert_eq!(parse_key_combo("Ctrl+Shift"), None);
+        assert_eq!(parse_key_combo("Hyper+S"), None);
+        assert_eq!(parse_key_combo("Ctrl+F13"), None);
+        assert_eq!(parse_key_combo(""), None);
+    }
+
+    #[test]
+    fn test_keybindings_override_defaults() {
+        let keybindings: BTreeMap<String, String> = [
+            ("SplitHorizontal", "Alt+S"),
+            ("ToggleBroadcast", "none"),
+            ("Copy", "Ctrl+Shift+S"),
+        ]
+        .into_iter()
+        .map(|(action, combo)| (action.to_string(), combo.to_string()))
+        .collect();
+        let handler = ShortcutHandler::with_bindings(&keybindings).unwrap();
+        let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+
+        assert_eq!(handler.match_shortcut(KeyCode::KeyS, ModifiersState::ALT), Some(ShortcutAction::SplitHorizontal));
+        assert_eq!(handler.match_shortcut(KeyCode::KeyS, ctrl_shift), Some(ShortcutAction::Copy));
+        // 割り当て直したアクションは既定のキーでは動かない
+        assert_eq!(handler.match_shortcut(KeyCode::KeyC, ctrl_shift), None);
+        assert_eq!(handler.match_shortcut(KeyCode::KeyB, ctrl_shift), None);
+        // 割り当てのないアクションは既定のまま
+        assert_eq!(handler.match_shortcut(KeyCode::KeyV, ctrl_shift), Some(ShortcutAction::SplitVertical));
+        assert_eq!(handler.match_shortcut(KeyCode::PageUp, ModifiersState::SHIFT), Some(ShortcutAction::ScrollPageUp));
+    }
+
+    #[test]
+    fn test_invalid_keybindings() {
+        let bindings = |action: &str, combo: &str| BTreeMap::from([(action.to_string(), combo.to_string())]);
+
+        let error = ShortcutHandler::with_bindings(&bindings("SplitDiagonal", "Ctrl+Shift+D")).err().unwrap();
+        assert!(error.to_string().contains("SplitDiagonal"));
+        let error = ShortcutHandler::with_bindings(&bindings("ClosePane", "Ctrl+Shfit+Q")).err().unwrap();
+        assert!(error.to_string().contains("Ctrl+Shfit+Q"));
+
+        let mut twice = bindings("ClosePane", "Alt+Q");
+        twice.insert("Copy".to_string(), "Alt+Q".to_string());
+        assert!(ShortcutHandler::with_bindings(&twice).is_err());
+
+        assert!(ShortcutHandler::with_bindings(&BTreeMap::new()).is_ok());
     }
 }
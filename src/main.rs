@@ -11,11 +11,12 @@ use app::App;
 use config::init_config;
 use clap::Parser;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::window::{Window, WindowId};
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
+use winit::window::{Fullscreen, UserAttentionType, Window, WindowId, WindowLevel};
 
 /// Terbulator - 超軽量なGUI端末エミュレータ
 #[derive(Parser, Debug)]
@@ -24,23 +25,40 @@ struct Args {
     /// Config file path (default: ~/.config/terbulator/config.yaml)
     #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
+
+    /// Run this command (and the arguments after it) in the first pane instead of the shell
+    #[arg(short = 'e', long, value_name = "COMMAND", num_args = 1.., allow_hyphen_values = true, trailing_var_arg = true)]
+    command: Option<Vec<String>>,
+
+    /// Keep the window above other windows (overrides `window.always_on_top`)
+    #[arg(long)]
+    always_on_top: bool,
 }
 
+/// How often the exit countdown is checked while it is shown
+const COUNTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 struct TerbulatorApp {
     window: Option<Window>,
     app: Option<App>,
     modifiers: winit::keyboard::ModifiersState,
     config_path: Option<PathBuf>,
+    /// `--command` for the first pane
+    command: Option<Vec<String>>,
+    /// `--always-on-top`
+    always_on_top: bool,
     cursor_position: (f64, f64),
-    last_cursor_blink: Instant,
-    cursor_blink_interval: Duration,
+    /// Wakes the event loop when a PTY has new output
+    proxy: EventLoopProxy<()>,
+    /// Seconds shown by the countdown after the last pane exited
+    shown_exit_countdown: Option<u64>,
 }
 
 impl ApplicationHandler for TerbulatorApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.window.is_none() {
             // Load config
-            let config = match init_config(self.config_path.clone()) {
+            let mut config = match init_config(self.config_path.clone()) {
                 Ok(cfg) => cfg,
                 Err(e) => {
                     log::error!("Failed to load config: {}", e);
@@ -49,9 +67,14 @@ impl ApplicationHandler for TerbulatorApp {
                 }
             };
 
+            config.window.always_on_top |= self.always_on_top;
+
             // Create window
             let mut window_attrs = winit::window::WindowAttributes::default()
                 .with_title(&config.window.title);
+            if config.window.always_on_top {
+                window_attrs = window_attrs.with_window_level(WindowLevel::AlwaysOnTop);
+            }
 
             // Set window size or maximize
             if config.window.maximize {
@@ -75,7 +98,7 @@ impl ApplicationHandler for TerbulatorApp {
             };
 
             // Create app
-            let app = match App::new(config, &window) {
+            let mut app = match App::new(config, self.config_path.clone(), self.command.as_deref(), &window) {
                 Ok(a) => a,
                 Err(e) => {
                     log::error!("Failed to create app: {}", e);
@@ -95,6 +118,12 @@ impl ApplicationHandler for TerbulatorApp {
             window.set_ime_cursor_area(winit::dpi::PhysicalPosition::new(0, 0), winit::dpi::PhysicalSize::new(1, 1));
             log::info!("IME support enabled");
 
+            // PTY output arrives on reader threads; wake the loop instead of polling for it
+            let proxy = self.proxy.clone();
+            app.set_output_waker(Arc::new(move || {
+                let _ = proxy.send_event(());
+            }));
+
             self.window = Some(window);
             self.app = Some(app);
 
@@ -106,22 +135,61 @@ impl ApplicationHandler for TerbulatorApp {
         }
     }
 
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {
+        // New PTY output (or an exited shell) to process
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        // Check if cursor should blink
-        let now = Instant::now();
-        if now.duration_since(self.last_cursor_blink) >= self.cursor_blink_interval {
-            self.last_cursor_blink = now;
-            if let Some(window) = &self.window {
+        let Some(app) = &mut self.app else {
+            return;
+        };
+
+        // Redraw the exit countdown whenever its seconds change; the redraw at zero closes the window
+        let exit_countdown = app.exit_countdown_secs();
+        let mut needs_redraw = exit_countdown != self.shown_exit_countdown;
+        self.shown_exit_countdown = exit_countdown;
+
+        // A blink only matters when the active pane shows its cursor
+        needs_redraw |= app.update_cursor_blink();
+
+        if let Err(e) = app.flush_mouse_motion() {
+            log::error!("Failed to report mouse motion: {}", e);
+        }
+        app.flush_pane_logs();
+
+        if let Some(window) = &self.window {
+            // A pane going silent produces no output that would redraw the window
+            if app.update_monitor_indicators() {
+                update_window_title(app, window);
+            }
+            if needs_redraw {
                 window.request_redraw();
             }
         }
 
-        // Calculate time until next cursor blink
-        let elapsed = now.duration_since(self.last_cursor_blink);
-        let next_blink = self.cursor_blink_interval.saturating_sub(elapsed);
-
-        // Wait until next cursor blink time or until an event occurs
-        event_loop.set_control_flow(ControlFlow::WaitUntil(now + next_blink));
+        // Sleep until the next blink, held-back mouse motion report, log flush, silence
+        // check or countdown check; PTY output and input wake the loop on their own
+        let mut wake_at = app.next_cursor_blink();
+        if exit_countdown.is_some() {
+            let countdown_check = Instant::now() + COUNTDOWN_POLL_INTERVAL;
+            wake_at = Some(wake_at.map_or(countdown_check, |blink| blink.min(countdown_check)));
+        }
+        if let Some(motion) = app.next_mouse_motion() {
+            wake_at = Some(wake_at.map_or(motion, |other| other.min(motion)));
+        }
+        if let Some(flush) = app.next_log_flush() {
+            wake_at = Some(wake_at.map_or(flush, |other| other.min(flush)));
+        }
+        if let Some(silence) = app.next_monitor_check() {
+            wake_at = Some(wake_at.map_or(silence, |other| other.min(silence)));
+        }
+        event_loop.set_control_flow(match wake_at {
+            Some(wake_at) => ControlFlow::WaitUntil(wake_at),
+            None => ControlFlow::Wait,
+        });
     }
 
     fn window_event(
@@ -141,6 +209,7 @@ impl ApplicationHandler for TerbulatorApp {
         match event {
             WindowEvent::CloseRequested => {
                 log::info!("Close requested, exiting");
+                app.save_layout();
                 event_loop.exit();
             }
 
@@ -151,6 +220,12 @@ impl ApplicationHandler for TerbulatorApp {
                 window.request_redraw();
             }
 
+            WindowEvent::Occluded(false) => {
+                // The window contents may have been lost while hidden
+                app.invalidate();
+                window.request_redraw();
+            }
+
             WindowEvent::RedrawRequested => {
                 // Process PTY output
                 let (has_output, should_exit) = match app.process_pty_output() {
@@ -161,6 +236,11 @@ impl ApplicationHandler for TerbulatorApp {
                     }
                 };
 
+                // Ask for attention when a bell rings in an unfocused window
+                if app.take_bell() && !window.has_focus() {
+                    window.request_user_attention(Some(UserAttentionType::Informational));
+                }
+
                 // Exit if all panes have closed
                 if should_exit {
                     log::info!("All panes closed, exiting application");
@@ -168,6 +248,14 @@ impl ApplicationHandler for TerbulatorApp {
                     return;
                 }
 
+                // The title shows the active pane's dynamic title and the
+                // activity/silence of unfocused panes
+                let indicators_changed = app.update_monitor_indicators();
+                let title_changed = app.update_dynamic_title();
+                if indicators_changed || title_changed {
+                    update_window_title(app, window);
+                }
+
                 // Render
                 if let Err(e) = app.render() {
                     log::error!("Failed to render: {}", e);
@@ -181,9 +269,15 @@ impl ApplicationHandler for TerbulatorApp {
                     winit::dpi::PhysicalSize::new(cell_width as u32, cell_height as u32)
                 );
 
-                // Request another redraw if there was PTY output
-                // (data might still be coming)
-                if has_output {
+                // Request another redraw if there was PTY output (data might still
+                // be coming) or the focus flash, visual bell, scroll indicator or notice has to be cleared
+                if has_output
+                    || app.has_pending_output()
+                    || app.is_focus_flash_active()
+                    || app.is_visual_bell_active()
+                    || app.is_scroll_indicator_active()
+                    || app.is_notice_active()
+                {
                     window.request_redraw();
                 }
             }
@@ -195,9 +289,20 @@ impl ApplicationHandler for TerbulatorApp {
 
             WindowEvent::KeyboardInput { event, .. } => {
                 if event.state == ElementState::Pressed {
-                    if let Err(e) = app.handle_keyboard_input(&event.physical_key, self.modifiers) {
+                    let unmodified_text = text_without_option(&event, self.modifiers);
+                    if let Err(e) = app.handle_keyboard_input(&event.physical_key, event.text.as_deref(), unmodified_text.as_deref(), self.modifiers) {
                         log::error!("Failed to handle keyboard input: {}", e);
                     }
+                    if let Some(level) = app.take_window_level_change() {
+                        window.set_window_level(level);
+                    }
+                    // Toggle from the window's own state, which also changes when the window
+                    // manager leaves fullscreen. The new window size arrives as a Resized
+                    // event, which resizes the panes.
+                    if app.take_fullscreen_toggle() {
+                        let fullscreen = window.fullscreen().is_none();
+                        window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+                    }
 
                     update_window_title(app, window);
                     window.request_redraw();
@@ -241,19 +346,19 @@ impl ApplicationHandler for TerbulatorApp {
                 }
             }
 
-            WindowEvent::MouseInput { state, button: winit::event::MouseButton::Left, .. } => {
+            WindowEvent::MouseInput { state, button, .. } => {
                 let (x, y) = self.cursor_position;
                 match state {
                     ElementState::Pressed => {
-                        // Handle left mouse button press
-                        if let Err(e) = app.handle_mouse_press(x, y) {
+                        // Handle mouse button press
+                        if let Err(e) = app.handle_mouse_press(x, y, button) {
                             log::error!("Failed to handle mouse press: {}", e);
                         }
                         window.request_redraw();
                     }
                     ElementState::Released => {
-                        // Handle left mouse button release
-                        if let Err(e) = app.handle_mouse_release() {
+                        // Handle mouse button release
+                        if let Err(e) = app.handle_mouse_release(x, y, button) {
                             log::error!("Failed to handle mouse release: {}", e);
                         }
                         window.request_redraw();
@@ -261,6 +366,14 @@ impl ApplicationHandler for TerbulatorApp {
                 }
             }
 
+            WindowEvent::MouseWheel { delta, .. } => {
+                // Scroll the active pane through its scrollback
+                let scrolled = app.handle_mouse_wheel(delta);
+                if scrolled {
+                    window.request_redraw();
+                }
+            }
+
             WindowEvent::CursorMoved { position, .. } => {
                 // Store cursor position
                 self.cursor_position = (position.x, position.y);
@@ -285,7 +398,30 @@ impl ApplicationHandler for TerbulatorApp {
     }
 }
 
-/// Update window title based on app state (broadcast, mark mode, IME)
+/// The character of a key pressed with Option, without what Option composes
+/// (`b` rather than `∫` for Option+b), so Option can act as Meta
+#[cfg(target_os = "macos")]
+fn text_without_option(event: &KeyEvent, modifiers: winit::keyboard::ModifiersState) -> Option<String> {
+    use winit::keyboard::Key;
+    use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+
+    if !modifiers.alt_key() {
+        return None;
+    }
+    match event.key_without_modifiers() {
+        // Shift is dropped along with Option, so put it back for letters
+        Key::Character(text) if modifiers.shift_key() => Some(text.to_uppercase()),
+        Key::Character(text) => Some(text.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn text_without_option(_event: &KeyEvent, _modifiers: winit::keyboard::ModifiersState) -> Option<String> {
+    None
+}
+
+/// Update window title based on app state (broadcast, mark mode, pane limit, IME)
 fn update_window_title(app: &App, window: &Window) {
     let base_title = app.base_title();
     let mut title_parts = vec![];
@@ -298,6 +434,19 @@ fn update_window_title(app: &App, window: &Window) {
         title_parts.push("MARK");
     }
 
+    if app.has_pane_activity() {
+        title_parts.push("Activity");
+    }
+
+    if app.has_pane_silence() {
+        title_parts.push("Silence");
+    }
+
+    let pane_limit_notice = app.pane_limit_notice();
+    if let Some(notice) = &pane_limit_notice {
+        title_parts.push(notice);
+    }
+
     if app.is_ime_enabled() {
         title_parts.push("あ");
         log::debug!("IME is enabled, adding [あ] to title");
@@ -330,7 +479,7 @@ fn main() {
     }
 
     // Create event loop
-    let event_loop = match EventLoop::new() {
+    let event_loop = match EventLoop::with_user_event().build() {
         Ok(el) => el,
         Err(e) => {
             eprintln!("Failed to create event loop: {}", e);
@@ -343,9 +492,11 @@ fn main() {
         app: None,
         modifiers: winit::keyboard::ModifiersState::empty(),
         config_path: args.config,
+        command: args.command,
+        always_on_top: args.always_on_top,
         cursor_position: (0.0, 0.0),
-        last_cursor_blink: Instant::now(),
-        cursor_blink_interval: Duration::from_millis(500),
+        proxy: event_loop.create_proxy(),
+        shown_exit_countdown: None,
     };
 
     // Run event loop
@@ -354,3 +505,23 @@ fn main() {
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_takes_remaining_arguments() {
+        let args = Args::try_parse_from(["terbulator", "-c", "my.yaml", "-e", "htop", "-d", "5"]).unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("my.yaml")));
+        assert_eq!(args.command, Some(vec!["htop".to_string(), "-d".to_string(), "5".to_string()]));
+
+        let args = Args::try_parse_from(["terbulator"]).unwrap();
+        assert_eq!(args.command, None);
+        assert!(!args.always_on_top);
+
+        let args = Args::try_parse_from(["terbulator", "--always-on-top", "-e", "htop", "--always-on-top"]).unwrap();
+        assert!(args.always_on_top);
+        assert_eq!(args.command, Some(vec!["htop".to_string(), "--always-on-top".to_string()]));
+    }
+}
@@ -1,13 +1,17 @@
 use crate::pane::PaneId;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::rc::Rc;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SplitDirection {
     Horizontal,
     Vertical,
 }
 
 /// ペインの矩形領域
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Rect {
     pub x: u32,
     pub y: u32,
@@ -22,7 +26,8 @@ impl Rect {
 }
 
 /// レイアウトツリーのノード
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum LayoutNode {
     Leaf {
         pane_id: PaneId,
@@ -35,21 +40,79 @@ pub enum LayoutNode {
     },
 }
 
+/// ファイルに保存するレイアウト（分割の向きと比率だけで、シェルは含まない）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLayout {
+    /// ペインの数（ツリーの葉の数と一致しなければ使わない）
+    pub panes: usize,
+    /// アクティブなペインの位置（葉の順、0始まり）
+    pub active: usize,
+    pub root: LayoutNode,
+}
+
+/// ペインIDの払い出し元。タブ間で共有し、ウィンドウ内でペインIDが重ならないようにする
+#[derive(Debug, Clone, Default)]
+pub struct PaneIds(Rc<Cell<PaneId>>);
+
+impl PaneIds {
+    /// まだ使われていないID
+    pub fn next(&self) -> PaneId {
+        let id = self.0.get();
+        self.0.set(id + 1);
+        id
+    }
+}
+
 /// ペインのレイアウト管理
+#[derive(Clone)]
 pub struct Layout {
     root: LayoutNode,
-    next_id: PaneId,
+    ids: PaneIds,
 }
 
 impl Layout {
     /// 単一ペインで初期化
     pub fn new() -> Self {
+        Self::with_ids(PaneIds::default())
+    }
+
+    /// `ids` からIDを払い出す単一ペインのレイアウト（最初のペインのIDも`ids`から取る）
+    pub fn with_ids(ids: PaneIds) -> Self {
         Self {
-            root: LayoutNode::Leaf { pane_id: 0 },
-            next_id: 1,
+            root: LayoutNode::Leaf { pane_id: ids.next() },
+            ids,
+        }
+    }
+
+    /// 保存したツリーからレイアウトを作成。最初の葉を`first_id`、残りの葉を`ids`から
+    /// 払い出したIDに振り直す。比率が0より大きく1より小さくなければNone
+    pub fn from_saved(mut root: LayoutNode, first_id: PaneId, ids: PaneIds) -> Option<Self> {
+        let mut first_id = Some(first_id);
+        if !Self::renumber_node(&mut root, &mut first_id, &ids) {
+            return None;
+        }
+        Some(Self { root, ids })
+    }
+
+    fn renumber_node(node: &mut LayoutNode, first_id: &mut Option<PaneId>, ids: &PaneIds) -> bool {
+        match node {
+            LayoutNode::Leaf { pane_id } => {
+                *pane_id = first_id.take().unwrap_or_else(|| ids.next());
+                true
+            }
+            LayoutNode::Branch { ratio, first, second, .. } => {
+                *ratio > 0.0 && *ratio < 1.0
+                    && Self::renumber_node(first, first_id, ids)
+                    && Self::renumber_node(second, first_id, ids)
+            }
         }
     }
 
+    /// ペインIDの払い出し元（同じ払い出し元を使うレイアウトとはIDが重ならない）
+    pub fn pane_ids(&self) -> &PaneIds {
+        &self.ids
+    }
+
     /// ルートノードを取得
     pub fn root(&self) -> &LayoutNode {
         &self.root
@@ -57,9 +120,7 @@ impl Layout {
 
     /// 次のペインIDを生成
     pub fn next_id(&mut self) -> PaneId {
-        let id = self.next_id;
-        self.next_id += 1;
-        id
+        self.ids.next()
     }
 
     /// 指定されたペインを分割
@@ -146,6 +207,32 @@ impl Layout {
         }
     }
 
+    /// 2つのペインの位置を入れ替える（葉のペインIDを交換する）
+    pub fn swap_panes(&mut self, a: PaneId, b: PaneId) -> bool {
+        let ids = self.all_pane_ids();
+        if a == b || !ids.contains(&a) || !ids.contains(&b) {
+            return false;
+        }
+        Self::swap_node(&mut self.root, a, b);
+        true
+    }
+
+    fn swap_node(node: &mut LayoutNode, a: PaneId, b: PaneId) {
+        match node {
+            LayoutNode::Leaf { pane_id } => {
+                if *pane_id == a {
+                    *pane_id = b;
+                } else if *pane_id == b {
+                    *pane_id = a;
+                }
+            }
+            LayoutNode::Branch { first, second, .. } => {
+                Self::swap_node(first, a, b);
+                Self::swap_node(second, a, b);
+            }
+        }
+    }
+
     /// レイアウトを計算して各ペインの矩形を返す
     pub fn calculate_rects(&self, window_rect: Rect) -> Vec<(PaneId, Rect)> {
         let mut rects = Vec::new();
@@ -208,6 +295,75 @@ impl Layout {
         }
     }
 
+    /// 全ての分割比率を均等に戻す
+    /// 同じ方向に続けて分割したペインは、その方向に並ぶペインの数に応じて同じ大きさにする
+    /// （3回縦に分割した4列も等幅になる）
+    pub fn balance(&mut self) {
+        Self::balance_node(&mut self.root);
+    }
+
+    fn balance_node(node: &mut LayoutNode) {
+        if let LayoutNode::Branch { direction, ratio, first, second } = node {
+            let first_count = Self::span_count(first, *direction) as f32;
+            let second_count = Self::span_count(second, *direction) as f32;
+            *ratio = first_count / (first_count + second_count);
+            Self::balance_node(first);
+            Self::balance_node(second);
+        }
+    }
+
+    /// `direction` の向きに並ぶペインの数（別の向きの分割はまとめて1つと数える）
+    fn span_count(node: &LayoutNode, direction: SplitDirection) -> usize {
+        match node {
+            LayoutNode::Branch { direction: d, first, second, .. } if *d == direction => {
+                Self::span_count(first, direction) + Self::span_count(second, direction)
+            }
+            _ => 1,
+        }
+    }
+
+    /// 各ペインが最小サイズ（ピクセル）以上になるように分割比率を調整する
+    /// 領域が足りない分割の比率はそのまま
+    pub fn clamp_to_min_size(&mut self, window_rect: Rect, min_width: u32, min_height: u32) {
+        Self::clamp_node(&mut self.root, window_rect, min_width, min_height);
+    }
+
+    fn clamp_node(node: &mut LayoutNode, rect: Rect, min_width: u32, min_height: u32) {
+        if let LayoutNode::Branch { direction, ratio, first, second } = node {
+            let first_min = Self::min_extent(first, min_width, min_height);
+            let second_min = Self::min_extent(second, min_width, min_height);
+            let (extent, first_need, second_need) = match direction {
+                SplitDirection::Horizontal => (rect.height, first_min.1, second_min.1),
+                SplitDirection::Vertical => (rect.width, first_min.0, second_min.0),
+            };
+            if extent > 0 && first_need + second_need <= extent {
+                // 最初の子の大きさは切り捨てで決まるので、下限は少しだけ上げておく
+                let high = (extent - second_need) as f32 / extent as f32;
+                let low = ((first_need as f32 + 0.01) / extent as f32).min(high);
+                *ratio = ratio.clamp(low, high);
+            }
+
+            let (first_rect, second_rect) = split_rect(rect, *direction, *ratio);
+            Self::clamp_node(first, first_rect, min_width, min_height);
+            Self::clamp_node(second, second_rect, min_width, min_height);
+        }
+    }
+
+    /// ノード以下の全ペインが最小サイズを満たすのに必要な（幅, 高さ）
+    fn min_extent(node: &LayoutNode, min_width: u32, min_height: u32) -> (u32, u32) {
+        match node {
+            LayoutNode::Leaf { .. } => (min_width, min_height),
+            LayoutNode::Branch { direction, first, second, .. } => {
+                let (first_width, first_height) = Self::min_extent(first, min_width, min_height);
+                let (second_width, second_height) = Self::min_extent(second, min_width, min_height);
+                match direction {
+                    SplitDirection::Horizontal => (first_width.max(second_width), first_height + second_height),
+                    SplitDirection::Vertical => (first_width + second_width, first_height.max(second_height)),
+                }
+            }
+        }
+    }
+
     /// 全ペインIDを取得
     pub fn all_pane_ids(&self) -> Vec<PaneId> {
         let mut ids = Vec::new();
@@ -383,8 +539,167 @@ impl Layout {
     }
 }
 
+/// 分割比率で矩形を2つに分ける（最初の子の大きさは切り捨て）
+fn split_rect(rect: Rect, direction: SplitDirection, ratio: f32) -> (Rect, Rect) {
+    match direction {
+        SplitDirection::Horizontal => {
+            let first_height = (rect.height as f32 * ratio) as u32;
+            (
+                Rect::new(rect.x, rect.y, rect.width, first_height),
+                Rect::new(rect.x, rect.y + first_height, rect.width, rect.height.saturating_sub(first_height)),
+            )
+        }
+        SplitDirection::Vertical => {
+            let first_width = (rect.width as f32 * ratio) as u32;
+            (
+                Rect::new(rect.x, rect.y, first_width, rect.height),
+                Rect::new(rect.x + first_width, rect.y, rect.width.saturating_sub(first_width), rect.height),
+            )
+        }
+    }
+}
+
 impl Default for Layout {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_ratios(node: &LayoutNode, ratios: &mut Vec<f32>) {
+        if let LayoutNode::Branch { ratio, first, second, .. } = node {
+            ratios.push(*ratio);
+            collect_ratios(first, ratios);
+            collect_ratios(second, ratios);
+        }
+    }
+
+    #[test]
+    fn test_balance_skewed_layout() {
+        // 2x2のグリッドを偏った比率で作成
+        let mut layout = Layout::new();
+        let right = layout.split_pane_with_ratio(0, SplitDirection::Vertical, 0.8).unwrap();
+        layout.split_pane_with_ratio(0, SplitDirection::Horizontal, 0.3).unwrap();
+        layout.split_pane_with_ratio(right, SplitDirection::Horizontal, 0.9).unwrap();
+
+        layout.balance();
+
+        let mut ratios = Vec::new();
+        collect_ratios(layout.root(), &mut ratios);
+        assert_eq!(ratios, vec![0.5, 0.5, 0.5]);
+
+        // 対称なツリーなので全ペインが同じ大きさになる
+        let rects = layout.calculate_rects(Rect::new(0, 0, 800, 600));
+        assert_eq!(rects.len(), 4);
+        for (_, rect) in rects {
+            assert_eq!((rect.width, rect.height), (400, 300));
+        }
+    }
+
+    #[test]
+    fn test_swap_panes() {
+        // 2x2のグリッド: 左上0、左下2、右上1、右下3
+        let mut layout = Layout::new();
+        let right = layout.split_pane(0, SplitDirection::Vertical).unwrap();
+        let bottom_left = layout.split_pane(0, SplitDirection::Horizontal).unwrap();
+        let bottom_right = layout.split_pane(right, SplitDirection::Horizontal).unwrap();
+        let window = Rect::new(0, 0, 800, 600);
+        let before = layout.calculate_rects(window);
+        let rect_of = |rects: &[(PaneId, Rect)], id: PaneId| rects.iter().find(|(pane_id, _)| *pane_id == id).unwrap().1;
+
+        assert!(layout.swap_panes(0, bottom_right));
+        let after = layout.calculate_rects(window);
+        assert_eq!(rect_of(&after, 0), rect_of(&before, bottom_right));
+        assert_eq!(rect_of(&after, bottom_right), rect_of(&before, 0));
+        // 他のペインの位置は変わらない
+        assert_eq!(rect_of(&after, right), rect_of(&before, right));
+        assert_eq!(rect_of(&after, bottom_left), rect_of(&before, bottom_left));
+
+        // 存在しないペインや同じペインとは入れ替えない
+        assert!(!layout.swap_panes(0, 9));
+        assert!(!layout.swap_panes(right, right));
+    }
+
+    #[test]
+    fn test_balance_repeated_splits() {
+        // 右端のペインを続けて縦に分割すると幅が半分ずつになっていく
+        let mut layout = Layout::new();
+        let second = layout.split_pane_with_ratio(0, SplitDirection::Vertical, 0.5).unwrap();
+        let third = layout.split_pane_with_ratio(second, SplitDirection::Vertical, 0.5).unwrap();
+        let fourth = layout.split_pane_with_ratio(third, SplitDirection::Vertical, 0.5).unwrap();
+        // 最初のペインの上下分割は1列と数える
+        layout.split_pane_with_ratio(0, SplitDirection::Horizontal, 0.2).unwrap();
+
+        layout.balance();
+
+        let mut ratios = Vec::new();
+        collect_ratios(layout.root(), &mut ratios);
+        assert_eq!(ratios, vec![0.25, 0.5, 1.0 / 3.0, 0.5]);
+
+        let rects = layout.calculate_rects(Rect::new(0, 0, 800, 600));
+        assert_eq!(rects.len(), 5);
+        for (id, rect) in rects {
+            assert_eq!(rect.width, 200, "pane {}", id);
+            let height = if id == 0 || id > fourth { 300 } else { 600 };
+            assert_eq!(rect.height, height, "pane {}", id);
+        }
+    }
+
+    #[test]
+    fn test_saved_layout_round_trip() {
+        // 上下に分けた下側を左右に分け、途中のペインを閉じてIDを飛ばす
+        let mut layout = Layout::new();
+        let bottom = layout.split_pane_with_ratio(0, SplitDirection::Horizontal, 0.7).unwrap();
+        let closed = layout.split_pane_with_ratio(bottom, SplitDirection::Vertical, 0.5).unwrap();
+        layout.split_pane_with_ratio(bottom, SplitDirection::Vertical, 0.4).unwrap();
+        layout.remove_pane(closed);
+
+        let yaml = serde_yaml::to_string(layout.root()).unwrap();
+        let ids = PaneIds::default();
+        ids.next();
+        let mut restored = Layout::from_saved(serde_yaml::from_str(&yaml).unwrap(), 0, ids).unwrap();
+
+        // 比率と形はそのままで、最初の葉以外のIDは払い出し元から振り直される
+        let window_rect = Rect::new(0, 0, 800, 600);
+        let rects: Vec<Rect> = layout.calculate_rects(window_rect).into_iter().map(|(_, rect)| rect).collect();
+        let restored_rects = restored.calculate_rects(window_rect);
+        assert_eq!(restored_rects.iter().map(|&(_, rect)| rect).collect::<Vec<_>>(), rects);
+        assert_eq!(restored.all_pane_ids(), vec![0, 1, 2]);
+        assert_eq!(restored.next_id(), 3);
+
+        let invalid: LayoutNode = serde_yaml::from_str(
+            "type: branch\ndirection: vertical\nratio: 1.5\nfirst: {type: leaf, pane_id: 0}\nsecond: {type: leaf, pane_id: 1}\n",
+        )
+        .unwrap();
+        assert!(Layout::from_saved(invalid, 0, PaneIds::default()).is_none());
+    }
+
+    #[test]
+    fn test_clamp_to_min_size() {
+        // 左右に分けた右側をさらに上下に分ける
+        let mut layout = Layout::new();
+        let right = layout.split_pane_with_ratio(0, SplitDirection::Vertical, 0.9).unwrap();
+        layout.split_pane_with_ratio(right, SplitDirection::Horizontal, 0.05).unwrap();
+
+        let window_rect = Rect::new(0, 0, 800, 600);
+        layout.clamp_to_min_size(window_rect, 200, 100);
+        for (id, rect) in layout.calculate_rects(window_rect) {
+            assert!(rect.width >= 200 && rect.height >= 100, "pane {}: {:?}", id, rect);
+        }
+        // 最小サイズを満たす比率は変えない
+        let before = layout.calculate_rects(window_rect);
+        layout.clamp_to_min_size(window_rect, 100, 50);
+        assert_eq!(layout.calculate_rects(window_rect), before);
+
+        // 領域が足りなければ比率はそのまま
+        let mut ratios = Vec::new();
+        collect_ratios(layout.root(), &mut ratios);
+        layout.clamp_to_min_size(window_rect, 500, 400);
+        let mut unchanged = Vec::new();
+        collect_ratios(layout.root(), &mut unchanged);
+        assert_eq!(unchanged, ratios);
+    }
+}
@@ -1,7 +1,26 @@
+use crate::pane::output_log::LogFormat;
 use crate::pane::{Pane, PaneId};
-use crate::pane::layout::{Layout, Rect, SplitDirection};
-use crate::utils::Result;
+use crate::renderer::backend::{Color, CursorStyle};
+use crate::terminal::palette::Palette;
+use crate::pane::layout::{Layout, Rect, SavedLayout, SplitDirection};
+use crate::terminal::pty::{OutputWaker, ShellCommand};
+use crate::terminal::TerminalEvent;
+use crate::utils::{Result, TerbulatorError};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// `pane.log_path` がないときにトグルしたペインのログを書く場所
+/// 共有の一時ディレクトリではなくユーザーごとの `$XDG_STATE_HOME/terbulator`（なければ
+/// `~/.local/state/terbulator`）に書き、同時に動く他のterbulatorと重ならないようにプロセスIDを含める
+fn default_log_path() -> PathBuf {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .unwrap_or_else(std::env::temp_dir);
+    state_dir.join("terbulator").join(format!("pane-{{pane}}-{}.log", std::process::id()))
+}
 
 /// ペイン管理マネージャー
 pub struct PaneManager {
@@ -9,24 +28,97 @@ pub struct PaneManager {
     layout: Layout,
     active_pane_id: PaneId,
     broadcast_enabled: bool,
-    shell: String,
+    shell: ShellCommand, // 新しいペインで起動するシェル（TERM・作業ディレクトリ・環境変数を含む）
     scrollback: usize,
+    max_panes: usize, // 同時に開けるペインの上限（0で無制限）
+    focus_flash: FocusFlash,
+    monitor: ActivityMonitor,
+    palette: Palette,
+    cursor_style: CursorStyle,
+    pin_prompt: bool,
+    hold_on_exit: bool, // 終了したペインを閉じずに終了状態を表示する
+    hold_message_colors: (Option<Color>, Option<Color>), // 終了状態の行の文字色と背景色
+    forward_unhandled: bool, // 未対応のOSC/DCSシーケンスをイベントとして報告する
+    cell_size: Option<(f32, f32)>, // 描画されるセルのピクセルサイズ（画像の大きさの計算用）
+    log_path: PathBuf, // 出力ログのパス（`{pane}` はペインIDに置き換える）
+    log_format: LogFormat,
+    log_new_panes: bool, // 作成したペインの出力を最初からログに書く
+    output_waker: Option<OutputWaker>, // PTYの出力でイベントループを起こす
+    min_pane_size: (usize, usize), // ペインの最小の（列数, 行数）
+    visual_bell: bool, // ベルが鳴ったペインを点滅させる
 }
 
 impl PaneManager {
-    /// 単一ペインで初期化
-    pub fn new(cols: usize, rows: usize, scrollback: usize, shell: String) -> Result<Self> {
+    /// 単一ペインで初期化（最初のペインだけ`initial_shell`で起動する）
+    pub fn new(cols: usize, rows: usize, scrollback: usize, shell: ShellCommand, initial_shell: &ShellCommand) -> Result<Self> {
+        let layout = Layout::new();
+        let initial_id = layout.all_pane_ids()[0];
         let mut panes = HashMap::new();
-        let initial_pane = Pane::new(0, cols, rows, scrollback, &shell)?;
-        panes.insert(0, initial_pane);
+        let initial_pane = Pane::new(initial_id, cols, rows, scrollback, initial_shell)?;
+        panes.insert(initial_id, initial_pane);
 
         Ok(Self {
             panes,
-            layout: Layout::new(),
-            active_pane_id: 0,
+            layout,
+            active_pane_id: initial_id,
             broadcast_enabled: false,
             shell,
             scrollback,
+            max_panes: 0,
+            focus_flash: FocusFlash::new(false),
+            monitor: ActivityMonitor::new(false, None),
+            palette: Palette::default(),
+            cursor_style: CursorStyle::default(),
+            pin_prompt: false,
+            hold_on_exit: false,
+            hold_message_colors: (None, None),
+            forward_unhandled: false,
+            cell_size: None,
+            log_path: default_log_path(),
+            log_format: LogFormat::default(),
+            log_new_panes: false,
+            output_waker: None,
+            min_pane_size: (1, 1),
+            visual_bell: false,
+        })
+    }
+
+    /// 同時に開けるペインの上限を設定（0で無制限）
+    pub fn set_max_panes(&mut self, max_panes: usize) {
+        self.max_panes = max_panes;
+    }
+
+    /// 同じ設定（シェル・色・ログ・監視など）で単一ペインの新しいマネージャーを作成（新しいタブ用）
+    /// ペインIDは同じ払い出し元から取るので、イベントやログのパスでタブ間のペインを区別できる
+    pub fn new_like(&self, cols: usize, rows: usize) -> Result<Self> {
+        let layout = Layout::with_ids(self.layout.pane_ids().clone());
+        let initial_id = layout.all_pane_ids()[0];
+        let mut panes = HashMap::new();
+        panes.insert(initial_id, self.spawn_pane(initial_id, cols, rows)?);
+
+        Ok(Self {
+            panes,
+            layout,
+            active_pane_id: initial_id,
+            broadcast_enabled: false,
+            shell: self.shell.clone(),
+            scrollback: self.scrollback,
+            max_panes: self.max_panes,
+            focus_flash: FocusFlash::new(self.focus_flash.enabled),
+            monitor: ActivityMonitor::new(self.monitor.activity_enabled, self.monitor.silence_threshold),
+            palette: self.palette.clone(),
+            cursor_style: self.cursor_style,
+            pin_prompt: self.pin_prompt,
+            hold_on_exit: self.hold_on_exit,
+            hold_message_colors: self.hold_message_colors,
+            forward_unhandled: self.forward_unhandled,
+            cell_size: self.cell_size,
+            log_path: self.log_path.clone(),
+            log_format: self.log_format,
+            log_new_panes: self.log_new_panes,
+            output_waker: self.output_waker.clone(),
+            min_pane_size: self.min_pane_size,
+            visual_bell: self.visual_bell,
         })
     }
 
@@ -45,6 +137,11 @@ impl PaneManager {
         self.panes.get_mut(&self.active_pane_id)
     }
 
+    /// アクティブなペインのアプリケーションが設定したウィンドウタイトル
+    pub fn active_pane_title(&self) -> Option<&str> {
+        self.active_pane().and_then(|pane| pane.title())
+    }
+
     /// 指定されたIDのペインを取得
     pub fn pane(&self, pane_id: PaneId) -> Option<&Pane> {
         self.panes.get(&pane_id)
@@ -84,9 +181,197 @@ impl PaneManager {
         log::info!("Broadcast mode: {}", if self.broadcast_enabled { "enabled" } else { "disabled" });
     }
 
+    /// フォーカス変更時のボーダー点滅を有効/無効にする
+    pub fn set_focus_flash_enabled(&mut self, enabled: bool) {
+        self.focus_flash.enabled = enabled;
+    }
+
+    /// フォーカス変更の点滅表示中か
+    pub fn is_focus_flash_active(&self) -> bool {
+        self.focus_flash.is_active(Instant::now())
+    }
+
+    /// ベル（BEL）が鳴ったペインを点滅させるか
+    pub fn set_visual_bell_enabled(&mut self, enabled: bool) {
+        self.visual_bell = enabled;
+    }
+
+    /// ビジュアルベルで点滅中のペインがあるか
+    pub fn is_visual_bell_active(&self) -> bool {
+        let now = Instant::now();
+        self.panes.values().any(|pane| pane.is_visual_bell_active(now))
+    }
+
+    /// 設定のカラーパレットを設定（既存ペインと今後作成するペインに適用）
+    pub fn set_palette(&mut self, palette: Palette) {
+        for pane in self.panes.values_mut() {
+            pane.terminal_mut().set_initial_palette(palette.clone());
+        }
+        self.palette = palette;
+    }
+
+    /// 設定のカーソル形状を設定（既存ペインと今後作成するペインに適用）
+    pub fn set_cursor_style(&mut self, style: CursorStyle) {
+        for pane in self.panes.values_mut() {
+            pane.terminal_mut().set_default_cursor_style(style);
+        }
+        self.cursor_style = style;
+    }
+
+    /// 実行中コマンドのプロンプト行の固定表示を有効/無効にする（既存ペインと今後作成するペインに適用）
+    pub fn set_pin_prompt(&mut self, enabled: bool) {
+        for pane in self.panes.values_mut() {
+            pane.terminal_mut().set_pin_prompt(enabled);
+        }
+        self.pin_prompt = enabled;
+    }
+
+    /// 未対応のOSC/DCSシーケンスを`take_events`で受け取るか
+    pub fn set_forward_unhandled(&mut self, enabled: bool) {
+        for pane in self.panes.values_mut() {
+            pane.terminal_mut().set_forward_unhandled(enabled);
+        }
+        self.forward_unhandled = enabled;
+    }
+
+    /// 描画されるセルのピクセルサイズ（画像が何セル分を占めるかの計算に使う）
+    pub fn set_cell_size(&mut self, cell_width: f32, cell_height: f32) {
+        for pane in self.panes.values_mut() {
+            pane.terminal_mut().set_cell_size(cell_width, cell_height);
+        }
+        self.cell_size = Some((cell_width, cell_height));
+    }
+
+    /// 出力ログの設定。`path` を指定すると既存ペインと今後作成するペインの出力を最初から書く
+    /// （指定しなければトグルしたペインだけをユーザーごとの状態ディレクトリに書く）
+    pub fn set_output_log(&mut self, path: Option<PathBuf>, format: LogFormat) {
+        self.log_format = format;
+        self.log_new_panes = path.is_some();
+        if let Some(path) = path {
+            self.log_path = path;
+            let ids: Vec<PaneId> = self.panes.keys().copied().collect();
+            for id in ids {
+                self.start_pane_log(id);
+            }
+        }
+    }
+
+    /// アクティブなペインのログを切り替え、ログ中になったかを返す
+    pub fn toggle_active_pane_log(&mut self) -> Result<bool> {
+        let path = self.pane_log_path(self.active_pane_id);
+        let format = self.log_format;
+        let Some(pane) = self.active_pane_mut() else {
+            return Ok(false);
+        };
+        if pane.is_logging() {
+            pane.stop_log();
+            Ok(false)
+        } else {
+            pane.start_log(&path, format)?;
+            Ok(true)
+        }
+    }
+
+    fn pane_log_path(&self, id: PaneId) -> PathBuf {
+        PathBuf::from(self.log_path.to_string_lossy().replace("{pane}", &id.to_string()))
+    }
+
+    /// ログファイルを開けなくてもペインはそのまま使えるようにする
+    fn start_pane_log(&mut self, id: PaneId) {
+        let path = self.pane_log_path(id);
+        let format = self.log_format;
+        if let Some(pane) = self.panes.get_mut(&id)
+            && !pane.is_logging()
+            && let Err(e) = pane.start_log(&path, format)
+        {
+            log::warn!("Failed to open log file {} for pane {}: {}", path.display(), id, e);
+        }
+    }
+
+    /// 全ペインのバッファに残ったログを、時間が来ていれば書き出す
+    pub fn flush_logs_if_due(&mut self) {
+        self.panes.values_mut().for_each(Pane::flush_log_if_due);
+    }
+
+    /// 次にいずれかのペインのログを書き出す時刻
+    pub fn next_log_flush(&self) -> Option<Instant> {
+        self.panes.values().filter_map(Pane::next_log_flush).min()
+    }
+
+    /// プロセスが終了したペインを、キー入力があるまで閉じずに残すか
+    pub fn set_hold_on_exit(&mut self, enabled: bool) {
+        self.hold_on_exit = enabled;
+    }
+
+    /// 残したペインの終了状態の行の文字色と背景色（Noneは既定の色）
+    pub fn set_hold_message_colors(&mut self, fg: Option<Color>, bg: Option<Color>) {
+        self.hold_message_colors = (fg, bg);
+    }
+
+    /// ペインの最小の列数と行数。分割はこれより小さいペインを作らず、境界のドラッグや
+    /// ウィンドウのリサイズでも可能な限りこの大きさを保つ
+    pub fn set_min_pane_size(&mut self, cols: usize, rows: usize) {
+        self.min_pane_size = (cols.max(1), rows.max(1));
+    }
+
+    /// 最小サイズのピクセル数（幅, 高さ）
+    fn min_pane_pixels(&self, cell_width: f32, cell_height: f32) -> (u32, u32) {
+        let (cols, rows) = self.min_pane_size;
+        ((cols as f32 * cell_width).ceil() as u32, (rows as f32 * cell_height).ceil() as u32)
+    }
+
+    /// PTYの出力が届いたときに呼ぶ関数を設定（既存ペインと今後作成するペインに適用）
+    pub fn set_output_waker(&mut self, waker: OutputWaker) {
+        for pane in self.panes.values() {
+            pane.set_output_waker(waker.clone());
+        }
+        self.output_waker = Some(waker);
+    }
+
+    /// 出力の監視設定（非アクティブペインのアクティビティ/無出力の検出）
+    pub fn set_monitoring(&mut self, activity: bool, silence: Option<Duration>) {
+        self.monitor.activity_enabled = activity;
+        self.monitor.silence_threshold = silence;
+    }
+
+    /// 前回フォーカスされて以降に出力があった非アクティブペイン
+    pub fn activity_panes(&self) -> Vec<PaneId> {
+        let mut ids: Vec<PaneId> = self.panes.keys()
+            .copied()
+            .filter(|id| *id != self.active_pane_id && self.monitor.has_activity(*id))
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 一定時間出力がない非アクティブペイン
+    pub fn silent_panes(&self) -> Vec<PaneId> {
+        let now = Instant::now();
+        let mut ids: Vec<PaneId> = self.panes.keys()
+            .copied()
+            .filter(|id| *id != self.active_pane_id && self.monitor.is_silent(*id, now))
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 次に非アクティブペインが無出力になる時刻（出力がなくてもタイトルを更新するため）
+    pub fn next_silence(&self) -> Option<Instant> {
+        let now = Instant::now();
+        self.panes.keys()
+            .filter(|id| **id != self.active_pane_id)
+            .filter_map(|id| self.monitor.silence_deadline(*id, now))
+            .min()
+    }
+
     /// アクティブペインを指定されたIDに設定
     pub fn set_active_pane(&mut self, pane_id: PaneId) -> bool {
         if self.panes.contains_key(&pane_id) {
+            if pane_id != self.active_pane_id {
+                self.focus_flash.trigger(Instant::now());
+            }
+            self.monitor.focus(pane_id, Instant::now());
+
             // 前のアクティブペインを非アクティブに
             if let Some(old_pane) = self.panes.get_mut(&self.active_pane_id) {
                 old_pane.set_active(false);
@@ -106,13 +391,22 @@ impl PaneManager {
     }
 
     /// アクティブペインを分割
-    pub fn split_active_pane(&mut self, direction: SplitDirection, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<PaneId> {
+    /// ペインが最小サイズより小さくなる場合は分割せずにNoneを返す
+    pub fn split_active_pane(&mut self, direction: SplitDirection, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<Option<PaneId>> {
         self.split_active_pane_with_ratio(direction, window_rect, cell_width, cell_height, 0.5)
     }
 
     /// アクティブペインを指定された比率で分割
-    pub fn split_active_pane_with_ratio(&mut self, direction: SplitDirection, window_rect: Rect, cell_width: f32, cell_height: f32, ratio: f32) -> Result<PaneId> {
+    /// ペインが最小サイズより小さくなる場合は分割せずにNoneを返す
+    pub fn split_active_pane_with_ratio(&mut self, direction: SplitDirection, window_rect: Rect, cell_width: f32, cell_height: f32, ratio: f32) -> Result<Option<PaneId>> {
         let active_id = self.active_pane_id;
+        let previous_layout = self.layout.clone();
+
+        // 上限に達している場合はレイアウトもPTYも変更せずに断る
+        if self.max_panes > 0 && self.panes.len() >= self.max_panes {
+            log::warn!("Cannot split pane {}: {} panes already open (pane.max_panes)", active_id, self.panes.len());
+            return Err(TerbulatorError::PaneLimit(self.max_panes));
+        }
 
         // レイアウトツリーを分割（新しいペインIDが返される）
         let new_id = if let Some(id) = self.layout.split_pane_with_ratio(active_id, direction, ratio) {
@@ -121,6 +415,19 @@ impl PaneManager {
             return Err(crate::utils::TerbulatorError::rendering("Failed to split pane in layout"));
         };
 
+        // 比率を調整しても最小サイズに収まらなければ元のレイアウトに戻す
+        let (min_width, min_height) = self.min_pane_pixels(cell_width, cell_height);
+        self.layout.clamp_to_min_size(window_rect, min_width, min_height);
+        let too_small = self.layout.calculate_rects(window_rect)
+            .iter()
+            .any(|(_, rect)| rect.width < min_width || rect.height < min_height);
+        if too_small {
+            self.layout = previous_layout;
+            let (cols, rows) = self.min_pane_size;
+            log::warn!("Not splitting pane {}: panes would be smaller than {}x{}", active_id, cols, rows);
+            return Ok(None);
+        }
+
         // 新しいペインの矩形を計算
         let rects = self.layout.calculate_rects(window_rect);
 
@@ -130,31 +437,66 @@ impl PaneManager {
             let cols = (new_rect.width as f32 / cell_width).max(1.0) as usize;
             let rows = (new_rect.height as f32 / cell_height).max(1.0) as usize;
             log::info!("Split active pane {}: new_id={}, cols={}, rows={}, rect={}x{}, cell={}x{}, shell={}",
-                active_id, new_id, cols, rows, new_rect.width, new_rect.height, cell_width, cell_height, self.shell);
-
-            let new_pane = match Pane::new(new_id, cols, rows, self.scrollback, &self.shell) {
-                Ok(pane) => {
-                    log::info!("Successfully created new pane {}", new_id);
-                    pane
-                }
-                Err(e) => {
-                    log::error!("Failed to create new pane {}: {}", new_id, e);
-                    return Err(e);
-                }
-            };
+                active_id, new_id, cols, rows, new_rect.width, new_rect.height, cell_width, cell_height, self.shell.program);
 
+            let new_pane = self.spawn_pane(new_id, cols, rows)?;
             self.panes.insert(new_id, new_pane);
 
             // 全ペインをリサイズ
             self.resize_all_panes(window_rect, cell_width, cell_height)?;
 
             log::info!("Split pane {} in {:?} direction, created pane {}", active_id, direction, new_id);
-            Ok(new_id)
+            Ok(Some(new_id))
         } else {
             Err(crate::utils::TerbulatorError::rendering("Failed to calculate new pane rect"))
         }
     }
 
+    /// 設定を適用した新しいペインを作成（シェルを起動）
+    fn spawn_pane(&self, id: PaneId, cols: usize, rows: usize) -> Result<Pane> {
+        let mut pane = match Pane::new(id, cols, rows, self.scrollback, &self.shell) {
+            Ok(pane) => {
+                log::info!("Successfully created new pane {}", id);
+                pane
+            }
+            Err(e) => {
+                log::error!("Failed to create new pane {}: {}", id, e);
+                return Err(e);
+            }
+        };
+
+        pane.terminal_mut().set_initial_palette(self.palette.clone());
+        pane.terminal_mut().set_default_cursor_style(self.cursor_style);
+        pane.terminal_mut().set_pin_prompt(self.pin_prompt);
+        pane.terminal_mut().set_forward_unhandled(self.forward_unhandled);
+        if let Some((cell_width, cell_height)) = self.cell_size {
+            pane.terminal_mut().set_cell_size(cell_width, cell_height);
+        }
+        if let Some(waker) = &self.output_waker {
+            pane.set_output_waker(waker.clone());
+        }
+        if self.log_new_panes {
+            let path = self.pane_log_path(id);
+            if let Err(e) = pane.start_log(&path, self.log_format) {
+                log::warn!("Failed to open log file {} for pane {}: {}", path.display(), id, e);
+            }
+        }
+        Ok(pane)
+    }
+
+    /// 終了したペインを同じ位置・サイズの新しいシェルで置き換える
+    pub fn respawn_pane(&mut self, pane_id: PaneId) -> Result<()> {
+        let Some(old_pane) = self.panes.get(&pane_id) else {
+            return Ok(());
+        };
+        let grid = old_pane.terminal().grid();
+        let mut pane = self.spawn_pane(pane_id, grid.cols(), grid.rows())?;
+        pane.set_active(pane_id == self.active_pane_id);
+        self.panes.insert(pane_id, pane);
+        log::info!("Respawned shell in pane {}", pane_id);
+        Ok(())
+    }
+
     /// 指定されたペインを閉じる
     pub fn close_pane(&mut self, pane_id: PaneId, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<bool> {
         // 最後のペインは閉じられない
@@ -171,6 +513,7 @@ impl PaneManager {
 
         // ペインを削除
         self.panes.remove(&pane_id);
+        self.monitor.remove(pane_id);
 
         // アクティブペインが削除された場合、次のペインをアクティブに
         if self.active_pane_id == pane_id {
@@ -191,6 +534,11 @@ impl PaneManager {
         self.close_pane(active_id, window_rect, cell_width, cell_height)
     }
 
+    /// いずれかのペインに未処理のPTY出力があるか
+    pub fn has_pending_output(&mut self) -> bool {
+        self.panes.values_mut().any(|pane| pane.has_pending_output())
+    }
+
     /// 全ペインのPTY出力を処理
     /// 戻り値: (has_output, should_exit)
     /// - has_output: 何らかの出力があったか
@@ -198,14 +546,21 @@ impl PaneManager {
     pub fn process_all_pty_output(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<(bool, bool)> {
         let mut has_any_output = false;
         let mut dead_panes = Vec::new();
+        let now = Instant::now();
 
         // PTY出力を処理し、終了したペインを収集
         for (pane_id, pane) in self.panes.iter_mut() {
+            // 残したペインは閉じられるのを待つだけ
+            if pane.is_held() {
+                continue;
+            }
+
             match pane.process_pty_output() {
                 Ok(has_output) => {
                     if has_output {
                         log::trace!("Pane {} has output", pane_id);
                     }
+                    self.monitor.observe(*pane_id, has_output, *pane_id == self.active_pane_id, now);
                     has_any_output = has_any_output || has_output;
                 }
                 Err(e) => {
@@ -216,7 +571,14 @@ impl PaneManager {
             // プロセスが終了しているかチェック
             if !pane.is_alive() {
                 log::info!("Pane {} is not alive", pane_id);
-                dead_panes.push(*pane_id);
+                if self.hold_on_exit {
+                    // ペインを閉じずに終了状態を表示する
+                    let (fg, bg) = self.hold_message_colors;
+                    pane.hold(fg, bg);
+                    has_any_output = true;
+                } else {
+                    dead_panes.push(*pane_id);
+                }
             }
         }
 
@@ -239,29 +601,136 @@ impl PaneManager {
         Ok((has_any_output, false))
     }
 
+    /// 全ペインの端末イベントを取り出す
+    pub fn take_events(&mut self) -> Vec<(PaneId, TerminalEvent)> {
+        let mut events = Vec::new();
+        for (pane_id, pane) in self.panes.iter_mut() {
+            events.extend(pane.take_events().into_iter().map(|event| (*pane_id, event)));
+        }
+        events
+    }
+
+    /// ビジュアルベルが有効なら、指定されたペインの点滅を始める
+    /// 戻り値: ペインがこのマネージャーにあるか
+    pub fn ring_visual_bell(&mut self, pane_id: PaneId) -> bool {
+        let visual_bell = self.visual_bell;
+        let Some(pane) = self.panes.get_mut(&pane_id) else {
+            return false;
+        };
+        if visual_bell {
+            pane.ring_visual_bell(Instant::now());
+        }
+        true
+    }
+
     /// 入力を送信（Broadcastモード対応）
-    pub fn write_input(&self, data: &[u8]) -> Result<()> {
+    /// 入力を受け取ったペインはスクロールバック表示から最新の画面に戻る
+    /// プロセスが終了して残っているペインには送らない
+    pub fn write_input(&mut self, data: &[u8]) -> Result<()> {
         if self.broadcast_enabled {
             // Broadcastモード: 全ペインに送信
-            for pane in self.panes.values() {
+            for pane in self.panes.values_mut().filter(|pane| !pane.is_held()) {
+                pane.terminal_mut().grid_mut().reset_view();
                 pane.write_input(data)?;
             }
             log::trace!("Broadcast input to {} panes: {} bytes", self.panes.len(), data.len());
         } else {
             // 通常モード: アクティブペインのみに送信
-            if let Some(pane) = self.panes.get(&self.active_pane_id) {
+            if let Some(pane) = self.panes.get_mut(&self.active_pane_id).filter(|pane| !pane.is_held()) {
+                pane.terminal_mut().grid_mut().reset_view();
                 pane.write_input(data)?;
             }
         }
         Ok(())
     }
 
+    /// 全ペインの大きさを均等にする
+    pub fn balance_panes(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<()> {
+        self.layout.balance();
+        self.resize_all_panes(window_rect, cell_width, cell_height)
+    }
+
+    /// レイアウト（分割の向きと比率、アクティブなペイン）をファイルに保存する
+    pub fn save_layout(&self, path: &Path) -> Result<()> {
+        let ids = self.layout.all_pane_ids();
+        let saved = SavedLayout {
+            panes: ids.len(),
+            active: ids.iter().position(|&id| id == self.active_pane_id).unwrap_or(0),
+            root: self.layout.root().clone(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_yaml::to_string(&saved)?)?;
+        log::info!("Saved layout of {} panes to {}", saved.panes, path.display());
+        Ok(())
+    }
+
+    /// 保存したレイアウトを復元し、最初のペイン以外の葉ごとに新しいシェルを起動する
+    /// （最初のペインだけのときに使う）。ファイルがない・読めない・ペインの数が合わない・
+    /// ペインの数が上限を超える場合は何もせずにfalseを返す
+    pub fn load_layout(&mut self, path: &Path, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<bool> {
+        if self.panes.len() != 1 {
+            return Ok(false);
+        }
+        let first_id = self.active_pane_id;
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to read saved layout {}: {}", path.display(), e);
+                }
+                return Ok(false);
+            }
+        };
+        let saved: SavedLayout = match serde_yaml::from_str(&content) {
+            Ok(saved) => saved,
+            Err(e) => {
+                log::warn!("Ignoring saved layout {}: {}", path.display(), e);
+                return Ok(false);
+            }
+        };
+        if self.max_panes > 0 && saved.panes > self.max_panes {
+            log::warn!(
+                "Saved layout {} has {} panes, more than pane.max_panes ({}), using the default layout",
+                path.display(), saved.panes, self.max_panes
+            );
+            return Ok(false);
+        }
+        let ids = self.layout.pane_ids().clone();
+        let Some(mut layout) = Layout::from_saved(saved.root, first_id, ids).filter(|layout| layout.all_pane_ids().len() == saved.panes) else {
+            log::warn!("Saved layout {} does not match its {} panes, using the default layout", path.display(), saved.panes);
+            return Ok(false);
+        };
+
+        // 新しいペインは復元するレイアウトでの大きさで起動する
+        let (min_width, min_height) = self.min_pane_pixels(cell_width, cell_height);
+        layout.clamp_to_min_size(window_rect, min_width, min_height);
+        let mut new_panes = Vec::new();
+        for (pane_id, rect) in layout.calculate_rects(window_rect).into_iter().filter(|&(id, _)| id != first_id) {
+            let cols = (rect.width as f32 / cell_width).max(1.0) as usize;
+            let rows = (rect.height as f32 / cell_height).max(1.0) as usize;
+            new_panes.push((pane_id, self.spawn_pane(pane_id, cols, rows)?));
+        }
+        self.panes.extend(new_panes);
+        let active = layout.all_pane_ids().get(saved.active).copied().unwrap_or(first_id);
+        self.layout = layout;
+        self.set_active_pane(active);
+        self.resize_all_panes(window_rect, cell_width, cell_height)?;
+        log::info!("Restored layout of {} panes from {}", saved.panes, path.display());
+        Ok(true)
+    }
+
     /// ウィンドウリサイズ時に全ペインをリサイズ
     pub fn resize_all_panes(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<()> {
+        let (min_width, min_height) = self.min_pane_pixels(cell_width, cell_height);
+        self.layout.clamp_to_min_size(window_rect, min_width, min_height);
         let rects = self.layout.calculate_rects(window_rect);
 
         log::debug!("Resizing all panes, window_rect: {}x{}, cell: {}x{}",
             window_rect.width, window_rect.height, cell_width, cell_height);
+        // フォントサイズの変更後に届く画像も正しいセル数で配置する
+        self.set_cell_size(cell_width, cell_height);
 
         for (pane_id, rect) in rects {
             if let Some(pane) = self.panes.get_mut(&pane_id) {
@@ -332,14 +801,55 @@ impl PaneManager {
         self.focus_direction(window_rect, Direction::Down)
     }
 
+    /// アクティブなペインを左のペインと入れ替える
+    pub fn swap_left(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<bool> {
+        self.swap_direction(window_rect, Direction::Left, cell_width, cell_height)
+    }
+
+    /// アクティブなペインを右のペインと入れ替える
+    pub fn swap_right(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<bool> {
+        self.swap_direction(window_rect, Direction::Right, cell_width, cell_height)
+    }
+
+    /// アクティブなペインを上のペインと入れ替える
+    pub fn swap_up(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<bool> {
+        self.swap_direction(window_rect, Direction::Up, cell_width, cell_height)
+    }
+
+    /// アクティブなペインを下のペインと入れ替える
+    pub fn swap_down(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<bool> {
+        self.swap_direction(window_rect, Direction::Down, cell_width, cell_height)
+    }
+
     /// 指定方向のペインにフォーカスを移動
     fn focus_direction(&mut self, window_rect: Rect, direction: Direction) -> bool {
+        if let Some(pane_id) = self.neighbor_in_direction(window_rect, direction) {
+            self.set_active_pane(pane_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// アクティブなペインを指定方向の隣のペインと入れ替える（フォーカスは移動したペインに残る）
+    fn swap_direction(&mut self, window_rect: Rect, direction: Direction, cell_width: f32, cell_height: f32) -> Result<bool> {
+        let Some(pane_id) = self.neighbor_in_direction(window_rect, direction) else {
+            return Ok(false);
+        };
+        // 端末はペインIDで引くので、葉のIDを入れ替えればpanesのキーはそのままでよい
+        self.layout.swap_panes(self.active_pane_id, pane_id);
+        self.resize_all_panes(window_rect, cell_width, cell_height)?;
+        Ok(true)
+    }
+
+    /// アクティブなペインから見て指定方向で最も近いペイン
+    fn neighbor_in_direction(&self, window_rect: Rect, direction: Direction) -> Option<PaneId> {
         let rects = self.layout.calculate_rects(window_rect);
 
         // 現在のペインの矩形を取得
         let current_rect = match rects.iter().find(|(id, _)| *id == self.active_pane_id) {
             Some((_, rect)) => rect,
-            None => return false,
+            None => return None,
         };
 
         // 現在のペインの中心座標
@@ -380,12 +890,7 @@ impl PaneManager {
             }
         }
 
-        if let Some(pane_id) = best_pane_id {
-            self.set_active_pane(pane_id);
-            true
-        } else {
-            false
-        }
+        best_pane_id
     }
 
     /// 指定された位置で境界をドラッグして分割比率を更新
@@ -400,11 +905,11 @@ impl PaneManager {
             let new_ratio = match direction {
                 SplitDirection::Horizontal => {
                     // 水平分割: y座標から比率を計算
-                    ((y - window_rect.y) as f32 / window_height).clamp(0.1, 0.9)
+                    (y.saturating_sub(window_rect.y) as f32 / window_height).clamp(0.1, 0.9)
                 }
                 SplitDirection::Vertical => {
                     // 垂直分割: x座標から比率を計算
-                    ((x - window_rect.x) as f32 / window_width).clamp(0.1, 0.9)
+                    (x.saturating_sub(window_rect.x) as f32 / window_width).clamp(0.1, 0.9)
                 }
             };
 
@@ -432,3 +937,451 @@ enum Direction {
     Up,
     Down,
 }
+
+/// フォーカス変更時の点滅時間
+const FOCUS_FLASH_DURATION: Duration = Duration::from_millis(200);
+
+/// フォーカス変更時のボーダー点滅状態
+///
+/// 連続したフォーカス変更では点滅が都度やり直されるため、
+/// 最後にフォーカスされたペインだけが一度点滅する（デバウンス）
+#[derive(Debug, Clone, Copy)]
+struct FocusFlash {
+    enabled: bool,
+    started_at: Option<Instant>,
+}
+
+impl FocusFlash {
+    fn new(enabled: bool) -> Self {
+        Self { enabled, started_at: None }
+    }
+
+    /// フォーカス変更を記録して点滅を開始
+    fn trigger(&mut self, now: Instant) {
+        if self.enabled {
+            self.started_at = Some(now);
+        }
+    }
+
+    /// 点滅表示中か
+    fn is_active(&self, now: Instant) -> bool {
+        self.started_at
+            .is_some_and(|started| now.saturating_duration_since(started) < FOCUS_FLASH_DURATION)
+    }
+}
+
+/// ペインごとの出力監視状態
+#[derive(Debug, Clone, Copy)]
+struct PaneActivity {
+    /// 最後に出力があった時刻（フォーカス時にもリセット）
+    quiet_since: Instant,
+    /// 非アクティブ中に出力があったか
+    has_activity: bool,
+}
+
+/// 非アクティブペインのアクティビティ/無出力の監視
+///
+/// tmuxのmonitor-activity / monitor-silence相当。
+/// フォーカスされるとフラグが消え、無出力の計測もやり直される
+#[derive(Debug, Clone)]
+struct ActivityMonitor {
+    activity_enabled: bool,
+    silence_threshold: Option<Duration>,
+    panes: HashMap<PaneId, PaneActivity>,
+}
+
+impl ActivityMonitor {
+    fn new(activity_enabled: bool, silence_threshold: Option<Duration>) -> Self {
+        Self { activity_enabled, silence_threshold, panes: HashMap::new() }
+    }
+
+    /// ペインの出力の有無を記録（初めて見るペインはこの時点から計測）
+    fn observe(&mut self, pane_id: PaneId, has_output: bool, focused: bool, now: Instant) {
+        let state = self.panes.entry(pane_id).or_insert(PaneActivity {
+            quiet_since: now,
+            has_activity: false,
+        });
+        if has_output {
+            state.quiet_since = now;
+            if self.activity_enabled && !focused {
+                state.has_activity = true;
+            }
+        }
+    }
+
+    /// フォーカスされたペインのフラグを消す
+    fn focus(&mut self, pane_id: PaneId, now: Instant) {
+        if let Some(state) = self.panes.get_mut(&pane_id) {
+            state.has_activity = false;
+            state.quiet_since = now;
+        }
+    }
+
+    fn remove(&mut self, pane_id: PaneId) {
+        self.panes.remove(&pane_id);
+    }
+
+    fn has_activity(&self, pane_id: PaneId) -> bool {
+        self.panes.get(&pane_id).is_some_and(|state| state.has_activity)
+    }
+
+    /// 無出力の時間がしきい値を超えているか
+    fn is_silent(&self, pane_id: PaneId, now: Instant) -> bool {
+        let (Some(threshold), Some(state)) = (self.silence_threshold, self.panes.get(&pane_id)) else {
+            return false;
+        };
+        now.saturating_duration_since(state.quiet_since) >= threshold
+    }
+
+    /// まだ無出力になっていないペインがしきい値に達する時刻
+    fn silence_deadline(&self, pane_id: PaneId, now: Instant) -> Option<Instant> {
+        let deadline = self.panes.get(&pane_id)?.quiet_since + self.silence_threshold?;
+        (deadline > now).then_some(deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> ShellCommand {
+        ShellCommand {
+            program: "/bin/sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            term: "dumb".to_string(),
+            ..ShellCommand::default()
+        }
+    }
+
+    #[test]
+    fn test_split_rejected_at_max_panes() {
+        let window_rect = Rect::new(0, 0, 400, 200);
+        let shell = ShellCommand {
+            program: "/bin/sh".to_string(),
+            term: "dumb".to_string(),
+            ..ShellCommand::default()
+        };
+        let mut manager = PaneManager::new(40, 10, 0, shell.clone(), &shell).unwrap();
+        manager.set_max_panes(2);
+        manager.split_active_pane(SplitDirection::Vertical, window_rect, 10.0, 20.0).unwrap();
+
+        // 3つ目は断られ、レイアウトもペインもそのまま
+        let ids = manager.layout().all_pane_ids();
+        let result = manager.split_active_pane(SplitDirection::Horizontal, window_rect, 10.0, 20.0);
+        assert!(matches!(result, Err(TerbulatorError::PaneLimit(2))));
+        assert_eq!(manager.layout().all_pane_ids(), ids);
+        assert_eq!(manager.panes().count(), 2);
+
+        // 閉じれば再び分割できる
+        manager.close_active_pane(window_rect, 10.0, 20.0).unwrap();
+        assert!(manager.split_active_pane(SplitDirection::Horizontal, window_rect, 10.0, 20.0).is_ok());
+    }
+
+    #[test]
+    fn test_focus_flash_on_focus_change() {
+        let mut flash = FocusFlash::new(true);
+        let now = Instant::now();
+        assert!(!flash.is_active(now));
+
+        flash.trigger(now);
+        assert!(flash.is_active(now));
+        assert!(flash.is_active(now + FOCUS_FLASH_DURATION / 2));
+        assert!(!flash.is_active(now + FOCUS_FLASH_DURATION));
+    }
+
+    #[test]
+    fn test_focus_flash_debounces_rapid_changes() {
+        let mut flash = FocusFlash::new(true);
+        let now = Instant::now();
+        flash.trigger(now);
+        flash.trigger(now + FOCUS_FLASH_DURATION / 2);
+
+        // 最後の変更から点滅時間が計測される
+        assert!(flash.is_active(now + FOCUS_FLASH_DURATION));
+        assert!(!flash.is_active(now + FOCUS_FLASH_DURATION * 2));
+    }
+
+    #[test]
+    fn test_focus_flash_disabled() {
+        let mut flash = FocusFlash::new(false);
+        let now = Instant::now();
+        flash.trigger(now);
+        assert!(!flash.is_active(now));
+    }
+
+    #[test]
+    fn test_visual_bell_flashes_ringing_pane() {
+        let shell = shell("sleep 10");
+        let mut manager = PaneManager::new(80, 10, 0, shell.clone(), &shell).unwrap();
+        let window_rect = Rect::new(0, 0, 800, 200);
+        let other = manager.split_active_pane(SplitDirection::Vertical, window_rect, 10.0, 20.0).unwrap().unwrap();
+
+        // イベントを取り出すだけでは点滅しない
+        manager.set_visual_bell_enabled(true);
+        manager.pane_mut(other).unwrap().terminal_mut().process_bytes(b"\x07");
+        assert_eq!(manager.take_events(), [(other, TerminalEvent::Bell)]);
+        assert!(!manager.is_visual_bell_active());
+
+        // 無効なときは点滅しない
+        manager.set_visual_bell_enabled(false);
+        assert!(manager.ring_visual_bell(other));
+        assert!(!manager.is_visual_bell_active());
+        assert!(!manager.ring_visual_bell(99));
+
+        // 有効なときは指定されたペインだけ点滅する
+        manager.set_visual_bell_enabled(true);
+        assert!(manager.ring_visual_bell(other));
+        let now = Instant::now();
+        assert!(manager.pane(other).unwrap().is_visual_bell_active(now));
+        assert!(!manager.pane(0).unwrap().is_visual_bell_active(now));
+        assert!(!manager.pane(other).unwrap().is_visual_bell_active(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_activity_in_unfocused_pane() {
+        let mut monitor = ActivityMonitor::new(true, None);
+        let now = Instant::now();
+        monitor.observe(0, true, true, now);
+        monitor.observe(1, true, false, now);
+
+        // フォーカス中のペインの出力はアクティビティにならない
+        assert!(!monitor.has_activity(0));
+        assert!(monitor.has_activity(1));
+
+        // フォーカスするとフラグが消える
+        monitor.focus(1, now);
+        assert!(!monitor.has_activity(1));
+    }
+
+    #[test]
+    fn test_activity_monitoring_disabled() {
+        let mut monitor = ActivityMonitor::new(false, None);
+        monitor.observe(1, true, false, Instant::now());
+        assert!(!monitor.has_activity(1));
+    }
+
+    #[test]
+    fn test_silence_detection() {
+        let threshold = Duration::from_secs(10);
+        let mut monitor = ActivityMonitor::new(false, Some(threshold));
+        let now = Instant::now();
+        monitor.observe(1, true, false, now);
+        assert!(!monitor.is_silent(1, now + threshold / 2));
+        assert!(monitor.is_silent(1, now + threshold));
+
+        // 出力があると計測がやり直される
+        monitor.observe(1, true, false, now + threshold);
+        assert!(!monitor.is_silent(1, now + threshold + threshold / 2));
+
+        // フォーカスでも計測がやり直される
+        monitor.focus(1, now + threshold * 3);
+        assert!(!monitor.is_silent(1, now + threshold * 3));
+
+        // 次に無出力になる時刻で起こされる。無出力になった後は起こさない
+        assert_eq!(monitor.silence_deadline(1, now + threshold * 3), Some(now + threshold * 4));
+        assert_eq!(monitor.silence_deadline(1, now + threshold * 4), None);
+    }
+
+    #[test]
+    fn test_hold_on_exit_keeps_last_pane() {
+        let shell = shell("exit 3");
+        let mut manager = PaneManager::new(60, 5, 0, shell.clone(), &shell).unwrap();
+        manager.set_hold_on_exit(true);
+
+        // 終了を検出するまでポーリングする（アプリケーションは終了しない）
+        let window_rect = Rect::new(0, 0, 600, 100);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !manager.active_pane().unwrap().is_held() && Instant::now() < deadline {
+            let (_, should_exit) = manager.process_all_pty_output(window_rect, 10.0, 20.0).unwrap();
+            assert!(!should_exit);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let pane = manager.active_pane().unwrap();
+        assert!(pane.is_held());
+        assert_eq!(pane.exit_status().map(|status| status.exit_code()), Some(3));
+        let grid = pane.terminal().grid();
+        let text: String = (0..grid.rows())
+            .flat_map(|row| grid.get_row(row).unwrap().iter().filter(|cell| !cell.is_continuation()).map(|cell| cell.ch))
+            .collect();
+        assert!(text.contains("[process exited with code 3 — press any key to close]"), "{:?}", text);
+
+        // 残したペインには入力を送らず、出力待ちにもならない
+        assert!(manager.write_input(b"x").is_ok());
+        assert!(!manager.has_pending_output());
+    }
+
+    #[test]
+    fn test_hold_after_alt_screen_program() {
+        // 代替画面で色と反転を有効にしたまま終了するプログラム
+        let shell = shell("printf 'shell\\n\\033[?1049h\\033[7;31;44mfullscreen'");
+        let mut manager = PaneManager::new(60, 5, 0, shell.clone(), &shell).unwrap();
+        manager.set_hold_on_exit(true);
+
+        let window_rect = Rect::new(0, 0, 600, 100);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !manager.active_pane().unwrap().is_held() && Instant::now() < deadline {
+            manager.process_all_pty_output(window_rect, 10.0, 20.0).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // 終了状態は通常画面に既定の属性で表示される
+        let terminal = manager.active_pane().unwrap().terminal();
+        assert!(!terminal.is_alt_screen());
+        let grid = terminal.grid();
+        let row = (0..grid.rows()).find(|&row| grid.get(0, row).unwrap().ch == '[').unwrap();
+        let banner = grid.get_row(row).unwrap();
+        let text: String = banner.iter().map(|cell| cell.ch).collect();
+        assert!(text.starts_with("[process exited with code 0"), "{:?}", text);
+        assert!(!(0..grid.rows()).any(|row| grid.get(0, row).unwrap().ch == 'f'));
+        let palette = terminal.palette();
+        for cell in &banner[..10] {
+            assert_eq!((cell.fg, cell.bg), (palette.foreground, palette.background));
+            assert_eq!(cell.attrs, Default::default());
+        }
+    }
+
+    #[test]
+    fn test_title_follows_active_pane() {
+        let shell = shell("sleep 10");
+        let mut manager = PaneManager::new(40, 10, 0, shell.clone(), &shell).unwrap();
+        let window_rect = Rect::new(0, 0, 800, 200);
+        let second = manager.split_active_pane(SplitDirection::Vertical, window_rect, 10.0, 20.0).unwrap().unwrap();
+
+        // ペインごとに別のタイトルスタックを持つ
+        manager.pane_mut(0).unwrap().terminal_mut().process_bytes(b"\x1b]2;first\x07");
+        manager.pane_mut(second).unwrap().terminal_mut().process_bytes(b"\x1b]2;main\x07\x1b[22t\x1b]2;ssh\x07");
+
+        manager.set_active_pane(second);
+        assert_eq!(manager.active_pane_title(), Some("ssh"));
+        manager.pane_mut(second).unwrap().terminal_mut().process_bytes(b"\x1b[23t");
+        assert_eq!(manager.active_pane_title(), Some("main"));
+        manager.set_active_pane(0);
+        assert_eq!(manager.active_pane_title(), Some("first"));
+    }
+
+    #[test]
+    fn test_split_below_min_size_rejected() {
+        let shell = shell("sleep 10");
+        // 80x10セルのウィンドウ
+        let mut manager = PaneManager::new(80, 10, 0, shell.clone(), &shell).unwrap();
+        manager.set_min_pane_size(20, 5);
+        let window_rect = Rect::new(0, 0, 800, 200);
+
+        // 40x10 と 40x5 までは分割できる
+        assert!(manager.split_active_pane(SplitDirection::Vertical, window_rect, 10.0, 20.0).unwrap().is_some());
+        assert!(manager.split_active_pane(SplitDirection::Horizontal, window_rect, 10.0, 20.0).unwrap().is_some());
+
+        // 5行のペインはこれ以上分割できず、レイアウトもペインも変わらない
+        let before = manager.layout().calculate_rects(window_rect);
+        let active = manager.active_pane_id();
+        assert!(manager.split_active_pane(SplitDirection::Horizontal, window_rect, 10.0, 20.0).unwrap().is_none());
+        assert_eq!(manager.layout().calculate_rects(window_rect), before);
+        assert_eq!(manager.panes().count(), 3);
+        assert_eq!(manager.active_pane_id(), active);
+
+        // 偏った比率は最小サイズを満たすように調整される
+        manager.set_active_pane(0);
+        let new_id = manager.split_active_pane_with_ratio(SplitDirection::Vertical, window_rect, 10.0, 20.0, 0.1).unwrap().unwrap();
+        let rects = manager.layout().calculate_rects(window_rect);
+        assert!(rects.iter().all(|(_, rect)| rect.width >= 200 && rect.height >= 100));
+        assert!(rects.iter().any(|(id, _)| *id == new_id));
+    }
+
+    #[test]
+    fn test_swap_with_neighbor() {
+        let shell = shell("sleep 10");
+        let mut manager = PaneManager::new(80, 10, 0, shell.clone(), &shell).unwrap();
+        let window_rect = Rect::new(0, 0, 800, 200);
+        // 左60列、右20列
+        let right = manager.split_active_pane_with_ratio(SplitDirection::Vertical, window_rect, 10.0, 20.0, 0.75).unwrap().unwrap();
+        assert!(!manager.swap_left(window_rect, 10.0, 20.0).unwrap());
+
+        // 端末は入れ替えた位置の大きさになり、フォーカスは元のペインのまま
+        assert!(manager.swap_right(window_rect, 10.0, 20.0).unwrap());
+        assert_eq!(manager.active_pane_id(), 0);
+        assert_eq!(manager.layout().all_pane_ids(), vec![right, 0]);
+        assert_eq!(manager.pane(0).unwrap().terminal().grid().cols(), 20);
+        assert_eq!(manager.pane(right).unwrap().terminal().grid().cols(), 60);
+    }
+
+    #[test]
+    fn test_save_and_load_layout() {
+        let shell = shell("sleep 10");
+        let path = std::env::temp_dir().join(format!("terbulator-layout-{}.yaml", std::process::id()));
+        let window_rect = Rect::new(0, 0, 800, 600);
+
+        // 3ペインのレイアウトを保存
+        let mut manager = PaneManager::new(80, 30, 0, shell.clone(), &shell).unwrap();
+        manager.split_active_pane_with_ratio(SplitDirection::Vertical, window_rect, 10.0, 20.0, 0.3).unwrap();
+        let bottom = manager.split_active_pane_with_ratio(SplitDirection::Horizontal, window_rect, 10.0, 20.0, 0.6).unwrap().unwrap();
+        manager.set_active_pane(bottom);
+        manager.save_layout(&path).unwrap();
+        let only_rects = |manager: &PaneManager| {
+            manager.layout().calculate_rects(window_rect).into_iter().map(|(_, rect)| rect).collect::<Vec<_>>()
+        };
+
+        // 新しいセッションではペインごとに新しいシェルが起動する（IDは葉の順に振り直す）
+        let mut restored = PaneManager::new(80, 30, 0, shell.clone(), &shell).unwrap();
+        assert!(restored.load_layout(&path, window_rect, 10.0, 20.0).unwrap());
+        assert_eq!(restored.panes().count(), 3);
+        assert_eq!(only_rects(&restored), only_rects(&manager));
+        assert_eq!(restored.active_pane_rect(window_rect), manager.active_pane_rect(window_rect));
+        assert_eq!(restored.active_pane().unwrap().terminal().grid().rows(), 12);
+
+        // 上限より多いペインのレイアウトは使わない
+        let mut limited = PaneManager::new(80, 30, 0, shell.clone(), &shell).unwrap();
+        limited.set_max_panes(2);
+        assert!(!limited.load_layout(&path, window_rect, 10.0, 20.0).unwrap());
+        assert_eq!(limited.panes().count(), 1);
+        limited.set_max_panes(3);
+        assert!(limited.load_layout(&path, window_rect, 10.0, 20.0).unwrap());
+
+        // ペインの数が合わないファイルやファイルがなければ最初のペインのまま
+        std::fs::write(&path, std::fs::read_to_string(&path).unwrap().replace("panes: 3", "panes: 4")).unwrap();
+        let mut fallback = PaneManager::new(80, 30, 0, shell.clone(), &shell).unwrap();
+        assert!(!fallback.load_layout(&path, window_rect, 10.0, 20.0).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(!fallback.load_layout(&path, window_rect, 10.0, 20.0).unwrap());
+        assert_eq!(fallback.panes().count(), 1);
+    }
+
+    #[test]
+    fn test_toggle_pane_log() {
+        let shell = shell("printf zero; read a; printf one; read a; printf two; sleep 10");
+        let mut manager = PaneManager::new(60, 5, 0, shell.clone(), &shell).unwrap();
+        let log_path = std::env::temp_dir().join(format!("terbulator-log-{}-{{pane}}.log", std::process::id()));
+        manager.set_output_log(None, LogFormat::Plain);
+        manager.log_path = log_path;
+
+        // 画面に `text` が出るまで出力を処理する
+        let window_rect = Rect::new(0, 0, 600, 100);
+        let wait_for = |manager: &mut PaneManager, text: &str| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                manager.process_all_pty_output(window_rect, 10.0, 20.0).unwrap();
+                let grid = manager.active_pane().unwrap().terminal().grid();
+                let screen: String = (0..grid.rows()).flat_map(|row| grid.get_row(row).unwrap().iter().map(|cell| cell.ch)).collect();
+                if screen.contains(text) {
+                    break;
+                }
+                assert!(Instant::now() < deadline, "{:?} not shown: {:?}", text, screen);
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        wait_for(&mut manager, "zero");
+        assert!(manager.toggle_active_pane_log().unwrap());
+        manager.write_input(b"\n").unwrap();
+        wait_for(&mut manager, "one");
+        assert!(!manager.toggle_active_pane_log().unwrap());
+        manager.write_input(b"\n").unwrap();
+        wait_for(&mut manager, "two");
+
+        // ログ中の出力だけが書かれ、止めるときに書き出される
+        let path = manager.pane_log_path(0);
+        let logged = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(logged, "\none");
+    }
+}
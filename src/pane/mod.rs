@@ -1,7 +1,11 @@
 pub mod pane;
 pub mod layout;
 pub mod manager;
+pub mod output_log;
+pub mod tabs;
 
 pub use pane::{Pane, PaneId};
 pub use layout::{SplitDirection, Rect};
 pub use manager::PaneManager;
+pub use output_log::LogFormat;
+pub use tabs::TabManager;
@@ -0,0 +1,214 @@
+use crate::utils::Result;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// バッファした出力をファイルに書き出す間隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// ログファイルに書き出す内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// PTYの出力をエスケープシーケンスも含めてそのまま書く
+    #[default]
+    Raw,
+    /// エスケープシーケンスと制御文字を除いたテキストだけを書く
+    Plain,
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(Self::Raw),
+            "plain" => Some(Self::Plain),
+            _ => None,
+        }
+    }
+}
+
+/// エスケープシーケンスの途中でPTYの読み込みが区切られても除去できるように状態を持つ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum FilterState {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    /// OSC・DCS・APC などのBELかSTで終わる文字列
+    String,
+    StringEscape,
+}
+
+/// プレーンテキストのログ用に、出力からエスケープシーケンスと制御文字を取り除く
+#[derive(Debug, Default)]
+struct PlainTextFilter {
+    state: FilterState,
+}
+
+impl PlainTextFilter {
+    fn filter(&mut self, bytes: &[u8], text: &mut Vec<u8>) {
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (FilterState::Ground, 0x1b) => FilterState::Escape,
+                (FilterState::Ground, b'\n' | b'\t') => {
+                    text.push(byte);
+                    FilterState::Ground
+                }
+                (FilterState::Ground, 0x00..=0x1f | 0x7f) => FilterState::Ground,
+                (FilterState::Ground, _) => {
+                    text.push(byte);
+                    FilterState::Ground
+                }
+                (FilterState::Escape, b'[') => FilterState::Csi,
+                (FilterState::Escape, b']' | b'P' | b'_' | b'^' | b'X') => FilterState::String,
+                // `ESC ( 0` などの中間バイトの後に終端バイトが続く
+                (FilterState::Escape, 0x20..=0x2f) => FilterState::Escape,
+                (FilterState::Escape, _) => FilterState::Ground,
+                (FilterState::Csi, 0x40..=0x7e) => FilterState::Ground,
+                (FilterState::Csi, _) => FilterState::Csi,
+                (FilterState::String | FilterState::StringEscape, 0x07) => FilterState::Ground,
+                (FilterState::String | FilterState::StringEscape, 0x1b) => FilterState::StringEscape,
+                (FilterState::StringEscape, b'\\') => FilterState::Ground,
+                (FilterState::String | FilterState::StringEscape, _) => FilterState::String,
+            };
+        }
+    }
+}
+
+/// ペインの出力を書き写すログファイル
+pub struct PaneLog {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    filter: Option<PlainTextFilter>,
+    last_flush: Instant,
+    /// バッファにファイルへ書き出していない出力があるか
+    unflushed: bool,
+}
+
+impl PaneLog {
+    /// 追記モードでログファイルを開く（なければディレクトリごと作る）
+    /// 端末の出力には秘密も含まれるので、unixでは本人だけが読めるように作り、
+    /// 他人が置いたシンボリックリンクはたどらない
+    pub fn open(path: &Path, format: LogFormat) -> Result<Self> {
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+            if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::DirBuilder::new().recursive(true).mode(0o700).create(parent)?;
+            }
+            options.mode(0o600).custom_flags(libc::O_NOFOLLOW);
+        }
+        #[cfg(not(unix))]
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = options.open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            filter: (format == LogFormat::Plain).then(PlainTextFilter::default),
+            last_flush: Instant::now(),
+            unflushed: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// PTYから読んだバイト列を書く。前回から `FLUSH_INTERVAL` 経っていればファイルにも書き出す
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        match &mut self.filter {
+            Some(filter) => {
+                let mut text = Vec::with_capacity(bytes.len());
+                filter.filter(bytes, &mut text);
+                self.writer.write_all(&text)?;
+            }
+            None => self.writer.write_all(bytes)?,
+        }
+        self.unflushed = true;
+        self.flush_if_due()
+    }
+
+    /// 出力が止まっている間もバッファに残った分を書き出す
+    pub fn flush_if_due(&mut self) -> Result<()> {
+        if self.unflushed && self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// バッファに残った出力を書き出す時刻（残っていなければNone）
+    pub fn next_flush(&self) -> Option<Instant> {
+        self.unflushed.then(|| self.last_flush + FLUSH_INTERVAL)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.last_flush = Instant::now();
+        self.unflushed = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(chunks: &[&[u8]]) -> String {
+        let mut filter = PlainTextFilter::default();
+        let mut text = Vec::new();
+        for chunk in chunks {
+            filter.filter(chunk, &mut text);
+        }
+        String::from_utf8(text).unwrap()
+    }
+
+    #[test]
+    fn test_plain_text_filter() {
+        assert_eq!(plain(&[b"\x1b[1;31mred\x1b[0m\r\n\tok\x07\n"]), "red\n\tok\n");
+        assert_eq!(plain(&[b"\x1b]0;title\x07a\x1bP$qm\x1b\\b\x1b(0c\x1b7d"]), "abcd");
+        // シーケンスが読み込みの境界で分かれても取り除く
+        assert_eq!(plain(&[b"x\x1b[3", b"8;5;1", b"my\x1b]8;;http", b"s://e\x1b", b"\\z"]), "xyz");
+        assert_eq!(plain(&["漢字\x1b[K".as_bytes()]), "漢字");
+    }
+
+    #[test]
+    fn test_flush_deadline() {
+        let path = std::env::temp_dir().join(format!("terbulator-log-flush-{}.log", std::process::id()));
+        let mut log = PaneLog::open(&path, LogFormat::Raw).unwrap();
+        assert_eq!(log.next_flush(), None);
+
+        // 書いた出力は前回の書き出しから `FLUSH_INTERVAL` 後に書き出す
+        log.last_flush = Instant::now();
+        log.write(b"output").unwrap();
+        assert_eq!(log.next_flush(), Some(log.last_flush + FLUSH_INTERVAL));
+        log.last_flush -= FLUSH_INTERVAL;
+        log.flush_if_due().unwrap();
+        assert_eq!(log.next_flush(), None);
+        assert_eq!(std::fs::read(&path).unwrap(), b"output");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_is_private_and_refuses_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("terbulator-log-open-{}", std::process::id()));
+        let path = dir.join("logs/pane.log");
+        let mut log = PaneLog::open(&path, LogFormat::Raw).unwrap();
+        log.write(b"secret").unwrap();
+        log.flush().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::metadata(path.parent().unwrap()).unwrap().permissions().mode() & 0o777, 0o700);
+
+        // 置かれたシンボリックリンクの先には書かない
+        let link = dir.join("logs/link.log");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        assert!(PaneLog::open(&link, LogFormat::Raw).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
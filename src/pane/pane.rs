@@ -1,8 +1,16 @@
-use crate::terminal::{PtyController, TerminalEmulator};
+use crate::pane::output_log::{LogFormat, PaneLog};
+use crate::renderer::backend::Color;
+use crate::terminal::pty::{OutputWaker, ShellCommand};
+use crate::terminal::{PtyController, TerminalEmulator, TerminalEvent};
 use crate::utils::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 pub type PaneId = usize;
 
+/// ビジュアルベルでペインを点滅させる時間
+const VISUAL_BELL_DURATION: Duration = Duration::from_millis(150);
+
 /// 個別のペイン（独立した端末エミュレータとPTYを持つ）
 pub struct Pane {
     id: PaneId,
@@ -10,11 +18,40 @@ pub struct Pane {
     pty: PtyController,
     is_active: bool,
     needs_redraw: bool, // Whether this pane needs to be redrawn
+    reported_dropped_bytes: usize, // Dropped PTY output already logged
+    tint: Option<Color>, // 背景に混ぜる色（本番環境などの目印）
+    held: bool, // プロセス終了後もキー入力まで閉じずに残している
+    log: Option<PaneLog>, // 出力を書き写すログファイル
+    visual_bell_at: Option<Instant>, // ビジュアルベルの点滅を始めた時刻
+}
+
+/// 終了後も残したペインに表示する行
+fn exit_message(status: Option<&portable_pty::ExitStatus>) -> String {
+    let description = match status {
+        Some(status) => match status.to_string().strip_prefix("Terminated by ") {
+            Some(signal) => format!("terminated by {}", signal),
+            None => format!("exited with code {}", status.exit_code()),
+        },
+        None => "exited".to_string(),
+    };
+    format!("[process {} — press any key to close]", description)
+}
+
+/// 終了状態の行の色を指定するSGR（指定がなければ既定の色のまま）
+fn message_sgr(fg: Option<Color>, bg: Option<Color>) -> String {
+    let mut sgr = String::from("\x1b[0m");
+    if let Some(fg) = fg {
+        sgr.push_str(&format!("\x1b[38;2;{};{};{}m", fg.r, fg.g, fg.b));
+    }
+    if let Some(bg) = bg {
+        sgr.push_str(&format!("\x1b[48;2;{};{};{}m", bg.r, bg.g, bg.b));
+    }
+    sgr
 }
 
 impl Pane {
-    pub fn new(id: PaneId, cols: usize, rows: usize, scrollback: usize, shell: &str) -> Result<Self> {
-        log::info!("Creating pane {} with size {}x{}, shell: {}", id, cols, rows, shell);
+    pub fn new(id: PaneId, cols: usize, rows: usize, scrollback: usize, shell: &ShellCommand) -> Result<Self> {
+        log::info!("Creating pane {} with size {}x{}, shell: {}", id, cols, rows, shell.program);
         let terminal = TerminalEmulator::new(cols, rows, scrollback);
 
         log::info!("Initializing PTY for pane {}", id);
@@ -37,6 +74,11 @@ impl Pane {
             pty,
             is_active: false,
             needs_redraw: true, // Initial draw needed
+            reported_dropped_bytes: 0,
+            tint: None,
+            held: false,
+            log: None,
+            visual_bell_at: None,
         })
     }
 
@@ -48,6 +90,11 @@ impl Pane {
         &self.terminal
     }
 
+    /// アプリケーションが設定したウィンドウタイトル
+    pub fn title(&self) -> Option<&str> {
+        self.terminal.dynamic_title()
+    }
+
     pub fn terminal_mut(&mut self) -> &mut TerminalEmulator {
         &mut self.terminal
     }
@@ -56,6 +103,17 @@ impl Pane {
         &mut self.pty
     }
 
+    /// 背景に混ぜる色
+    pub fn tint(&self) -> Option<Color> {
+        self.tint
+    }
+
+    /// 背景に混ぜる色を設定（Noneで解除）
+    pub fn set_tint(&mut self, tint: Option<Color>) {
+        self.tint = tint;
+        self.needs_redraw = true;
+    }
+
     pub fn is_active(&self) -> bool {
         self.is_active
     }
@@ -84,10 +142,50 @@ impl Pane {
         let alive = self.pty.is_alive();
         if !alive {
             log::info!("Pane {} PTY process has exited", self.id);
+            // 終了したプロセスが有効にしたままの入力モードを解除
+            self.terminal.reset_input_modes();
         }
         alive
     }
 
+    /// PTYのプロセスの終了状態（終了を検出した後のみ）
+    pub fn exit_status(&self) -> Option<&portable_pty::ExitStatus> {
+        self.pty.exit_status()
+    }
+
+    /// 終了したペインを閉じずに残し、終了状態を `fg` / `bg` の色で表示する
+    pub fn hold(&mut self, fg: Option<Color>, bg: Option<Color>) {
+        // 終了前に出力された分を先に表示する
+        while let Ok(true) = self.process_pty_output() {}
+
+        // 代替画面や色を残したまま終了したプログラムの後でも読めるように、
+        // 通常画面と既定の属性に戻してから表示する
+        self.terminal.reset_after_exit();
+        let message = exit_message(self.exit_status());
+        log::info!("Pane {} held open: {}", self.id, message);
+        self.terminal
+            .process_bytes(format!("\r\n{}{}\x1b[0m\x1b[?25l", message_sgr(fg, bg), message).as_bytes());
+        self.terminal.grid_mut().reset_view();
+        self.needs_redraw = true;
+        self.held = true;
+    }
+
+    /// プロセスが終了し、キー入力で閉じるのを待っているか
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// PTYの出力が届いたときにイベントループを起こす
+    pub fn set_output_waker(&self, waker: OutputWaker) {
+        self.pty.set_output_waker(waker);
+    }
+
+    /// PTYに未処理の出力があるか
+    pub fn has_pending_output(&mut self) -> bool {
+        // 終了したPTYはEOFを返し続けるため、残したペインには出力がないものとする
+        !self.held && self.pty.has_pending_output()
+    }
+
     pub fn process_pty_output(&mut self) -> Result<bool> {
         let mut buf = [0u8; 4096];
         let mut has_output = false;
@@ -99,7 +197,14 @@ impl Pane {
             match self.pty.read(&mut buf) {
                 Ok(n) if n > 0 => {
                     self.terminal.process_bytes(&buf[..n]);
+                    self.write_log(&buf[..n]);
                     has_output = true;
+
+                    // Send replies to queries (e.g. DECRQSS) back to the application
+                    let responses = self.terminal.take_responses();
+                    if !responses.is_empty() {
+                        self.pty.write(&responses)?;
+                    }
                     total_read += n;
 
                     // Stop if we've read enough for this frame to remain responsive
@@ -134,22 +239,94 @@ impl Pane {
             log::trace!("Pane {} read {} bytes from PTY", self.id, total_read);
         }
 
-        // Mark for redraw if there was output
+        // Report output lost while the main loop was stalled
+        let dropped_bytes = self.pty.dropped_bytes();
+        if dropped_bytes > self.reported_dropped_bytes {
+            log::warn!("Pane {} dropped {} bytes of PTY output (total {})",
+                self.id, dropped_bytes - self.reported_dropped_bytes, dropped_bytes);
+            self.reported_dropped_bytes = dropped_bytes;
+        }
+
+        if !has_output {
+            self.flush_log_if_due();
+        }
+
+        // Mark for redraw if there was output, showing the live screen again
         if has_output {
+            self.terminal.grid_mut().reset_view();
             self.needs_redraw = true;
         }
 
         Ok(has_output)
     }
 
+    /// 以降の出力を `path` に追記する
+    pub fn start_log(&mut self, path: &Path, format: LogFormat) -> Result<()> {
+        self.log = Some(PaneLog::open(path, format)?);
+        log::info!("Pane {} logging output to {}", self.id, path.display());
+        Ok(())
+    }
+
+    /// ログを止め、バッファに残った出力を書き出す
+    pub fn stop_log(&mut self) {
+        if let Some(mut log) = self.log.take() {
+            if let Err(e) = log.flush() {
+                log::warn!("Pane {} failed to flush {}: {}", self.id, log.path().display(), e);
+            }
+            log::info!("Pane {} stopped logging to {}", self.id, log.path().display());
+        }
+    }
+
+    pub fn is_logging(&self) -> bool {
+        self.log.is_some()
+    }
+
+    /// 出力が止まっている間にバッファに残ったログを書き出す
+    pub fn flush_log_if_due(&mut self) {
+        if let Some(log) = &mut self.log && let Err(e) = log.flush_if_due() {
+            log::warn!("Pane {} stopped logging to {}: {}", self.id, log.path().display(), e);
+            self.log = None;
+        }
+    }
+
+    /// バッファに残ったログを書き出す時刻
+    pub fn next_log_flush(&self) -> Option<Instant> {
+        self.log.as_ref().and_then(PaneLog::next_flush)
+    }
+
+    /// 書き込みに失敗したらログを止める（端末の表示は続ける）
+    fn write_log(&mut self, bytes: &[u8]) {
+        if let Some(log) = &mut self.log && let Err(e) = log.write(bytes) {
+            log::warn!("Pane {} stopped logging to {}: {}", self.id, log.path().display(), e);
+            self.log = None;
+        }
+    }
+
+    /// 端末からアプリケーションへのイベントを取り出す
+    pub fn take_events(&mut self) -> Vec<TerminalEvent> {
+        self.terminal.take_events()
+    }
+
+    /// ビジュアルベルの点滅を始める
+    pub fn ring_visual_bell(&mut self, now: Instant) {
+        self.visual_bell_at = Some(now);
+    }
+
+    /// ビジュアルベルの点滅中か
+    pub fn is_visual_bell_active(&self, now: Instant) -> bool {
+        self.visual_bell_at
+            .is_some_and(|rung| now.saturating_duration_since(rung) < VISUAL_BELL_DURATION)
+    }
+
     pub fn write_input(&self, data: &[u8]) -> Result<()> {
         self.pty.write(data)?;
         Ok(())
     }
 
-    /// Check if pane needs redraw
+    /// Check if pane needs redraw（リサイズなどPTY出力以外によるグリッドの変更も含む）
     pub fn needs_redraw(&self) -> bool {
-        self.needs_redraw
+        let grid = self.terminal.grid();
+        self.needs_redraw || grid.needs_full_redraw() || !grid.dirty_cells().is_empty()
     }
 
     /// Mark pane as needing redraw
@@ -162,3 +339,23 @@ impl Pane {
         self.needs_redraw = false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portable_pty::ExitStatus;
+
+    #[test]
+    fn test_exit_message() {
+        assert_eq!(
+            exit_message(Some(&ExitStatus::with_exit_code(3))),
+            "[process exited with code 3 — press any key to close]"
+        );
+        assert_eq!(exit_message(Some(&ExitStatus::with_exit_code(0))), "[process exited with code 0 — press any key to close]");
+        assert_eq!(
+            exit_message(Some(&ExitStatus::with_signal("Killed"))),
+            "[process terminated by Killed — press any key to close]"
+        );
+        assert_eq!(exit_message(None), "[process exited — press any key to close]");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::pane::layout::Rect;
+use crate::pane::{PaneId, PaneManager};
+use crate::terminal::pty::OutputWaker;
+use crate::terminal::TerminalEvent;
+use crate::utils::Result;
+use std::time::Instant;
+
+/// タブ管理マネージャー（タブごとに独立したペインレイアウトを持つ）
+pub struct TabManager {
+    tabs: Vec<PaneManager>,
+    active: usize,
+}
+
+impl TabManager {
+    /// 最初のタブで初期化
+    pub fn new(first: PaneManager) -> Self {
+        Self { tabs: vec![first], active: 0 }
+    }
+
+    /// タブの数
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    /// アクティブなタブの位置（0始まり）
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// アクティブなタブのペインマネージャー
+    pub fn active(&self) -> &PaneManager {
+        &self.tabs[self.active]
+    }
+
+    /// アクティブなタブのペインマネージャーを可変参照で取得
+    pub fn active_mut(&mut self) -> &mut PaneManager {
+        &mut self.tabs[self.active]
+    }
+
+    /// アクティブなタブと同じ設定で新しいタブを右隣に作成し、アクティブにする
+    pub fn open_tab(&mut self, cols: usize, rows: usize) -> Result<usize> {
+        let manager = self.active().new_like(cols, rows)?;
+        self.active += 1;
+        self.tabs.insert(self.active, manager);
+        log::info!("Opened tab {} of {}", self.active + 1, self.tabs.len());
+        Ok(self.active)
+    }
+
+    /// 指定されたタブを閉じる（最後のタブは閉じられない）
+    pub fn close_tab(&mut self, index: usize) -> bool {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return false;
+        }
+        // ペインを破棄するとシェルも終了する
+        self.tabs.remove(index);
+        if self.active > index || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+        log::info!("Closed tab {}, {} left", index + 1, self.tabs.len());
+        true
+    }
+
+    /// アクティブなタブを閉じる
+    pub fn close_active_tab(&mut self) -> bool {
+        self.close_tab(self.active)
+    }
+
+    /// 指定されたタブをアクティブにする
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.tabs.len() || index == self.active {
+            return false;
+        }
+        self.active = index;
+        true
+    }
+
+    /// 次のタブに移動（最後のタブからは最初に戻る）
+    pub fn select_next(&mut self) -> bool {
+        self.select((self.active + 1) % self.tabs.len())
+    }
+
+    /// 前のタブに移動（最初のタブからは最後に戻る）
+    pub fn select_prev(&mut self) -> bool {
+        self.select((self.active + self.tabs.len() - 1) % self.tabs.len())
+    }
+
+    /// タブバーに表示する名前（各タブのアクティブなペインのタイトル、なければ`default_title`）
+    pub fn titles(&self, default_title: &str) -> Vec<String> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| format!("{}: {}", index + 1, tab.active_pane_title().unwrap_or(default_title)))
+            .collect()
+    }
+
+    /// 全タブのPTY出力を処理。最後のペインが終了したタブは、他にタブがあれば閉じる
+    /// 戻り値: (has_output, should_exit)
+    /// - should_exit: 最後のタブの全ペインが終了したか（アクティブなタブに残る）
+    pub fn process_all_pty_output(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<(bool, bool)> {
+        let mut has_any_output = false;
+        let mut index = 0;
+        while index < self.tabs.len() {
+            let (has_output, tab_exited) = self.tabs[index].process_all_pty_output(window_rect, cell_width, cell_height)?;
+            has_any_output |= has_output;
+            if tab_exited {
+                if self.tabs.len() == 1 {
+                    return Ok((has_any_output, true));
+                }
+                self.close_tab(index);
+                has_any_output = true;
+                continue;
+            }
+            index += 1;
+        }
+        Ok((has_any_output, false))
+    }
+
+    /// 全タブの端末イベントを取り出す
+    pub fn take_events(&mut self) -> Vec<(PaneId, TerminalEvent)> {
+        self.tabs.iter_mut().flat_map(PaneManager::take_events).collect()
+    }
+
+    /// ベルが鳴ったペインを、ビジュアルベルが有効なら点滅させる
+    pub fn ring_visual_bell(&mut self, pane_id: PaneId) {
+        self.tabs.iter_mut().any(|tab| tab.ring_visual_bell(pane_id));
+    }
+
+    /// いずれかのタブに未処理のPTY出力があるか
+    pub fn has_pending_output(&mut self) -> bool {
+        self.tabs.iter_mut().any(PaneManager::has_pending_output)
+    }
+
+    /// 全タブのバッファに残ったログを、時間が来ていれば書き出す
+    pub fn flush_logs_if_due(&mut self) {
+        self.tabs.iter_mut().for_each(PaneManager::flush_logs_if_due);
+    }
+
+    /// 次にいずれかのタブのログを書き出す時刻
+    pub fn next_log_flush(&self) -> Option<Instant> {
+        self.tabs.iter().filter_map(PaneManager::next_log_flush).min()
+    }
+
+    /// PTYの出力が届いたときに呼ぶ関数を設定（今後作成するタブにも引き継がれる）
+    pub fn set_output_waker(&mut self, waker: OutputWaker) {
+        for tab in &mut self.tabs {
+            tab.set_output_waker(waker.clone());
+        }
+    }
+
+    /// 全タブのペインをリサイズ
+    pub fn resize_all_tabs(&mut self, window_rect: Rect, cell_width: f32, cell_height: f32) -> Result<()> {
+        for tab in &mut self.tabs {
+            tab.resize_all_panes(window_rect, cell_width, cell_height)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::pty::ShellCommand;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    fn shell(script: &str) -> ShellCommand {
+        ShellCommand {
+            program: "/bin/sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            term: "dumb".to_string(),
+            ..ShellCommand::default()
+        }
+    }
+
+    #[test]
+    fn test_open_and_switch_tabs() {
+        let shell = shell("sleep 10");
+        let mut tabs = TabManager::new(PaneManager::new(40, 10, 0, shell.clone(), &shell).unwrap());
+        assert!(!tabs.select_next());
+        assert!(!tabs.close_active_tab());
+
+        // 新しいタブはアクティブなタブの右隣に入る
+        assert_eq!(tabs.open_tab(40, 10).unwrap(), 1);
+        tabs.select(0);
+        assert_eq!(tabs.open_tab(40, 10).unwrap(), 1);
+        assert_eq!(tabs.len(), 3);
+        tabs.active_mut().active_pane_mut().unwrap().terminal_mut().process_bytes(b"\x1b]2;vim\x07");
+        assert_eq!(tabs.titles("sh"), ["1: sh", "2: vim", "3: sh"]);
+
+        // 前後の移動は端で反対側に回る
+        assert!(tabs.select_next());
+        assert_eq!(tabs.active_index(), 2);
+        assert!(tabs.select_next());
+        assert_eq!(tabs.active_index(), 0);
+        assert!(tabs.select_prev());
+        assert_eq!(tabs.active_index(), 2);
+        assert!(!tabs.select(5));
+
+        // 閉じたタブの右隣（最後のタブなら左隣）がアクティブになる
+        assert!(tabs.close_active_tab());
+        assert_eq!((tabs.len(), tabs.active_index()), (2, 1));
+        assert!(tabs.close_tab(0));
+        assert_eq!((tabs.len(), tabs.active_index()), (1, 0));
+        assert_eq!(tabs.active().active_pane_title(), Some("vim"));
+    }
+
+    #[test]
+    fn test_exited_tab_closes() {
+        // 最初のタブだけ残り続け、新しいタブのシェルはすぐに終了する
+        let mut tabs = TabManager::new(PaneManager::new(40, 10, 0, shell("exit 0"), &shell("sleep 10")).unwrap());
+        tabs.open_tab(40, 10).unwrap();
+
+        let window_rect = Rect::new(0, 0, 400, 200);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while tabs.len() > 1 && Instant::now() < deadline {
+            let (_, should_exit) = tabs.process_all_pty_output(window_rect, 10.0, 20.0).unwrap();
+            assert!(!should_exit);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!((tabs.len(), tabs.active_index()), (1, 0));
+    }
+
+    #[test]
+    fn test_tabs_log_to_separate_files() {
+        // どちらのタブも最初のペインから出力をログに書く
+        let mut first = PaneManager::new(40, 10, 0, shell("printf second; sleep 10"), &shell("printf first; sleep 10")).unwrap();
+        let log_path = std::env::temp_dir().join(format!("terbulator-tabs-{}-{{pane}}.log", std::process::id()));
+        first.set_output_log(Some(log_path.clone()), crate::pane::LogFormat::Plain);
+        let mut tabs = TabManager::new(first);
+        tabs.open_tab(40, 10).unwrap();
+
+        // ペインIDはタブをまたいで重ならない
+        let second_id = tabs.active().active_pane_id();
+        tabs.select(0);
+        let first_id = tabs.active().active_pane_id();
+        assert_ne!(first_id, second_id);
+
+        let window_rect = Rect::new(0, 0, 400, 200);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let shown = |tabs: &TabManager, index: usize, text: &str| {
+            let grid = tabs.tabs[index].active_pane().unwrap().terminal().grid();
+            (0..grid.cols()).map(|col| grid.get(col, 0).unwrap().ch).collect::<String>().contains(text)
+        };
+        while !(shown(&tabs, 0, "first") && shown(&tabs, 1, "second")) {
+            assert!(Instant::now() < deadline, "tabs did not show their output");
+            tabs.process_all_pty_output(window_rect, 10.0, 20.0).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // タブを閉じるとログが書き出される
+        let paths: Vec<_> = [first_id, second_id]
+            .iter()
+            .map(|id| PathBuf::from(log_path.to_string_lossy().replace("{pane}", &id.to_string())))
+            .collect();
+        drop(tabs);
+        let logged: Vec<String> = paths.iter().map(|path| std::fs::read_to_string(path).unwrap()).collect();
+        for path in &paths {
+            std::fs::remove_file(path).unwrap();
+        }
+        assert_eq!(logged, ["first", "second"]);
+    }
+}
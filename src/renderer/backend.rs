@@ -1,5 +1,8 @@
+use crate::pane::Rect;
 use crate::terminal::Grid;
+use crate::terminal::grid::{Cell, CellAttributes, Whitespace};
 use crate::utils::Result;
+use unicode_width::UnicodeWidthChar;
 use winit::window::Window;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -88,38 +91,360 @@ pub struct CursorInfo {
     pub col: usize,
     pub row: usize,
     pub visible: bool,
+    /// Draw as an unfilled outline (cursor of an inactive pane)
+    pub hollow: bool,
+    /// Invert the cell instead when the cursor would not be visible against it
+    pub auto_contrast: bool,
+    /// Paint a block cursor as a filled box instead of inverting the cell
+    pub solid_block: bool,
+    pub style: CursorStyle,
+    pub color: Color,
+}
+
+/// Per-pane rendering options derived from the terminal state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaneRenderOptions {
+    /// Screen-wide reverse video (DECSCNM)
+    pub reverse_video: bool,
+    /// Color blended into the pane's backgrounds (`pane.tint`)
+    pub tint: Option<Color>,
+    /// Mark trailing spaces and tabs (`terminal.show_whitespace`)
+    pub show_whitespace: bool,
+    /// Draw bold text in the 8 basic colors with their bright variants (`terminal.bold_is_bright`)
+    pub bold_is_bright: bool,
+}
+
+/// How strongly a pane tint colors the background, out of 255
+const TINT_STRENGTH: u16 = 40;
+
+/// Resolve the effective (foreground, background) colors of a cell, used by every
+/// render path. The steps apply in this order:
+/// 1. inverse: the cell's own attribute swaps the colors, then screen-wide reverse video
+/// 2. tint: the pane tint is blended into the resulting background
+/// 3. dim: the resulting foreground is blended toward the background
+/// 4. bold-is-bright: a bold foreground that is still the cell's own color, in one of
+///    the 8 basic colors (SGR 30-37), takes the bright variant, dimmed as in step 3;
+///    256-color and truecolor foregrounds are kept
+///
+/// Cursor and selection overlays are painted over the result by the caller.
+pub fn resolve_cell_colors(cell: &Cell, options: &PaneRenderOptions) -> (Color, Color) {
+    let swapped = cell.attrs.inverse != options.reverse_video;
+    let (fg, bg) = if swapped { (cell.bg, cell.fg) } else { (cell.fg, cell.bg) };
+    let bg = match options.tint {
+        Some(tint) => blend_tint(bg, tint),
+        None => bg,
+    };
+
+    let dim = |fg: Color| if cell.attrs.dim { mix_colors(fg, bg, 3) } else { fg };
+    match cell.bright_fg {
+        Some(bright) if options.bold_is_bright && cell.attrs.bold && !swapped => (dim(bright), bg),
+        _ => (dim(fg), bg),
+    }
+}
+
+/// Move `bg` a fixed part of the way toward `tint`
+fn blend_tint(bg: Color, tint: Color) -> Color {
+    let mix = |bg: u8, tint: u8| ((bg as u16 * (255 - TINT_STRENGTH) + tint as u16 * TINT_STRENGTH) / 255) as u8;
+    Color::rgba(mix(bg.r, tint.r), mix(bg.g, tint.g), mix(bg.b, tint.b), bg.a)
+}
+
+/// Pixel (width, height) of a cell at `font_size`: fixed monospace proportions
+/// (0.6 em wide, with line spacing)
+pub fn cell_size_for_font(font_size: f32) -> (f32, f32) {
+    (font_size * 0.6, font_size * 1.3)
+}
+
+/// Cursor color without a `theme.cursor` or preset
+pub const CURSOR_COLOR: Color = Color::WHITE;
+
+/// Colors closer than this (RGB distance) are treated as indistinguishable
+const MIN_CURSOR_CONTRAST: f32 = 64.0;
+
+/// Euclidean distance between two colors in RGB space
+pub fn color_distance(a: Color, b: Color) -> f32 {
+    let dr = a.r as f32 - b.r as f32;
+    let dg = a.g as f32 - b.g as f32;
+    let db = a.b as f32 - b.b as f32;
+    (dr * dr + dg * dg + db * db).sqrt()
+}
+
+/// Cursor shape, chosen by the application (DECSCUSR) or `terminal.cursor_style`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorStyle {
+    /// The whole cell: the glyph with inverse colors (or a solid box, `cursor.solid_block`)
+    Block,
+    /// A bar along the bottom of the cell
+    #[default]
+    Underline,
+    /// A bar along the left edge of the cell
+    Bar,
+}
+
+impl CursorStyle {
+    /// Style for a DECSCUSR (`CSI Ps SP q`) parameter: 0-2 block, 3-4 underline, 5-6 bar.
+    /// The blinking/steady distinction is ignored; the active cursor always blinks.
+    pub fn from_decscusr(param: u16) -> Option<Self> {
+        match param {
+            0..=2 => Some(CursorStyle::Block),
+            3 | 4 => Some(CursorStyle::Underline),
+            5 | 6 => Some(CursorStyle::Bar),
+            _ => None,
+        }
+    }
+
+    /// DECSCUSR parameter describing this style (the blinking variant)
+    pub fn decscusr(self) -> u16 {
+        match self {
+            CursorStyle::Block => 1,
+            CursorStyle::Underline => 3,
+            CursorStyle::Bar => 5,
+        }
+    }
+
+    /// Style for a `terminal.cursor_style` config value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "block" => Some(CursorStyle::Block),
+            "underline" => Some(CursorStyle::Underline),
+            "bar" => Some(CursorStyle::Bar),
+            _ => None,
+        }
+    }
+}
+
+/// How an underline or bar cursor of the active pane is painted over its cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorPaint {
+    /// The cursor shape in the cursor color
+    Solid(Color),
+    /// Swap the cell's foreground and background
+    InvertCell,
+}
+
+/// Choose how to paint the cursor over a cell with background `cell_bg`. With
+/// auto-contrast, a cursor color too close to the background falls back to inverting the cell.
+pub fn cursor_paint(cursor_color: Color, cell_bg: Color, auto_contrast: bool) -> CursorPaint {
+    if auto_contrast && color_distance(cursor_color, cell_bg) < MIN_CURSOR_CONTRAST {
+        CursorPaint::InvertCell
+    } else {
+        CursorPaint::Solid(cursor_color)
+    }
+}
+
+/// The cell as drawn under an inverting cursor: the same glyph with its colors swapped
+pub fn inverted_cell(cell: &Cell) -> Cell {
+    Cell { attrs: CellAttributes { inverse: !cell.attrs.inverse, ..cell.attrs }, ..*cell }
+}
+
+/// Marker drawn in place of a blank cell with `show_whitespace`: an arrow for the
+/// cells of a tab, a dot for a space after the last visible character of the row
+pub fn whitespace_marker(cell: &Cell, trailing: bool, options: &PaneRenderOptions) -> Option<char> {
+    if !options.show_whitespace || cell.ch != ' ' {
+        return None;
+    }
+    match cell.whitespace {
+        Whitespace::TabStart | Whitespace::Tab => Some('→'),
+        Whitespace::Space if trailing => Some('·'),
+        _ => None,
+    }
+}
+
+/// Color between the foreground and background, for markers that should not stand out
+pub fn faint_color(fg: Color, bg: Color) -> Color {
+    mix_colors(fg, bg, 2)
+}
+
+/// `fg_fifths`/5 of `fg` blended with the rest of `bg`
+fn mix_colors(fg: Color, bg: Color, fg_fifths: u16) -> Color {
+    let mix = |fg: u8, bg: u8| ((fg as u16 * fg_fifths + bg as u16 * (5 - fg_fifths)) / 5) as u8;
+    Color::rgb(mix(fg.r, bg.r), mix(fg.g, bg.g), mix(fg.b, bg.b))
+}
+
+/// Line decoration drawn across cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoration {
+    Underline,
+    Strikethrough,
+}
+
+/// A run of adjacent cells sharing the same decoration and color (end column exclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecorationRun {
+    pub decoration: Decoration,
+    pub start_col: usize,
+    pub end_col: usize,
+    pub color: Color,
+}
+
+/// Collect the underline and strikethrough runs of a row so each run can be drawn
+/// as one contiguous line instead of per-cell segments with gaps between them.
+/// A run ends where the decoration stops or its color changes.
+pub fn decoration_runs(row: &[Cell], options: &PaneRenderOptions) -> Vec<DecorationRun> {
+    let mut runs = Vec::new();
+
+    for decoration in [Decoration::Underline, Decoration::Strikethrough] {
+        let mut current: Option<DecorationRun> = None;
+        for (col, cell) in row.iter().enumerate() {
+            let decorated = match decoration {
+                // Hyperlinks (OSC 8) are underlined so they can be told apart
+                Decoration::Underline => cell.attrs.underline || cell.link.is_some(),
+                Decoration::Strikethrough => cell.attrs.strikethrough,
+            };
+            let color = decorated.then(|| resolve_cell_colors(cell, options).0);
+
+            match (&mut current, color) {
+                (Some(run), Some(color)) if run.color == color => run.end_col = col + 1,
+                (_, color) => {
+                    runs.extend(current.take());
+                    current = color.map(|color| DecorationRun {
+                        decoration,
+                        start_col: col,
+                        end_col: col + 1,
+                        color,
+                    });
+                }
+            }
+        }
+        runs.extend(current);
+    }
+
+    runs
+}
+
+/// Pixel span `[start, end)` covered by the cells `[start_col, end_col)`.
+/// Both edges are rounded the same way, so adjacent spans meet without gaps or overlap.
+pub fn cell_span_pixels(start_col: usize, end_col: usize, cell_width: f32) -> (i32, i32) {
+    (
+        (start_col as f32 * cell_width) as i32,
+        (end_col as f32 * cell_width) as i32,
+    )
+}
+
+/// Characters of a one-line label with the column each starts at, cut to `max_cols`
+/// columns with a trailing `…` when it does not fit. Zero-width characters are dropped.
+pub fn label_columns(text: &str, max_cols: usize) -> Vec<(usize, char)> {
+    let chars: Vec<(char, usize)> =
+        text.chars().filter_map(|ch| ch.width().filter(|&width| width > 0).map(|width| (ch, width))).collect();
+    let fits = chars.iter().map(|&(_, width)| width).sum::<usize>() <= max_cols;
+    let limit = if fits { max_cols } else { max_cols.saturating_sub(1) };
+
+    let mut columns = Vec::new();
+    let mut col = 0;
+    for (ch, width) in chars {
+        if col + width > limit {
+            break;
+        }
+        columns.push((col, ch));
+        col += width;
+    }
+    if !fits && max_cols > 0 {
+        columns.push((col, '…'));
+    }
+    columns
+}
+
+/// Font weights of normal and bold text (`terminal.font_weight` / `bold_font_weight`),
+/// on the usual 100-900 scale where 400 is regular and 700 bold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontWeights {
+    pub regular: u16,
+    pub bold: u16,
+}
+
+impl Default for FontWeights {
+    fn default() -> Self {
+        Self { regular: 400, bold: 700 }
+    }
+}
+
+impl FontWeights {
+    /// Weight for a config value: a name such as "light", "regular", "medium" or "bold",
+    /// or a number from 100 to 900
+    pub fn parse_weight(value: &str) -> Option<u16> {
+        let weight = match value.trim().to_ascii_lowercase().as_str() {
+            "thin" => 100,
+            "extralight" | "extra-light" => 200,
+            "light" => 300,
+            "regular" | "normal" => 400,
+            "medium" => 500,
+            "semibold" | "semi-bold" => 600,
+            "bold" => 700,
+            "extrabold" | "extra-bold" => 800,
+            "black" | "heavy" => 900,
+            number => number.parse().ok().filter(|weight| (100..=900).contains(weight))?,
+        };
+        Some(weight)
+    }
+
+    /// Weight of text drawn with or without the bold attribute
+    pub fn for_bold(self, bold: bool) -> u16 {
+        if bold { self.bold } else { self.regular }
+    }
+}
+
+/// Cells to repaint when only part of a pane changed: the grid's dirty cells as shown
+/// in the view, plus `extra` view cells (such as the old and new cursor cell). The
+/// second column of a wide character is replaced by its first, which paints both.
+pub fn damaged_cells(grid: &Grid, extra: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let offset = grid.scroll_offset();
+    let mut cells: Vec<(usize, usize)> = grid
+        .dirty_cells()
+        .iter()
+        .map(|&(col, row)| (col, row + offset))
+        .chain(extra.iter().copied())
+        .filter(|&(col, row)| row < grid.rows() && col < grid.cols())
+        .map(|(col, row)| {
+            let continuation = grid.view_row(row).and_then(|cells| cells.get(col)).is_some_and(Cell::is_continuation);
+            (if continuation { col.saturating_sub(1) } else { col }, row)
+        })
+        .collect();
+    cells.sort_unstable_by_key(|&(col, row)| (row, col));
+    cells.dedup();
+    cells
 }
 
 /// Abstract rendering backend trait
 pub trait RenderBackend {
-    /// Initialize the backend
-    fn new(window: &Window, font_size: f32) -> Result<Self>
+    /// Initialize the backend with the configured font
+    fn new(window: &Window, font_size: f32, font_family: &str) -> Result<Self>
     where
         Self: Sized;
 
     /// Handle window resize
     fn resize(&mut self, width: u32, height: u32) -> Result<()>;
 
-    /// Clear the rendering buffer
+    /// Clear the rendering buffer, so the panes of this frame are repainted entirely.
+    /// Without it, panes whose area and options are unchanged only repaint changed cells,
+    /// and panes not rendered in the frame keep their pixels.
     fn clear(&mut self) -> Result<()>;
 
     /// Render a frame with the given grid
     fn render_frame(&mut self, grid: &mut Grid, cursor: CursorInfo) -> Result<()>;
 
-    /// Render a pane at a specific offset with clipping
+    /// Render a pane into its area of the window, clipped to that area
     fn render_pane(
         &mut self,
         grid: &mut Grid,
         cursor: CursorInfo,
-        offset_x: i32,
-        offset_y: i32,
-        width: u32,
-        height: u32,
+        options: PaneRenderOptions,
+        area: Rect,
     ) -> Result<()>;
 
     /// Draw a border around a rectangular region
     fn draw_border(&mut self, x: i32, y: i32, width: i32, height: i32) -> Result<()>;
 
+    /// Draw the brief highlight shown around a pane that just received focus
+    fn draw_focus_flash(&mut self, x: i32, y: i32, width: i32, height: i32) -> Result<()>;
+
+    /// Flash a pane's area for the visual bell
+    fn draw_visual_bell(&mut self, area: Rect) -> Result<()>;
+
+    /// Draw a one-line label in the top-right corner of a pane (e.g. the scrollback position)
+    fn draw_corner_label(&mut self, text: &str, area: Rect) -> Result<()>;
+
+    /// Draw the tab bar in `area`, one equal-width label per tab, highlighting the active one
+    fn draw_tab_bar(&mut self, titles: &[String], active: usize, area: Rect) -> Result<()>;
+
     /// Draw selection highlight for a cell
     fn draw_selection_highlight(&mut self, col: usize, row: usize, cell_width: f32, cell_height: f32, offset_x: i32, offset_y: i32) -> Result<()>;
 
@@ -143,4 +468,243 @@ pub trait RenderBackend {
 
     /// Set font size and recalculate cell dimensions
     fn set_font_size(&mut self, size: f32) -> Result<()>;
+
+    /// Set the fonts tried in order for characters the primary font has no glyph for
+    fn set_fallback_fonts(&mut self, families: &[String]) -> Result<()>;
+
+    /// Set the weights of normal and bold text
+    fn set_font_weights(&mut self, weights: FontWeights) -> Result<()>;
+
+    /// Set the family bold text is drawn with; None draws it with the primary font
+    fn set_bold_font_family(&mut self, family: Option<&str>) -> Result<()>;
+
+    /// Set the color the window is cleared to before panes are drawn
+    fn set_clear_color(&mut self, color: Color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damaged_cells_after_small_change() {
+        let mut grid = Grid::new(80, 24, 100);
+        grid.clear_dirty();
+
+        // Typing two characters repaints them and the cells the cursor left and moved to
+        grid.set(0, 5, Cell::new('l'));
+        grid.set(1, 5, Cell::new('s'));
+        let cells = damaged_cells(&grid, &[(1, 5), (2, 5)]);
+        assert_eq!(cells, vec![(0, 5), (1, 5), (2, 5)]);
+        assert!(cells.len() * 100 < grid.cols() * grid.rows());
+
+        // The second column of a wide character is painted with its first
+        let wide = Cell { width: 2, ..Cell::new('漢') };
+        grid.set(10, 0, wide);
+        grid.set(11, 0, Cell::continuation(&wide));
+        grid.clear_dirty();
+        assert_eq!(damaged_cells(&grid, &[(11, 0)]), vec![(10, 0)]);
+    }
+
+    #[test]
+    fn test_resolve_cell_colors_reverse_video() {
+        let cell = Cell::default();
+        let normal = PaneRenderOptions::default();
+        let reverse = PaneRenderOptions { reverse_video: true, ..Default::default() };
+
+        assert_eq!(resolve_cell_colors(&cell, &normal), (Color::WHITE, Color::BLACK));
+        assert_eq!(resolve_cell_colors(&cell, &reverse), (Color::BLACK, Color::WHITE));
+
+        // Reverse video on an inverse cell shows its original colors
+        let mut inverse = Cell::default();
+        inverse.attrs.inverse = true;
+        assert_eq!(resolve_cell_colors(&inverse, &reverse), (Color::WHITE, Color::BLACK));
+    }
+
+    #[test]
+    fn test_tint_blends_background() {
+        let cell = Cell::default();
+        let red = PaneRenderOptions { tint: Some(Color::rgb(255, 0, 0)), ..PaneRenderOptions::default() };
+
+        // The black background moves toward red, the foreground keeps its color
+        let (fg, bg) = resolve_cell_colors(&cell, &red);
+        assert_eq!(fg, Color::WHITE);
+        assert_eq!(bg, Color::rgb(40, 0, 0));
+
+        // A white background only loses some of its green and blue
+        let (_, bg) = resolve_cell_colors(&cell, &PaneRenderOptions { reverse_video: true, ..red });
+        assert_eq!(bg, Color::rgb(255, 215, 215));
+    }
+
+    #[test]
+    fn test_resolve_cell_colors_attribute_order() {
+        let red = Color::from_ansi_256(1);
+        let bright_red = Color::from_ansi_256(9);
+        let bright = PaneRenderOptions { bold_is_bright: true, ..Default::default() };
+
+        // Bold basic colors brighten only with bold_is_bright
+        let bold_red = Cell {
+            fg: red,
+            bright_fg: Some(bright_red),
+            attrs: CellAttributes { bold: true, ..Default::default() },
+            ..Cell::new('x')
+        };
+        assert_eq!(resolve_cell_colors(&bold_red, &PaneRenderOptions::default()).0, red);
+        assert_eq!(resolve_cell_colors(&bold_red, &bright).0, bright_red);
+
+        // A 256-color (38;5;1) or truecolor foreground is never brightened
+        let bold_indexed = Cell { bright_fg: None, ..bold_red };
+        assert_eq!(resolve_cell_colors(&bold_indexed, &bright).0, red);
+
+        // Inverse swaps first, so dim fades the original background toward the foreground
+        let inverse_dim = Cell {
+            fg: Color::WHITE,
+            bg: Color::rgb(0, 0, 200),
+            attrs: CellAttributes { inverse: true, dim: true, ..Default::default() },
+            ..Cell::new('x')
+        };
+        assert_eq!(resolve_cell_colors(&inverse_dim, &bright), (Color::rgb(102, 102, 222), Color::WHITE));
+
+        // Bold bright and dim combine: the bright color is dimmed
+        let bold_dim = Cell { attrs: CellAttributes { bold: true, dim: true, ..Default::default() }, ..bold_red };
+        assert_eq!(resolve_cell_colors(&bold_dim, &bright), (Color::rgb(153, 0, 0), Color::BLACK));
+
+        // Bold-is-bright comes after inverse: the red moved to the background stays red
+        let bold_inverse = Cell { attrs: CellAttributes { bold: true, inverse: true, ..Default::default() }, ..bold_red };
+        assert_eq!(resolve_cell_colors(&bold_inverse, &bright), (Color::BLACK, red));
+        let reverse = PaneRenderOptions { reverse_video: true, ..bright };
+        assert_eq!(resolve_cell_colors(&bold_inverse, &reverse), (bright_red, Color::BLACK));
+    }
+
+    #[test]
+    fn test_block_cursor_inverts_cell() {
+        let options = PaneRenderOptions::default();
+        let cell = Cell { fg: Color::rgb(0xeb, 0xdb, 0xb2), bg: Color::rgb(0x28, 0x28, 0x28), ..Cell::new('x') };
+        let under_cursor = inverted_cell(&cell);
+
+        // The character stays, drawn in the background color on the foreground color
+        assert_eq!(under_cursor.ch, 'x');
+        assert_eq!(resolve_cell_colors(&under_cursor, &options), (cell.bg, cell.fg));
+        // An inverse cell under the cursor shows its normal colors
+        assert_eq!(resolve_cell_colors(&inverted_cell(&under_cursor), &options), (cell.fg, cell.bg));
+    }
+
+    #[test]
+    fn test_cursor_auto_contrast() {
+        let white_bg = Cell { bg: Color::WHITE, ..Cell::default() };
+        let (_, bg) = resolve_cell_colors(&white_bg, &PaneRenderOptions::default());
+
+        // A white cursor on a white cell inverts the cell instead
+        assert_eq!(cursor_paint(Color::WHITE, bg, true), CursorPaint::InvertCell);
+        assert_eq!(cursor_paint(Color::WHITE, Color::rgb(0xf0, 0xf0, 0xf0), true), CursorPaint::InvertCell);
+        assert_eq!(cursor_paint(Color::WHITE, bg, false), CursorPaint::Solid(Color::WHITE));
+        assert_eq!(cursor_paint(Color::WHITE, Color::BLACK, true), CursorPaint::Solid(Color::WHITE));
+    }
+
+    #[test]
+    fn test_decscusr_styles() {
+        let styles: Vec<_> = (0..=7).map(CursorStyle::from_decscusr).collect();
+        assert_eq!(styles, [
+            Some(CursorStyle::Block),
+            Some(CursorStyle::Block),
+            Some(CursorStyle::Block),
+            Some(CursorStyle::Underline),
+            Some(CursorStyle::Underline),
+            Some(CursorStyle::Bar),
+            Some(CursorStyle::Bar),
+            None,
+        ]);
+        for style in [CursorStyle::Block, CursorStyle::Underline, CursorStyle::Bar] {
+            assert_eq!(CursorStyle::from_decscusr(style.decscusr()), Some(style));
+        }
+    }
+
+    #[test]
+    fn test_adjacent_underlines_form_continuous_run() {
+        let mut underlined = Cell::new('a');
+        underlined.attrs.underline = true;
+        let red = Cell { fg: Color::rgb(255, 0, 0), ..underlined };
+        let row = [underlined, underlined, red, Cell::new('b'), underlined];
+
+        let runs = decoration_runs(&row, &PaneRenderOptions::default());
+        let spans: Vec<(usize, usize)> = runs.iter().map(|run| (run.start_col, run.end_col)).collect();
+        // Color change and undecorated cell both end a run
+        assert_eq!(spans, vec![(0, 2), (2, 3), (4, 5)]);
+
+        // The two first cells share one line covering every pixel of both cells
+        let cell_width = 8.4;
+        let (start, end) = cell_span_pixels(runs[0].start_col, runs[0].end_col, cell_width);
+        let (first_start, first_end) = cell_span_pixels(0, 1, cell_width);
+        let (second_start, second_end) = cell_span_pixels(1, 2, cell_width);
+        assert_eq!(first_end, second_start);
+        assert_eq!((start, end), (first_start, second_end));
+    }
+
+    #[test]
+    fn test_strikethrough_runs_independent_of_underline() {
+        let mut both = Cell::new('x');
+        both.attrs.underline = true;
+        both.attrs.strikethrough = true;
+        let mut strike = Cell::new('y');
+        strike.attrs.strikethrough = true;
+
+        let runs = decoration_runs(&[both, strike], &PaneRenderOptions::default());
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].decoration, runs[0].start_col, runs[0].end_col), (Decoration::Underline, 0, 1));
+        assert_eq!((runs[1].decoration, runs[1].start_col, runs[1].end_col), (Decoration::Strikethrough, 0, 2));
+    }
+
+    #[test]
+    fn test_links_are_underlined() {
+        let link = Cell { link: Some(0), ..Cell::new('a') };
+        let runs = decoration_runs(&[link, link, Cell::new(' ')], &PaneRenderOptions::default());
+        assert_eq!(runs.len(), 1);
+        assert_eq!((runs[0].decoration, runs[0].start_col, runs[0].end_col), (Decoration::Underline, 0, 2));
+    }
+
+    #[test]
+    fn test_whitespace_markers() {
+        let mut term = crate::terminal::TerminalEmulator::new(20, 2, 0);
+        term.process_bytes(b"a\tb x  ");
+        let row = term.grid().get_row(0).unwrap();
+        let len = crate::terminal::grid::row_logical_len(row);
+        assert_eq!(len, 11);
+
+        let markers = |options: &PaneRenderOptions| -> String {
+            row.iter()
+                .enumerate()
+                .map(|(col, cell)| whitespace_marker(cell, col >= len, options).unwrap_or(cell.ch))
+                .collect()
+        };
+        // Tabs anywhere, spaces only after the last character; cells never written stay blank
+        let on = PaneRenderOptions { show_whitespace: true, ..Default::default() };
+        assert_eq!(markers(&on), "a→→→→→→→b x··       ");
+        assert_eq!(markers(&PaneRenderOptions::default()), "a       b x         ");
+    }
+
+    #[test]
+    fn test_parse_font_weight() {
+        assert_eq!(FontWeights::parse_weight("regular"), Some(400));
+        assert_eq!(FontWeights::parse_weight("Medium"), Some(500));
+        assert_eq!(FontWeights::parse_weight("light"), Some(300));
+        assert_eq!(FontWeights::parse_weight("semi-bold"), Some(600));
+        assert_eq!(FontWeights::parse_weight("350"), Some(350));
+        assert_eq!(FontWeights::parse_weight("1000"), None);
+        assert_eq!(FontWeights::parse_weight("heavyish"), None);
+
+        let weights = FontWeights { regular: 500, bold: 800 };
+        assert_eq!((weights.for_bold(false), weights.for_bold(true)), (500, 800));
+    }
+
+    #[test]
+    fn test_label_columns() {
+        let text = |columns: Vec<(usize, char)>| columns.into_iter().map(|(_, ch)| ch).collect::<String>();
+        assert_eq!(text(label_columns("1: bash", 10)), "1: bash");
+        assert_eq!(text(label_columns("1: vim main.rs", 8)), "1: vim …");
+        assert_eq!(text(label_columns("abc", 0)), "");
+
+        // A wide character that no longer fits is left out whole
+        let columns = label_columns("2: 日本語", 7);
+        assert_eq!(columns, vec![(0, '2'), (1, ':'), (2, ' '), (3, '日'), (5, '…')]);
+    }
 }
@@ -0,0 +1,118 @@
+use crate::renderer::backend::Color;
+use crate::renderer::pixel_buffer::PixelBuffer;
+
+/// Arms of a light box-drawing character, each running from the cell center to an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Arms {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+}
+
+const fn arms(up: bool, down: bool, left: bool, right: bool) -> Arms {
+    Arms { up, down, left, right }
+}
+
+fn light_arms(ch: char) -> Option<Arms> {
+    Some(match ch {
+        '─' => arms(false, false, true, true),
+        '│' => arms(true, true, false, false),
+        '┌' => arms(false, true, false, true),
+        '┐' => arms(false, true, true, false),
+        '└' => arms(true, false, false, true),
+        '┘' => arms(true, false, true, false),
+        '├' => arms(true, true, false, true),
+        '┤' => arms(true, true, true, false),
+        '┬' => arms(false, true, true, true),
+        '┴' => arms(true, false, true, true),
+        '┼' => arms(true, true, true, true),
+        _ => return None,
+    })
+}
+
+/// Vertical position of a DEC scan line (⎺⎻⎼⎽) as a fraction of the cell height
+fn scan_line_position(ch: char) -> Option<f32> {
+    match ch {
+        '⎺' => Some(0.1),
+        '⎻' => Some(0.3),
+        '⎼' => Some(0.7),
+        '⎽' => Some(0.9),
+        _ => None,
+    }
+}
+
+/// Draw `ch` with rectangles when it is a supported line-drawing character, so lines
+/// reach the cell edges and join their neighbours regardless of the font.
+/// Returns false (drawing nothing) for other characters.
+pub fn draw_box_char(buffer: &mut PixelBuffer, ch: char, x: i32, y: i32, width: i32, height: i32, color: Color) -> bool {
+    let thickness = ((height as f32 / 14.0).round() as i32).max(1);
+
+    if let Some(position) = scan_line_position(ch) {
+        let line_y = y + ((height as f32 * position) as i32).min(height - thickness);
+        buffer.fill_rect(x, line_y, width, thickness, color);
+        return true;
+    }
+
+    let Some(arms) = light_arms(ch) else {
+        return false;
+    };
+
+    // Top-left corner of the square where the arms meet
+    let center_x = x + (width - thickness) / 2;
+    let center_y = y + (height - thickness) / 2;
+
+    if arms.left {
+        buffer.fill_rect(x, center_y, center_x + thickness - x, thickness, color);
+    }
+    if arms.right {
+        buffer.fill_rect(center_x, center_y, x + width - center_x, thickness, color);
+    }
+    if arms.up {
+        buffer.fill_rect(center_x, y, thickness, center_y + thickness - y, color);
+    }
+    if arms.down {
+        buffer.fill_rect(center_x, center_y, thickness, y + height - center_y, color);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn painted(buffer: &PixelBuffer, width: usize, x: usize, y: usize) -> bool {
+        buffer.pixels()[y * width + x] == PixelBuffer::to_u32(Color::WHITE)
+    }
+
+    #[test]
+    fn test_horizontal_line_spans_cell() {
+        let mut buffer = PixelBuffer::new(10, 20);
+        buffer.fill(Color::BLACK);
+        assert!(draw_box_char(&mut buffer, '─', 0, 0, 10, 20, Color::WHITE));
+
+        // Continuous across the full width at the vertical center, nothing above or below
+        assert!((0..10).all(|x| painted(&buffer, 10, x, 9)));
+        assert!((0..10).all(|x| !painted(&buffer, 10, x, 0) && !painted(&buffer, 10, x, 19)));
+    }
+
+    #[test]
+    fn test_corner_reaches_edges() {
+        let mut buffer = PixelBuffer::new(10, 20);
+        buffer.fill(Color::BLACK);
+        assert!(draw_box_char(&mut buffer, '┌', 0, 0, 10, 20, Color::WHITE));
+
+        assert!(painted(&buffer, 10, 9, 9)); // right edge
+        assert!(painted(&buffer, 10, 4, 19)); // bottom edge
+        assert!(!painted(&buffer, 10, 0, 9)); // no left arm
+        assert!(!painted(&buffer, 10, 4, 0)); // no up arm
+    }
+
+    #[test]
+    fn test_other_characters_are_not_drawn() {
+        let mut buffer = PixelBuffer::new(10, 20);
+        buffer.fill(Color::BLACK);
+        assert!(!draw_box_char(&mut buffer, 'q', 0, 0, 10, 20, Color::WHITE));
+        assert!(buffer.pixels().iter().all(|&pixel| pixel == PixelBuffer::to_u32(Color::BLACK)));
+    }
+}
@@ -1,3 +1,5 @@
 pub mod backend;
+pub mod box_drawing;
+pub mod pixel_buffer;
 pub mod wgpu_backend;
 pub mod softbuffer_backend;
@@ -0,0 +1,195 @@
+use crate::pane::Rect;
+use crate::renderer::backend::Color;
+
+/// CPU framebuffer (XRGB) with a clip region stack.
+///
+/// Every write goes through the current clip region, so a pane rendered with its
+/// rect pushed can never touch pixels of a neighbouring pane.
+pub struct PixelBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u32>,
+    /// Clip regions as (left, top, right, bottom), each already intersected with the previous one
+    clip_stack: Vec<(i32, i32, i32, i32)>,
+}
+
+impl PixelBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height) as usize],
+            clip_stack: Vec::new(),
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.pixels.resize((width * height) as usize, 0);
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    pub fn to_u32(color: Color) -> u32 {
+        // softbuffer uses 0RGB format (or XRGB), top 8 bits are ignored
+        // But we set alpha to 0xFF for compatibility
+        0xFF000000 | ((color.r as u32) << 16) | ((color.g as u32) << 8) | (color.b as u32)
+    }
+
+    /// Restrict drawing to `rect` (and any clip region already pushed)
+    pub fn push_clip(&mut self, rect: Rect) {
+        let (left, top, right, bottom) = self.clip();
+        let rect_right = (rect.x as i64 + rect.width as i64).min(i32::MAX as i64) as i32;
+        let rect_bottom = (rect.y as i64 + rect.height as i64).min(i32::MAX as i64) as i32;
+        self.clip_stack.push((
+            left.max(rect.x.min(i32::MAX as u32) as i32),
+            top.max(rect.y.min(i32::MAX as u32) as i32),
+            right.min(rect_right),
+            bottom.min(rect_bottom),
+        ));
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+
+    /// Current drawable region as (left, top, right, bottom), right/bottom exclusive
+    fn clip(&self) -> (i32, i32, i32, i32) {
+        self.clip_stack
+            .last()
+            .copied()
+            .unwrap_or((0, 0, self.width as i32, self.height as i32))
+    }
+
+    /// Fill the whole buffer, ignoring the clip region
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(Self::to_u32(color));
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let color_u32 = Self::to_u32(color);
+        let (left, top, right, bottom) = self.clip();
+        let (x0, x1) = (x.max(left), x.saturating_add(w).min(right));
+        let (y0, y1) = (y.max(top), y.saturating_add(h).min(bottom));
+
+        for py in y0..y1 {
+            let row_start = (py * self.width as i32) as usize;
+            for px in x0..x1 {
+                self.pixels[row_start + px as usize] = color_u32;
+            }
+        }
+    }
+
+    /// Fill a rect, blending with the existing pixels by `color.a`
+    pub fn blend_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let alpha = color.a as f32 / 255.0;
+        let (left, top, right, bottom) = self.clip();
+        let (x0, x1) = (x.max(left), x.saturating_add(w).min(right));
+        let (y0, y1) = (y.max(top), y.saturating_add(h).min(bottom));
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                self.blend_pixel(px, py, color, alpha);
+            }
+        }
+    }
+
+    /// Overwrite a single pixel
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(idx) = self.index(x, y) {
+            self.pixels[idx] = Self::to_u32(color);
+        }
+    }
+
+    /// Blend a single pixel with `alpha` (0.0-1.0); the alpha channel of `color` is ignored
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Color, alpha: f32) {
+        if alpha <= 0.0 {
+            return;
+        }
+        let Some(idx) = self.index(x, y) else { return };
+
+        let bg = self.pixels[idx];
+        let bg_r = ((bg >> 16) & 0xFF) as f32;
+        let bg_g = ((bg >> 8) & 0xFF) as f32;
+        let bg_b = (bg & 0xFF) as f32;
+
+        let r = (color.r as f32 * alpha + bg_r * (1.0 - alpha)) as u32;
+        let g = (color.g as f32 * alpha + bg_g * (1.0 - alpha)) as u32;
+        let b = (color.b as f32 * alpha + bg_b * (1.0 - alpha)) as u32;
+
+        self.pixels[idx] = 0xFF000000 | (r << 16) | (g << 8) | b;
+    }
+
+    /// Index of a pixel inside the current clip region
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let (left, top, right, bottom) = self.clip();
+        if x >= left && x < right && y >= top && y < bottom {
+            Some((y * self.width as i32 + x) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::rgb(255, 0, 0);
+
+    /// Pixels outside `rect` that differ from black
+    fn touched_outside(buffer: &PixelBuffer, rect: Rect) -> usize {
+        let width = buffer.width as usize;
+        buffer
+            .pixels()
+            .iter()
+            .enumerate()
+            .filter(|&(idx, &pixel)| {
+                let (x, y) = ((idx % width) as u32, (idx / width) as u32);
+                let inside = x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height;
+                !inside && pixel != PixelBuffer::to_u32(Color::BLACK)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_pane_fill_stays_inside_rect() {
+        // Two side-by-side panes; the left one overflows its rect on every side
+        let mut buffer = PixelBuffer::new(200, 100);
+        buffer.fill(Color::BLACK);
+        let left_pane = Rect::new(0, 0, 100, 100);
+
+        buffer.push_clip(left_pane);
+        buffer.fill_rect(-10, -10, 300, 300, RED);
+        buffer.blend_rect(95, 50, 20, 20, Color::rgba(0, 255, 0, 128));
+        for x in 90..120 {
+            buffer.blend_pixel(x, 10, Color::WHITE, 0.5);
+            buffer.set_pixel(x, 20, Color::WHITE);
+        }
+        buffer.pop_clip();
+
+        assert_eq!(touched_outside(&buffer, left_pane), 0);
+        assert_eq!(buffer.pixels()[99], PixelBuffer::to_u32(RED));
+    }
+
+    #[test]
+    fn test_nested_clip_is_intersected() {
+        let mut buffer = PixelBuffer::new(100, 100);
+        buffer.fill(Color::BLACK);
+
+        buffer.push_clip(Rect::new(10, 10, 50, 50));
+        buffer.push_clip(Rect::new(40, 40, 50, 50));
+        buffer.fill_rect(0, 0, 100, 100, RED);
+        buffer.pop_clip();
+        buffer.pop_clip();
+
+        assert_eq!(touched_outside(&buffer, Rect::new(40, 40, 20, 20)), 0);
+
+        // After popping everything the whole buffer is drawable again
+        buffer.fill_rect(0, 0, 100, 100, RED);
+        assert_eq!(buffer.pixels()[0], PixelBuffer::to_u32(RED));
+    }
+}
@@ -1,17 +1,69 @@
-use crate::renderer::backend::{BackendType, Color, CursorInfo, RenderBackend};
+use crate::renderer::backend::{
+    cell_size_for_font, cell_span_pixels, cursor_paint, damaged_cells, decoration_runs, faint_color, inverted_cell, label_columns, resolve_cell_colors,
+    whitespace_marker, BackendType, Color, CursorInfo, CursorPaint, CursorStyle, Decoration, FontWeights, PaneRenderOptions,
+    RenderBackend,
+};
+use crate::pane::Rect;
+use crate::renderer::box_drawing::draw_box_char;
+use crate::renderer::pixel_buffer::PixelBuffer;
 use crate::terminal::Grid;
+use crate::terminal::grid::{row_logical_len, Cell};
 use crate::utils::{Result, TerbulatorError};
 use cosmic_text::{Attrs, Buffer, Color as CosmicColor, FontSystem, Metrics, Shaping, SwashCache};
 use softbuffer::{Context, Surface};
 use std::collections::HashMap;
 use std::num::NonZeroU32;
+use unicode_width::UnicodeWidthChar;
 use winit::window::Window;
 
-/// Cache key for shaped glyphs
+/// Cache key for shaped glyphs. The font a glyph is shaped with is not part of the key:
+/// it only depends on the character, whether it is bold and the configured fonts, and
+/// the cache is cleared whenever those change.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct GlyphCacheKey {
     ch: char,
     bold: bool,
+    /// Font weight, from `weights` for bold or normal text
+    weight: u16,
+    italic: bool,
+    wide: bool,
+}
+
+impl GlyphCacheKey {
+    /// Single-width glyph without any attributes
+    fn plain(ch: char, weights: FontWeights) -> Self {
+        Self { ch, bold: false, weight: weights.regular, italic: false, wide: false }
+    }
+
+    fn for_cell(cell: &crate::terminal::grid::Cell, weights: FontWeights) -> Self {
+        Self {
+            ch: cell.ch,
+            bold: cell.attrs.bold,
+            weight: weights.for_bold(cell.attrs.bold),
+            italic: cell.attrs.italic,
+            wide: cell.width == 2,
+        }
+    }
+
+    /// cosmic-text attributes used to shape this glyph in `family` (None = Monospace)
+    fn attrs<'a>(&self, family: Option<&'a str>) -> Attrs<'a> {
+        let family = family.map_or(cosmic_text::Family::Monospace, cosmic_text::Family::Name);
+        let mut attrs = Attrs::new().family(family).weight(cosmic_text::Weight(self.weight));
+        if self.italic {
+            attrs = attrs.style(cosmic_text::Style::Italic);
+        }
+        attrs
+    }
+}
+
+/// What a pane looked like when it was last drawn, so the next frame can repaint
+/// only the cells that changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DrawnPane {
+    area: Rect,
+    options: PaneRenderOptions,
+    /// View cell the cursor was drawn over
+    cursor: Option<(usize, usize)>,
 }
 
 pub struct SoftbufferBackend {
@@ -20,20 +72,22 @@ pub struct SoftbufferBackend {
     swash_cache: SwashCache,
     glyph_buffer_cache: HashMap<GlyphCacheKey, Buffer>,
     font_size: f32,
+    font_family: Option<String>, // Installed family to shape with (None = generic Monospace)
+    fallback_fonts: Vec<String>, // Installed families tried in order for glyphs the primary font lacks
+    bold_font_family: Option<String>, // Installed family for bold text (None = the primary font)
+    font_weights: FontWeights,
     cell_width: f32,
     cell_height: f32,
     width: u32,
     height: u32,
-    buffer: Vec<u32>,
+    buffer: PixelBuffer,
+    clear_color: Color,
+    frame_cleared: bool, // The buffer was cleared since the last present
+    drawn_panes: Vec<DrawnPane>, // Panes of the last presented frame
+    drawing_panes: Vec<DrawnPane>, // Panes drawn so far in this frame
 }
 
 impl SoftbufferBackend {
-    fn color_to_u32(color: Color) -> u32 {
-        // softbuffer uses 0RGB format (or XRGB), top 8 bits are ignored
-        // But we set alpha to 0xFF for compatibility
-        0xFF000000 | ((color.r as u32) << 16) | ((color.g as u32) << 8) | (color.b as u32)
-    }
-
     fn render_text_to_buffer(&mut self, grid: &mut Grid, cursor: CursorInfo) {
         let grid_cols = grid.cols();
         let grid_rows = grid.rows();
@@ -51,14 +105,11 @@ impl SoftbufferBackend {
             }
         }
 
-        // Draw cursor as an underline
         if cursor.visible && cursor.row < grid_rows && cursor.col < grid_cols {
             let x = (cursor.col as f32 * self.cell_width) as i32;
             let y = (cursor.row as f32 * self.cell_height) as i32;
-            let cursor_height = 2; // Underline style cursor
-            // Position cursor at about 80% down the cell height
-            let cursor_y = y + (self.cell_height * 0.8) as i32;
-            self.draw_rect(x, cursor_y, self.cell_width as i32, cursor_height, Color::WHITE);
+            let cell = grid.get(cursor.col, cursor.row).copied().unwrap_or_default();
+            self.draw_cursor(x, y, &cell, &cursor, &PaneRenderOptions::default());
         }
 
         // Clear dirty tracking after rendering
@@ -70,73 +121,28 @@ impl SoftbufferBackend {
         let y = (row as f32 * self.cell_height) as i32;
 
         // Determine colors (handle inverse)
-        let (fg, bg) = if cell.attrs.inverse {
-            (cell.bg, cell.fg)
-        } else {
-            (cell.fg, cell.bg)
-        };
+        let (fg, bg) = resolve_cell_colors(cell, &PaneRenderOptions::default());
 
         // Draw background
         self.draw_rect(x, y, self.cell_width as i32, self.cell_height as i32, bg);
 
         // Draw character using cosmic-text
         if cell.ch != ' ' && cell.ch != '\0' {
-            self.draw_char(x, y, cell.ch, fg, cell.attrs.bold);
+            self.draw_char(x, y, GlyphCacheKey { wide: false, ..GlyphCacheKey::for_cell(cell, self.font_weights) }, fg);
         }
     }
 
     fn draw_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
-        let width = self.width as i32;
-        let height = self.height as i32;
-        let color_u32 = Self::color_to_u32(color);
-
-        for dy in 0..h {
-            for dx in 0..w {
-                let px = x + dx;
-                let py = y + dy;
-
-                if px >= 0 && px < width && py >= 0 && py < height {
-                    let idx = (py * width + px) as usize;
-                    if idx < self.buffer.len() {
-                        self.buffer[idx] = color_u32;
-                    }
-                }
-            }
-        }
+        self.buffer.fill_rect(x, y, w, h, color);
     }
 
     fn draw_rect_blend(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
-        let width = self.width as i32;
-        let height = self.height as i32;
-        let alpha = color.a as f32 / 255.0;
-
-        for dy in 0..h {
-            for dx in 0..w {
-                let px = x + dx;
-                let py = y + dy;
-
-                if px >= 0 && px < width && py >= 0 && py < height {
-                    let idx = (py * width + px) as usize;
-                    if idx < self.buffer.len() {
-                        let bg = self.buffer[idx];
-                        let bg_r = ((bg >> 16) & 0xFF) as f32;
-                        let bg_g = ((bg >> 8) & 0xFF) as f32;
-                        let bg_b = (bg & 0xFF) as f32;
-
-                        let r = (color.r as f32 * alpha + bg_r * (1.0 - alpha)) as u32;
-                        let g = (color.g as f32 * alpha + bg_g * (1.0 - alpha)) as u32;
-                        let b = (color.b as f32 * alpha + bg_b * (1.0 - alpha)) as u32;
-
-                        self.buffer[idx] = (255 << 24) | (r << 16) | (g << 8) | b;
-                    }
-                }
-            }
-        }
+        self.buffer.blend_rect(x, y, w, h, color);
     }
 
-    fn draw_char(&mut self, x: i32, y: i32, ch: char, color: Color, bold: bool) {
-        // Try to get from cache
-        let cache_key = GlyphCacheKey { ch, bold };
+    /// Draw a glyph at (x, y); `wide` glyphs get two cells of room
+    fn draw_char(&mut self, x: i32, y: i32, cache_key: GlyphCacheKey, color: Color) {
+        let GlyphCacheKey { ch, wide, .. } = cache_key;
 
         // Get or create the buffer for this character
         let buffer = if let Some(cached_buffer) = self.glyph_buffer_cache.get(&cache_key) {
@@ -144,18 +150,20 @@ impl SoftbufferBackend {
             cached_buffer
         } else {
             // Create a new buffer and cache it
+            let family = self.glyph_family(cache_key);
             let metrics = Metrics::new(self.font_size, self.cell_height);
             let mut buffer = Buffer::new(&mut self.font_system, metrics);
 
             // Set buffer size to cell width to constrain text
-            buffer.set_size(&mut self.font_system, self.cell_width, self.cell_height);
-
-            let mut attrs = Attrs::new().family(cosmic_text::Family::Monospace);
-            if bold {
-                attrs = attrs.weight(cosmic_text::Weight::BOLD);
-            }
-
-            buffer.set_text(&mut self.font_system, &ch.to_string(), attrs, Shaping::Advanced);
+            let glyph_width = if wide { self.cell_width * 2.0 } else { self.cell_width };
+            buffer.set_size(&mut self.font_system, glyph_width, self.cell_height);
+
+            buffer.set_text(
+                &mut self.font_system,
+                &ch.to_string(),
+                cache_key.attrs(family.as_deref()),
+                Shaping::Advanced,
+            );
             buffer.shape_until_scroll(&mut self.font_system, false);
 
             // Insert into cache and return reference
@@ -186,33 +194,11 @@ impl SoftbufferBackend {
                         let px = physical_glyph.x + gx;
                         let py = physical_glyph.y + gy;
 
-                        // Check bounds (disabled cell clipping for debugging)
-                        if px >= 0 && px < self.width as i32
-                            && py >= 0 && py < self.height as i32
-                        {
-                            let idx = (py * self.width as i32 + px) as usize;
-                            if idx < self.buffer.len() {
-                                // Blend the glyph with the background
-                                let color_u32 = alpha_color.0;
-                                let alpha = ((color_u32 >> 24) & 0xFF) as f32 / 255.0;
-                                if alpha > 0.0 {
-                                    let fg_r = ((color_u32 >> 16) & 0xFF) as f32;
-                                    let fg_g = ((color_u32 >> 8) & 0xFF) as f32;
-                                    let fg_b = (color_u32 & 0xFF) as f32;
-
-                                    let bg = self.buffer[idx];
-                                    let bg_r = ((bg >> 16) & 0xFF) as f32;
-                                    let bg_g = ((bg >> 8) & 0xFF) as f32;
-                                    let bg_b = (bg & 0xFF) as f32;
-
-                                    let r = (fg_r * alpha + bg_r * (1.0 - alpha)) as u32;
-                                    let g = (fg_g * alpha + bg_g * (1.0 - alpha)) as u32;
-                                    let b = (fg_b * alpha + bg_b * (1.0 - alpha)) as u32;
-
-                                    self.buffer[idx] = 0xFF000000 | (r << 16) | (g << 8) | b;
-                                }
-                            }
-                        }
+                        // Blend the glyph with the background (clipped by the buffer)
+                        let color_u32 = alpha_color.0;
+                        let alpha = ((color_u32 >> 24) & 0xFF) as f32 / 255.0;
+                        let fg = Color::rgb((color_u32 >> 16) as u8, (color_u32 >> 8) as u8, color_u32 as u8);
+                        self.buffer.blend_pixel(px, py, fg, alpha);
                     },
                 );
             }
@@ -220,85 +206,280 @@ impl SoftbufferBackend {
     }
 }
 
+/// Name of the installed family matching `requested` (case-insensitive), or None
+/// when the generic monospace family was asked for or nothing matches
+fn find_font_family<'a>(mut installed: impl Iterator<Item = &'a str>, requested: &str) -> Option<String> {
+    let requested = requested.trim();
+    if requested.is_empty() || requested.eq_ignore_ascii_case("monospace") {
+        return None;
+    }
+    installed.find(|name| name.eq_ignore_ascii_case(requested)).map(str::to_string)
+}
+
+/// Family bold or normal text is shaped with before falling back: the bold font for
+/// bold text when one is configured, otherwise the primary font
+fn primary_family<'a>(bold: bool, font_family: &'a Option<String>, bold_font_family: &'a Option<String>) -> &'a Option<String> {
+    if bold && bold_font_family.is_some() { bold_font_family } else { font_family }
+}
+
+/// Whether `family` (None = generic monospace) has a glyph for the key's character,
+/// checked in the face closest to the key's style and weight
+fn has_glyph(font_system: &mut FontSystem, family: Option<&str>, key: GlyphCacheKey) -> bool {
+    let attrs = key.attrs(None);
+    let db = font_system.db();
+    let name = family.unwrap_or_else(|| db.family_name(&cosmic_text::Family::Monospace));
+    let face = db
+        .faces()
+        .filter(|face| face.families.iter().any(|(face_name, _)| face_name == name))
+        .min_by_key(|face| (face.style != attrs.style, face.weight.0.abs_diff(attrs.weight.0)));
+    let Some(id) = face.map(|face| face.id) else {
+        return false;
+    };
+    font_system
+        .get_font(id)
+        .is_some_and(|font| font.rustybuzz().glyph_index(key.ch).is_some())
+}
+
+/// Family to shape a glyph with: the primary (or bold) font when it has the glyph, otherwise the
+/// first fallback font that does. When none has it, the primary font is used and
+/// cosmic-text's own fallback picks a font.
+fn covering_family(
+    font_system: &mut FontSystem,
+    primary: Option<String>,
+    fallback_fonts: &[String],
+    key: GlyphCacheKey,
+) -> Option<String> {
+    if fallback_fonts.is_empty() || key.ch.is_ascii() {
+        return primary;
+    }
+    let candidates: Vec<Option<String>> = std::iter::once(primary.clone())
+        .chain(fallback_fonts.iter().cloned().map(Some))
+        .collect();
+    first_covering(&candidates, |family| has_glyph(font_system, family.as_deref(), key)).unwrap_or(primary)
+}
+
+/// First of `candidates` (the primary font, then the fallbacks) that has a glyph for a character
+fn first_covering<T: Clone>(candidates: &[T], mut has_glyph: impl FnMut(&T) -> bool) -> Option<T> {
+    candidates.iter().find(|&candidate| has_glyph(candidate)).cloned()
+}
+
 impl SoftbufferBackend {
+    /// Resolve a configured family against the installed fonts, logging the result
+    fn resolve_font_family(font_system: &FontSystem, requested: &str) -> Option<String> {
+        let family = Self::installed_family(font_system, requested);
+        match &family {
+            Some(name) => log::info!("Using font family '{}'", name),
+            None if requested.trim().is_empty() || requested.trim().eq_ignore_ascii_case("monospace") => {
+                log::info!("Using the default monospace font")
+            }
+            None => log::warn!("Font family '{}' not found, falling back to monospace", requested),
+        }
+        family
+    }
+
+    fn installed_family(font_system: &FontSystem, requested: &str) -> Option<String> {
+        let installed = font_system
+            .db()
+            .faces()
+            .flat_map(|face| face.families.iter().map(|(name, _)| name.as_str()));
+        find_font_family(installed, requested)
+    }
+
+    /// Family to shape a glyph with (see `covering_family`)
+    fn glyph_family(&mut self, key: GlyphCacheKey) -> Option<String> {
+        let primary = primary_family(key.bold, &self.font_family, &self.bold_font_family).clone();
+        covering_family(&mut self.font_system, primary, &self.fallback_fonts, key)
+    }
+
     /// Clear the entire buffer
     fn clear_buffer(&mut self) {
-        let bg_color = Color::BLACK;
-        self.buffer.fill(Self::color_to_u32(bg_color));
+        self.buffer.fill(self.clear_color);
     }
 
-    /// Render text to buffer with offset and clipping
+    /// Render a pane into its area; all drawing is clipped to the area
     fn render_text_to_buffer_with_offset(
         &mut self,
         grid: &mut Grid,
         cursor: CursorInfo,
-        offset_x: i32,
-        offset_y: i32,
-        clip_width: u32,
-        clip_height: u32,
+        options: PaneRenderOptions,
+        area: Rect,
     ) {
         let grid_cols = grid.cols();
         let grid_rows = grid.rows();
-
-        // Always render all cells for correctness
-        // Buffer is already cleared in clear() before rendering all panes
-        for row in 0..grid_rows {
-            for col in 0..grid_cols {
-                if let Some(cell) = grid.get(col, row) {
+        let offset_x = area.x as i32;
+        let offset_y = area.y as i32;
+
+        // The cursor moves down with the live screen while the view is scrolled back
+        let cursor = CursorInfo { row: cursor.row + grid.scroll_offset(), ..cursor };
+        let cursor_cell = (cursor.visible && cursor.row < grid_rows && cursor.col < grid_cols)
+            .then_some((cursor.col, cursor.row));
+
+        // Repaint only what changed when the previous frame left this pane intact
+        let previous = self.drawn_panes.iter().find(|drawn| drawn.area == area).copied();
+        let damaged = previous
+            .filter(|previous| !self.frame_cleared && !grid.needs_full_redraw() && previous.options == options)
+            .map(|previous| damaged_cells(grid, &previous.cursor.into_iter().chain(cursor_cell).collect::<Vec<_>>()));
+        self.drawing_panes.push(DrawnPane { area, options, cursor: cursor_cell });
+
+        self.buffer.push_clip(area);
+
+        // A change can make the spaces before it trailing (or no longer trailing),
+        // so rows with whitespace markers are repainted as a whole
+        let damaged = damaged.map(|cells| {
+            if !options.show_whitespace {
+                return cells;
+            }
+            let mut rows: Vec<usize> = cells.iter().map(|&(_, row)| row).collect();
+            rows.dedup();
+            rows.into_iter().flat_map(|row| (0..grid_cols).map(move |col| (col, row))).collect()
+        });
+
+        let rows: Vec<usize> = match &damaged {
+            Some(cells) => {
+                log::trace!("Repainting {} of {} cells", cells.len(), grid_cols * grid_rows);
+                let mut logical_len = None;
+                for &(col, row) in cells {
+                    let Some(row_cells) = grid.view_row(row) else { continue };
+                    let Some(cell) = row_cells.get(col).copied() else { continue };
+                    let len = match logical_len {
+                        Some((cached_row, len)) if cached_row == row => len,
+                        _ => {
+                            let len = row_logical_len(row_cells);
+                            logical_len = Some((row, len));
+                            len
+                        }
+                    };
                     let x = offset_x + (col as f32 * self.cell_width) as i32;
                     let y = offset_y + (row as f32 * self.cell_height) as i32;
+                    self.render_cell_at(x, y, &cell, col >= len, &options);
+                }
+                let mut rows: Vec<usize> = cells.iter().map(|&(_, row)| row).collect();
+                rows.dedup();
+                rows
+            }
+            None => {
+                // Under reverse video or a tint the default background of the whole pane changes,
+                // including the margin not covered by cells
+                if options.reverse_video || options.tint.is_some() {
+                    let (_, bg) = resolve_cell_colors(&crate::terminal::grid::Cell::default(), &options);
+                    self.draw_rect(offset_x, offset_y, area.width as i32, area.height as i32, bg);
+                } else if !self.frame_cleared {
+                    self.draw_rect(offset_x, offset_y, area.width as i32, area.height as i32, self.clear_color);
+                }
 
-                    // Clip to pane boundaries
-                    if x < offset_x || x >= (offset_x + clip_width as i32) {
-                        continue;
-                    }
-                    if y < offset_y || y >= (offset_y + clip_height as i32) {
-                        continue;
+                for row in 0..grid_rows {
+                    let Some(cells) = grid.view_row(row) else { continue };
+                    let len = row_logical_len(cells);
+                    for (col, cell) in cells.iter().take(grid_cols).enumerate() {
+                        let x = offset_x + (col as f32 * self.cell_width) as i32;
+                        let y = offset_y + (row as f32 * self.cell_height) as i32;
+                        self.render_cell_at(x, y, cell, col >= len, &options);
                     }
-
-                    self.render_cell_at(x, y, cell);
                 }
+                (0..grid_rows).collect()
+            }
+        };
+
+        // Draw underlines and strikethroughs as runs so adjacent cells join seamlessly
+        let line_thickness = ((self.cell_height / 14.0).round() as i32).max(1);
+        for row in rows {
+            let y = offset_y + (row as f32 * self.cell_height) as i32;
+            let Some(cells) = grid.view_row(row) else { continue };
+
+            for run in decoration_runs(cells, &options) {
+                let (start, end) = cell_span_pixels(run.start_col, run.end_col, self.cell_width);
+                let line_y = match run.decoration {
+                    Decoration::Underline => y + (self.cell_height * 0.9) as i32 - line_thickness,
+                    Decoration::Strikethrough => y + (self.cell_height * 0.5) as i32,
+                };
+                self.draw_rect(offset_x + start, line_y, end - start, line_thickness, run.color);
             }
         }
 
-        // Draw cursor
-        if cursor.visible && cursor.row < grid_rows && cursor.col < grid_cols {
+        if cursor.visible && cursor.hollow && cursor.row < grid_rows && cursor.col < grid_cols {
+            // Hollow cursor of an inactive pane: a 1px outline around the cell,
+            // shrunk to stay fully visible in a cell cut off by the pane edge
             let x = offset_x + (cursor.col as f32 * self.cell_width) as i32;
             let y = offset_y + (cursor.row as f32 * self.cell_height) as i32;
-            let cursor_height = 2;
-            let cursor_y = y + (self.cell_height * 0.8) as i32;
-
-            // Clip cursor to pane boundaries
-            if x >= offset_x && x < (offset_x + clip_width as i32) &&
-               cursor_y >= offset_y && cursor_y < (offset_y + clip_height as i32) {
-                self.draw_rect(x, cursor_y, self.cell_width as i32, cursor_height, Color::WHITE);
+            let w = (self.cell_width as i32).min(offset_x + area.width as i32 - x);
+            let h = (self.cell_height as i32).min(offset_y + area.height as i32 - y);
+            if w > 0 && h > 0 {
+                self.draw_rect(x, y, w, 1, Color::WHITE);
+                self.draw_rect(x, y + h - 1, w, 1, Color::WHITE);
+                self.draw_rect(x, y, 1, h, Color::WHITE);
+                self.draw_rect(x + w - 1, y, 1, h, Color::WHITE);
             }
+        } else if cursor.visible && cursor.row < grid_rows && cursor.col < grid_cols {
+            let x = offset_x + (cursor.col as f32 * self.cell_width) as i32;
+            let y = offset_y + (cursor.row as f32 * self.cell_height) as i32;
+            let cell = grid.view_row(cursor.row).and_then(|cells| cells.get(cursor.col)).copied().unwrap_or_default();
+            self.draw_cursor(x, y, &cell, &cursor, &options);
         }
 
+        self.buffer.pop_clip();
+
         // Clear dirty tracking after rendering
         grid.clear_dirty();
     }
 
-    fn render_cell_at(&mut self, x: i32, y: i32, cell: &crate::terminal::grid::Cell) {
-        // Determine colors (handle inverse)
-        let (fg, bg) = if cell.attrs.inverse {
-            (cell.bg, cell.fg)
-        } else {
-            (cell.fg, cell.bg)
-        };
+    /// Draw the active pane's cursor in its style over `cell`, whose top-left corner is (x, y)
+    fn draw_cursor(&mut self, x: i32, y: i32, cell: &Cell, cursor: &CursorInfo, options: &PaneRenderOptions) {
+        // Block cursors keep the character readable by inverting the cell
+        if cursor.style == CursorStyle::Block && !cursor.solid_block {
+            self.render_cell_at(x, y, &inverted_cell(cell), false, options);
+            return;
+        }
 
-        // Draw background
-        self.draw_rect(x, y, self.cell_width as i32, self.cell_height as i32, bg);
+        let (_, cell_bg) = resolve_cell_colors(cell, options);
+        match cursor_paint(cursor.color, cell_bg, cursor.auto_contrast) {
+            CursorPaint::InvertCell => self.render_cell_at(x, y, &inverted_cell(cell), false, options),
+            CursorPaint::Solid(color) if cursor.style == CursorStyle::Block => {
+                let (start, end) = cell_span_pixels(0, cell.width.max(1) as usize, self.cell_width);
+                self.draw_rect(x, y, end - start, self.cell_height as i32, color);
+            }
+            CursorPaint::Solid(color) if cursor.style == CursorStyle::Bar => {
+                let bar_width = ((self.cell_width / 8.0).round() as i32).max(2);
+                self.draw_rect(x, y, bar_width, self.cell_height as i32, color);
+            }
+            CursorPaint::Solid(color) => {
+                // A 2px underline at about 80% down the cell
+                let cursor_y = y + (self.cell_height * 0.8) as i32;
+                self.draw_rect(x, cursor_y, self.cell_width as i32, 2, color);
+            }
+        }
+    }
 
-        // Draw character using cosmic-text
-        if cell.ch != ' ' && cell.ch != '\0' {
-            self.draw_char(x, y, cell.ch, fg, cell.attrs.bold);
+    /// Draw a cell whose top-left corner is (x, y). `trailing` tells that it comes after
+    /// the last visible character of its row, for the whitespace markers.
+    fn render_cell_at(&mut self, x: i32, y: i32, cell: &crate::terminal::grid::Cell, trailing: bool, options: &PaneRenderOptions) {
+        // The second column of a wide character is painted together with the first
+        if cell.is_continuation() {
+            return;
+        }
+
+        // Determine colors (handle inverse and reverse video)
+        let (fg, bg) = resolve_cell_colors(cell, options);
+
+        // Draw background (across both columns of a wide character)
+        let (start, end) = cell_span_pixels(0, cell.width.max(1) as usize, self.cell_width);
+        self.draw_rect(x, y, end - start, self.cell_height as i32, bg);
+
+        if let Some(marker) = whitespace_marker(cell, trailing, options) {
+            self.draw_char(x, y, GlyphCacheKey::plain(marker, self.font_weights), faint_color(fg, bg));
+            return;
+        }
+
+        // Draw line-drawing characters with rectangles, anything else using cosmic-text
+        if cell.ch != ' ' && cell.ch != '\0'
+            && !draw_box_char(&mut self.buffer, cell.ch, x, y, end - start, self.cell_height as i32, fg)
+        {
+            self.draw_char(x, y, GlyphCacheKey::for_cell(cell, self.font_weights), fg);
         }
     }
 }
 
 impl RenderBackend for SoftbufferBackend {
-    fn new(window: &Window, font_size: f32) -> Result<Self> {
+    fn new(window: &Window, font_size: f32, font_family: &str) -> Result<Self> {
         // SAFETY: We extend the lifetime of the window reference to 'static
         // This is safe because the window lifetime is managed by the application
         // and will outlive the backend
@@ -316,16 +497,15 @@ impl RenderBackend for SoftbufferBackend {
 
         let mut font_system = FontSystem::new();
         let swash_cache = SwashCache::new();
+        let font_family = Self::resolve_font_family(&font_system, font_family);
 
         // Use fixed monospace dimensions - more reliable
         // (Previously used cosmic-text measurement, but fixed dimensions are more consistent)
-        let cell_width = font_size * 0.6; // Fixed monospace width
-        let cell_height = font_size * 1.3; // Line height with spacing
+        let (cell_width, cell_height) = cell_size_for_font(font_size);
 
         let measured_width = cell_width; // For logging
 
-        let buffer_size = (width * height) as usize;
-        let buffer = vec![0u32; buffer_size];
+        let buffer = PixelBuffer::new(width, height);
 
         // Initialize surface size
         surface
@@ -351,11 +531,19 @@ impl RenderBackend for SoftbufferBackend {
             swash_cache,
             glyph_buffer_cache: HashMap::new(),
             font_size,
+            font_family,
+            fallback_fonts: Vec::new(),
+            bold_font_family: None,
+            font_weights: FontWeights::default(),
             cell_width,
             cell_height,
             width,
             height,
             buffer,
+            clear_color: Color::BLACK,
+            frame_cleared: false,
+            drawn_panes: Vec::new(),
+            drawing_panes: Vec::new(),
         })
     }
 
@@ -363,7 +551,8 @@ impl RenderBackend for SoftbufferBackend {
         if width > 0 && height > 0 {
             self.width = width;
             self.height = height;
-            self.buffer.resize((width * height) as usize, 0);
+            self.buffer.resize(width, height);
+            self.drawn_panes.clear();
 
             self.surface
                 .resize(
@@ -377,6 +566,7 @@ impl RenderBackend for SoftbufferBackend {
 
     fn clear(&mut self) -> Result<()> {
         self.clear_buffer();
+        self.frame_cleared = true;
         Ok(())
     }
 
@@ -389,12 +579,10 @@ impl RenderBackend for SoftbufferBackend {
         &mut self,
         grid: &mut Grid,
         cursor: CursorInfo,
-        offset_x: i32,
-        offset_y: i32,
-        width: u32,
-        height: u32,
+        options: PaneRenderOptions,
+        area: Rect,
     ) -> Result<()> {
-        self.render_text_to_buffer_with_offset(grid, cursor, offset_x, offset_y, width, height);
+        self.render_text_to_buffer_with_offset(grid, cursor, options, area);
         Ok(())
     }
 
@@ -414,23 +602,102 @@ impl RenderBackend for SoftbufferBackend {
         Ok(())
     }
 
+    fn draw_focus_flash(&mut self, x: i32, y: i32, width: i32, height: i32) -> Result<()> {
+        let flash_color = Color::rgb(255, 255, 255);
+        let thickness = 4;
+
+        self.draw_rect(x, y, width, thickness, flash_color);
+        self.draw_rect(x, y + height - thickness, width, thickness, flash_color);
+        self.draw_rect(x, y, thickness, height, flash_color);
+        self.draw_rect(x + width - thickness, y, thickness, height, flash_color);
+
+        Ok(())
+    }
+
+    fn draw_visual_bell(&mut self, area: Rect) -> Result<()> {
+        // Wash the pane out towards white, keeping the text readable
+        let flash_color = Color::rgba(255, 255, 255, 96);
+        self.draw_rect_blend(area.x as i32, area.y as i32, area.width as i32, area.height as i32, flash_color);
+        Ok(())
+    }
+
+    fn draw_corner_label(&mut self, text: &str, area: Rect) -> Result<()> {
+        // Same colors as the help overlay, inset from the pane's corner
+        let margin = 4;
+        let label_width = ((text.chars().count() as f32 + 2.0) * self.cell_width) as i32;
+        let label_height = self.cell_height as i32 + 2;
+        let x = area.x as i32 + area.width as i32 - label_width - margin;
+        let y = area.y as i32 + margin;
+
+        self.buffer.push_clip(area);
+        self.draw_rect(x, y, label_width, label_height, Color::rgb(100, 150, 255));
+        self.draw_rect(x + 1, y + 1, label_width - 2, label_height - 2, Color::rgb(40, 40, 60));
+        for (i, ch) in text.chars().enumerate() {
+            let char_x = x + ((i as f32 + 1.0) * self.cell_width) as i32;
+            self.draw_char(char_x, y + 1, GlyphCacheKey::plain(ch, self.font_weights), Color::WHITE);
+        }
+        self.buffer.pop_clip();
+
+        Ok(())
+    }
+
+    fn draw_tab_bar(&mut self, titles: &[String], active: usize, area: Rect) -> Result<()> {
+        // Same colors as the help overlay, with the active tab in the border color
+        let border_color = Color::rgb(100, 150, 255);
+        let count = titles.len().max(1) as u32;
+        let (y, height) = (area.y as i32, area.height as i32);
+
+        self.buffer.push_clip(area);
+        self.draw_rect(area.x as i32, y, area.width as i32, height, Color::rgb(40, 40, 60));
+        for (index, title) in titles.iter().enumerate() {
+            let x = area.x + area.width * index as u32 / count;
+            let width = area.x + area.width * (index as u32 + 1) / count - x;
+            if index == active {
+                self.draw_rect(x as i32, y, width as i32, height, border_color);
+            } else if index > 0 && index != active + 1 {
+                self.draw_rect(x as i32, y, 1, height, border_color);
+            }
+
+            let max_cols = ((width as f32 / self.cell_width) as usize).saturating_sub(2);
+            for (col, ch) in label_columns(title, max_cols) {
+                let char_x = x as i32 + ((col as f32 + 1.0) * self.cell_width) as i32;
+                let key = GlyphCacheKey { wide: ch.width() == Some(2), ..GlyphCacheKey::plain(ch, self.font_weights) };
+                self.draw_char(char_x, y, key, Color::WHITE);
+            }
+        }
+        self.buffer.pop_clip();
+
+        Ok(())
+    }
+
     fn present(&mut self) -> Result<()> {
+        // The buffer keeps this frame's pixels, which the next frame builds on; panes
+        // not drawn in this frame still show those of an earlier one
+        let drawing = std::mem::take(&mut self.drawing_panes);
+        if self.frame_cleared {
+            self.drawn_panes = drawing;
+        } else {
+            self.drawn_panes.retain(|drawn| drawing.iter().all(|pane| pane.area != drawn.area));
+            self.drawn_panes.extend(drawing);
+        }
+        self.frame_cleared = false;
+
         let mut surface_buffer = self
             .surface
             .buffer_mut()
             .map_err(|e| TerbulatorError::rendering(format!("Failed to get surface buffer: {}", e)))?;
 
         // Verify buffer sizes match
-        if surface_buffer.len() != self.buffer.len() {
+        if surface_buffer.len() != self.buffer.pixels().len() {
             log::error!(
                 "Buffer size mismatch: surface={}, internal={}",
                 surface_buffer.len(),
-                self.buffer.len()
+                self.buffer.pixels().len()
             );
             return Err(TerbulatorError::rendering("Buffer size mismatch"));
         }
 
-        surface_buffer.copy_from_slice(&self.buffer);
+        surface_buffer.copy_from_slice(self.buffer.pixels());
 
         surface_buffer
             .present()
@@ -477,7 +744,7 @@ impl RenderBackend for SoftbufferBackend {
         for line in help_text {
             for (i, ch) in line.chars().enumerate() {
                 let char_x = text_x + (i as f32 * self.cell_width) as i32;
-                self.draw_char(char_x, text_y, ch, text_color, false);
+                self.draw_char(char_x, text_y, GlyphCacheKey::plain(ch, self.font_weights), text_color);
             }
             text_y += self.cell_height as i32;
         }
@@ -492,14 +759,68 @@ impl RenderBackend for SoftbufferBackend {
     fn set_font_size(&mut self, size: f32) -> Result<()> {
         self.font_size = size;
         // Recalculate cell dimensions
-        self.cell_width = size * 0.6;
-        self.cell_height = size * 1.3;
+        (self.cell_width, self.cell_height) = cell_size_for_font(size);
         // Clear glyph cache as font size changed
         self.glyph_buffer_cache.clear();
+        self.drawn_panes.clear();
         log::info!("Font size changed to {}, cell dimensions: {}x{}, glyph cache cleared", size, self.cell_width, self.cell_height);
         Ok(())
     }
 
+    fn set_fallback_fonts(&mut self, families: &[String]) -> Result<()> {
+        let fallback_fonts: Vec<String> = families
+            .iter()
+            .filter_map(|family| {
+                let installed = Self::installed_family(&self.font_system, family);
+                match &installed {
+                    Some(name) => log::info!("Using fallback font '{}'", name),
+                    None => log::warn!("Fallback font '{}' not found, skipping it", family),
+                }
+                installed
+            })
+            .collect();
+        if fallback_fonts != self.fallback_fonts {
+            self.fallback_fonts = fallback_fonts;
+            self.glyph_buffer_cache.clear();
+            self.drawn_panes.clear();
+        }
+        Ok(())
+    }
+
+    fn set_font_weights(&mut self, weights: FontWeights) -> Result<()> {
+        if weights != self.font_weights {
+            log::info!("Using font weight {} ({} for bold text)", weights.regular, weights.bold);
+            self.font_weights = weights;
+            self.glyph_buffer_cache.clear();
+            self.drawn_panes.clear();
+        }
+        Ok(())
+    }
+
+    fn set_bold_font_family(&mut self, family: Option<&str>) -> Result<()> {
+        let family = family.and_then(|family| {
+            let installed = Self::installed_family(&self.font_system, family);
+            match &installed {
+                Some(name) => log::info!("Using bold font '{}'", name),
+                None => log::warn!("Bold font '{}' not found, using the primary font in bold", family),
+            }
+            installed
+        });
+        if family != self.bold_font_family {
+            self.bold_font_family = family;
+            self.glyph_buffer_cache.clear();
+            self.drawn_panes.clear();
+        }
+        Ok(())
+    }
+
+    fn set_clear_color(&mut self, color: Color) {
+        if color != self.clear_color {
+            self.clear_color = color;
+            self.drawn_panes.clear();
+        }
+    }
+
     fn draw_selection_highlight(&mut self, col: usize, row: usize, cell_width: f32, cell_height: f32, offset_x: i32, offset_y: i32) -> Result<()> {
         let x = offset_x + (col as f32 * cell_width) as i32;
         let y = offset_y + (row as f32 * cell_height) as i32;
@@ -521,32 +842,156 @@ impl RenderBackend for SoftbufferBackend {
             let dest_x = x + px as i32;
             let dest_y = y + py as i32;
 
-            // Check bounds
-            if dest_x >= 0 && dest_x < self.width as i32 && dest_y >= 0 && dest_y < self.height as i32 {
-                let idx = (dest_y * self.width as i32 + dest_x) as usize;
-                if idx < self.buffer.len() {
-                    // Blend pixel with background if it has alpha
-                    let alpha = pixel[3] as f32 / 255.0;
-                    if alpha > 0.999 {
-                        // Fully opaque
-                        self.buffer[idx] = 0xFF000000 | ((pixel[0] as u32) << 16) | ((pixel[1] as u32) << 8) | (pixel[2] as u32);
-                    } else if alpha > 0.0 {
-                        // Alpha blending
-                        let bg = self.buffer[idx];
-                        let bg_r = ((bg >> 16) & 0xFF) as f32;
-                        let bg_g = ((bg >> 8) & 0xFF) as f32;
-                        let bg_b = (bg & 0xFF) as f32;
-
-                        let r = (pixel[0] as f32 * alpha + bg_r * (1.0 - alpha)) as u32;
-                        let g = (pixel[1] as f32 * alpha + bg_g * (1.0 - alpha)) as u32;
-                        let b = (pixel[2] as f32 * alpha + bg_b * (1.0 - alpha)) as u32;
-
-                        self.buffer[idx] = 0xFF000000 | (r << 16) | (g << 8) | b;
-                    }
-                }
+            // Blend pixel with background if it has alpha
+            let color = Color::rgb(pixel[0], pixel[1], pixel[2]);
+            let alpha = pixel[3] as f32 / 255.0;
+            if alpha > 0.999 {
+                // Fully opaque
+                self.buffer.set_pixel(dest_x, dest_y, color);
+            } else {
+                self.buffer.blend_pixel(dest_x, dest_y, color, alpha);
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A row with one cell for each combination of bold, italic, underline and strikethrough
+    fn attribute_combinations() -> Vec<Cell> {
+        (0..16u8)
+            .map(|bits| {
+                let mut cell = Cell::new('a');
+                cell.attrs.bold = bits & 1 != 0;
+                cell.attrs.italic = bits & 2 != 0;
+                cell.attrs.underline = bits & 4 != 0;
+                cell.attrs.strikethrough = bits & 8 != 0;
+                cell
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_all_attribute_combinations() {
+        let row = attribute_combinations();
+
+        // Each bold/italic combination is shaped (and cached) separately
+        let weights = FontWeights::default();
+        let keys: std::collections::HashSet<_> = row.iter().map(|cell| GlyphCacheKey::for_cell(cell, weights)).collect();
+        assert_eq!(keys.len(), 4);
+        for cell in &row {
+            let attrs = GlyphCacheKey::for_cell(cell, weights).attrs(None);
+            let weight = if cell.attrs.bold { cosmic_text::Weight::BOLD } else { cosmic_text::Weight::NORMAL };
+            let style = if cell.attrs.italic { cosmic_text::Style::Italic } else { cosmic_text::Style::Normal };
+            assert_eq!(attrs.weight, weight);
+            assert_eq!(attrs.style, style);
+        }
+
+        // Every underlined/struck-through cell is covered by exactly one line of that kind
+        let runs = decoration_runs(&row, &PaneRenderOptions::default());
+        for (col, cell) in row.iter().enumerate() {
+            let covered = |decoration| {
+                runs.iter()
+                    .filter(|run| run.decoration == decoration && (run.start_col..run.end_col).contains(&col))
+                    .count()
+            };
+            assert_eq!(covered(Decoration::Underline), cell.attrs.underline as usize);
+            assert_eq!(covered(Decoration::Strikethrough), cell.attrs.strikethrough as usize);
+        }
+    }
+
+    #[test]
+    fn test_find_font_family() {
+        let installed = ["DejaVu Sans Mono", "JetBrains Mono", "Noto Sans CJK JP"];
+        let find = |requested| find_font_family(installed.iter().copied(), requested);
+
+        assert_eq!(find("JetBrains Mono").as_deref(), Some("JetBrains Mono"));
+        assert_eq!(find("jetbrains mono").as_deref(), Some("JetBrains Mono"));
+        assert_eq!(find("Missing Font"), None);
+        assert_eq!(find("monospace"), None);
+        assert_eq!(find(""), None);
+
+        let key = GlyphCacheKey::plain('a', FontWeights::default());
+        assert_eq!(key.attrs(Some("JetBrains Mono")).family, cosmic_text::Family::Name("JetBrains Mono"));
+        assert_eq!(key.attrs(None).family, cosmic_text::Family::Monospace);
+    }
+
+    #[test]
+    fn test_configured_font_weights() {
+        let weights = FontWeights { regular: 500, bold: 800 };
+        let normal = Cell::new('a');
+        let mut bold = Cell::new('a');
+        bold.attrs.bold = true;
+
+        // The weights are part of the key, so changing them never reuses a cached glyph
+        let normal_key = GlyphCacheKey::for_cell(&normal, weights);
+        let bold_key = GlyphCacheKey::for_cell(&bold, weights);
+        assert_eq!(normal_key.attrs(None).weight, cosmic_text::Weight(500));
+        assert_eq!(bold_key.attrs(None).weight, cosmic_text::Weight(800));
+        assert_eq!(GlyphCacheKey::plain('a', weights).attrs(None).weight, cosmic_text::Weight(500));
+        assert_ne!(normal_key, GlyphCacheKey::for_cell(&normal, FontWeights::default()));
+
+        // Bold text uses the bold font when there is one
+        let regular_font = Some("Iosevka".to_string());
+        let bold_font = Some("Iosevka Heavy".to_string());
+        assert_eq!(primary_family(false, &regular_font, &bold_font), &regular_font);
+        assert_eq!(primary_family(true, &regular_font, &bold_font), &bold_font);
+        assert_eq!(primary_family(true, &regular_font, &None), &regular_font);
+    }
+
+    #[test]
+    fn test_fallback_fonts_cover_mixed_text() {
+        // Primary font with ASCII only, then an emoji font and a CJK font
+        let covers = |name: &str, ch: char| match name {
+            "Mono" => ch.is_ascii(),
+            "Emoji" => ch == '😀',
+            _ => ('\u{4E00}'..='\u{9FFF}').contains(&ch),
+        };
+        let candidates = ["Mono", "Emoji", "CJK"];
+
+        for (ch, expected) in [('a', "Mono"), ('😀', "Emoji"), ('漢', "CJK")] {
+            let family = first_covering(&candidates, |&name| covers(name, ch));
+            assert_eq!(family, Some(expected), "glyph for {:?}", ch);
+        }
+
+        // No installed font has it: left to cosmic-text's own fallback
+        assert_eq!(first_covering(&candidates, |_| false), None);
+    }
+
+    #[test]
+    fn test_covering_family_with_installed_fonts() {
+        let mut font_system = FontSystem::new();
+        let weights = FontWeights::default();
+        let (ascii, cjk) = (GlyphCacheKey::plain('a', weights), GlyphCacheKey::plain('漢', weights));
+
+        let mut families: Vec<String> = font_system
+            .db()
+            .faces()
+            .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+            .collect();
+        families.sort();
+        families.dedup();
+        let mut covering = |ch| -> Vec<String> {
+            families.iter().filter(|family| has_glyph(&mut font_system, Some(family.as_str()), ch)).cloned().collect()
+        };
+        let (latin, cjk_fonts) = (covering(ascii), covering(cjk));
+        // A font with Latin glyphs but no CJK ones as the primary font
+        let Some(primary) = latin.into_iter().find(|family| !cjk_fonts.contains(family)) else {
+            return;
+        };
+
+        // ASCII stays with the primary font; CJK goes to the first fallback that has it,
+        // or back to the primary font when no installed font has it
+        let fallbacks = [primary.clone()].into_iter().chain(cjk_fonts.first().cloned()).collect::<Vec<_>>();
+        assert!(has_glyph(&mut font_system, Some(&primary), ascii));
+        assert_eq!(covering_family(&mut font_system, Some(primary.clone()), &fallbacks, ascii), Some(primary.clone()));
+        assert_eq!(
+            covering_family(&mut font_system, Some(primary.clone()), &fallbacks, cjk),
+            Some(cjk_fonts.first().unwrap_or(&primary).clone())
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use crate::renderer::backend::{BackendType, Color, CursorInfo, RenderBackend};
+use crate::renderer::backend::{BackendType, Color, CursorInfo, FontWeights, PaneRenderOptions, RenderBackend};
+use crate::pane::Rect;
 use crate::terminal::Grid;
 use crate::utils::{Result, TerbulatorError};
 use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, SwashCache};
@@ -207,7 +208,7 @@ impl WgpuBackend {
 }
 
 impl RenderBackend for WgpuBackend {
-    fn new(_window: &Window, _font_size: f32) -> Result<Self> {
+    fn new(_window: &Window, _font_size: f32, _font_family: &str) -> Result<Self> {
         // GPU backend is not yet fully implemented
         // For now, we return an error and fall back to CPU backend
         Err(TerbulatorError::backend_init(
@@ -309,10 +310,8 @@ impl RenderBackend for WgpuBackend {
         &mut self,
         grid: &mut Grid,
         _cursor: CursorInfo,
-        _offset_x: i32,
-        _offset_y: i32,
-        _width: u32,
-        _height: u32,
+        _options: PaneRenderOptions,
+        _area: Rect,
     ) -> Result<()> {
         // Clear dirty tracking (wgpu backend not yet fully implemented)
         grid.clear_dirty();
@@ -325,6 +324,26 @@ impl RenderBackend for WgpuBackend {
         Ok(())
     }
 
+    fn draw_focus_flash(&mut self, _x: i32, _y: i32, _width: i32, _height: i32) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
+    fn draw_visual_bell(&mut self, _area: Rect) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
+    fn draw_corner_label(&mut self, _text: &str, _area: Rect) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
+    fn draw_tab_bar(&mut self, _titles: &[String], _active: usize, _area: Rect) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
     fn present(&mut self) -> Result<()> {
         // Present is handled by the surface in wgpu
         Ok(())
@@ -357,6 +376,25 @@ impl RenderBackend for WgpuBackend {
         Ok(())
     }
 
+    fn set_fallback_fonts(&mut self, _families: &[String]) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
+    fn set_font_weights(&mut self, _weights: FontWeights) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
+    fn set_bold_font_family(&mut self, _family: Option<&str>) -> Result<()> {
+        // GPU backend not yet implemented
+        Ok(())
+    }
+
+    fn set_clear_color(&mut self, _color: Color) {
+        // GPU backend not yet implemented
+    }
+
     fn draw_selection_highlight(&mut self, _col: usize, _row: usize, _cell_width: f32, _cell_height: f32, _offset_x: i32, _offset_y: i32) -> Result<()> {
         // GPU backend not yet implemented
         Ok(())
@@ -0,0 +1,65 @@
+/// Character set that can be designated into G0/G1 (SCS, `ESC ( F` / `ESC ) F`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    #[default]
+    Ascii,
+    /// DEC Special Graphics (`F` = `0`), used by TUIs for line drawing
+    DecSpecialGraphics,
+}
+
+impl Charset {
+    /// Charset for the final byte of an SCS sequence; unsupported sets fall back to ASCII
+    pub fn from_designator(byte: u8) -> Self {
+        match byte {
+            b'0' => Charset::DecSpecialGraphics,
+            _ => Charset::Ascii,
+        }
+    }
+
+    /// Map a printed character through this charset
+    pub fn translate(self, ch: char) -> char {
+        match self {
+            Charset::Ascii => ch,
+            Charset::DecSpecialGraphics => dec_special_graphics(ch),
+        }
+    }
+}
+
+/// DEC Special Graphics table (0x5F-0x7E); other characters are unchanged
+fn dec_special_graphics(ch: char) -> char {
+    match ch {
+        '_' => '\u{00A0}', // blank
+        '`' => '◆',
+        'a' => '▒',
+        'b' => '␉',
+        'c' => '␌',
+        'd' => '␍',
+        'e' => '␊',
+        'f' => '°',
+        'g' => '±',
+        'h' => '␤',
+        'i' => '␋',
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'o' => '⎺',
+        'p' => '⎻',
+        'q' => '─',
+        'r' => '⎼',
+        's' => '⎽',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'y' => '≤',
+        'z' => '≥',
+        '{' => 'π',
+        '|' => '≠',
+        '}' => '£',
+        '~' => '·',
+        _ => ch,
+    }
+}
@@ -1,41 +1,305 @@
-use crate::renderer::backend::Color;
-use crate::terminal::grid::{Cell, CellAttributes, Grid};
+use crate::clipboard::ClipboardKind;
+use crate::input::mouse::{MouseEncoding, MouseMode};
+use crate::renderer::backend::{Color, CursorStyle};
+use crate::terminal::charset::Charset;
+use crate::terminal::grid::{Cell, CellAttributes, Grid, Whitespace};
 use crate::terminal::image::{KittyImageParser, SixelImageParser, TerminalImage};
+use crate::terminal::palette::{parse_color_spec, Palette};
+use base64::Engine;
+use unicode_width::UnicodeWidthChar;
 use vte::{Params, Perform};
+#[cfg(test)]
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Largest base64 payload accepted by OSC 52 (1 MiB of decoded text)
+const MAX_SELECTION_PAYLOAD: usize = 4 * 1024 * 1024 / 3;
+
+/// Longest OSC sequence buffered by the parser; longer ones are abandoned. OSC 52
+/// carries the largest payloads.
+const MAX_OSC_LEN: usize = MAX_SELECTION_PAYLOAD + 16;
+
+/// Longest URI accepted by OSC 8
+const MAX_LINK_LEN: usize = 2048;
+
+/// Hyperlink targets kept by OSC 8; once full, entries no cell refers to any more are reused
+const MAX_LINKS: usize = 1024;
+
+/// Longest unhandled OSC/DCS payload forwarded to the application
+const MAX_FORWARDED_PAYLOAD: usize = 64 * 1024;
+
+/// Cell size assumed for images until the renderer's is known
+const DEFAULT_CELL_SIZE: (f32, f32) = (10.0, 20.0);
+
+/// Titles kept by XTWINOPS 22, as in xterm; pushing more drops the oldest
+const TITLE_STACK_LIMIT: usize = 10;
+
+/// Request from the application that the emulator cannot fulfil itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalEvent {
+    /// OSC 52 - Store text in a system selection
+    ClipboardStore { kind: ClipboardKind, text: String },
+    /// An OSC or DCS sequence the emulator does not handle, reported only when
+    /// forwarding is enabled. The payload is what follows `ESC ]` / `ESC P` up to
+    /// the terminator.
+    UnhandledSequence { kind: SequenceKind, payload: Vec<u8> },
+    /// BEL outside a control string
+    Bell,
+}
+
+/// Kind of a forwarded control string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceKind {
+    Osc,
+    Dcs,
+}
+
+impl SequenceKind {
+    /// Lowercase name (`osc` / `dcs`)
+    pub fn name(self) -> &'static str {
+        match self {
+            SequenceKind::Osc => "osc",
+            SequenceKind::Dcs => "dcs",
+        }
+    }
+}
+
+/// DCS string being received
+enum Dcs {
+    /// DECRQSS request
+    StatusRequest(Vec<u8>),
+    /// Unhandled DCS to forward: its parameters and final byte, then the data
+    Unhandled(Vec<u8>),
+}
+
+/// Cursor state saved by DECSC / `?1048` / `?1049`
+#[derive(Debug, Clone, Copy)]
+struct SavedCursor {
+    col: usize,
+    row: usize,
+    fg: Color,
+    bright_fg: Option<Color>,
+    bg: Color,
+    attrs: CellAttributes,
+    origin_mode: bool,
+}
 
 pub struct TerminalEmulator {
+    /// Active screen buffer
     grid: Grid,
+    /// Inactive screen buffer (the primary screen while the alternate screen is active)
+    inactive_grid: Grid,
+    alt_screen_active: bool,
+    /// Images belonging to the inactive screen buffer
+    inactive_images: Vec<TerminalImage>,
     cursor_col: usize,
     cursor_row: usize,
     cursor_visible: bool,
+    /// Cursor shape (DECSCUSR)
+    cursor_style: CursorStyle,
+    /// Cursor shape restored by RIS (`terminal.cursor_style`)
+    default_cursor_style: CursorStyle,
+    /// DECSCNM - screen-wide reverse video
+    reverse_video: bool,
+    /// DECOM - absolute cursor rows count from the top margin and stay inside the scroll region
+    origin_mode: bool,
     current_fg: Color,
+    /// Bright variant of `current_fg` when it was set with SGR 30-37
+    current_bright_fg: Option<Color>,
     current_bg: Color,
     current_attrs: CellAttributes,
-    saved_cursor: Option<(usize, usize)>,
+    saved_cursor: Option<SavedCursor>,
+    /// Character sets designated into G0 and G1
+    charsets: [Charset; 2],
+    /// Index of the invoked charset (0 = G0 via SI, 1 = G1 via SO)
+    active_charset: usize,
+    /// Top margin of the scroll region (0-based, inclusive)
+    scroll_top: usize,
+    /// Bottom margin of the scroll region (0-based, inclusive)
+    scroll_bottom: usize,
+    /// Whether each column has a tab stop (HTS / TBC)
+    tab_stops: Vec<bool>,
     parser: vte::Parser,
+    osc_limit: OscLimit,
     kitty_parser: KittyImageParser,
     sixel_parser: SixelImageParser,
     images: Vec<TerminalImage>,
+    /// Pixel (width, height) of a cell as drawn, to tell how many cells an image covers
+    cell_size: (f32, f32),
+    /// DCS string currently being received
+    dcs: Option<Dcs>,
+    /// Report unhandled OSC/DCS sequences as events
+    forward_unhandled: bool,
+    /// Reply bytes to be written back to the PTY
+    responses: Vec<u8>,
+    /// Events to be handled by the application
+    events: Vec<TerminalEvent>,
+    mouse_mode: MouseMode,
+    mouse_encoding: MouseEncoding,
+    /// DECCKM - arrow keys send SS3 sequences instead of CSI
+    application_cursor_keys: bool,
+    /// `?2004` - pasted text is wrapped in `ESC [200~` ... `ESC [201~`
+    bracketed_paste: bool,
+    /// Indexed and default colors, redefinable via OSC 4/10/11
+    palette: Palette,
+    /// Palette restored by RIS (built-in colors with the configured defaults)
+    initial_palette: Palette,
+    /// Window title requested by the application (OSC 0 / OSC 2)
+    dynamic_title: Option<String>,
+    /// Titles saved by XTWINOPS 22 and restored by XTWINOPS 23 (None = the configured title)
+    title_stack: Vec<Option<String>>,
+    /// Hyperlink targets (OSC 8), indexed by `Cell::link`
+    links: Vec<String>,
+    /// Hyperlink given to printed characters until the OSC 8 closer
+    current_link: Option<usize>,
+    /// Prompt positions reported by the shell (OSC 133)
+    shell_marks: ShellMarks,
+    /// Keep the command line of a running command on the bottom row once it has
+    /// scrolled off (`scroll.pin_prompt`)
+    pin_prompt: bool,
+}
+
+/// Shell integration state (OSC 133 `A` prompt, `B` command, `C` output, `D` done)
+#[derive(Debug, Clone, Default)]
+struct ShellMarks {
+    /// Line of the current prompt, as `Grid::scrolled_lines` + row
+    command_line: Option<u64>,
+    /// Line and contents of the command line whose output is being printed
+    running: Option<(u64, Vec<Cell>)>,
+}
+
+/// What to do with a byte, depending on the OSC sequence it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OscStep {
+    Parse,
+    /// The sequence got too long: drop what the parser buffered so far
+    Abandon,
+    /// Part of an abandoned sequence
+    Skip,
+}
+
+/// Length of the OSC sequence going through the parser, which buffers the whole
+/// payload until the terminator arrives
+#[derive(Debug, Clone, Copy, Default)]
+struct OscLimit {
+    /// The previous byte was ESC
+    escape: bool,
+    /// Bytes received of the sequence in progress
+    len: Option<usize>,
+    /// The sequence in progress was abandoned
+    discarding: bool,
+}
+
+impl OscLimit {
+    fn step(&mut self, byte: u8) -> OscStep {
+        let escape = std::mem::replace(&mut self.escape, byte == 0x1b);
+        // BEL, CAN, SUB and ESC (starting ST) end the sequence
+        let ends = matches!(byte, 0x07 | 0x18 | 0x1a | 0x1b);
+        if self.discarding {
+            if !ends {
+                return OscStep::Skip;
+            }
+            self.discarding = false;
+            // ESC may start the next sequence
+            return if byte == 0x1b { OscStep::Parse } else { OscStep::Skip };
+        }
+        match &mut self.len {
+            Some(_) if ends => self.len = None,
+            Some(len) => {
+                *len += 1;
+                if *len > MAX_OSC_LEN {
+                    self.len = None;
+                    self.discarding = true;
+                    return OscStep::Abandon;
+                }
+            }
+            None if escape && byte == b']' => self.len = Some(0),
+            None => {}
+        }
+        OscStep::Parse
+    }
 }
 
 impl TerminalEmulator {
     pub fn new(cols: usize, rows: usize, scrollback: usize) -> Self {
         Self {
             grid: Grid::new(cols, rows, scrollback),
+            // The alternate screen has no scrollback of its own
+            inactive_grid: Grid::new(cols, rows, 0),
+            alt_screen_active: false,
+            inactive_images: Vec::new(),
             cursor_col: 0,
             cursor_row: 0,
             cursor_visible: true,
+            cursor_style: CursorStyle::default(),
+            default_cursor_style: CursorStyle::default(),
+            reverse_video: false,
+            origin_mode: false,
             current_fg: Color::WHITE,
+            current_bright_fg: None,
             current_bg: Color::BLACK,
             current_attrs: CellAttributes::default(),
             saved_cursor: None,
+            charsets: [Charset::Ascii; 2],
+            active_charset: 0,
+            scroll_top: 0,
+            scroll_bottom: rows.saturating_sub(1),
+            tab_stops: default_tab_stops(0, cols).collect(),
             parser: vte::Parser::new(),
+            osc_limit: OscLimit::default(),
             kitty_parser: KittyImageParser::new(),
             sixel_parser: SixelImageParser::new(),
             images: Vec::new(),
+            cell_size: DEFAULT_CELL_SIZE,
+            dcs: None,
+            forward_unhandled: false,
+            responses: Vec::new(),
+            events: Vec::new(),
+            mouse_mode: MouseMode::Off,
+            mouse_encoding: MouseEncoding::Default,
+            application_cursor_keys: false,
+            bracketed_paste: false,
+            palette: Palette::default(),
+            initial_palette: Palette::default(),
+            dynamic_title: None,
+            title_stack: Vec::new(),
+            links: Vec::new(),
+            current_link: None,
+            shell_marks: ShellMarks::default(),
+            pin_prompt: false,
         }
     }
 
+    /// Set the configured palette. Its default colors are used by SGR 0/39/49 and
+    /// blank cells, and RIS returns to it.
+    pub fn set_initial_palette(&mut self, palette: Palette) {
+        self.set_default_foreground(palette.foreground);
+        self.set_default_background(palette.background);
+        self.palette = palette.clone();
+        self.initial_palette = palette;
+    }
+
+    /// Set the configured cursor style, used until the application picks another (DECSCUSR)
+    pub fn set_default_cursor_style(&mut self, style: CursorStyle) {
+        self.cursor_style = style;
+        self.default_cursor_style = style;
+    }
+
+    pub fn set_pin_prompt(&mut self, enabled: bool) {
+        self.pin_prompt = enabled;
+        self.update_pinned_prompt();
+    }
+
+    /// Pixel size of a cell in the renderer; images received later are sized with it
+    pub fn set_cell_size(&mut self, width: f32, height: f32) {
+        self.cell_size = (width, height);
+    }
+
+    /// Report the OSC and DCS sequences the emulator ignores as `UnhandledSequence` events
+    pub fn set_forward_unhandled(&mut self, enabled: bool) {
+        self.forward_unhandled = enabled;
+    }
+
     pub fn grid(&self) -> &Grid {
         &self.grid
     }
@@ -52,43 +316,238 @@ impl TerminalEmulator {
         self.cursor_visible
     }
 
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style
+    }
+
+    /// Whether screen-wide reverse video (DECSCNM) is enabled
+    pub fn reverse_video(&self) -> bool {
+        self.reverse_video
+    }
+
+    /// Mouse tracking mode requested by the application
+    pub fn mouse_mode(&self) -> MouseMode {
+        self.mouse_mode
+    }
+
+    /// Encoding for mouse reports
+    pub fn mouse_encoding(&self) -> MouseEncoding {
+        self.mouse_encoding
+    }
+
+    /// Whether application cursor keys mode (DECCKM) is enabled
+    pub fn application_cursor_keys(&self) -> bool {
+        self.application_cursor_keys
+    }
+
+    /// Whether bracketed paste mode (`?2004`) is enabled
+    pub fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    /// Window title set by the application, if any
+    pub fn dynamic_title(&self) -> Option<&str> {
+        self.dynamic_title.as_deref()
+    }
+
+    /// Reset the modes that change how keyboard, mouse and pasted input is encoded
+    /// (mouse tracking and encoding, application cursor keys, bracketed paste).
+    ///
+    /// An application that dies without restoring them would otherwise leave the next
+    /// program receiving escape sequences it never asked for, so they reset automatically:
+    /// - mouse tracking and encoding when leaving the alternate screen
+    /// - all of them on RIS and when the shell process exits
+    pub fn reset_input_modes(&mut self) {
+        self.reset_mouse_modes();
+        self.application_cursor_keys = false;
+        self.bracketed_paste = false;
+    }
+
+    /// Undo what a program that exited may have left behind, so text written next is
+    /// legible: back on the primary screen with the default pen and character set,
+    /// no scroll region and no screen-wide reverse video.
+    pub fn reset_after_exit(&mut self) {
+        if self.alt_screen_active {
+            self.switch_screen(false);
+            self.restore_cursor();
+        }
+        self.reverse_video = false;
+        self.current_fg = self.palette.foreground;
+        self.current_bright_fg = None;
+        self.current_bg = self.palette.background;
+        self.current_attrs = CellAttributes::default();
+        self.current_link = None;
+        self.charsets = [Charset::Ascii; 2];
+        self.active_charset = 0;
+        self.scroll_top = 0;
+        self.scroll_bottom = self.grid.rows().saturating_sub(1);
+        self.grid.mark_full_redraw();
+    }
+
+    /// DECSTR - Soft terminal reset: return the modes and the pen to their defaults
+    /// (input modes, cursor visibility and shape, character sets, SGR attributes, scroll
+    /// region, screen-wide reverse video) while the screen contents, the cursor position
+    /// and the current screen buffer stay as they are. This recovers a
+    /// pane a program left in a broken mode, like typing `reset` but without clearing.
+    pub fn soft_reset(&mut self) {
+        log::debug!("Soft terminal reset (DECSTR)");
+        self.reset_input_modes();
+        self.cursor_visible = true;
+        self.cursor_style = self.default_cursor_style;
+        self.reverse_video = false;
+        self.origin_mode = false;
+        self.current_fg = self.palette.foreground;
+        self.current_bright_fg = None;
+        self.current_bg = self.palette.background;
+        self.current_attrs = CellAttributes::default();
+        self.current_link = None;
+        self.charsets = [Charset::Ascii; 2];
+        self.active_charset = 0;
+        self.scroll_top = 0;
+        self.scroll_bottom = self.grid.rows().saturating_sub(1);
+        self.grid.mark_full_redraw();
+    }
+
+    /// Whether the alternate screen buffer is shown
+    pub fn is_alt_screen(&self) -> bool {
+        self.alt_screen_active
+    }
+
+    fn reset_mouse_modes(&mut self) {
+        self.mouse_mode = MouseMode::Off;
+        self.mouse_encoding = MouseEncoding::Default;
+    }
+
+    /// Current color palette, including runtime changes made by the application
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Logical lines of the scrollback and screen that match `pattern`, each with up to
+    /// `context` lines before and after it. Returns (line number, text) in order, numbered
+    /// from the oldest scrollback line; a gap in the numbers separates the groups.
+    #[cfg(test)]
+    pub(crate) fn grep(&self, pattern: &Regex, context: usize) -> Vec<(usize, String)> {
+        let lines = self.grid.logical_lines();
+        let mut keep = vec![false; lines.len()];
+        for (index, line) in lines.iter().enumerate() {
+            if pattern.is_match(line) {
+                let end = (index + context).min(lines.len() - 1);
+                keep[index.saturating_sub(context)..=end].fill(true);
+            }
+        }
+        lines.into_iter().enumerate().filter(|&(index, _)| keep[index]).collect()
+    }
+
     pub fn resize(&mut self, cols: usize, rows: usize) {
-        self.grid.resize(cols, rows);
-        self.cursor_col = self.cursor_col.min(cols.saturating_sub(1));
-        self.cursor_row = self.cursor_row.min(rows.saturating_sub(1));
+        // Only the primary screen is rewrapped; full-screen applications redraw the alternate one
+        if self.alt_screen_active {
+            self.grid.resize(cols, rows);
+            self.cursor_col = self.cursor_col.min(cols.saturating_sub(1));
+            self.cursor_row = self.cursor_row.min(rows.saturating_sub(1));
+            // The primary screen's cursor is the one saved on entering the alternate screen
+            let saved = self.saved_cursor.map_or((0, 0), |saved| (saved.col, saved.row));
+            let (col, row) = self.inactive_grid.reflow(cols, rows, saved);
+            if let Some(saved) = &mut self.saved_cursor {
+                (saved.col, saved.row) = (col, row);
+            }
+        } else {
+            (self.cursor_col, self.cursor_row) = self.grid.reflow(cols, rows, (self.cursor_col, self.cursor_row));
+            self.inactive_grid.resize(cols, rows);
+        }
+        // Never leave the cursor on the second half of a wide character,
+        // where the next character would land between its halves
+        if self.grid.get(self.cursor_col, self.cursor_row).is_some_and(Cell::is_continuation) {
+            self.cursor_col -= 1;
+        }
+        // Margins no longer match the new size, fall back to the full screen
+        self.scroll_top = 0;
+        self.scroll_bottom = rows.saturating_sub(1);
+        // Keep the stops that still fit and give new columns the default ones
+        let old_cols = self.tab_stops.len();
+        self.tab_stops.truncate(cols);
+        self.tab_stops.extend(default_tab_stops(old_cols, cols));
+    }
+
+    /// Panic if the cursor, margins or either screen are out of bounds (fuzz tests)
+    #[cfg(test)]
+    pub(crate) fn assert_invariants(&self) {
+        let (cols, rows) = (self.grid.cols(), self.grid.rows());
+        // The cursor may sit one past the last column until the next character wraps
+        assert!(self.cursor_col <= cols, "cursor column {} outside {} columns", self.cursor_col, cols);
+        assert!(self.cursor_row < rows.max(1), "cursor row {} outside {} rows", self.cursor_row, rows);
+        assert!(self.scroll_top <= self.scroll_bottom && self.scroll_bottom < rows.max(1), "bad scroll region");
+        assert_eq!(self.tab_stops.len(), cols, "tab stops do not match the width");
+        assert_eq!((self.inactive_grid.cols(), self.inactive_grid.rows()), (cols, rows), "screens differ in size");
+        self.grid.assert_invariants();
+        self.inactive_grid.assert_invariants();
     }
 
     pub fn process_byte(&mut self, byte: u8) {
         // Temporarily take the parser to avoid borrowing issues
         let mut parser = std::mem::replace(&mut self.parser, vte::Parser::new());
-        parser.advance(self, byte);
+        self.advance(&mut parser, byte);
         self.parser = parser;
+        self.update_pinned_prompt();
     }
 
     pub fn process_bytes(&mut self, bytes: &[u8]) {
         // Process all bytes with parser to avoid repeated moves
         let mut parser = std::mem::replace(&mut self.parser, vte::Parser::new());
         for &byte in bytes {
-            parser.advance(self, byte);
+            self.advance(&mut parser, byte);
 
             // Also try to parse images
             if let Some(image) = self.kitty_parser.process_byte(byte) {
-                self.add_image(image);
+                self.add_image(image.image, image.cols, image.rows);
             }
             if let Some(image) = self.sixel_parser.process_byte(byte) {
-                self.add_image(image);
+                self.add_image(image, None, None);
             }
         }
         self.parser = parser;
+        self.update_pinned_prompt();
     }
 
-    fn add_image(&mut self, image: image::DynamicImage) {
-        // Calculate image dimensions in cells
-        let cell_width = 10.0; // Approximate cell width in pixels (will be refined later)
-        let cell_height = 20.0; // Approximate cell height in pixels
+    /// Pin the running command's line to the bottom row while it is scrolled off
+    fn update_pinned_prompt(&mut self) {
+        let pin = match &self.shell_marks.running {
+            Some((line, _)) => self.pin_prompt && !self.alt_screen_active && *line < self.grid.scrolled_lines(),
+            None => false,
+        };
+        if pin != self.grid.has_pinned_row() {
+            let line = self.shell_marks.running.as_ref().filter(|_| pin).map(|(_, cells)| cells.clone());
+            self.grid.set_pinned_row(line);
+        }
+    }
+
+    /// Feed a byte to the parser, abandoning OSC sequences that grow past `MAX_OSC_LEN`
+    fn advance(&mut self, parser: &mut vte::Parser, byte: u8) {
+        match self.osc_limit.step(byte) {
+            OscStep::Parse => parser.advance(self, byte),
+            OscStep::Abandon => {
+                log::warn!("Abandoning OSC sequence longer than {} bytes", MAX_OSC_LEN);
+                *parser = vte::Parser::new();
+            }
+            OscStep::Skip => {}
+        }
+    }
 
-        let width_cells = ((image.width() as f32 / cell_width).ceil() as usize).max(1);
-        let height_cells = ((image.height() as f32 / cell_height).ceil() as usize).max(1);
+    /// Place an image at the cursor, `cols` x `rows` cells large when the sender gave
+    /// a size. With only one of them, the other keeps the image's aspect ratio.
+    fn add_image(&mut self, image: image::DynamicImage, cols: Option<usize>, rows: Option<usize>) {
+        let (cell_width, cell_height) = self.cell_size;
+
+        let (width, height) = (image.width().max(1) as f32, image.height().max(1) as f32);
+        let (width_cells, height_cells) = match (cols, rows) {
+            (Some(cols), Some(rows)) => (cols, rows),
+            (Some(cols), None) => (cols, (cols as f32 * cell_width * height / width / cell_height).ceil() as usize),
+            (None, Some(rows)) => ((rows as f32 * cell_height * width / height / cell_width).ceil() as usize, rows),
+            (None, None) => ((width / cell_width).ceil() as usize, (height / cell_height).ceil() as usize),
+        };
+        // The sender's size is not trusted: an image never covers more than the screen
+        let width_cells = width_cells.clamp(1, self.grid.cols().max(1));
+        let height_cells = height_cells.clamp(1, self.grid.rows().max(1));
 
         let terminal_image = TerminalImage::new(
             image,
@@ -119,23 +578,301 @@ impl TerminalEmulator {
         &self.images
     }
 
+    /// Take the pending reply bytes (e.g. DECRQSS answers) to send back to the PTY
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// Take the pending events for the application
+    pub fn take_events(&mut self) -> Vec<TerminalEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// OSC 0 / OSC 2 - Set the window title. vte splits the payload at `;`, so the
+    /// pieces are joined back; an empty title returns to the configured one.
+    fn set_title(&mut self, parts: &[&[u8]]) {
+        let title = String::from_utf8_lossy(&parts.join(&b';')).into_owned();
+        log::debug!("Window title set to {:?}", title);
+        self.dynamic_title = (!title.is_empty()).then_some(title);
+    }
+
+    /// XTWINOPS (`CSI Ps ; Ps t`) - only the title stack is supported: 22 saves and
+    /// 23 restores the window title. The second parameter selects the titles (0 both,
+    /// 1 icon, 2 window); there is no separate icon title, so 1 alone is ignored.
+    fn window_ops(&mut self, params: &Params) {
+        if csi_param(params, 1).unwrap_or(0) == 1 {
+            return;
+        }
+        match csi_param(params, 0) {
+            Some(22) => {
+                if self.title_stack.len() == TITLE_STACK_LIMIT {
+                    self.title_stack.remove(0);
+                }
+                self.title_stack.push(self.dynamic_title.clone());
+            }
+            Some(23) => {
+                // Popping an empty stack keeps the current title
+                if let Some(title) = self.title_stack.pop() {
+                    log::debug!("Window title restored to {:?}", title);
+                    self.dynamic_title = title;
+                }
+            }
+            param => log::trace!("Unhandled window operation: {:?}", param),
+        }
+    }
+
+    /// OSC 8 - Start (`8;params;uri`) or end (`8;;`) a hyperlink. vte splits the
+    /// URI at `;`, so the pieces are joined back.
+    fn set_hyperlink(&mut self, uri: &[&[u8]]) {
+        let uri = uri.join(&b';');
+        if uri.is_empty() {
+            self.current_link = None;
+            return;
+        }
+        if uri.len() > MAX_LINK_LEN {
+            log::warn!("Ignoring OSC 8 link of {} bytes (limit {})", uri.len(), MAX_LINK_LEN);
+            self.current_link = None;
+            return;
+        }
+
+        let uri = String::from_utf8_lossy(&uri).into_owned();
+        // A link opened again (e.g. on every line) reuses its entry
+        if let Some(index) = self.links.iter().position(|link| *link == uri) {
+            self.current_link = Some(index);
+        } else if self.links.len() < MAX_LINKS {
+            self.links.push(uri);
+            self.current_link = Some(self.links.len() - 1);
+        } else {
+            // Take over the entry of a link that has left the scrollback
+            let in_use = self.links_in_use();
+            self.current_link = (0..self.links.len()).find(|index| !in_use.contains(index));
+            match self.current_link {
+                Some(index) => self.links[index] = uri,
+                None => log::warn!("Ignoring OSC 8 link: all {} link entries are in use", MAX_LINKS),
+            }
+        }
+    }
+
+    /// Entries of the link table referenced by a cell of either screen
+    fn links_in_use(&self) -> HashSet<usize> {
+        let mut in_use = self.grid.links_in_use();
+        in_use.extend(self.inactive_grid.links_in_use());
+        if let Some((_, cells)) = &self.shell_marks.running {
+            in_use.extend(cells.iter().filter_map(|cell| cell.link));
+        }
+        in_use
+    }
+
+    /// Target of the hyperlink at a position of the displayed view
+    pub fn link_at(&self, col: usize, row: usize) -> Option<&str> {
+        let link = self.grid.view_row(row)?.get(col)?.link?;
+        self.links.get(link).map(String::as_str)
+    }
+
+    /// OSC 133 - Shell integration marks. The command line is remembered when its
+    /// output starts (`C`) so it can be pinned while the output scrolls.
+    fn set_shell_mark(&mut self, mark: &[u8]) {
+        let line = self.grid.scrolled_lines() + self.cursor_row as u64;
+        match mark.first() {
+            Some(b'A') => {
+                self.shell_marks = ShellMarks {
+                    command_line: Some(line),
+                    running: None,
+                };
+            }
+            // The command is typed on this line, which may be below a multi-line prompt
+            Some(b'B') => self.shell_marks.command_line = Some(line),
+            Some(b'C') => {
+                self.shell_marks.running = self.shell_marks.command_line.and_then(|line| {
+                    let row = line.checked_sub(self.grid.scrolled_lines())?;
+                    let cells = self.grid.get_row(row as usize)?.to_vec();
+                    Some((line, cells))
+                });
+            }
+            Some(b'D') => self.shell_marks.running = None,
+            _ => log::trace!("Unhandled OSC 133 mark: {:?}", String::from_utf8_lossy(mark)),
+        }
+    }
+
+    /// OSC 4 - Change indexed colors (`4;index;spec;index;spec...`)
+    fn set_palette_colors(&mut self, pairs: &[&[u8]]) {
+        for pair in pairs.chunks_exact(2) {
+            let index = std::str::from_utf8(pair[0]).ok().and_then(|index| index.parse::<u8>().ok());
+            match (index, parse_color_spec(pair[1])) {
+                (Some(index), Some(color)) => self.palette.set(index, color),
+                // Color queries (`?`) are not answered
+                _ => log::debug!("Ignoring OSC 4 color change: {:?}", String::from_utf8_lossy(pair[1])),
+            }
+        }
+    }
+
+    /// OSC 10/11 - Change the default foreground/background color.
+    /// Additional specs apply to the following dynamic colors (`10;fg;bg`).
+    fn set_dynamic_colors(&mut self, first: u16, specs: &[&[u8]]) {
+        for (code, spec) in (first..).zip(specs) {
+            let Some(color) = parse_color_spec(spec) else {
+                log::debug!("Ignoring OSC {} color change: {:?}", code, String::from_utf8_lossy(spec));
+                continue;
+            };
+            match code {
+                10 => self.set_default_foreground(color),
+                11 => self.set_default_background(color),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_default_foreground(&mut self, color: Color) {
+        if self.current_fg == self.palette.foreground {
+            self.current_fg = color;
+        }
+        self.palette.foreground = color;
+        self.update_blank_colors();
+    }
+
+    fn set_default_background(&mut self, color: Color) {
+        if self.current_bg == self.palette.background {
+            self.current_bg = color;
+        }
+        self.palette.background = color;
+        self.update_blank_colors();
+    }
+
+    /// Recolor blank cells of both screens with the current default colors
+    fn update_blank_colors(&mut self) {
+        let (fg, bg) = (self.palette.foreground, self.palette.background);
+        self.grid.set_blank_colors(fg, bg);
+        self.inactive_grid.set_blank_colors(fg, bg);
+    }
+
+    /// OSC 52 - Manipulate selection data (`52 ; Pc ; Pd`)
+    fn set_selection_data(&mut self, targets: &[u8], data: &[u8]) {
+        // Reading the selection back is not supported
+        if data == b"?" {
+            log::debug!("Ignoring OSC 52 selection read request");
+            return;
+        }
+        if data.len() > MAX_SELECTION_PAYLOAD {
+            log::warn!("Ignoring oversized OSC 52 payload ({} bytes)", data.len());
+            return;
+        }
+
+        let text = match base64::engine::general_purpose::STANDARD.decode(data) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                log::debug!("Ignoring OSC 52 with invalid base64: {}", e);
+                return;
+            }
+        };
+
+        // `c` is the clipboard and `p` the primary selection; an empty target means `s 0`,
+        // which is treated as the clipboard like other selection names
+        let mut kinds = Vec::new();
+        if targets.contains(&b'p') {
+            kinds.push(ClipboardKind::Primary);
+        }
+        if targets.is_empty() || targets.iter().any(|&t| t != b'p') {
+            kinds.push(ClipboardKind::Clipboard);
+        }
+
+        for kind in kinds {
+            self.events.push(TerminalEvent::ClipboardStore { kind, text: text.clone() });
+        }
+    }
+
+    /// Answer a DECRQSS (DCS $ q <request> ST) status-string query
+    fn report_status_string(&mut self, request: &[u8]) {
+        let status = match request {
+            b"m" => Some(format!("{}m", self.sgr_report())),
+            // The active cursor always blinks
+            b" q" => Some(format!("{} q", self.cursor_style.decscusr())),
+            b"r" => Some(format!("{};{}r", self.scroll_top + 1, self.scroll_bottom + 1)),
+            _ => None,
+        };
+
+        let reply = match status {
+            Some(status) => format!("\x1bP1$r{}\x1b\\", status),
+            None => {
+                log::debug!("Unsupported DECRQSS request: {:?}", String::from_utf8_lossy(request));
+                "\x1bP0$r\x1b\\".to_string()
+            }
+        };
+        self.responses.extend_from_slice(reply.as_bytes());
+    }
+
+    /// Format the current SGR state as semicolon-separated parameters
+    fn sgr_report(&self) -> String {
+        let mut params = vec!["0".to_string()];
+        if self.current_attrs.bold {
+            params.push("1".to_string());
+        }
+        if self.current_attrs.dim {
+            params.push("2".to_string());
+        }
+        if self.current_attrs.italic {
+            params.push("3".to_string());
+        }
+        if self.current_attrs.underline {
+            params.push("4".to_string());
+        }
+        if self.current_attrs.inverse {
+            params.push("7".to_string());
+        }
+        if self.current_attrs.strikethrough {
+            params.push("9".to_string());
+        }
+        if self.current_fg != self.palette.foreground {
+            let c = self.current_fg;
+            params.push(format!("38;2;{};{};{}", c.r, c.g, c.b));
+        }
+        if self.current_bg != self.palette.background {
+            let c = self.current_bg;
+            params.push(format!("48;2;{};{};{}", c.r, c.g, c.b));
+        }
+        params.join(";")
+    }
+
     fn write_char(&mut self, ch: char) {
-        if self.cursor_col >= self.grid.cols() {
-            self.cursor_col = 0;
-            self.cursor_row += 1;
-            if self.cursor_row >= self.grid.rows() {
-                self.grid.scroll_up(1);
-                self.cursor_row = self.grid.rows() - 1;
+        let ch = self.charsets[self.active_charset].translate(ch);
+
+        // Combining and other zero-width characters have no cell of their own
+        let width = match ch.width() {
+            Some(0) | None => {
+                log::trace!("Dropping zero-width character U+{:04X}", ch as u32);
+                return;
             }
+            // A wide character cannot be shown on a grid only one column wide
+            Some(2) if self.grid.cols() >= 2 => 2,
+            Some(_) => 1,
+        };
+
+        // Auto-wrap: implicit newline. A wide character that would straddle the
+        // right edge wraps as a whole, leaving the last column blank.
+        if self.cursor_col + width > self.grid.cols() {
+            self.grid.set_wrapped(self.cursor_row, true);
+            self.cursor_col = 0;
+            self.line_feed();
         }
 
         let mut cell = Cell::new(ch);
         cell.fg = self.current_fg;
+        cell.bright_fg = self.current_bright_fg;
         cell.bg = self.current_bg;
         cell.attrs = self.current_attrs;
+        cell.width = width as u8;
+        cell.link = self.current_link;
+        if ch == ' ' {
+            cell.whitespace = Whitespace::Space;
+        }
 
+        for col in self.cursor_col..self.cursor_col + width {
+            self.grid.break_wide_char(col, self.cursor_row);
+        }
         self.grid.set(self.cursor_col, self.cursor_row, cell);
-        self.cursor_col += 1;
+        if width == 2 {
+            self.grid.set(self.cursor_col + 1, self.cursor_row, Cell::continuation(&cell));
+        }
+        self.cursor_col += width;
     }
 
     fn carriage_return(&mut self) {
@@ -143,10 +880,194 @@ impl TerminalEmulator {
     }
 
     fn line_feed(&mut self) {
-        self.cursor_row += 1;
-        if self.cursor_row >= self.grid.rows() {
-            self.grid.scroll_up(1);
-            self.cursor_row = self.grid.rows() - 1;
+        if self.cursor_row == self.scroll_bottom {
+            // At the bottom margin: scroll the region, keep the cursor in place
+            self.grid.scroll_region_up(self.scroll_top, self.scroll_bottom, 1);
+        } else if self.cursor_row + 1 < self.grid.rows() {
+            // Inside the region, or below it (no scrolling at the screen bottom)
+            self.cursor_row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        if self.cursor_row == self.scroll_top {
+            // At the top margin: scroll the region down
+            self.grid.scroll_region_down(self.scroll_top, self.scroll_bottom, 1);
+        } else if self.cursor_row > 0 {
+            self.cursor_row -= 1;
+        }
+    }
+
+    /// DECSET/DECRST - Set or reset DEC private modes (CSI ? Pm h / CSI ? Pm l)
+    fn set_private_mode(&mut self, params: &Params, enable: bool) {
+        for param in params.iter() {
+            let Some(&mode) = param.first() else { continue };
+            match mode {
+                1 => {
+                    // DECCKM - Application cursor keys
+                    self.application_cursor_keys = enable;
+                }
+                5 => {
+                    // DECSCNM - Reverse video for the whole screen
+                    self.reverse_video = enable;
+                }
+                6 => {
+                    // DECOM - Origin mode; switching it either way homes the cursor
+                    self.origin_mode = enable;
+                    self.cursor_home();
+                }
+                25 => {
+                    // DECTCEM - Show/hide cursor
+                    self.cursor_visible = enable;
+                }
+                47 => {
+                    // Alternate screen without cursor save/restore or clearing
+                    self.switch_screen(enable);
+                }
+                1000 => {
+                    // Report button presses and releases
+                    self.mouse_mode = if enable { MouseMode::Normal } else { MouseMode::Off };
+                }
+                1002 => {
+                    // Report presses, releases and drags
+                    self.mouse_mode = if enable { MouseMode::ButtonEvent } else { MouseMode::Off };
+                }
+                1003 => {
+                    // Report presses, releases and all motion
+                    self.mouse_mode = if enable { MouseMode::AnyEvent } else { MouseMode::Off };
+                }
+                1005 | 1006 => {
+                    // UTF-8 / SGR extended mouse coordinates
+                    let encoding = if mode == 1005 { MouseEncoding::Utf8 } else { MouseEncoding::Sgr };
+                    if enable {
+                        self.mouse_encoding = encoding;
+                    } else if self.mouse_encoding == encoding {
+                        self.mouse_encoding = MouseEncoding::Default;
+                    }
+                }
+                2004 => {
+                    // Bracketed paste
+                    self.bracketed_paste = enable;
+                }
+                1047 => {
+                    // Alternate screen, cleared when leaving it
+                    if !enable && self.alt_screen_active {
+                        self.grid.clear();
+                        self.images.clear();
+                    }
+                    self.switch_screen(enable);
+                }
+                1048 => {
+                    // Save/restore cursor as DECSC/DECRC
+                    if enable {
+                        self.save_cursor();
+                    } else {
+                        self.restore_cursor();
+                    }
+                }
+                1049 => {
+                    // Save cursor, then switch to a freshly cleared alternate screen
+                    if enable {
+                        if !self.alt_screen_active {
+                            self.save_cursor();
+                            self.switch_screen(true);
+                            self.grid.clear();
+                            self.images.clear();
+                        }
+                    } else if self.alt_screen_active {
+                        self.switch_screen(false);
+                        self.restore_cursor();
+                    }
+                }
+                _ => {
+                    log::trace!("Unhandled private mode: {} ({})", mode, if enable { "set" } else { "reset" });
+                }
+            }
+        }
+    }
+
+    /// Switch between the primary and alternate screen buffers
+    fn switch_screen(&mut self, alternate: bool) {
+        if self.alt_screen_active == alternate {
+            return;
+        }
+        std::mem::swap(&mut self.grid, &mut self.inactive_grid);
+        std::mem::swap(&mut self.images, &mut self.inactive_images);
+        self.alt_screen_active = alternate;
+        // The newly visible buffer must be repainted entirely
+        self.grid.mark_full_redraw();
+        // Mouse reporting belongs to the full-screen application that is going away
+        if !alternate {
+            self.reset_mouse_modes();
+        }
+    }
+
+    /// Save the cursor position and pen attributes
+    fn save_cursor(&mut self) {
+        self.saved_cursor = Some(SavedCursor {
+            col: self.cursor_col,
+            row: self.cursor_row,
+            fg: self.current_fg,
+            bright_fg: self.current_bright_fg,
+            bg: self.current_bg,
+            attrs: self.current_attrs,
+            origin_mode: self.origin_mode,
+        });
+    }
+
+    fn restore_cursor(&mut self) {
+        if let Some(saved) = self.saved_cursor {
+            self.cursor_col = saved.col.min(self.grid.cols().saturating_sub(1));
+            self.cursor_row = saved.row.min(self.grid.rows().saturating_sub(1));
+            self.current_fg = saved.fg;
+            self.current_bright_fg = saved.bright_fg;
+            self.current_bg = saved.bg;
+            self.current_attrs = saved.attrs;
+            self.origin_mode = saved.origin_mode;
+        }
+    }
+
+    /// DECSTBM - Set top and bottom margins (1-based parameters)
+    fn set_scroll_region(&mut self, params: &Params) {
+        let rows = self.grid.rows();
+        let mut iter = params.iter();
+        let top = iter.next().and_then(|p| p.first()).map(|&n| n as usize).filter(|&n| n > 0).unwrap_or(1);
+        let bottom = iter
+            .next()
+            .and_then(|p| p.first())
+            .map(|&n| n as usize)
+            .filter(|&n| n > 0)
+            .unwrap_or(rows)
+            .min(rows);
+
+        // The region must be at least two lines
+        if top >= bottom {
+            log::debug!("Ignoring invalid scroll region {};{}", top, bottom);
+            return;
+        }
+
+        self.scroll_top = top - 1;
+        self.scroll_bottom = bottom - 1;
+
+        // DECSTBM moves the cursor to the home position
+        self.cursor_home();
+    }
+
+    /// Move the cursor to the top left corner (of the scroll region in origin mode)
+    fn cursor_home(&mut self) {
+        self.cursor_col = 0;
+        self.cursor_row = if self.origin_mode { self.scroll_top } else { 0 };
+    }
+
+    /// 0-based row for the 1-based row parameter at `index` of CUP and VPA: counted from
+    /// the top margin and confined to the scroll region in origin mode, clamped to the
+    /// screen otherwise
+    fn absolute_row(&self, params: &Params, index: usize) -> usize {
+        if self.origin_mode {
+            let height = self.scroll_bottom - self.scroll_top + 1;
+            self.scroll_top + csi_count(params, index, height) - 1
+        } else {
+            csi_count(params, index, self.grid.rows()) - 1
         }
     }
 
@@ -157,67 +1078,126 @@ impl TerminalEmulator {
     }
 
     fn tab(&mut self) {
-        // Move to next tab stop (every 8 columns)
-        self.cursor_col = ((self.cursor_col / 8) + 1) * 8;
-        if self.cursor_col >= self.grid.cols() {
-            self.cursor_col = self.grid.cols() - 1;
+        // Move to the next tab stop, or the last column when there is none
+        let last_col = self.grid.cols() - 1;
+        let next_col = (self.cursor_col + 1..last_col)
+            .find(|&col| self.tab_stops.get(col).copied().unwrap_or(false))
+            .unwrap_or(last_col);
+
+        // Remember the blank cells the tab skipped, so they can be shown and copied as a tab.
+        // Where the tab starts is recorded too: with custom stops, runs do not line up with
+        // any fixed width.
+        let mut whitespace = Whitespace::TabStart;
+        for col in self.cursor_col..next_col {
+            if let Some(&cell) = self.grid.get(col, self.cursor_row).filter(|cell| cell.ch == ' ') {
+                self.grid.set(col, self.cursor_row, Cell { whitespace, ..cell });
+                whitespace = Whitespace::Tab;
+            }
+        }
+        self.cursor_col = next_col;
+    }
+
+    /// Parse the color following an SGR 38/48 code.
+    ///
+    /// Accepts both the semicolon form (`38;5;n`, `38;2;r;g;b`), which consumes the
+    /// following params from `iter`, and the colon form (`38:5:n`, `38:2::r:g:b`), which
+    /// arrives as subparameters of `param`. Returns `None` for truncated sequences.
+    fn parse_extended_color(&self, param: &[u16], iter: &mut vte::ParamsIter<'_>) -> Option<Color> {
+        if param.len() > 1 {
+            return match param[1] {
+                5 => param.get(2).map(|&index| self.palette.get(clamp_u8(index))),
+                2 => {
+                    // An optional color space id may precede the components
+                    let components = &param[2..];
+                    let rgb = if components.len() >= 4 { &components[1..4] } else { components };
+                    match rgb {
+                        [r, g, b, ..] => Some(Color::rgb(clamp_u8(*r), clamp_u8(*g), clamp_u8(*b))),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+        }
+
+        let mut next = || iter.next().and_then(|p| p.first().copied());
+        match next()? {
+            5 => next().map(|index| self.palette.get(clamp_u8(index))),
+            2 => {
+                let r = next()?;
+                let g = next()?;
+                let b = next()?;
+                Some(Color::rgb(clamp_u8(r), clamp_u8(g), clamp_u8(b)))
+            }
+            _ => None,
         }
     }
 
     fn set_sgr(&mut self, params: &Params) {
         if params.is_empty() {
             // Reset all attributes
-            self.current_fg = Color::WHITE;
-            self.current_bg = Color::BLACK;
+            self.current_fg = self.palette.foreground;
+            self.current_bright_fg = None;
+            self.current_bg = self.palette.background;
             self.current_attrs = CellAttributes::default();
             return;
         }
 
         let mut iter = params.iter();
         while let Some(param) = iter.next() {
-            let n = param[0];
+            let Some(&n) = param.first() else { continue };
             match n {
                 0 => {
                     // Reset
-                    self.current_fg = Color::WHITE;
-                    self.current_bg = Color::BLACK;
+                    self.current_fg = self.palette.foreground;
+                    self.current_bright_fg = None;
+                    self.current_bg = self.palette.background;
                     self.current_attrs = CellAttributes::default();
                 }
                 1 => self.current_attrs.bold = true,
+                2 => self.current_attrs.dim = true,
                 3 => self.current_attrs.italic = true,
                 4 => self.current_attrs.underline = true,
                 7 => self.current_attrs.inverse = true,
-                22 => self.current_attrs.bold = false,
+                9 => self.current_attrs.strikethrough = true,
+                // Normal intensity: neither bold nor faint
+                22 => {
+                    self.current_attrs.bold = false;
+                    self.current_attrs.dim = false;
+                }
                 23 => self.current_attrs.italic = false,
                 24 => self.current_attrs.underline = false,
                 27 => self.current_attrs.inverse = false,
+                29 => self.current_attrs.strikethrough = false,
                 // Foreground colors (30-37, 90-97)
-                30..=37 => self.current_fg = Color::from_ansi_256((n - 30) as u8),
-                90..=97 => self.current_fg = Color::from_ansi_256((n - 90 + 8) as u8),
+                30..=37 => {
+                    self.current_fg = self.palette.get((n - 30) as u8);
+                    self.current_bright_fg = Some(self.palette.get((n - 30 + 8) as u8));
+                }
+                90..=97 => {
+                    self.current_fg = self.palette.get((n - 90 + 8) as u8);
+                    self.current_bright_fg = None;
+                }
                 // Background colors (40-47, 100-107)
-                40..=47 => self.current_bg = Color::from_ansi_256((n - 40) as u8),
-                100..=107 => self.current_bg = Color::from_ansi_256((n - 100 + 8) as u8),
-                // 256-color mode
+                40..=47 => self.current_bg = self.palette.get((n - 40) as u8),
+                100..=107 => self.current_bg = self.palette.get((n - 100 + 8) as u8),
+                // Extended colors (256-color and 24-bit truecolor)
                 38 => {
-                    if let Some(next) = iter.next() {
-                        if next[0] == 5 {
-                            if let Some(color) = iter.next() {
-                                self.current_fg = Color::from_ansi_256(color[0] as u8);
-                            }
-                        }
+                    if let Some(color) = self.parse_extended_color(param, &mut iter) {
+                        self.current_fg = color;
+                        self.current_bright_fg = None;
                     }
                 }
                 48 => {
-                    if let Some(next) = iter.next() {
-                        if next[0] == 5 {
-                            if let Some(color) = iter.next() {
-                                self.current_bg = Color::from_ansi_256(color[0] as u8);
-                            }
-                        }
+                    if let Some(color) = self.parse_extended_color(param, &mut iter) {
+                        self.current_bg = color;
                     }
                 }
-                39 => self.current_fg = Color::WHITE, // Default foreground
-                49 => self.current_bg = Color::BLACK, // Default background
+                39 => {
+                    // Default foreground
+                    self.current_fg = self.palette.foreground;
+                    self.current_bright_fg = None;
+                }
+                49 => self.current_bg = self.palette.background, // Default background
                 _ => {}
             }
         }
@@ -235,67 +1215,123 @@ impl Perform for TerminalEmulator {
             b'\r' => self.carriage_return(),
             b'\x08' => self.backspace(),
             b'\t' => self.tab(),
-            b'\x07' => {} // Bell - ignore for now
+            // Bell; a run of them before the events are taken is reported once
+            b'\x07' if self.events.last() != Some(&TerminalEvent::Bell) => self.events.push(TerminalEvent::Bell),
+            b'\x07' => {}
+            0x0E => self.active_charset = 1, // SO - Invoke G1
+            0x0F => self.active_charset = 0, // SI - Invoke G0
             _ => {}
         }
     }
 
-    fn hook(&mut self, _params: &Params, _intermediates: &[u8], _ignore: bool, _c: char) {}
-
-    fn put(&mut self, _byte: u8) {}
+    fn hook(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, c: char) {
+        self.dcs = if intermediates == b"$" && c == 'q' {
+            // DECRQSS: DCS $ q <request> ST
+            Some(Dcs::StatusRequest(Vec::new()))
+        } else if self.forward_unhandled && !(intermediates.is_empty() && c == 'q') {
+            // Anything but sixel images (DCS q), which the image parser decodes
+            let params: Vec<String> = params
+                .iter()
+                .map(|param| param.iter().map(u16::to_string).collect::<Vec<_>>().join(":"))
+                .collect();
+            let mut payload = params.join(";").into_bytes();
+            payload.extend_from_slice(intermediates);
+            payload.extend_from_slice(c.to_string().as_bytes());
+            Some(Dcs::Unhandled(payload))
+        } else {
+            None
+        };
+    }
 
-    fn unhook(&mut self) {}
+    fn put(&mut self, byte: u8) {
+        match &mut self.dcs {
+            // DECRQSS requests are only a couple of bytes long
+            Some(Dcs::StatusRequest(payload)) if payload.len() < 16 => payload.push(byte),
+            Some(Dcs::Unhandled(payload)) if payload.len() < MAX_FORWARDED_PAYLOAD => payload.push(byte),
+            Some(Dcs::Unhandled(_)) => {
+                log::debug!("Dropping unhandled DCS longer than {} bytes", MAX_FORWARDED_PAYLOAD);
+                self.dcs = None;
+            }
+            _ => {}
+        }
+    }
 
-    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
+    fn unhook(&mut self) {
+        match self.dcs.take() {
+            Some(Dcs::StatusRequest(payload)) => self.report_status_string(&payload),
+            Some(Dcs::Unhandled(payload)) => {
+                self.events.push(TerminalEvent::UnhandledSequence { kind: SequenceKind::Dcs, payload });
+            }
+            None => {}
+        }
+    }
 
-    fn csi_dispatch(&mut self, params: &Params, _intermediates: &[u8], _ignore: bool, c: char) {
-        match c {
-            'H' | 'f' => {
-                // Cursor position
-                let row = if params.is_empty() {
-                    1
-                } else {
-                    params.iter().next().unwrap()[0].max(1)
-                };
-                let col = if params.len() < 2 {
-                    1
-                } else {
-                    params.iter().nth(1).unwrap()[0].max(1)
-                };
-                self.cursor_row = (row as usize - 1).min(self.grid.rows() - 1);
-                self.cursor_col = (col as usize - 1).min(self.grid.cols() - 1);
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        match params {
+            [b"0" | b"2", title @ ..] => self.set_title(title),
+            // Icon names are not shown anywhere
+            [b"1", ..] => {}
+            [b"4", pairs @ ..] => self.set_palette_colors(pairs),
+            [b"8", _params, uri @ ..] => self.set_hyperlink(uri),
+            [b"10", specs @ ..] => self.set_dynamic_colors(10, specs),
+            [b"11", specs @ ..] => self.set_dynamic_colors(11, specs),
+            [b"52", targets, data, ..] => self.set_selection_data(targets, data),
+            [b"133", mark, ..] => self.set_shell_mark(mark),
+            _ => {
+                log::trace!("Unhandled OSC: {:?}", params.first().map(|p| String::from_utf8_lossy(p)));
+                let payload = params.join(&b';');
+                if self.forward_unhandled && payload.len() <= MAX_FORWARDED_PAYLOAD {
+                    self.events.push(TerminalEvent::UnhandledSequence { kind: SequenceKind::Osc, payload });
+                }
+            }
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, c: char) {
+        match c {
+            'H' | 'f' => {
+                // Cursor position
+                let col = csi_count(params, 1, self.grid.cols());
+                self.cursor_row = self.absolute_row(params, 0);
+                self.cursor_col = col - 1;
+            }
+            'd' => {
+                // VPA - Line position absolute (the column stays)
+                self.cursor_row = self.absolute_row(params, 0);
             }
             'A' => {
-                // Cursor up
-                let n = if params.is_empty() { 1 } else { params.iter().next().unwrap()[0].max(1) };
-                self.cursor_row = self.cursor_row.saturating_sub(n as usize);
+                // Cursor up (stops at the top margin when inside the scroll region)
+                let n = csi_count(params, 0, self.grid.rows());
+                let top = if self.cursor_row >= self.scroll_top { self.scroll_top } else { 0 };
+                self.cursor_row = self.cursor_row.saturating_sub(n).max(top);
             }
-            'B' => {
-                // Cursor down
-                let n = if params.is_empty() { 1 } else { params.iter().next().unwrap()[0].max(1) };
-                self.cursor_row = (self.cursor_row + n as usize).min(self.grid.rows() - 1);
+            'B' | 'e' => {
+                // Cursor down / VPR - Line position relative (stops at the bottom margin
+                // when inside the scroll region)
+                let n = csi_count(params, 0, self.grid.rows());
+                let bottom = if self.cursor_row <= self.scroll_bottom {
+                    self.scroll_bottom
+                } else {
+                    self.grid.rows() - 1
+                };
+                self.cursor_row = (self.cursor_row + n).min(bottom);
             }
             'C' => {
                 // Cursor forward
-                let n = if params.is_empty() { 1 } else { params.iter().next().unwrap()[0].max(1) };
-                self.cursor_col = (self.cursor_col + n as usize).min(self.grid.cols() - 1);
+                let n = csi_count(params, 0, self.grid.cols());
+                self.cursor_col = (self.cursor_col + n).min(self.grid.cols() - 1);
             }
             'D' => {
                 // Cursor backward
-                let n = if params.is_empty() { 1 } else { params.iter().next().unwrap()[0].max(1) };
-                self.cursor_col = self.cursor_col.saturating_sub(n as usize);
+                let n = csi_count(params, 0, self.grid.cols());
+                self.cursor_col = self.cursor_col.saturating_sub(n);
             }
             'J' => {
                 // Erase in display
-                let n = if params.is_empty() { 0 } else { params.iter().next().unwrap()[0] };
-                match n {
+                match csi_param(params, 0).unwrap_or(0) {
                     0 => {
                         // Clear from cursor to end of screen
-                        for col in self.cursor_col..self.grid.cols() {
-                            if let Some(cell) = self.grid.get_mut(col, self.cursor_row) {
-                                cell.reset();
-                            }
-                        }
+                        self.grid.erase_cells(self.cursor_row, self.cursor_col, self.grid.cols());
                         for row in (self.cursor_row + 1)..self.grid.rows() {
                             self.grid.clear_row(row);
                         }
@@ -305,11 +1341,7 @@ impl Perform for TerminalEmulator {
                         for row in 0..self.cursor_row {
                             self.grid.clear_row(row);
                         }
-                        for col in 0..=self.cursor_col {
-                            if let Some(cell) = self.grid.get_mut(col, self.cursor_row) {
-                                cell.reset();
-                            }
-                        }
+                        self.grid.erase_cells(self.cursor_row, 0, self.cursor_col + 1);
                     }
                     2 | 3 => {
                         // Clear entire screen
@@ -320,23 +1352,14 @@ impl Perform for TerminalEmulator {
             }
             'K' => {
                 // Erase in line
-                let n = if params.is_empty() { 0 } else { params.iter().next().unwrap()[0] };
-                match n {
+                match csi_param(params, 0).unwrap_or(0) {
                     0 => {
                         // Clear from cursor to end of line
-                        for col in self.cursor_col..self.grid.cols() {
-                            if let Some(cell) = self.grid.get_mut(col, self.cursor_row) {
-                                cell.reset();
-                            }
-                        }
+                        self.grid.erase_cells(self.cursor_row, self.cursor_col, self.grid.cols());
                     }
                     1 => {
                         // Clear from cursor to beginning of line
-                        for col in 0..=self.cursor_col {
-                            if let Some(cell) = self.grid.get_mut(col, self.cursor_row) {
-                                cell.reset();
-                            }
-                        }
+                        self.grid.erase_cells(self.cursor_row, 0, self.cursor_col + 1);
                     }
                     2 => {
                         // Clear entire line
@@ -345,38 +1368,164 @@ impl Perform for TerminalEmulator {
                     _ => {}
                 }
             }
+            // With several parameters, `CSI Ps ; ... T` starts xterm's highlight mouse tracking
+            'T' if params.len() > 1 => log::trace!("Ignoring highlight mouse tracking"),
+            'S' | 'T' if intermediates.is_empty() => {
+                // SU / SD - Scroll the scroll region up or down, the cursor stays put
+                let n = csi_count(params, 0, self.grid.rows());
+                if c == 'S' {
+                    self.grid.scroll_region_up(self.scroll_top, self.scroll_bottom, n);
+                } else {
+                    self.grid.scroll_region_down(self.scroll_top, self.scroll_bottom, n);
+                }
+            }
+            'L' | 'M' => {
+                // IL / DL - Insert or delete lines (only inside the scroll region)
+                let n = csi_count(params, 0, self.grid.rows());
+                if self.cursor_row >= self.scroll_top && self.cursor_row <= self.scroll_bottom {
+                    if c == 'L' {
+                        self.grid.insert_lines(self.cursor_row, n, self.scroll_bottom);
+                    } else {
+                        self.grid.delete_lines(self.cursor_row, n, self.scroll_bottom);
+                    }
+                    self.cursor_col = 0;
+                }
+            }
+            '@' if intermediates.is_empty() => {
+                // ICH - Insert blank characters
+                let n = csi_count(params, 0, self.grid.cols());
+                self.grid.insert_chars(self.cursor_col, self.cursor_row, n);
+            }
+            'P' => {
+                // DCH - Delete characters
+                let n = csi_count(params, 0, self.grid.cols());
+                self.grid.delete_chars(self.cursor_col, self.cursor_row, n);
+            }
+            'X' => {
+                // ECH - Erase characters
+                let n = csi_count(params, 0, self.grid.cols());
+                self.grid.erase_chars(self.cursor_col, self.cursor_row, n);
+            }
+            'g' if intermediates.is_empty() => {
+                // TBC - Tab clear (0 = at the cursor, 3 = all)
+                match csi_param(params, 0).unwrap_or(0) {
+                    0 => {
+                        if let Some(stop) = self.tab_stops.get_mut(self.cursor_col) {
+                            *stop = false;
+                        }
+                    }
+                    3 => self.tab_stops.fill(false),
+                    _ => {}
+                }
+            }
             'm' => {
                 // SGR - Select Graphic Rendition
                 self.set_sgr(params);
             }
+            'h' if intermediates == b"?" => {
+                // DECSET - Enable private modes
+                self.set_private_mode(params, true);
+            }
+            'l' if intermediates == b"?" => {
+                // DECRST - Disable private modes
+                self.set_private_mode(params, false);
+            }
+            'p' if intermediates == b"!" => {
+                // DECSTR - Soft terminal reset
+                self.soft_reset();
+            }
+            'r' if intermediates.is_empty() => {
+                // DECSTBM - Set scroll region
+                self.set_scroll_region(params);
+            }
+            'q' if intermediates == b" " => {
+                // DECSCUSR - Set cursor style
+                let param = csi_param(params, 0).unwrap_or(0);
+                match CursorStyle::from_decscusr(param) {
+                    Some(style) => self.cursor_style = style,
+                    None => log::debug!("Ignoring unknown cursor style {}", param),
+                }
+            }
+            't' if intermediates.is_empty() => {
+                // XTWINOPS - Window operations
+                self.window_ops(params);
+            }
             's' => {
                 // Save cursor position
-                self.saved_cursor = Some((self.cursor_col, self.cursor_row));
+                self.save_cursor();
             }
             'u' => {
                 // Restore cursor position
-                if let Some((col, row)) = self.saved_cursor {
-                    self.cursor_col = col;
-                    self.cursor_row = row;
-                }
+                self.restore_cursor();
             }
             _ => {}
         }
     }
 
-    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, byte: u8) {
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        // SCS - Designate a character set into G0 (`ESC ( F`) or G1 (`ESC ) F`)
+        match intermediates {
+            [b'('] => {
+                self.charsets[0] = Charset::from_designator(byte);
+                return;
+            }
+            [b')'] => {
+                self.charsets[1] = Charset::from_designator(byte);
+                return;
+            }
+            _ => {}
+        }
+
         match byte {
+            b'D' => {
+                // IND - Index
+                self.line_feed();
+            }
+            b'E' => {
+                // NEL - Next line
+                self.carriage_return();
+                self.line_feed();
+            }
+            b'M' => {
+                // RI - Reverse index
+                self.reverse_index();
+            }
+            b'H' => {
+                // HTS - Set a tab stop at the cursor column
+                if let Some(stop) = self.tab_stops.get_mut(self.cursor_col) {
+                    *stop = true;
+                }
+            }
             b'c' => {
                 // RIS - Reset to Initial State
                 log::debug!("Reset to initial state (RIS)");
+                self.switch_screen(false);
+                self.inactive_grid.clear();
+                self.inactive_images.clear();
                 self.grid.clear();
                 self.cursor_col = 0;
                 self.cursor_row = 0;
                 self.cursor_visible = true;
-                self.current_fg = Color::WHITE;
-                self.current_bg = Color::BLACK;
+                self.cursor_style = self.default_cursor_style;
+                self.reverse_video = false;
+                self.origin_mode = false;
+                self.palette = self.initial_palette.clone();
+                self.update_blank_colors();
+                self.current_fg = self.palette.foreground;
+                self.current_bright_fg = None;
+                self.current_bg = self.palette.background;
                 self.current_attrs = CellAttributes::default();
                 self.saved_cursor = None;
+                self.charsets = [Charset::Ascii; 2];
+                self.active_charset = 0;
+                self.scroll_top = 0;
+                self.scroll_bottom = self.grid.rows().saturating_sub(1);
+                self.tab_stops = default_tab_stops(0, self.grid.cols()).collect();
+                self.dynamic_title = None;
+                self.title_stack.clear();
+                self.current_link = None;
+                self.shell_marks = ShellMarks::default();
+                self.reset_input_modes();
             }
             _ => {
                 log::trace!("Unhandled ESC dispatch: byte={}", byte);
@@ -384,3 +1533,1173 @@ impl Perform for TerminalEmulator {
         }
     }
 }
+
+/// Value of the `index`-th CSI param (its first subparameter), if present
+fn csi_param(params: &Params, index: usize) -> Option<u16> {
+    params.iter().nth(index).and_then(|param| param.first().copied())
+}
+
+/// Count or position param (CUU, CUP, IL, ...): missing and 0 mean 1, and
+/// values beyond `max` (a grid dimension) are clamped to it
+fn csi_count(params: &Params, index: usize, max: usize) -> usize {
+    (csi_param(params, index).unwrap_or(1).max(1) as usize).min(max.max(1))
+}
+
+/// Color component or palette index, saturated instead of wrapped
+fn clamp_u8(value: u16) -> u8 {
+    value.min(u8::MAX as u16) as u8
+}
+
+/// Default tab stops (every 8th column) for the columns `start..end`
+fn default_tab_stops(start: usize, end: usize) -> impl Iterator<Item = bool> {
+    (start..end).map(|col| col > 0 && col % 8 == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decrqss_sgr() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[1;4m");
+        term.process_bytes(b"\x1bP$qm\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP1$r0;1;4m\x1b\\");
+
+        term.process_bytes(b"\x1b[0m\x1bP$qm\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP1$r0m\x1b\\");
+    }
+
+    #[test]
+    fn test_decrqss_scroll_region() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1bP$qr\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP1$r1;24r\x1b\\");
+    }
+
+    #[test]
+    fn test_decrqss_scroll_region_after_decstbm() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[2;23r\x1bP$qr\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP1$r2;23r\x1b\\");
+    }
+
+    #[test]
+    fn test_tab_copies_as_tab() {
+        use crate::clipboard::Selection;
+
+        let mut term = TerminalEmulator::new(20, 3, 100);
+        term.process_bytes(b"a\tb\r\n\t\tc\r\nx\ty");
+        // Text over the skipped cells replaces the tab
+        term.process_bytes(b"\x1b[3;4Hz");
+
+        let mut selection = Selection::new();
+        selection.start_at(0, 0);
+        selection.update_end(17, 2);
+        let text = selection.get_text(term.grid());
+        let lines: Vec<&str> = text.split('\n').map(|line| line.trim_end_matches(' ')).collect();
+        assert_eq!(lines, ["a\tb", "\t\tc", "x\tz\ty"]);
+    }
+
+    #[test]
+    fn test_tab_copies_with_custom_tab_stops() {
+        use crate::clipboard::Selection;
+
+        let copy_row = |term: &TerminalEmulator, row: usize| {
+            let mut selection = Selection::new();
+            selection.start_at(0, row);
+            selection.update_end(19, row);
+            selection.get_text(term.grid()).trim_end_matches(' ').to_string()
+        };
+
+        // Extra stop at column 4 (HTS): two adjacent tabs, 0->4 and 4->8
+        let mut term = TerminalEmulator::new(20, 3, 100);
+        term.process_bytes(b"\x1b[1;5H\x1bH\r\t\tA");
+        assert_eq!(copy_row(&term, 0), "\t\tA");
+
+        // Without the stop at 8 (TBC), one tab from 4 to 16 crosses it
+        term.process_bytes(b"\x1b[2;9H\x1b[g\r1234\tB");
+        assert_eq!(term.cursor_position(), (17, 1));
+        assert_eq!(copy_row(&term, 1), "1234\tB");
+    }
+
+    fn row_text(term: &TerminalEmulator, row: usize) -> String {
+        let grid = term.grid();
+        (0..grid.cols())
+            .map(|col| grid.get(col, row).unwrap().ch)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_line_feed_at_bottom_margin_scrolls_region_only() {
+        let mut term = TerminalEmulator::new(10, 5, 100);
+        // Status line on the last row, region covers rows 1-4
+        term.process_bytes(b"\x1b[5;1Hstatus\x1b[1;4r");
+        term.process_bytes(b"a\r\nb\r\nc\r\nd");
+        assert_eq!(term.cursor_position(), (1, 3));
+
+        // Line feed at the bottom margin scrolls only the region
+        term.process_bytes(b"\r\ne");
+        assert_eq!(term.cursor_position(), (1, 3));
+        assert_eq!(row_text(&term, 0), "b");
+        assert_eq!(row_text(&term, 2), "d");
+        assert_eq!(row_text(&term, 3), "e");
+        assert_eq!(row_text(&term, 4), "status");
+        assert_eq!(term.grid().scrollback_len(), 0);
+    }
+
+    #[test]
+    fn test_auto_wrap_and_index_scroll_region() {
+        let mut term = TerminalEmulator::new(4, 4, 100);
+        term.process_bytes(b"\x1b[4;1Hbar\x1b[1;3r\x1b[3;1H");
+        // Auto-wrap at the bottom margin scrolls the region
+        term.process_bytes(b"wxyzq");
+        assert_eq!(row_text(&term, 1), "wxyz");
+        assert_eq!(row_text(&term, 2), "q");
+        // IND at the bottom margin scrolls the region as well
+        term.process_bytes(b"\x1bD");
+        assert_eq!(row_text(&term, 0), "wxyz");
+        assert_eq!(row_text(&term, 1), "q");
+        assert_eq!(row_text(&term, 3), "bar");
+    }
+
+    #[test]
+    fn test_su_clears_screen_into_scrollback() {
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        term.process_bytes(b"one\r\ntwo\r\nthree");
+        term.process_bytes(b"\x1b[999S");
+        assert_eq!(term.grid().scrollback_len(), 3);
+        assert!((0..3).all(|row| row_text(&term, row).is_empty()));
+        assert_eq!(term.cursor_position(), (5, 2));
+    }
+
+    #[test]
+    fn test_su_sd_scroll_region_only() {
+        let mut term = TerminalEmulator::new(10, 5, 100);
+        term.process_bytes(b"top\r\na\r\nb\r\nc\r\nstatus\x1b[2;4r");
+
+        term.process_bytes(b"\x1b[3;2H\x1b[S");
+        assert_eq!(term.cursor_position(), (1, 2));
+        assert_eq!(row_text(&term, 0), "top");
+        assert_eq!(row_text(&term, 1), "b");
+        assert_eq!(row_text(&term, 2), "c");
+        assert_eq!(row_text(&term, 3), "");
+        assert_eq!(row_text(&term, 4), "status");
+        assert_eq!(term.grid().scrollback_len(), 0);
+
+        term.process_bytes(b"\x1b[2T");
+        assert_eq!(term.cursor_position(), (1, 2));
+        assert_eq!(row_text(&term, 1), "");
+        assert_eq!(row_text(&term, 2), "");
+        assert_eq!(row_text(&term, 3), "b");
+        assert_eq!(row_text(&term, 4), "status");
+
+        // Counts beyond the region clear it; highlight mouse tracking does not scroll
+        term.process_bytes(b"\x1b[99T");
+        assert!((1..4).all(|row| row_text(&term, row).is_empty()));
+        term.process_bytes(b"\x1b[1;4rx\x1b[1;1;1;2;2T");
+        assert_eq!(row_text(&term, 0), "xop");
+    }
+
+    #[test]
+    fn test_line_feed_below_region_does_not_scroll() {
+        let mut term = TerminalEmulator::new(10, 5, 100);
+        term.process_bytes(b"top\x1b[1;3r\x1b[5;1Hlast");
+        term.process_bytes(b"\n\n");
+        assert_eq!(term.cursor_position().1, 4);
+        assert_eq!(row_text(&term, 0), "top");
+        assert_eq!(row_text(&term, 4), "last");
+    }
+
+    #[test]
+    fn test_reverse_video_mode() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        assert!(!term.reverse_video());
+        term.process_bytes(b"\x1b[?5h");
+        assert!(term.reverse_video());
+        term.process_bytes(b"\x1b[?5l");
+        assert!(!term.reverse_video());
+
+        // RIS resets reverse video
+        term.process_bytes(b"\x1b[?5h\x1bc");
+        assert!(!term.reverse_video());
+    }
+
+    #[test]
+    fn test_decrqss_invalid_request() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1bP$qx\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP0$r\x1b\\");
+        assert!(term.take_responses().is_empty());
+    }
+
+    #[test]
+    fn test_alt_screen_47_keeps_cursor() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"primary\x1b[5;10H");
+        term.process_bytes(b"\x1b[?47h");
+        assert!(term.alt_screen_active);
+        assert_eq!(term.cursor_position(), (9, 4));
+
+        term.process_bytes(b"\x1b[1;1Halt\x1b[3;3H");
+        term.process_bytes(b"\x1b[?47l");
+        assert!(!term.alt_screen_active);
+        // No cursor restore on exit
+        assert_eq!(term.cursor_position(), (2, 2));
+        assert_eq!(row_text(&term, 0), "primary");
+
+        // ?47 does not clear the alternate screen on exit
+        term.process_bytes(b"\x1b[?47h");
+        assert_eq!(row_text(&term, 0), "alt");
+    }
+
+    #[test]
+    fn test_alt_screen_1047_clears_on_exit() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[?1047halt");
+        assert_eq!(row_text(&term, 0), "alt");
+        term.process_bytes(b"\x1b[?1047l");
+        assert_eq!(row_text(&term, 0), "");
+
+        term.process_bytes(b"\x1b[?1047h");
+        assert_eq!(row_text(&term, 0), "");
+    }
+
+    #[test]
+    fn test_alt_screen_1049_saves_and_restores_cursor() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"shell$ \x1b[?1049h");
+        assert!(term.alt_screen_active);
+        term.process_bytes(b"\x1b[10;10Heditor");
+        term.process_bytes(b"\x1b[?1049l");
+        assert!(!term.alt_screen_active);
+        assert_eq!(term.cursor_position(), (7, 0));
+        assert_eq!(row_text(&term, 0), "shell$");
+        assert_eq!(row_text(&term, 9), "");
+    }
+
+    #[test]
+    fn test_sgr_truecolor() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[38;2;255;100;0;48;2;1;2;3;1mA");
+        let cell = *term.grid().get(0, 0).unwrap();
+        assert_eq!(cell.fg, Color::rgb(255, 100, 0));
+        assert_eq!(cell.bg, Color::rgb(1, 2, 3));
+        // Codes after the color are not misread as components
+        assert!(cell.attrs.bold);
+
+        // Colon-separated form, with and without a color space id
+        term.process_bytes(b"\x1b[38:2:10:20:30;48:2::40:50:60mB");
+        let cell = *term.grid().get(1, 0).unwrap();
+        assert_eq!(cell.fg, Color::rgb(10, 20, 30));
+        assert_eq!(cell.bg, Color::rgb(40, 50, 60));
+    }
+
+    #[test]
+    fn test_sgr_dim_and_bright_variant() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[1;2;31mA\x1b[22mB\x1b[38;5;1mC\x1b[94mD\x1b[31;39mE");
+        let cells: Vec<Cell> = (0..5).map(|col| *term.grid().get(col, 0).unwrap()).collect();
+
+        assert!(cells[0].attrs.bold && cells[0].attrs.dim);
+        assert_eq!(cells[0].bright_fg, Some(Color::from_ansi_256(9)));
+        // SGR 22 ends both bold and faint
+        assert!(!cells[1].attrs.bold && !cells[1].attrs.dim);
+        // Only SGR 30-37 have a bright variant, not the same color picked by index
+        assert_eq!(cells[2].fg, Color::from_ansi_256(1));
+        assert_eq!(cells[2].bright_fg, None);
+        assert_eq!(cells[3].bright_fg, None);
+        assert_eq!(cells[4].bright_fg, None);
+
+        term.process_bytes(b"\x1b[0;2m\x1bP$qm\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP1$r0;2m\x1b\\");
+    }
+
+    #[test]
+    fn test_sgr_truncated_truecolor_keeps_color() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[31m\x1b[38;2;255;100mA");
+        assert_eq!(term.grid().get(0, 0).unwrap().fg, Color::from_ansi_256(1));
+    }
+
+    #[test]
+    fn test_alt_screen_preserves_primary_screen() {
+        let mut term = TerminalEmulator::new(20, 5, 100);
+        term.process_bytes(b"line1\r\nline2\r\n\x1b[31mred");
+        let primary: Vec<String> = (0..5).map(|row| row_text(&term, row)).collect();
+
+        term.process_bytes(b"\x1b[?1049h\x1b[0;1m");
+        assert!(term.alt_screen_active);
+        assert_eq!(row_text(&term, 0), "");
+        // Full-screen output scrolls the alternate buffer only
+        for _ in 0..10 {
+            term.process_bytes(b"vim\r\n");
+        }
+        term.process_bytes(b"\x1b[?1049l");
+
+        assert!(!term.alt_screen_active);
+        let restored: Vec<String> = (0..5).map(|row| row_text(&term, row)).collect();
+        assert_eq!(restored, primary);
+        assert_eq!(term.grid().scrollback_len(), 0);
+        assert_eq!(term.cursor_position(), (3, 2));
+
+        // Pen attributes are restored along with the cursor
+        term.process_bytes(b"!");
+        let cell = term.grid().get(3, 2).unwrap();
+        assert_eq!(cell.fg, Color::from_ansi_256(1));
+        assert!(!cell.attrs.bold);
+    }
+
+    #[test]
+    fn test_alt_screen_scrolling_skips_scrollback() {
+        let mut term = TerminalEmulator::new(20, 5, 100);
+        for line in 0..8 {
+            term.process_bytes(format!("shell {}\r\n", line).as_bytes());
+        }
+        let scrollback = term.grid().scrollback_len();
+        assert_eq!(scrollback, 4);
+
+        term.process_bytes(b"\x1b[?1049h");
+        for line in 0..30 {
+            term.process_bytes(format!("vim {}\r\n", line).as_bytes());
+        }
+        term.process_bytes(b"\x1b[2;4r\x1b[4H\n\n\n");
+        term.resize(15, 5);
+        assert_eq!(term.grid().scrollback_len(), 0);
+        assert_eq!(term.inactive_grid.scrollback_len(), scrollback);
+
+        term.process_bytes(b"\x1b[?1049l");
+        assert_eq!(term.grid().scrollback_len(), scrollback);
+        // Only the shell's lines are in the history
+        term.grid_mut().scroll_view_up(scrollback);
+        assert_eq!(term.grid().view_row(0).map(|row| row[0].ch), Some('s'));
+    }
+
+    #[test]
+    fn test_grep_with_context() {
+
+        let mut term = TerminalEmulator::new(12, 4, 100);
+        for line in ["$ make", "cc main.c", "error: a", "cc util.c", "ok", "ok", "error: b", "$ "] {
+            term.process_bytes(format!("{}\r\n", line).as_bytes());
+        }
+        // A line wrapped over two rows is matched as a whole
+        term.process_bytes(b"see https://example.com/x");
+
+        let lines = |matches: Vec<(usize, String)>| matches.into_iter().map(|(line, _)| line).collect::<Vec<_>>();
+        let error = Regex::new(r"^error:").unwrap();
+        assert_eq!(
+            term.grep(&error, 0),
+            vec![(2, "error: a".to_string()), (6, "error: b".to_string())]
+        );
+        assert_eq!(lines(term.grep(&error, 1)), [1, 2, 3, 5, 6, 7]);
+        // Overlapping context is not repeated, and stops at either end
+        assert_eq!(lines(term.grep(&error, 3)), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let url = Regex::new(r"https://\S+").unwrap();
+        let (_, text) = &term.grep(&url, 0)[0];
+        assert_eq!(url.find(text).unwrap().as_str(), "https://example.com/x");
+        assert!(term.grep(&Regex::new("missing").unwrap(), 2).is_empty());
+    }
+
+    #[test]
+    fn test_alt_screen_keeps_images_per_screen() {
+        let mut term = TerminalEmulator::new(20, 5, 100);
+        term.add_image(image::DynamicImage::new_rgba8(1, 1), None, None);
+        assert_eq!(term.images().len(), 1);
+
+        term.process_bytes(b"\x1b[?1049h");
+        assert!(term.images().is_empty());
+        term.process_bytes(b"\x1b[?1049l");
+        assert_eq!(term.images().len(), 1);
+    }
+
+    #[test]
+    fn test_scroll_region_keeps_status_lines_pinned() {
+        let mut term = TerminalEmulator::new(20, 24, 100);
+        term.process_bytes(b"header\x1b[24;1Hstatus");
+        term.process_bytes(b"\x1b[2;23r\x1b[2;1H");
+        for i in 0..30 {
+            term.process_bytes(format!("line{}\r\n", i).as_bytes());
+        }
+
+        assert_eq!(row_text(&term, 0), "header");
+        assert_eq!(row_text(&term, 23), "status");
+        assert_eq!(row_text(&term, 1), "line9");
+        assert_eq!(row_text(&term, 21), "line29");
+        assert_eq!(row_text(&term, 22), "");
+        assert_eq!(term.grid().scrollback_len(), 0);
+    }
+
+    #[test]
+    fn test_cursor_movement_clamped_to_scroll_region() {
+        let mut term = TerminalEmulator::new(20, 24, 100);
+        term.process_bytes(b"\x1b[5;20r\x1b[10;1H");
+        term.process_bytes(b"\x1b[99A");
+        assert_eq!(term.cursor_position(), (0, 4));
+        term.process_bytes(b"\x1b[99B");
+        assert_eq!(term.cursor_position(), (0, 19));
+
+        // Outside the region the screen edges apply
+        term.process_bytes(b"\x1b[2;1H\x1b[99A");
+        assert_eq!(term.cursor_position(), (0, 0));
+        term.process_bytes(b"\x1b[22;1H\x1b[99B");
+        assert_eq!(term.cursor_position(), (0, 23));
+    }
+
+    #[test]
+    fn test_vpa_without_origin_mode() {
+        let mut term = TerminalEmulator::new(20, 24, 100);
+        term.process_bytes(b"\x1b[5;20r\x1b[1;7H");
+
+        // Rows count from the top of the screen and clamp to its edges, the column stays
+        term.process_bytes(b"\x1b[3d");
+        assert_eq!(term.cursor_position(), (6, 2));
+        term.process_bytes(b"\x1b[0d");
+        assert_eq!(term.cursor_position(), (6, 0));
+        term.process_bytes(b"\x1b[99d");
+        assert_eq!(term.cursor_position(), (6, 23));
+
+        // VPR stops at the bottom margin from inside the region
+        term.process_bytes(b"\x1b[18d\x1b[99e");
+        assert_eq!(term.cursor_position(), (6, 19));
+        term.process_bytes(b"\x1b[e");
+        assert_eq!(term.cursor_position(), (6, 19));
+    }
+
+    #[test]
+    fn test_vpa_with_origin_mode() {
+        let mut term = TerminalEmulator::new(20, 24, 100);
+        term.process_bytes(b"\x1b[5;20r\x1b[?6h");
+        assert_eq!(term.cursor_position(), (0, 4));
+
+        // Rows count from the top margin and stay inside the region
+        term.process_bytes(b"\x1b[1;7H\x1b[3d");
+        assert_eq!(term.cursor_position(), (6, 6));
+        term.process_bytes(b"\x1b[0d");
+        assert_eq!(term.cursor_position(), (6, 4));
+        term.process_bytes(b"\x1b[99d");
+        assert_eq!(term.cursor_position(), (6, 19));
+        term.process_bytes(b"\x1b[99;99H");
+        assert_eq!(term.cursor_position(), (19, 19));
+
+        // A new region homes the cursor to its top margin
+        term.process_bytes(b"\x1b[10;15r");
+        assert_eq!(term.cursor_position(), (0, 9));
+
+        // Leaving origin mode homes the cursor to the screen corner
+        term.process_bytes(b"\x1b[?6l");
+        assert_eq!(term.cursor_position(), (0, 0));
+        term.process_bytes(b"\x1b[3d");
+        assert_eq!(term.cursor_position(), (0, 2));
+    }
+
+    #[test]
+    fn test_huge_params_are_clamped() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[12;40H");
+
+        // Beyond u16 (saturated by the parser) and beyond the grid both clamp to the edge
+        term.process_bytes(b"\x1b[99999999999A");
+        assert_eq!(term.cursor_position(), (39, 0));
+        term.process_bytes(b"\x1b[99999B\x1b[99999C");
+        assert_eq!(term.cursor_position(), (79, 23));
+        term.process_bytes(b"\x1b[99999D\x1b[99999;99999H");
+        assert_eq!(term.cursor_position(), (79, 23));
+
+        // Line/char edits with huge counts affect at most the rest of the screen/line
+        term.process_bytes(b"\x1b[1;1Habc\x1b[1;1H\x1b[65535@\x1b[65535P\x1b[65535X\x1b[65535L\x1b[65535M");
+        assert_eq!(term.grid().get(0, 0).unwrap().ch, ' ');
+
+        // Out-of-range color components saturate instead of wrapping
+        term.process_bytes(b"\x1b[38;2;300;65535;0m\x1b[48;5;999mX");
+        let cell = term.grid().get(0, 0).unwrap();
+        assert_eq!(cell.fg, Color::rgb(255, 255, 0));
+        assert_eq!(cell.bg, Color::from_ansi_256(255));
+    }
+
+    #[test]
+    fn test_empty_and_extra_params() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+
+        // Empty params take their defaults
+        term.process_bytes(b"\x1b[5;5H\x1b[;H");
+        assert_eq!(term.cursor_position(), (0, 0));
+        term.process_bytes(b"\x1b[;10H");
+        assert_eq!(term.cursor_position(), (9, 0));
+        term.process_bytes(b"\x1b[0B\x1b[C");
+        assert_eq!(term.cursor_position(), (10, 1));
+
+        // Extra params are ignored
+        term.process_bytes(b"\x1b[2;3;4;5;6H\x1b[1;2;3A");
+        assert_eq!(term.cursor_position(), (2, 0));
+
+        // Truncated and empty SGR / scroll region params do not panic
+        term.process_bytes(b"\x1b[38;5m\x1b[38;2;1m\x1b[48:2m\x1b[;m\x1b[;r\x1b[?;h\x1b[;J\x1b[;K\x1b[;g");
+        term.process_bytes(b"ok");
+        assert_eq!(term.grid().get(0, 0).unwrap().ch, 'o');
+    }
+
+    #[test]
+    fn test_insert_delete_line_and_char_sequences() {
+        let mut term = TerminalEmulator::new(10, 4, 100);
+        term.process_bytes(b"top\r\nmid\r\nbottom\r\nstatus");
+        term.process_bytes(b"\x1b[1;3r\x1b[2;2H\x1b[L");
+        assert_eq!(row_text(&term, 1), "");
+        assert_eq!(row_text(&term, 2), "mid");
+        assert_eq!(row_text(&term, 3), "status");
+        assert_eq!(term.cursor_position(), (0, 1));
+
+        term.process_bytes(b"\x1b[M");
+        assert_eq!(row_text(&term, 1), "mid");
+        assert_eq!(row_text(&term, 2), "");
+
+        term.process_bytes(b"\x1b[2;2H\x1b[2@");
+        assert_eq!(row_text(&term, 1), "m  id");
+        term.process_bytes(b"\x1b[P");
+        assert_eq!(row_text(&term, 1), "m id");
+        term.process_bytes(b"\x1b[2X");
+        assert_eq!(row_text(&term, 1), "m  d");
+    }
+
+    #[test]
+    fn test_dectcem_cursor_visibility() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        assert!(term.cursor_visible());
+        term.process_bytes(b"\x1b[?25l");
+        assert!(!term.cursor_visible());
+        term.process_bytes(b"\x1b[?25h");
+        assert!(term.cursor_visible());
+    }
+
+    #[test]
+    fn test_osc52_selection_targets() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        // "aGVsbG8=" is "hello"
+        term.process_bytes(b"\x1b]52;p;aGVsbG8=\x07");
+        assert_eq!(
+            term.take_events(),
+            vec![TerminalEvent::ClipboardStore { kind: ClipboardKind::Primary, text: "hello".to_string() }]
+        );
+
+        term.process_bytes(b"\x1b]52;c;aGVsbG8=\x1b\\");
+        assert_eq!(
+            term.take_events(),
+            vec![TerminalEvent::ClipboardStore { kind: ClipboardKind::Clipboard, text: "hello".to_string() }]
+        );
+
+        // Both selections at once
+        term.process_bytes(b"\x1b]52;pc;aGVsbG8=\x07");
+        assert_eq!(term.take_events().len(), 2);
+    }
+
+    #[test]
+    fn test_osc52_ignores_query_and_invalid_payload() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b]52;c;?\x07\x1b]52;c;!!not-base64!!\x07");
+        assert!(term.take_events().is_empty());
+
+        // Payloads up to the limit are accepted, larger ones dropped
+        let sequence = |len: usize| [&b"\x1b]52;c;"[..], &vec![b'A'; len], b"\x07"].concat();
+        term.process_bytes(&sequence(MAX_SELECTION_PAYLOAD / 4 * 4));
+        assert_eq!(term.take_events().len(), 1);
+        term.process_bytes(&sequence(MAX_SELECTION_PAYLOAD + 4));
+        assert!(term.take_events().is_empty());
+    }
+
+    #[test]
+    fn test_overlong_osc_is_abandoned() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        let payload = vec![b'A'; MAX_OSC_LEN + 1];
+        // Sent in pieces, as the PTY reader would
+        term.process_bytes(b"\x1b]2;");
+        for chunk in payload.chunks(4096) {
+            term.process_bytes(chunk);
+        }
+        term.process_bytes(b"\x07ok");
+        assert_eq!(term.dynamic_title(), None);
+        assert_eq!(term.grid().get(0, 0).map(|cell| cell.ch), Some('o'));
+
+        // The sequences after it are parsed again
+        term.process_bytes(b"\x1b]52;c;aGVsbG8=\x1b\\");
+        assert_eq!(term.take_events().len(), 1);
+    }
+
+    #[test]
+    fn test_mouse_tracking_modes() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+        term.process_bytes(b"\x1b[?1000h");
+        assert_eq!(term.mouse_mode(), MouseMode::Normal);
+        term.process_bytes(b"\x1b[?1002h\x1b[?1006h");
+        assert_eq!(term.mouse_mode(), MouseMode::ButtonEvent);
+        assert_eq!(term.mouse_encoding(), MouseEncoding::Sgr);
+
+        // Resetting a different encoding leaves SGR in place
+        term.process_bytes(b"\x1b[?1005l");
+        assert_eq!(term.mouse_encoding(), MouseEncoding::Sgr);
+        term.process_bytes(b"\x1b[?1006l\x1b[?1002l");
+        assert_eq!(term.mouse_encoding(), MouseEncoding::Default);
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+
+        term.process_bytes(b"\x1b[?1003h");
+        assert_eq!(term.mouse_mode(), MouseMode::AnyEvent);
+        term.process_bytes(b"\x1b[?1003l");
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+    }
+
+    #[test]
+    fn test_application_cursor_keys_mode() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        assert!(!term.application_cursor_keys());
+        term.process_bytes(b"\x1b[?1h");
+        assert!(term.application_cursor_keys());
+        term.process_bytes(b"\x1b[?1l");
+        assert!(!term.application_cursor_keys());
+
+        term.process_bytes(b"\x1b[?1h\x1bc");
+        assert!(!term.application_cursor_keys());
+    }
+
+    #[test]
+    fn test_osc_color_changes() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b]4;1;rgb:cc/24/1d;2;#98971a\x07\x1b]10;#ebdbb2;#282828\x07");
+        assert_eq!(term.palette().get(1), Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(term.palette().get(2), Color::rgb(0x98, 0x97, 0x1a));
+        assert_eq!(term.palette().foreground, Color::rgb(0xeb, 0xdb, 0xb2));
+        assert_eq!(term.palette().background, Color::rgb(0x28, 0x28, 0x28));
+
+        // New output uses the changed colors
+        term.process_bytes(b"\x1b[31mA\x1b[0mB");
+        assert_eq!(term.grid().get(0, 0).unwrap().fg, Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(term.grid().get(1, 0).unwrap().fg, Color::rgb(0xeb, 0xdb, 0xb2));
+        assert_eq!(term.grid().get(1, 0).unwrap().bg, Color::rgb(0x28, 0x28, 0x28));
+
+        // Queries and malformed specs leave the palette untouched
+        term.process_bytes(b"\x1b]4;1;?\x07\x1b]11;bogus\x07");
+        assert_eq!(term.palette().get(1), Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(term.palette().background, Color::rgb(0x28, 0x28, 0x28));
+    }
+
+    #[test]
+    fn test_configured_default_colors() {
+        let fg = Color::rgb(0x28, 0x28, 0x28);
+        let bg = Color::rgb(0xfb, 0xf1, 0xc7);
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        term.set_initial_palette(Palette::new(fg, bg));
+
+        // Blank cells and SGR resets use the configured colors
+        assert_eq!(term.grid().get(5, 2).unwrap().bg, bg);
+        term.process_bytes(b"\x1b[31;44mA\x1b[39;49mB\x1b[0mC");
+        let cell = |term: &TerminalEmulator, col| *term.grid().get(col, 0).unwrap();
+        assert_eq!((cell(&term, 1).fg, cell(&term, 1).bg), (fg, bg));
+        assert_eq!((cell(&term, 2).fg, cell(&term, 2).bg), (fg, bg));
+
+        // Erased and scrolled-in cells too
+        term.process_bytes(b"\x1b[44m\x1b[2J\x1b[0m\n\n\n");
+        assert_eq!(cell(&term, 0).bg, bg);
+
+        // RIS returns to the configured colors, not the built-in ones
+        term.process_bytes(b"\x1b]11;#000000\x07\x1bc");
+        assert_eq!(term.palette().background, bg);
+        assert_eq!(cell(&term, 0).bg, bg);
+    }
+
+    #[test]
+    fn test_configured_ansi_colors() {
+        let mut palette = Palette::default();
+        palette.set(1, Color::rgb(0xcc, 0x24, 0x1d));
+        palette.set(9, Color::rgb(0xfb, 0x49, 0x34));
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        term.set_initial_palette(palette);
+
+        term.process_bytes(b"\x1b[31mA\x1b[91mB\x1b[38;5;1mC\x1b[38;5;196mD");
+        let fg = |col| term.grid().get(col, 0).unwrap().fg;
+        assert_eq!(fg(0), Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(fg(1), Color::rgb(0xfb, 0x49, 0x34));
+        assert_eq!(fg(2), Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(fg(3), Color::from_ansi_256(196));
+    }
+
+    #[test]
+    fn test_tab_stops_follow_resize() {
+        let mut term = TerminalEmulator::new(20, 3, 100);
+        // Custom stop at column 3 (HTS), default stop at 8 removed (TBC 0)
+        term.process_bytes(b"\x1b[1;4H\x1bH\x1b[1;9H\x1b[g\r");
+        let tab_stops = |term: &mut TerminalEmulator| {
+            let mut stops = Vec::new();
+            term.process_bytes(b"\r");
+            loop {
+                term.process_bytes(b"\t");
+                let col = term.cursor_position().0;
+                if stops.last() == Some(&col) {
+                    return stops;
+                }
+                stops.push(col);
+            }
+        };
+        assert_eq!(tab_stops(&mut term), vec![3, 16, 19]);
+
+        // New columns get the default stops, existing ones are kept
+        term.resize(40, 3);
+        assert_eq!(tab_stops(&mut term), vec![3, 16, 24, 32, 39]);
+
+        // Stops beyond the new width are dropped
+        term.resize(10, 3);
+        term.resize(20, 3);
+        assert_eq!(tab_stops(&mut term), vec![3, 16, 19]);
+    }
+
+    #[test]
+    fn test_clear_all_tab_stops() {
+        let mut term = TerminalEmulator::new(40, 3, 100);
+        term.process_bytes(b"\x1b[3g\tA");
+        assert_eq!(term.grid().get(39, 0).unwrap().ch, 'A');
+    }
+
+    #[test]
+    fn test_osc_window_title() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        assert_eq!(term.dynamic_title(), None);
+
+        // BEL-terminated OSC 0
+        term.process_bytes(b"\x1b]0;vim - main.rs\x07");
+        assert_eq!(term.dynamic_title(), Some("vim - main.rs"));
+
+        // ST-terminated OSC 2, with a `;` inside the title
+        term.process_bytes(b"\x1b]2;user@host: ~/a;b\x1b\\");
+        assert_eq!(term.dynamic_title(), Some("user@host: ~/a;b"));
+
+        // An empty title and RIS both return to the configured title
+        term.process_bytes(b"\x1b]2;\x07");
+        assert_eq!(term.dynamic_title(), None);
+        term.process_bytes(b"\x1b]0;htop\x07\x1bc");
+        assert_eq!(term.dynamic_title(), None);
+    }
+
+    #[test]
+    fn test_forward_unhandled_sequences() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        let sequences = b"\x1b]1337;CustomThing=1\x07\x1bP1;2|data\x1b\\\x1b]2;title\x07\x1bP$qm\x1b\\";
+
+        // Ignored unless forwarding is enabled
+        term.process_bytes(sequences);
+        assert!(term.take_events().is_empty());
+
+        // Handled sequences (titles, DECRQSS) are not forwarded
+        term.set_forward_unhandled(true);
+        term.process_bytes(sequences);
+        assert_eq!(term.take_events(), [
+            TerminalEvent::UnhandledSequence { kind: SequenceKind::Osc, payload: b"1337;CustomThing=1".to_vec() },
+            TerminalEvent::UnhandledSequence { kind: SequenceKind::Dcs, payload: b"1;2|data".to_vec() },
+        ]);
+        assert_eq!(term.dynamic_title(), Some("title"));
+    }
+
+    #[test]
+    fn test_bell_event() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        // BEL terminating an OSC is not a bell
+        term.process_bytes(b"\x1b]2;title\x07");
+        assert!(term.take_events().is_empty());
+
+        term.process_bytes(b"\x07\x07\x07\x1b[?1049h\x07");
+        assert_eq!(term.take_events(), [TerminalEvent::Bell]);
+        term.process_bytes(b"\x07");
+        assert_eq!(term.take_events(), [TerminalEvent::Bell]);
+    }
+
+    #[test]
+    fn test_kitty_image_cell_size() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        let rgba = base64::engine::general_purpose::STANDARD.encode([255u8; 40 * 20 * 4]);
+
+        // Without c/r the size is estimated from the pixels
+        term.process_bytes(format!("\x1b_Ga=T,f=32,s=40,v=20;{}\x1b\\", rgba).as_bytes());
+        // c and r as given, or one of them with the aspect ratio kept
+        term.process_bytes(format!("\x1b_Ga=T,f=32,s=40,v=20,c=8,r=3;{}\x1b\\", rgba).as_bytes());
+        term.process_bytes(format!("\x1b_Ga=T,f=32,s=40,v=20,c=8;{}\x1b\\", rgba).as_bytes());
+        // Sizes larger than the screen are cut down to it
+        term.process_bytes(format!("\x1b_Ga=T,f=32,s=40,v=20,c=4000000000,r=4000000000;{}\x1b\\", rgba).as_bytes());
+
+        let sizes: Vec<_> = term.images().iter().map(|image| (image.width_cells, image.height_cells)).collect();
+        assert_eq!(sizes, [(4, 1), (8, 3), (8, 2), (80, 24)]);
+    }
+
+    #[test]
+    fn test_image_size_uses_cell_size() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        let (cell_width, cell_height) = crate::renderer::backend::cell_size_for_font(14.0);
+        term.set_cell_size(cell_width, cell_height);
+
+        // 8.4 x 18.2 pixel cells: 100 pixels are 12 columns and 6 rows
+        term.add_image(image::DynamicImage::new_rgba8(100, 100), None, None);
+        let image = &term.images()[0];
+        assert_eq!((image.width_cells, image.height_cells), (12, 6));
+        assert_eq!(term.cursor_position().1, 6);
+    }
+
+    #[test]
+    fn test_title_stack() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+
+        // set -> push -> set -> pop restores the earlier title
+        term.process_bytes(b"\x1b]2;shell\x07\x1b[22;0t\x1b]2;ssh host\x07");
+        assert_eq!(term.dynamic_title(), Some("ssh host"));
+        term.process_bytes(b"\x1b[23;0t");
+        assert_eq!(term.dynamic_title(), Some("shell"));
+
+        // The configured title is restored too, and popping an empty stack changes nothing
+        term.process_bytes(b"\x1b]2;\x07\x1b[22;2t\x1b]0;vim\x07\x1b[23;2t");
+        assert_eq!(term.dynamic_title(), None);
+        term.process_bytes(b"\x1b]0;top\x07\x1b[23;0t");
+        assert_eq!(term.dynamic_title(), Some("top"));
+
+        // Icon-only pushes and OSC 1 leave the window title alone
+        term.process_bytes(b"\x1b[22;1t\x1b]1;icon\x07\x1b]2;less\x07\x1b[23;1t");
+        assert_eq!(term.dynamic_title(), Some("less"));
+
+        // Only the newest titles are kept
+        for index in 0..=TITLE_STACK_LIMIT {
+            term.process_bytes(format!("\x1b]2;t{}\x07\x1b[22t", index).as_bytes());
+        }
+        for _ in 0..=TITLE_STACK_LIMIT {
+            term.process_bytes(b"\x1b[23t");
+        }
+        assert_eq!(term.dynamic_title(), Some("t1"));
+    }
+
+    #[test]
+    fn test_decscusr_cursor_style() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.set_default_cursor_style(CursorStyle::Bar);
+        assert_eq!(term.cursor_style(), CursorStyle::Bar);
+
+        term.process_bytes(b"\x1b[2 q");
+        assert_eq!(term.cursor_style(), CursorStyle::Block);
+        term.process_bytes(b"\x1b[4 q");
+        assert_eq!(term.cursor_style(), CursorStyle::Underline);
+        // Unknown styles and `q` without the space are ignored
+        term.process_bytes(b"\x1b[9 q\x1b[6q");
+        assert_eq!(term.cursor_style(), CursorStyle::Underline);
+
+        term.process_bytes(b"\x1bP$q q\x1b\\");
+        assert_eq!(term.take_responses(), b"\x1bP1$r3 q\x1b\\");
+
+        // RIS returns to the configured style
+        term.process_bytes(b"\x1b[0 q");
+        assert_eq!(term.cursor_style(), CursorStyle::Block);
+        term.process_bytes(b"\x1bc");
+        assert_eq!(term.cursor_style(), CursorStyle::Bar);
+    }
+
+    #[test]
+    fn test_osc8_hyperlink() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"see \x1b]8;;https://example.com/a;b\x1b\\docs\x1b]8;;\x1b\\ here");
+        assert_eq!(row_text(&term, 0), "see docs here");
+
+        assert_eq!(term.link_at(3, 0), None);
+        assert_eq!(term.link_at(4, 0), Some("https://example.com/a;b"));
+        assert_eq!(term.link_at(7, 0), Some("https://example.com/a;b"));
+        // The closer ends the link
+        assert_eq!(term.link_at(8, 0), None);
+        assert_eq!(term.link_at(9, 0), None);
+
+        // Link parameters (`id=`) are ignored; a new target gets its own entry
+        term.process_bytes(b"\r\n\x1b]8;id=1;file:///tmp\x07x\x1b]8;;\x07");
+        assert_eq!(term.link_at(0, 1), Some("file:///tmp"));
+        assert_eq!(term.link_at(4, 0), Some("https://example.com/a;b"));
+        // Opening a known target again reuses its entry
+        term.process_bytes(b"\x1b]8;;https://example.com/a;b\x07y\x1b]8;;\x07");
+        assert_eq!(term.links.len(), 2);
+    }
+
+    #[test]
+    fn test_osc8_link_table_reuses_scrolled_out_entries() {
+        let mut term = TerminalEmulator::new(20, 2, 1);
+        for n in 0..MAX_LINKS + 10 {
+            term.process_bytes(format!("\r\n\x1b]8;;https://example.com/{}\x07x\x1b]8;;\x07", n).as_bytes());
+        }
+        assert_eq!(term.links.len(), MAX_LINKS);
+        assert_eq!(term.link_at(0, 1), Some(format!("https://example.com/{}", MAX_LINKS + 9).as_str()));
+        assert_eq!(term.link_at(0, 0), Some(format!("https://example.com/{}", MAX_LINKS + 8).as_str()));
+    }
+
+    fn view_row_text(term: &TerminalEmulator, row: usize) -> String {
+        let line = term.grid().view_row(row).unwrap();
+        line.iter().map(|cell| cell.ch).collect::<String>().trim_end().to_string()
+    }
+
+    #[test]
+    fn test_pin_prompt_during_output() {
+        let mut term = TerminalEmulator::new(20, 5, 100);
+        term.set_pin_prompt(true);
+        term.process_bytes(b"\x1b]133;A\x07$ \x1b]133;B\x07make\r\n\x1b]133;C\x07");
+        term.process_bytes(b"line 1\r\nline 2\r\nline 3\r\n");
+        // The command line is still on screen
+        assert_eq!(view_row_text(&term, 4), "");
+
+        term.process_bytes(b"line 4\r\nline 5\r\nline 6");
+        assert_eq!(view_row_text(&term, 4), "$ make");
+        assert_eq!(row_text(&term, 4), "line 6");
+
+        // Pinning stops when the command finishes
+        term.process_bytes(b"\r\n\x1b]133;D;0\x07");
+        assert_eq!(view_row_text(&term, 4), "");
+    }
+
+    #[test]
+    fn test_pin_prompt_disabled() {
+        let mut term = TerminalEmulator::new(20, 5, 100);
+        term.process_bytes(b"\x1b]133;A\x07$ \x1b]133;B\x07make\r\n\x1b]133;C\x07");
+        term.process_bytes(b"1\r\n2\r\n3\r\n4\r\n5\r\n6");
+        assert_eq!(view_row_text(&term, 4), "6");
+
+        // Turning the option on pins the command that is already running
+        term.set_pin_prompt(true);
+        assert_eq!(view_row_text(&term, 4), "$ make");
+    }
+
+    #[test]
+    fn test_input_modes_reset_on_exit() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b[?1002h\x1b[?1006h\x1b[?1h\x1b[?2004h");
+        assert!(term.bracketed_paste());
+
+        // Process exit
+        term.reset_input_modes();
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+        assert_eq!(term.mouse_encoding(), MouseEncoding::Default);
+        assert!(!term.application_cursor_keys());
+        assert!(!term.bracketed_paste());
+
+        // Leaving the alternate screen drops mouse reporting but keeps cursor key mode
+        term.process_bytes(b"\x1b[?1049h\x1b[?1000h\x1b[?1006h\x1b[?1h\x1b[?1049l");
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+        assert_eq!(term.mouse_encoding(), MouseEncoding::Default);
+        assert!(term.application_cursor_keys());
+
+        // Mouse reporting on the primary screen is left alone by alt screen toggles
+        term.process_bytes(b"\x1b[?1000h\x1b[?1049l");
+        assert_eq!(term.mouse_mode(), MouseMode::Normal);
+
+        term.process_bytes(b"\x1bc");
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+        assert!(!term.application_cursor_keys());
+    }
+
+    #[test]
+    fn test_soft_reset_keeps_screen() {
+        let mut term = TerminalEmulator::new(20, 12, 100);
+        term.process_bytes(b"hello\r\nworld");
+        // What a crashed program may leave behind: mouse tracking, cursor keys, paste
+        // mode, hidden cursor, reverse video, line drawing set, attributes, margins
+        term.process_bytes(b"\x1b[?1002h\x1b[?1006h\x1b[?1h\x1b[?2004h\x1b[?25l\x1b[?5h");
+        term.process_bytes(b"\x1b(0\x1b[1;7;31m\x1b[3;8r\x1b[2;6H");
+        let cursor = term.cursor_position();
+
+        term.soft_reset();
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+        assert_eq!(term.mouse_encoding(), MouseEncoding::Default);
+        assert!(!term.application_cursor_keys());
+        assert!(!term.bracketed_paste());
+        assert!(term.cursor_visible());
+        assert!(!term.reverse_video());
+        assert_eq!((term.scroll_top, term.scroll_bottom), (0, 11));
+        assert_eq!(term.cursor_position(), cursor);
+        assert_eq!(view_row_text(&term, 0), "hello");
+        assert_eq!(view_row_text(&term, 1), "world");
+
+        // Text is written in ASCII with the default pen again
+        term.process_bytes(b"q");
+        let cell = *term.grid().get(5, 1).unwrap();
+        assert_eq!(cell.ch, 'q');
+        assert_eq!(cell.attrs, CellAttributes::default());
+        assert_eq!(cell.fg, term.palette().foreground);
+
+        // DECSTR from the application does the same
+        term.process_bytes(b"\x1b[?1000h\x1b[?25l\x1b[!p");
+        assert_eq!(term.mouse_mode(), MouseMode::Off);
+        assert!(term.cursor_visible());
+        assert_eq!(view_row_text(&term, 1), "worldq");
+    }
+
+    #[test]
+    fn test_wide_characters_take_two_cells() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes("日本語a".as_bytes());
+        assert_eq!(term.cursor_position(), (7, 0));
+
+        for (col, ch) in [(0, '日'), (2, '本'), (4, '語')] {
+            let cell = term.grid().get(col, 0).unwrap();
+            assert_eq!((cell.ch, cell.width), (ch, 2));
+            assert!(term.grid().get(col + 1, 0).unwrap().is_continuation());
+        }
+        assert_eq!(term.grid().get(6, 0).unwrap().width, 1);
+    }
+
+    #[test]
+    fn test_wide_character_wraps_at_right_edge() {
+        let mut term = TerminalEmulator::new(5, 3, 100);
+        term.process_bytes("abcd日".as_bytes());
+        assert_eq!(term.grid().get(4, 0).unwrap().ch, ' ');
+        assert_eq!(term.grid().get(0, 1).unwrap().ch, '日');
+        assert_eq!(term.cursor_position(), (2, 1));
+    }
+
+    #[test]
+    fn test_overwriting_half_of_wide_character() {
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        // Overwrite the second column of the first wide char
+        term.process_bytes("日本\x1b[1;2Hx".as_bytes());
+        assert_eq!(term.grid().get(0, 0).unwrap().ch, ' ');
+        assert_eq!(term.grid().get(1, 0).unwrap().ch, 'x');
+        assert_eq!(term.grid().get(2, 0).unwrap().ch, '本');
+
+        // Overwrite the first column of the second wide char
+        term.process_bytes(b"\x1b[1;3Hy");
+        assert_eq!(term.grid().get(2, 0).unwrap().ch, 'y');
+        assert!(!term.grid().get(3, 0).unwrap().is_continuation());
+    }
+
+    #[test]
+    fn test_erasing_half_of_wide_character() {
+        // Row text without the second halves of wide characters
+        let text = |term: &TerminalEmulator| {
+            let grid = term.grid();
+            (0..grid.cols())
+                .map(|col| grid.get(col, 0).unwrap())
+                .filter(|cell| !cell.is_continuation())
+                .map(|cell| cell.ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+
+        // Overwriting either half of "あ" with "x" blanks the other half
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        term.process_bytes("あ\x1b[1;2Hx".as_bytes());
+        assert_eq!(text(&term), " x");
+        term.process_bytes("\x1b[2J\x1b[Hあ\x1b[1;1Hx".as_bytes());
+        assert_eq!(text(&term), "x");
+        assert_eq!(term.grid().get(1, 0).unwrap().ch, ' ');
+        term.assert_invariants();
+
+        // ECH, EL, ED, ICH and DCH starting or ending inside a wide character
+        for (sequence, expected) in [
+            ("\x1b[1;2H\x1b[X", "  い"),
+            ("\x1b[1;3H\x1b[X", "あ"),
+            ("\x1b[1;4H\x1b[K", "あ"),
+            ("\x1b[1;1H\x1b[1K", "  い"),
+            ("\x1b[1;3H\x1b[1J", ""),
+            ("\x1b[1;2H\x1b[@", "   い"),
+            ("\x1b[1;2H\x1b[P", " い"),
+            ("\x1b[1;3H\x1b[P", "あ"),
+        ] {
+            term.process_bytes(format!("\x1b[2J\x1b[Hあい{}", sequence).as_bytes());
+            assert_eq!(text(&term), expected, "{:?}", sequence);
+            term.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn test_dec_special_graphics() {
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.process_bytes(b"\x1b(0lqk\x1b(Bq");
+        assert_eq!(row_text(&term, 0), "┌─┐q");
+
+        // G1 designated as line drawing, invoked with SO and back with SI
+        term.process_bytes(b"\r\n\x1b)0x\x0ex\x0fx");
+        assert_eq!(row_text(&term, 1), "x│x");
+
+        term.process_bytes(b"\x1b(0\x1bc");
+        term.process_bytes(b"q");
+        assert_eq!(row_text(&term, 0), "q");
+    }
+
+    #[test]
+    fn test_reflow_paragraph_shrink_and_grow() {
+        let mut term = TerminalEmulator::new(20, 4, 100);
+        term.process_bytes(b"The quick brown fox jumps over the lazy dog again\r\n$ ");
+        assert_eq!(term.cursor_position(), (2, 3));
+
+        // Seven columns: the paragraph takes seven rows, the first four go to the scrollback
+        term.resize(7, 4);
+        term.assert_invariants();
+        assert_eq!(term.grid().scrollback_len(), 4);
+        assert_eq!((0..3).map(|row| row_text(&term, row)).collect::<Vec<_>>(), ["er the", "lazy do", "g again"]);
+        assert_eq!(row_text(&term, 3), "$");
+        assert_eq!(term.cursor_position(), (2, 3));
+
+        // Back to twenty columns: the lines are joined again, nothing is lost
+        term.resize(20, 4);
+        term.assert_invariants();
+        assert_eq!(term.grid().scrollback_len(), 0);
+        assert_eq!((0..3).map(|row| row_text(&term, row)).collect::<Vec<_>>(), [
+            "The quick brown fox", "jumps over the lazy", "dog again",
+        ]);
+        assert_eq!(row_text(&term, 3), "$");
+        assert_eq!(term.cursor_position(), (2, 3));
+    }
+
+    #[test]
+    fn test_resize_moves_cursor_off_wide_continuation() {
+        // The cursor on the second half of "日" while the primary screen is rewrapped
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        term.process_bytes("ab日本\x1b[1;4H".as_bytes());
+        term.resize(5, 3);
+        term.assert_invariants();
+        assert_eq!(term.cursor_position(), (2, 0));
+        term.process_bytes(b"x");
+        assert_eq!(row_text(&term, 0), "abx");
+        assert_eq!(term.grid().get(0, 1).unwrap().ch, '本');
+
+        // On the alternate screen, which is resized without rewrapping
+        let mut term = TerminalEmulator::new(10, 3, 100);
+        term.process_bytes("\x1b[?1049hab日本\x1b[1;6H".as_bytes());
+        term.resize(8, 3);
+        term.assert_invariants();
+        let (col, row) = term.cursor_position();
+        assert_eq!((col, row), (4, 0));
+        assert!(!term.grid().get(col, row).unwrap().is_continuation());
+
+        // Shrinking cuts "本" off; the cursor stays inside the new width
+        term.resize(5, 3);
+        term.assert_invariants();
+        let (col, row) = term.cursor_position();
+        assert_eq!((col, row), (4, 0));
+        assert!(!term.grid().get(col, row).unwrap().is_continuation());
+    }
+
+    #[test]
+    fn test_reflow_very_long_line() {
+        // A megabyte without a newline: one logical line far longer than the scrollback
+        let data: Vec<u8> = (0..1024 * 1024).map(|i| b'0' + (i % 10) as u8).collect();
+        let mut term = TerminalEmulator::new(80, 24, 1000);
+        term.process_bytes(&data);
+
+        let start = std::time::Instant::now();
+        for (cols, rows) in [(57, 30), (123, 20), (80, 24)] {
+            term.resize(cols, rows);
+            term.assert_invariants();
+            assert!(term.grid().scrollback_len() <= 1000);
+
+            // The text right before the cursor is the end of the output
+            let (col, row) = term.cursor_position();
+            let text: String = (0..=row).map(|row| row_text(&term, row)).collect();
+            let before_cursor = &text[..row * cols + col];
+            let expected = std::str::from_utf8(&data[data.len() - before_cursor.len()..]).unwrap();
+            assert_eq!(before_cursor, expected, "after resizing to {}x{}", cols, rows);
+        }
+        // Rewrapping is linear in the stored cells; a quadratic rebuild would take minutes
+        assert!(start.elapsed() < std::time::Duration::from_secs(5), "reflow took {:?}", start.elapsed());
+
+        // Output continues on the same line
+        term.process_bytes(b"xyz");
+        let (col, row) = term.cursor_position();
+        assert_eq!(&row_text(&term, row)[col - 3..], "xyz");
+    }
+}
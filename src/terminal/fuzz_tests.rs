@@ -0,0 +1,89 @@
+//! Randomized escape-sequence tests: random byte streams with resizes interleaved
+//! must never panic or leave the emulator in an inconsistent state.
+//!
+//! `cargo test` runs a bounded number of cases. Longer runs and reproductions use
+//! `TERBULATOR_FUZZ_ITERATIONS` and `TERBULATOR_FUZZ_SEED` (the seed of a failing
+//! case is printed in the panic message).
+
+use crate::terminal::TerminalEmulator;
+use std::panic::{self, AssertUnwindSafe};
+
+const DEFAULT_ITERATIONS: u64 = 200;
+const CHUNKS_PER_CASE: usize = 32;
+
+/// Pieces that make random input likely to form real sequences
+const FRAGMENTS: &[&[u8]] = &[
+    b"\x1b[", b"\x1b]", b"\x1bP", b"\x1b\\", b"\x1b", b"\x07", b"\x1b(0", b"\x1b)0", b"\x0e", b"\x0f",
+    b"\x1b[?", b"\x1b[>", b"\x1b[!p", b"\x1bc", b"\x1bH", b"\x1b7", b"\x1b8", b"\x1bM", b"\x1bD",
+    b"\x1b[?1049h", b"\x1b[?1049l", b"\x1b[?47h", b"\x1b[?6h", b"\x1b[?5h", b"\x1b[r",
+    b"\x1b]0;", b"\x1b]4;", b"\x1b]8;;", b"\x1b]10;", b"\x1b]52;c;", b"\x1b]133;", b"\x1bP$q", b"\x1b_G",
+    b";", b":", b"65535", b"4294967296", b"99999", b"0", b"1", b"-", b"?", b"$", b"%",
+    b"\r", b"\n", b"\t", b"\x08", b"\x0b", b"\x0c",
+    "漢字".as_bytes(), "😀".as_bytes(), "e\u{301}".as_bytes(), b"\xff\xfe", b"\xe6\xbc",
+];
+
+/// Final bytes of CSI sequences the emulator handles (and some it does not)
+const CSI_FINALS: &[u8] = b"@ABCDEFGHIJKLMPSTXZ`abcdefghlmnqrsuxz";
+
+fn random_chunk(rng: &mut fastrand::Rng) -> Vec<u8> {
+    let mut chunk = Vec::new();
+    for _ in 0..rng.usize(1..64) {
+        match rng.u8(0..10) {
+            0..=4 => chunk.extend_from_slice(FRAGMENTS[rng.usize(..FRAGMENTS.len())]),
+            5 => chunk.push(CSI_FINALS[rng.usize(..CSI_FINALS.len())]),
+            6 => chunk.extend_from_slice(rng.usize(0..100_000).to_string().as_bytes()),
+            7 => chunk.push(rng.u8(0x20..0x7f)),
+            _ => chunk.push(rng.u8(..)),
+        }
+    }
+    chunk
+}
+
+/// Feed one random case into a fresh emulator, checking the invariants after every step
+fn run_case(seed: u64) {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut term = TerminalEmulator::new(rng.usize(1..100), rng.usize(1..40), rng.usize(0..50));
+    term.set_pin_prompt(rng.bool());
+
+    for _ in 0..CHUNKS_PER_CASE {
+        if rng.u8(0..8) == 0 {
+            term.resize(rng.usize(1..120), rng.usize(1..50));
+        } else {
+            term.process_bytes(&random_chunk(&mut rng));
+        }
+        term.assert_invariants();
+
+        // Replies and events are drained by the pane every frame
+        term.take_responses();
+        term.take_events();
+    }
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    std::env::var(name).ok().and_then(|value| value.parse().ok())
+}
+
+#[test]
+fn test_random_input_keeps_invariants() {
+    let iterations = env_u64("TERBULATOR_FUZZ_ITERATIONS").unwrap_or(DEFAULT_ITERATIONS);
+    let first_seed = env_u64("TERBULATOR_FUZZ_SEED").unwrap_or(0);
+
+    for seed in first_seed..first_seed + iterations {
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| run_case(seed))) {
+            let message = panic
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| panic.downcast_ref::<&str>().copied())
+                .unwrap_or("(non-string panic)");
+            panic!("fuzz case failed (TERBULATOR_FUZZ_SEED={} TERBULATOR_FUZZ_ITERATIONS=1): {}", seed, message);
+        }
+    }
+}
+
+#[test]
+fn test_single_column_and_row() {
+    // The smallest grid the panes can produce
+    let mut term = TerminalEmulator::new(1, 1, 0);
+    term.process_bytes("漢\x1b[5;5H\x1b[10@\x1b[10P\x1b[10L\x1b[10M\x1b[2J\x1b[?6h\x1b[2;1r\tx\x1bD\x1bM".as_bytes());
+    term.assert_invariants();
+}
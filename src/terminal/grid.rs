@@ -1,31 +1,59 @@
 use crate::renderer::backend::Color;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CellAttributes {
     pub bold: bool,
+    /// Faint (SGR 2)
+    pub dim: bool,
     pub italic: bool,
     pub underline: bool,
     pub inverse: bool,
+    pub strikethrough: bool,
 }
 
 impl Default for CellAttributes {
     fn default() -> Self {
         Self {
             bold: false,
+            dim: false,
             italic: false,
             underline: false,
             inverse: false,
+            strikethrough: false,
         }
     }
 }
 
+/// Whitespace written by the application, shown with `show_whitespace`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Whitespace {
+    /// Not whitespace, or a cell only ever cleared
+    #[default]
+    None,
+    /// A printed space
+    Space,
+    /// The first blank cell skipped over by a tab (HT)
+    TabStart,
+    /// A further blank cell skipped over by the same tab
+    Tab,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Cell {
     pub ch: char,
     pub fg: Color,
     pub bg: Color,
+    /// Bright counterpart of `fg` when it is one of the 8 basic colors (SGR 30-37),
+    /// used for bold text with `terminal.bold_is_bright`
+    pub bright_fg: Option<Color>,
     pub attrs: CellAttributes,
+    /// Columns taken by the character: 1, 2 for wide (CJK, emoji),
+    /// or 0 for the placeholder in the second column of a wide character
+    pub width: u8,
+    /// Hyperlink (OSC 8), as an index into the emulator's link table
+    pub link: Option<usize>,
+    pub whitespace: Whitespace,
 }
 
 impl Default for Cell {
@@ -34,7 +62,11 @@ impl Default for Cell {
             ch: ' ',
             fg: Color::WHITE,
             bg: Color::BLACK,
+            bright_fg: None,
             attrs: CellAttributes::default(),
+            width: 1,
+            link: None,
+            whitespace: Whitespace::None,
         }
     }
 }
@@ -47,20 +79,55 @@ impl Cell {
         }
     }
 
-    pub fn reset(&mut self) {
-        *self = Self::default();
+    /// Placeholder for the second column of a wide character.
+    /// It holds `'\0'`, which is neither drawn nor copied.
+    pub fn continuation(of: &Cell) -> Self {
+        Self {
+            ch: '\0',
+            width: 0,
+            ..*of
+        }
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.width == 0
     }
 }
 
+/// Columns up to the last visible character of a row; the cells after it are
+/// trailing whitespace
+pub fn row_logical_len(row: &[Cell]) -> usize {
+    row.iter().rposition(|cell| cell.ch != ' ').map_or(0, |col| col + 1)
+}
+
+/// A row saved to scrollback
+#[derive(Debug, Clone)]
+struct ScrollbackLine {
+    cells: Vec<Cell>,
+    /// The line continues on the next row (auto-wrapped)
+    wrapped: bool,
+}
+
 /// Terminal grid containing cells
 pub struct Grid {
     cells: Vec<Cell>,
     cols: usize,
     rows: usize,
-    scrollback: Vec<Vec<Cell>>,
+    /// Per screen row: the line continues on the next row (auto-wrapped),
+    /// so a resize can rewrap it
+    wrapped: Vec<bool>,
+    scrollback: VecDeque<ScrollbackLine>,
     max_scrollback: usize,
     dirty_cells: HashSet<(usize, usize)>,
     full_redraw_needed: bool,
+    /// Lines the view is scrolled back into scrollback (0 = live screen)
+    scroll_offset: usize,
+    /// Cell written by clears, scrolls and resizes (carries the default colors)
+    blank: Cell,
+    /// Lines scrolled off the top of the screen since the grid was created
+    scrolled_lines: u64,
+    /// Line displayed over the bottom row of the live screen (pinned prompt)
+    pinned_row: Option<Vec<Cell>>,
 }
 
 impl Grid {
@@ -70,10 +137,15 @@ impl Grid {
             cells,
             cols,
             rows,
-            scrollback: Vec::new(),
+            wrapped: vec![false; rows],
+            scrollback: VecDeque::new(),
             max_scrollback,
             dirty_cells: HashSet::new(),
             full_redraw_needed: true,
+            scroll_offset: 0,
+            blank: Cell::default(),
+            scrolled_lines: 0,
+            pinned_row: None,
         }
     }
 
@@ -85,12 +157,115 @@ impl Grid {
         self.rows
     }
 
+    /// Number of lines stored in scrollback
+    #[cfg(test)]
+    pub(crate) fn scrollback_len(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// Change the default colors of blank cells; cells that are still blank are recolored
+    pub fn set_blank_colors(&mut self, fg: Color, bg: Color) {
+        let old_blank = self.blank;
+        self.blank = Cell { fg, bg, ..old_blank };
+        let scrollback_cells = self.scrollback.iter_mut().flat_map(|line| line.cells.iter_mut());
+        for cell in self.cells.iter_mut().chain(scrollback_cells) {
+            if *cell == old_blank {
+                *cell = self.blank;
+            }
+        }
+        self.full_redraw_needed = true;
+    }
+
+    /// Resize without rewrapping: rows keep their position and are cut or padded
+    /// at the right edge (the alternate screen, whose application redraws anyway)
     pub fn resize(&mut self, cols: usize, rows: usize) {
+        let mut cells = vec![self.blank; cols * rows];
+        for row in 0..rows.min(self.rows) {
+            let kept = cols.min(self.cols);
+            let old = &self.cells[row * self.cols..row * self.cols + kept];
+            let new = &mut cells[row * cols..row * cols + kept];
+            new.copy_from_slice(old);
+            // A wide character whose second half was cut off
+            if kept < self.cols && kept > 0 && new[kept - 1].width == 2 {
+                new[kept - 1] = self.blank;
+            }
+        }
+        self.cells = cells;
+        self.wrapped.resize(rows, false);
+        self.cols = cols;
+        self.rows = rows;
+        self.full_redraw_needed = true;
+        self.dirty_cells.clear();
+    }
+
+    /// Resize and rewrap auto-wrapped lines (scrollback included) to the new width.
+    /// Lines are rewrapped one at a time, so the cost is linear in the stored cells even
+    /// for a single line spanning the whole scrollback. Returns where `cursor` ends up.
+    pub fn reflow(&mut self, cols: usize, rows: usize, cursor: (usize, usize)) -> (usize, usize) {
+        if cols == 0 || rows == 0 || self.cols == 0 || self.rows == 0 {
+            self.resize(cols, rows);
+            return (0, 0);
+        }
+
+        // Rows of the screen worth keeping: up to the cursor or the last row with content
+        let cursor_row = cursor.1.min(self.rows - 1);
+        let last_row = (cursor_row..self.rows)
+            .rev()
+            .find(|&row| {
+                self.is_wrapped(row) || self.cells[row * self.cols..(row + 1) * self.cols].iter().any(|cell| *cell != self.blank)
+            })
+            .unwrap_or(cursor_row);
+
+        let old_scrollback = std::mem::take(&mut self.scrollback);
+        let old_scrollback_len = old_scrollback.len();
+        let old_cells = std::mem::take(&mut self.cells);
+        let old_cols = self.cols;
+        let source: Vec<(&[Cell], bool)> = old_scrollback
+            .iter()
+            .map(|line| (line.cells.as_slice(), line.wrapped))
+            .chain((0..=last_row).map(|row| (&old_cells[row * old_cols..(row + 1) * old_cols], self.wrapped[row])))
+            .collect();
+        let cursor_source_row = old_scrollback_len + cursor_row;
+
+        let mut output = Rewrap::new(cols, self.blank);
+        let mut start = 0;
+        while start < source.len() {
+            // Rows that make up one logical line
+            let end = (start..source.len()).find(|&row| !source[row].1).map_or(source.len(), |row| row + 1);
+            let line = &source[start..end];
+            let cursor_col = (start..end).contains(&cursor_source_row).then_some(cursor.0);
+            output.push_line(line, cursor_source_row.saturating_sub(start), cursor_col);
+            start = end;
+        }
+        drop(source);
+
+        let (mut lines, new_cursor) = output.finish();
+        let (cursor_col, cursor_line) = new_cursor.unwrap_or((0, lines.len().saturating_sub(1)));
+
+        // Bottom-align the content on the new screen, keeping the cursor on it
+        let screen_start = lines.len().saturating_sub(rows).min(cursor_line);
+        let screen: Vec<ScrollbackLine> = lines.drain(screen_start..).take(rows).collect();
+
+        self.cells = Vec::with_capacity(cols * rows);
+        self.wrapped = Vec::with_capacity(rows);
+        for line in &screen {
+            self.cells.extend_from_slice(&line.cells);
+            self.wrapped.push(line.wrapped);
+        }
+        self.cells.resize(cols * rows, self.blank);
+        self.wrapped.resize(rows, false);
+
+        lines.drain(..lines.len().saturating_sub(self.max_scrollback));
+        self.scrollback = lines;
+        // Lines moved between the screen and the scrollback
+        self.scrolled_lines = (self.scrolled_lines + self.scrollback.len() as u64).saturating_sub(old_scrollback_len as u64);
+        self.scroll_offset = 0;
+
         self.cols = cols;
         self.rows = rows;
-        self.cells.resize(cols * rows, Cell::default());
         self.full_redraw_needed = true;
         self.dirty_cells.clear();
+        (cursor_col, cursor_line - screen_start)
     }
 
     pub fn get(&self, col: usize, row: usize) -> Option<&Cell> {
@@ -118,10 +293,29 @@ impl Grid {
         }
     }
 
+    /// Blank out the other half of a wide character about to be partly overwritten at (col, row)
+    pub fn break_wide_char(&mut self, col: usize, row: usize) {
+        let Some(cell) = self.get(col, row) else { return };
+        let partner = if cell.is_continuation() {
+            col.checked_sub(1)
+        } else if cell.width == 2 {
+            Some(col + 1)
+        } else {
+            None
+        };
+
+        let Some(partner) = partner else { return };
+        if let Some(other) = self.get(partner, row) {
+            let blank = Cell { ch: ' ', width: 1, ..*other };
+            self.set(partner, row, blank);
+        }
+    }
+
     pub fn clear(&mut self) {
         for cell in &mut self.cells {
-            cell.reset();
+            *cell = self.blank;
         }
+        self.wrapped.fill(false);
         self.full_redraw_needed = true;
         self.dirty_cells.clear();
     }
@@ -131,8 +325,9 @@ impl Grid {
             let start = row * self.cols;
             let end = start + self.cols;
             for cell in &mut self.cells[start..end] {
-                cell.reset();
+                *cell = self.blank;
             }
+            self.wrapped[row] = false;
             // Mark entire row as dirty
             for col in 0..self.cols {
                 self.dirty_cells.insert((col, row));
@@ -140,33 +335,41 @@ impl Grid {
         }
     }
 
+    /// Scroll the whole screen up, saving the lines that leave the top to scrollback.
+    /// Scrolling by the screen height or more saves every row and clears the screen.
     pub fn scroll_up(&mut self, lines: usize) {
-        if lines == 0 || lines >= self.rows {
+        let lines = lines.min(self.rows);
+        if lines == 0 {
             return;
         }
 
+        self.scrolled_lines += lines as u64;
+
         // Save top lines to scrollback
         for i in 0..lines {
             let start = i * self.cols;
             let end = start + self.cols;
-            let line = self.cells[start..end].to_vec();
-            self.scrollback.push(line);
+            let line = ScrollbackLine { cells: self.cells[start..end].to_vec(), wrapped: self.wrapped[i] };
+            self.scrollback.push_back(line);
 
             // Limit scrollback size
             if self.scrollback.len() > self.max_scrollback {
-                self.scrollback.remove(0);
+                self.scrollback.pop_front();
             }
         }
+        self.scroll_offset = self.scroll_offset.min(self.scrollback.len());
 
         // Shift cells up
         let shift_amount = lines * self.cols;
         self.cells.copy_within(shift_amount.., 0);
+        self.wrapped.copy_within(lines.., 0);
 
         // Clear bottom lines
         let clear_start = (self.rows - lines) * self.cols;
         for cell in &mut self.cells[clear_start..] {
-            cell.reset();
+            *cell = self.blank;
         }
+        self.wrapped[self.rows - lines..].fill(false);
 
         // Scroll affects entire screen
         self.full_redraw_needed = true;
@@ -174,25 +377,191 @@ impl Grid {
     }
 
     pub fn scroll_down(&mut self, lines: usize) {
-        if lines == 0 || lines >= self.rows {
+        let lines = lines.min(self.rows);
+        if lines == 0 {
             return;
         }
 
         // Shift cells down
         let shift_amount = lines * self.cols;
         self.cells.copy_within(..self.cols * (self.rows - lines), shift_amount);
+        self.wrapped.copy_within(..self.rows - lines, lines);
 
         // Clear top lines
         let clear_end = lines * self.cols;
         for cell in &mut self.cells[..clear_end] {
-            cell.reset();
+            *cell = self.blank;
         }
+        self.wrapped[..lines].fill(false);
 
         // Scroll affects entire screen
         self.full_redraw_needed = true;
         self.dirty_cells.clear();
     }
 
+    /// Scroll the rows within [top, bottom] up, leaving rows outside the region untouched.
+    /// Only a full-screen region pushes lines into scrollback.
+    pub fn scroll_region_up(&mut self, top: usize, bottom: usize, lines: usize) {
+        let bottom = bottom.min(self.rows.saturating_sub(1));
+        if top == 0 && bottom + 1 == self.rows {
+            self.scroll_up(lines);
+            return;
+        }
+        self.shift_rows_up(top, bottom, lines);
+    }
+
+    /// Shift the rows within [top, bottom] up without touching scrollback
+    fn shift_rows_up(&mut self, top: usize, bottom: usize, lines: usize) {
+        let bottom = bottom.min(self.rows.saturating_sub(1));
+        if lines == 0 || top > bottom {
+            return;
+        }
+
+        let lines = lines.min(bottom - top + 1);
+        let cols = self.cols;
+
+        // Shift region rows up
+        self.cells.copy_within((top + lines) * cols..(bottom + 1) * cols, top * cols);
+        self.wrapped.copy_within(top + lines..bottom + 1, top);
+
+        // Clear the rows vacated at the bottom of the region
+        for cell in &mut self.cells[(bottom + 1 - lines) * cols..(bottom + 1) * cols] {
+            *cell = self.blank;
+        }
+        self.wrapped[bottom + 1 - lines..bottom + 1].fill(false);
+
+        self.full_redraw_needed = true;
+        self.dirty_cells.clear();
+    }
+
+    /// Scroll the rows within [top, bottom] down, leaving rows outside the region untouched
+    pub fn scroll_region_down(&mut self, top: usize, bottom: usize, lines: usize) {
+        let bottom = bottom.min(self.rows.saturating_sub(1));
+        if lines == 0 || top > bottom {
+            return;
+        }
+
+        let lines = lines.min(bottom - top + 1);
+        let cols = self.cols;
+
+        // Shift region rows down
+        self.cells.copy_within(top * cols..(bottom + 1 - lines) * cols, (top + lines) * cols);
+        self.wrapped.copy_within(top..bottom + 1 - lines, top + lines);
+
+        // Clear the rows vacated at the top of the region
+        for cell in &mut self.cells[top * cols..(top + lines) * cols] {
+            *cell = self.blank;
+        }
+        self.wrapped[top..top + lines].fill(false);
+
+        self.full_redraw_needed = true;
+        self.dirty_cells.clear();
+    }
+
+    /// IL - Insert blank lines at `row`, pushing lines below it down to `bottom`
+    pub fn insert_lines(&mut self, row: usize, lines: usize, bottom: usize) {
+        self.scroll_region_down(row, bottom, lines);
+    }
+
+    /// DL - Delete lines at `row`, pulling lines up from `bottom`
+    pub fn delete_lines(&mut self, row: usize, lines: usize, bottom: usize) {
+        self.shift_rows_up(row, bottom, lines);
+    }
+
+    /// ICH - Insert blank cells at (col, row), shifting the rest of the line right
+    pub fn insert_chars(&mut self, col: usize, row: usize, count: usize) {
+        if row >= self.rows || col >= self.cols {
+            return;
+        }
+        // Inserting between the halves of a wide character splits it
+        if self.cells[row * self.cols + col].is_continuation() {
+            self.erase_cells(row, col, col + 1);
+        }
+        let count = count.min(self.cols - col);
+        let start = row * self.cols;
+        let line = &mut self.cells[start..start + self.cols];
+        line.copy_within(col..self.cols - count, col + count);
+        for cell in &mut line[col..col + count] {
+            *cell = self.blank;
+        }
+        // A wide character whose second half was pushed off the end
+        if line[self.cols - 1].width == 2 {
+            line[self.cols - 1] = self.blank;
+        }
+        self.mark_row_dirty(row, col);
+    }
+
+    /// DCH - Delete cells at (col, row), shifting the rest of the line left
+    pub fn delete_chars(&mut self, col: usize, row: usize, count: usize) {
+        if row >= self.rows || col >= self.cols {
+            return;
+        }
+        let count = count.min(self.cols - col);
+        // Wide characters cut by either end of the deleted cells lose their other half too
+        self.erase_cells(row, col, col + 1);
+        self.erase_cells(row, col + count - 1, col + count);
+        let start = row * self.cols;
+        let line = &mut self.cells[start..start + self.cols];
+        line.copy_within(col + count.., col);
+        for cell in &mut line[self.cols - count..] {
+            *cell = self.blank;
+        }
+        self.mark_row_dirty(row, col);
+    }
+
+    /// ECH - Erase cells starting at (col, row) without shifting
+    pub fn erase_chars(&mut self, col: usize, row: usize, count: usize) {
+        self.erase_cells(row, col, col.saturating_add(count));
+    }
+
+    /// Blank columns `start..end` of a row (EL, ED, ECH). A wide character cut by
+    /// either end is blanked whole, so no half of it is left behind.
+    pub fn erase_cells(&mut self, row: usize, start: usize, end: usize) {
+        let end = end.min(self.cols);
+        if row >= self.rows || start >= end {
+            return;
+        }
+        let line = &self.cells[row * self.cols..(row + 1) * self.cols];
+        let start = if line[start].is_continuation() { start.saturating_sub(1) } else { start };
+        let end = if line[end - 1].width == 2 { (end + 1).min(self.cols) } else { end };
+        for col in start..end {
+            self.set(col, row, self.blank);
+        }
+    }
+
+    /// Mark the cells of a row from `from_col` to the end as dirty
+    fn mark_row_dirty(&mut self, row: usize, from_col: usize) {
+        for col in from_col..self.cols {
+            self.dirty_cells.insert((col, row));
+        }
+    }
+
+    /// Text of the logical lines in the scrollback and on the screen, oldest first.
+    /// Rows joined by auto-wrap form one line; trailing blanks and the empty rows
+    /// below the last text are dropped.
+    #[cfg(test)]
+    pub(crate) fn logical_lines(&self) -> Vec<String> {
+        let scrollback = self.scrollback.iter().map(|line| (line.cells.as_slice(), line.wrapped));
+        let screen = self.cells.chunks(self.cols.max(1)).zip(self.wrapped.iter().copied());
+
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        for (cells, wrapped) in scrollback.chain(screen) {
+            line.extend(cells.iter().filter(|cell| !cell.is_continuation()).map(|cell| cell.ch));
+            if !wrapped {
+                lines.push(line.trim_end().to_string());
+                line.clear();
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line.trim_end().to_string());
+        }
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines
+    }
+
     pub fn iter_rows(&self) -> impl Iterator<Item = &[Cell]> {
         self.cells.chunks(self.cols)
     }
@@ -207,6 +576,119 @@ impl Grid {
         }
     }
 
+    /// Mark whether the line on `row` continues on the next row (auto-wrap)
+    pub fn set_wrapped(&mut self, row: usize, wrapped: bool) {
+        if let Some(flag) = self.wrapped.get_mut(row) {
+            *flag = wrapped;
+        }
+    }
+
+    pub fn is_wrapped(&self, row: usize) -> bool {
+        self.wrapped.get(row).copied().unwrap_or(false)
+    }
+
+    /// How many lines the view is scrolled back into scrollback (0 = live screen)
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    /// Scroll the view back by `lines`, stopping at the oldest scrollback line
+    pub fn scroll_view_up(&mut self, lines: usize) {
+        self.set_scroll_offset(self.scroll_offset.saturating_add(lines).min(self.scrollback.len()));
+    }
+
+    /// Scroll the view forward by `lines`, stopping at the live screen
+    pub fn scroll_view_down(&mut self, lines: usize) {
+        self.set_scroll_offset(self.scroll_offset.saturating_sub(lines));
+    }
+
+    /// Snap the view back to the live screen
+    pub fn reset_view(&mut self) {
+        self.set_scroll_offset(0);
+    }
+
+    fn set_scroll_offset(&mut self, offset: usize) {
+        if offset != self.scroll_offset {
+            self.scroll_offset = offset;
+            self.full_redraw_needed = true;
+        }
+    }
+
+    /// Row as currently displayed: the newest scrollback lines sit above the
+    /// live screen while the view is scrolled back. Scrollback lines keep the
+    /// width they were written with, so they may be shorter than `cols`.
+    pub fn view_row(&self, row: usize) -> Option<&[Cell]> {
+        if row >= self.rows {
+            return None;
+        }
+        if row < self.scroll_offset {
+            let index = self.scrollback.len() - self.scroll_offset + row;
+            self.scrollback.get(index).map(|line| line.cells.as_slice())
+        } else if row + 1 == self.rows && self.scroll_offset == 0 && self.pinned_row.is_some() {
+            self.pinned_row.as_deref().map(|line| &line[..line.len().min(self.cols)])
+        } else {
+            self.get_row(row - self.scroll_offset)
+        }
+    }
+
+    /// Whether the displayed row continues on the next displayed row (auto-wrap)
+    pub fn view_row_wrapped(&self, row: usize) -> bool {
+        if row >= self.rows {
+            false
+        } else if row < self.scroll_offset {
+            let index = self.scrollback.len() - self.scroll_offset + row;
+            self.scrollback.get(index).is_some_and(|line| line.wrapped)
+        } else if row + 1 == self.rows && self.scroll_offset == 0 && self.pinned_row.is_some() {
+            false
+        } else {
+            self.is_wrapped(row - self.scroll_offset)
+        }
+    }
+
+    /// Lines scrolled off the top so far; with a row index this gives a position
+    /// that stays the same while the line moves up the screen
+    pub fn scrolled_lines(&self) -> u64 {
+        self.scrolled_lines
+    }
+
+    /// Show `line` in place of the bottom row of the live screen (None to stop)
+    pub fn set_pinned_row(&mut self, line: Option<Vec<Cell>>) {
+        if line != self.pinned_row {
+            self.pinned_row = line;
+            self.full_redraw_needed = true;
+        }
+    }
+
+    pub fn has_pinned_row(&self) -> bool {
+        self.pinned_row.is_some()
+    }
+
+    /// Hyperlinks (`Cell::link`) still referenced by the screen or the scrollback
+    pub fn links_in_use(&self) -> HashSet<usize> {
+        let scrollback = self.scrollback.iter().flat_map(|line| &line.cells);
+        let pinned = self.pinned_row.iter().flatten();
+        self.cells.iter().chain(scrollback).chain(pinned).filter_map(|cell| cell.link).collect()
+    }
+
+    /// Panic if the internal state no longer matches the grid size (fuzz tests)
+    #[cfg(test)]
+    pub(crate) fn assert_invariants(&self) {
+        assert_eq!(self.cells.len(), self.cols * self.rows, "cell vector does not match {}x{}", self.cols, self.rows);
+        assert_eq!(self.wrapped.len(), self.rows, "wrap flags do not match the rows");
+        assert!(self.scrollback.len() <= self.max_scrollback, "scrollback over its limit");
+        assert!(self.scroll_offset <= self.scrollback.len(), "view scrolled past the scrollback");
+        assert!(self.dirty_cells.iter().all(|&(col, row)| col < self.cols && row < self.rows), "dirty cell out of bounds");
+        for row in 0..self.rows {
+            assert!(self.view_row(row).is_some(), "view row {} missing", row);
+            let line = &self.cells[row * self.cols..(row + 1) * self.cols];
+            for col in 0..self.cols {
+                let lead = line[col].width == 2;
+                let continued = line.get(col + 1).is_some_and(Cell::is_continuation);
+                assert_eq!(lead, continued, "half a wide character at {},{}", col, row);
+            }
+        }
+    }
+
     /// Check if full redraw is needed
     pub fn needs_full_redraw(&self) -> bool {
         self.full_redraw_needed
@@ -217,9 +699,270 @@ impl Grid {
         &self.dirty_cells
     }
 
+    /// Request a repaint of the whole grid
+    pub fn mark_full_redraw(&mut self) {
+        self.full_redraw_needed = true;
+    }
+
     /// Clear dirty tracking (called after rendering)
     pub fn clear_dirty(&mut self) {
         self.dirty_cells.clear();
         self.full_redraw_needed = false;
     }
 }
+
+/// Builds the rows of rewrapped lines at a new width
+struct Rewrap {
+    cols: usize,
+    blank: Cell,
+    lines: VecDeque<ScrollbackLine>,
+    /// Row being filled
+    row: Vec<Cell>,
+    /// Where the cursor landed: (col, index into `lines`)
+    cursor: Option<(usize, usize)>,
+}
+
+impl Rewrap {
+    fn new(cols: usize, blank: Cell) -> Self {
+        Self { cols, blank, lines: VecDeque::new(), row: Vec::with_capacity(cols), cursor: None }
+    }
+
+    /// Finish the current row; `wrapped` when the line continues on the next one
+    fn end_row(&mut self, wrapped: bool) {
+        let mut row = std::mem::replace(&mut self.row, Vec::with_capacity(self.cols));
+        row.resize(self.cols, self.blank);
+        self.lines.push_back(ScrollbackLine { cells: row, wrapped });
+    }
+
+    /// Rewrap one logical line given as its old rows. `cursor_col` is set when the cursor
+    /// is on row `cursor_row` of this line.
+    fn push_line(&mut self, rows: &[(&[Cell], bool)], cursor_row: usize, cursor_col: Option<usize>) {
+        let last = rows.len() - 1;
+        for (index, &(cells, _)) in rows.iter().enumerate() {
+            let mut len = cells.len();
+            if index == last {
+                // Trailing blanks of the line, except where the cursor sits
+                len = cells.iter().rposition(|cell| *cell != self.blank).map_or(0, |col| col + 1);
+            } else if rows[index + 1].0.first().is_some_and(|cell| cell.width == 2)
+                && cells.last().is_some_and(|cell| *cell == self.blank)
+            {
+                // Padding left where a wide character wrapped early
+                len -= 1;
+            }
+            let cursor = cursor_col.filter(|_| index == cursor_row);
+            if let Some(col) = cursor {
+                len = len.max(col.min(cells.len()));
+            }
+
+            for (col, cell) in cells[..len].iter().enumerate() {
+                self.push_cell(*cell, cursor == Some(col));
+            }
+            if let Some(col) = cursor
+                && col >= len
+            {
+                // Past the end of the text: keep the cursor's distance from it
+                for _ in len..col {
+                    self.push_cell(self.blank, false);
+                }
+                self.cursor = Some((self.row.len(), self.lines.len()));
+            }
+        }
+        self.end_row(false);
+    }
+
+    /// Append a cell, starting a new row when it does not fit. `at_cursor` records the
+    /// cursor on it.
+    fn push_cell(&mut self, cell: Cell, at_cursor: bool) {
+        if cell.is_continuation() {
+            // Rebuilt together with the first half, which the cursor moves onto so that
+            // the next character replaces the whole wide character
+            if at_cursor {
+                let col = match self.row.last() {
+                    Some(last) if last.is_continuation() => self.row.len() - 2,
+                    _ => self.row.len().saturating_sub(1),
+                };
+                self.cursor = Some((col, self.lines.len()));
+            }
+            return;
+        }
+        // A wide character cannot be shown on a grid only one column wide
+        let cell = if cell.width == 2 && self.cols < 2 { Cell { width: 1, ..cell } } else { cell };
+        let width = cell.width.max(1) as usize;
+        if self.row.len() + width > self.cols {
+            self.end_row(true);
+        }
+        if at_cursor {
+            self.cursor = Some((self.row.len(), self.lines.len()));
+        }
+        self.row.push(cell);
+        if width == 2 {
+            self.row.push(Cell::continuation(&cell));
+        }
+    }
+
+    /// The rewrapped rows and the cursor position within them
+    fn finish(self) -> (VecDeque<ScrollbackLine>, Option<(usize, usize)>) {
+        (self.lines, self.cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_from(lines: &[&str]) -> Grid {
+        let cols = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let mut grid = Grid::new(cols, lines.len(), 100);
+        for (row, line) in lines.iter().enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                grid.set(col, row, Cell::new(ch));
+            }
+        }
+        grid
+    }
+
+    fn row_text(grid: &Grid, row: usize) -> String {
+        grid.get_row(row).unwrap().iter().map(|cell| cell.ch).collect()
+    }
+
+    #[test]
+    fn test_insert_lines_within_region() {
+        let mut grid = grid_from(&["aa", "bb", "cc", "dd"]);
+        grid.insert_lines(1, 1, 2);
+        assert_eq!(row_text(&grid, 0), "aa");
+        assert_eq!(row_text(&grid, 1), "  ");
+        assert_eq!(row_text(&grid, 2), "bb");
+        // Lines below the region bottom are untouched
+        assert_eq!(row_text(&grid, 3), "dd");
+
+        // Count larger than the remaining space blanks to the bottom
+        grid.insert_lines(2, 10, 3);
+        assert_eq!(row_text(&grid, 1), "  ");
+        assert_eq!(row_text(&grid, 2), "  ");
+        assert_eq!(row_text(&grid, 3), "  ");
+    }
+
+    #[test]
+    fn test_delete_lines_does_not_touch_scrollback() {
+        let mut grid = grid_from(&["aa", "bb", "cc", "dd"]);
+        grid.delete_lines(0, 1, 3);
+        assert_eq!(row_text(&grid, 0), "bb");
+        assert_eq!(row_text(&grid, 2), "dd");
+        assert_eq!(row_text(&grid, 3), "  ");
+        assert_eq!(grid.scrollback_len(), 0);
+
+        // Deleting at the last row of the region and past the end
+        grid.delete_lines(2, 10, 3);
+        assert_eq!(row_text(&grid, 1), "cc");
+        assert_eq!(row_text(&grid, 2), "  ");
+        grid.delete_lines(3, 1, 3);
+        assert_eq!(row_text(&grid, 3), "  ");
+    }
+
+    #[test]
+    fn test_insert_and_delete_chars() {
+        let mut grid = grid_from(&["abcde"]);
+        grid.insert_chars(1, 0, 2);
+        assert_eq!(row_text(&grid, 0), "a  bc");
+        grid.delete_chars(1, 0, 2);
+        assert_eq!(row_text(&grid, 0), "abc  ");
+
+        // Counts past the end of the line clear to the edge
+        grid.insert_chars(4, 0, 10);
+        assert_eq!(row_text(&grid, 0), "abc  ");
+        grid.delete_chars(1, 0, 99);
+        assert_eq!(row_text(&grid, 0), "a    ");
+
+        // Out-of-range positions are ignored
+        grid.insert_chars(5, 0, 1);
+        grid.delete_chars(0, 1, 1);
+        assert_eq!(row_text(&grid, 0), "a    ");
+    }
+
+    #[test]
+    fn test_erase_chars() {
+        let mut grid = grid_from(&["abcde"]);
+        grid.erase_chars(1, 0, 2);
+        assert_eq!(row_text(&grid, 0), "a  de");
+        grid.erase_chars(3, 0, 100);
+        assert_eq!(row_text(&grid, 0), "a    ");
+    }
+
+    #[test]
+    fn test_scroll_up_by_screen_height_or_more() {
+        let mut grid = grid_from(&["aa", "bb", "cc"]);
+        grid.scroll_up(999);
+        assert_eq!(grid.scrollback_len(), 3);
+        assert_eq!(grid.scrolled_lines(), 3);
+        assert!((0..3).all(|row| row_text(&grid, row) == "  "));
+
+        grid.set(0, 0, Cell::new('x'));
+        grid.scroll_down(3);
+        assert_eq!(row_text(&grid, 0), "  ");
+    }
+
+    #[test]
+    fn test_scroll_view_through_scrollback() {
+        let mut grid = grid_from(&["aa", "bb", "cc"]);
+        grid.scroll_up(1);
+        grid.scroll_up(1);
+        grid.set(0, 1, Cell::new('d'));
+        assert_eq!(grid.scrollback_len(), 2);
+
+        grid.scroll_view_up(1);
+        assert_eq!(grid.scroll_offset(), 1);
+        assert_eq!(grid.view_row(0).unwrap().iter().map(|cell| cell.ch).collect::<String>(), "bb");
+        assert_eq!(grid.view_row(1).unwrap().iter().map(|cell| cell.ch).collect::<String>(), "cc");
+        assert!(grid.view_row(3).is_none());
+
+        // Scrolling past the oldest line is clamped
+        grid.scroll_view_up(10);
+        assert_eq!(grid.scroll_offset(), 2);
+        assert_eq!(grid.view_row(0).unwrap()[0].ch, 'a');
+        assert_eq!(grid.view_row(2).unwrap()[0].ch, 'c');
+
+        grid.scroll_view_down(1);
+        assert_eq!(grid.scroll_offset(), 1);
+        grid.reset_view();
+        assert_eq!(grid.scroll_offset(), 0);
+        assert_eq!(grid.view_row(1).unwrap()[0].ch, 'd');
+        grid.scroll_view_down(1);
+        assert_eq!(grid.scroll_offset(), 0);
+    }
+
+    #[test]
+    fn test_reflow_rewraps_wrapped_lines() {
+        let mut grid = grid_from(&["abcd", "ef", "gh", "  "]);
+        grid.set_wrapped(0, true);
+
+        // Narrower: "abcdef" takes two full rows, "gh" stays its own line.
+        // The cursor after "ef" waits at the right edge for the next character.
+        let cursor = grid.reflow(3, 4, (2, 1));
+        assert_eq!(cursor, (3, 1));
+        assert_eq!((0..4).map(|row| row_text(&grid, row)).collect::<Vec<_>>(), ["abc", "def", "gh ", "   "]);
+        assert!(grid.is_wrapped(0) && !grid.is_wrapped(1));
+
+        // Wider again: the rows join back into one
+        let cursor = grid.reflow(8, 4, cursor);
+        assert_eq!(cursor, (6, 0));
+        assert_eq!(row_text(&grid, 0), "abcdef  ");
+        assert_eq!(row_text(&grid, 1), "gh      ");
+        assert_eq!(grid.scrollback_len(), 0);
+    }
+
+    #[test]
+    fn test_reflow_keeps_wide_characters_whole() {
+        let mut grid = Grid::new(3, 2, 10);
+        let wide = Cell { width: 2, ..Cell::new('漢') };
+        grid.set(0, 0, Cell::new('a'));
+        grid.set(1, 0, wide);
+        grid.set(2, 0, Cell::continuation(&wide));
+
+        // Only the first column fits next to 'a' on a two-column row
+        assert_eq!(grid.reflow(2, 3, (0, 1)), (0, 2));
+        assert_eq!(row_text(&grid, 0), "a ");
+        assert_eq!(grid.get(0, 1).unwrap().ch, '漢');
+        assert!(grid.get(1, 1).unwrap().is_continuation());
+        assert!(grid.is_wrapped(0));
+    }
+}
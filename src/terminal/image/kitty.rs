@@ -1,6 +1,61 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
-use image::{DynamicImage, ImageFormat};
-use std::io::Cursor;
+use image::DynamicImage;
+
+/// Largest image accepted from a chunked transfer (decoded bytes)
+const MAX_IMAGE_DATA: usize = 64 * 1024 * 1024;
+
+/// An image received with the Kitty graphics protocol
+#[derive(Debug, Clone)]
+pub struct KittyImage {
+    pub image: DynamicImage,
+    /// Columns and rows the sender wants the image shown in (`c` / `r`)
+    pub cols: Option<usize>,
+    pub rows: Option<usize>,
+}
+
+/// Control data of a graphics command: the `key=value` pairs before the `;`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Controls {
+    /// `a`: t (transmit), T (transmit and display), q (query), ...
+    action: char,
+    /// `f`: 24 (raw RGB), 32 (raw RGBA) or 100 (PNG)
+    format: u32,
+    /// `s` / `v`: Size of raw pixel data
+    width: Option<u32>,
+    height: Option<u32>,
+    /// `c` / `r`: Size to show the image in, in cells
+    cols: Option<usize>,
+    rows: Option<usize>,
+    /// `m=1`: More chunks of the payload follow
+    more: bool,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self { action: 't', format: 32, width: None, height: None, cols: None, rows: None, more: false }
+    }
+}
+
+impl Controls {
+    /// Parse `key=value,...`; unknown keys and malformed values are ignored
+    fn parse(control: &str) -> Self {
+        let mut controls = Self::default();
+        for (key, value) in control.split(',').filter_map(|pair| pair.split_once('=')) {
+            let number = value.parse::<u32>().ok();
+            match key {
+                "a" => controls.action = value.chars().next().unwrap_or(controls.action),
+                "f" => controls.format = number.unwrap_or(controls.format),
+                "s" => controls.width = number,
+                "v" => controls.height = number,
+                "c" => controls.cols = number.filter(|&cols| cols > 0).map(|cols| cols as usize),
+                "r" => controls.rows = number.filter(|&rows| rows > 0).map(|rows| rows as usize),
+                "m" => controls.more = number == Some(1),
+                _ => {}
+            }
+        }
+        controls
+    }
+}
 
 /// Parser for Kitty graphics protocol
 /// Format: ESC _G<control data>;<payload>ESC \
@@ -8,6 +63,8 @@ use std::io::Cursor;
 pub struct KittyImageParser {
     buffer: Vec<u8>,
     in_sequence: bool,
+    /// Control data and payload received so far of a chunked transfer (m=1)
+    pending: Option<(Controls, Vec<u8>)>,
 }
 
 impl KittyImageParser {
@@ -15,11 +72,12 @@ impl KittyImageParser {
         Self {
             buffer: Vec::new(),
             in_sequence: false,
+            pending: None,
         }
     }
 
     /// Process a byte, returns Some(image) if complete image sequence was parsed
-    pub fn process_byte(&mut self, byte: u8) -> Option<DynamicImage> {
+    pub fn process_byte(&mut self, byte: u8) -> Option<KittyImage> {
         // Kitty graphics protocol: ESC _G ... ESC \
         // We detect ESC _G to start, ESC \ to end
 
@@ -70,53 +128,68 @@ impl KittyImageParser {
         None
     }
 
-    fn parse_sequence(&self) -> Option<DynamicImage> {
+    fn parse_sequence(&mut self) -> Option<KittyImage> {
         // Format: <control data>;<payload>
-        // Control data: key=value,key=value,...
-        // We mainly care about the payload (base64 encoded image)
-
-        let seq_str = String::from_utf8_lossy(&self.buffer);
-
-        // Split by semicolon
-        let parts: Vec<&str> = seq_str.splitn(2, ';').collect();
-        if parts.len() < 2 {
-            log::warn!("Invalid Kitty image format: no payload separator");
-            return None;
-        }
-
-        let _control_data = parts[0];
-        let payload = parts[1];
+        let (control, payload) = match self.buffer.iter().position(|&byte| byte == b';') {
+            Some(split) => (&self.buffer[..split], &self.buffer[split + 1..]),
+            None => (&self.buffer[..], &[][..]),
+        };
+        let controls = Controls::parse(&String::from_utf8_lossy(control));
 
-        // Decode base64
-        let image_data = match STANDARD.decode(payload.trim()) {
+        let data = match STANDARD.decode(payload.trim_ascii()) {
             Ok(data) => data,
             Err(e) => {
                 log::warn!("Failed to decode Kitty image base64: {}", e);
+                self.pending = None;
                 return None;
             }
         };
 
-        // Try to load as PNG first (most common), then other formats
-        match image::load_from_memory(&image_data) {
-            Ok(img) => {
-                log::info!(
-                    "Successfully loaded Kitty image: {}x{}",
-                    img.width(),
-                    img.height()
-                );
-                Some(img)
+        // Later chunks of a chunked transfer (m=1) only carry `m`; the first one has the other keys
+        let (controls, data) = match self.pending.take() {
+            Some((first, mut received)) => {
+                received.extend_from_slice(&data);
+                (Controls { more: controls.more, ..first }, received)
             }
-            Err(e) => {
-                log::warn!("Failed to load Kitty image: {}", e);
-                None
+            None => (controls, data),
+        };
+        if controls.more {
+            if data.len() > MAX_IMAGE_DATA {
+                log::warn!("Kitty image larger than {} bytes, dropping it", MAX_IMAGE_DATA);
+            } else {
+                self.pending = Some((controls, data));
             }
+            return None;
         }
+
+        // Queries (a=q) only ask whether images are supported
+        if controls.action == 'q' {
+            return None;
+        }
+
+        let image = match (controls.format, controls.width, controls.height) {
+            (24, Some(width), Some(height)) => {
+                image::RgbImage::from_raw(width, height, data).map(DynamicImage::ImageRgb8)
+            }
+            (32, Some(width), Some(height)) => {
+                image::RgbaImage::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+            }
+            // PNG (f=100), or raw data without its size, which may still be an encoded image
+            _ => image::load_from_memory(&data).inspect_err(|e| log::warn!("Failed to load Kitty image: {}", e)).ok(),
+        };
+        let Some(image) = image else {
+            log::warn!("Kitty image data does not match f={} s={:?} v={:?}", controls.format, controls.width, controls.height);
+            return None;
+        };
+        log::info!("Successfully loaded Kitty image: {}x{}", image.width(), image.height());
+        Some(KittyImage { image, cols: controls.cols, rows: controls.rows })
     }
 
     /// Reset parser state
     pub fn reset(&mut self) {
         self.buffer.clear();
         self.in_sequence = false;
+        self.pending = None;
     }
 }
 
@@ -125,3 +198,62 @@ impl Default for KittyImageParser {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(parser: &mut KittyImageParser, control: &str, data: &[u8]) -> Option<KittyImage> {
+        let sequence = format!("\x1b_G{};{}\x1b\\", control, STANDARD.encode(data));
+        sequence.bytes().fold(None, |image, byte| parser.process_byte(byte).or(image))
+    }
+
+    #[test]
+    fn test_parse_controls() {
+        let controls = Controls::parse("a=T,f=24,s=3,v=2,c=10,r=5,m=1,i=7,x");
+        assert_eq!(controls, Controls {
+            action: 'T',
+            format: 24,
+            width: Some(3),
+            height: Some(2),
+            cols: Some(10),
+            rows: Some(5),
+            more: true,
+        });
+        assert_eq!(Controls::parse(""), Controls::default());
+        assert_eq!(Controls::parse("s=abc,c=0").width, None);
+    }
+
+    #[test]
+    fn test_raw_pixels_with_cell_size() {
+        let mut parser = KittyImageParser::new();
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let image = feed(&mut parser, "a=T,f=24,s=2,v=2,c=4,r=1", &rgb).unwrap();
+        assert_eq!((image.cols, image.rows), (Some(4), Some(1)));
+        let pixels = image.image.to_rgba8();
+        assert_eq!(pixels.dimensions(), (2, 2));
+        assert_eq!(pixels.get_pixel(1, 0).0, [0, 255, 0, 255]);
+        assert_eq!(pixels.get_pixel(1, 1).0, [255, 255, 255, 255]);
+
+        // RGBA, and data that does not fill the given size
+        let image = feed(&mut parser, "f=32,s=1,v=1", &[1, 2, 3, 4]).unwrap();
+        assert_eq!(image.image.to_rgba8().get_pixel(0, 0).0, [1, 2, 3, 4]);
+        assert!(feed(&mut parser, "f=32,s=2,v=2", &[1, 2, 3, 4]).is_none());
+    }
+
+    #[test]
+    fn test_chunked_transfer() {
+        let mut parser = KittyImageParser::new();
+        let rgba: Vec<u8> = (0..16).collect();
+
+        // Only the first chunk has the keys; the image is complete after m=0
+        assert!(feed(&mut parser, "a=T,f=32,s=2,v=2,c=3,m=1", &rgba[..6]).is_none());
+        assert!(feed(&mut parser, "m=1", &rgba[6..12]).is_none());
+        let image = feed(&mut parser, "m=0", &rgba[12..]).unwrap();
+        assert_eq!(image.cols, Some(3));
+        assert_eq!(image.image.to_rgba8().into_raw(), rgba);
+
+        // Queries are not shown
+        assert!(feed(&mut parser, "a=q,f=24,s=1,v=1", &[0, 0, 0]).is_none());
+    }
+}
@@ -1,4 +1,11 @@
-use image::{DynamicImage, RgbaImage};
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::iter::Peekable;
+
+/// Number of color registers
+const PALETTE_SIZE: usize = 256;
+
+/// Largest image width or height decoded; pixels beyond it are dropped
+const MAX_SIXEL_SIZE: usize = 4096;
 
 /// Parser for Sixel graphics protocol
 /// Format: ESC P q ... ESC \
@@ -23,9 +30,11 @@ impl SixelImageParser {
         // 'q' indicates sixel mode
 
         if !self.in_sequence {
-            // Look for start sequence: ESC P q
+            // Look for start sequence: ESC P [params] q
             if byte == b'q' {
-                let len = self.buffer.len();
+                // The introducer may carry parameters (aspect ratio, background), e.g. ESC P 0;1;0 q
+                let params = self.buffer.iter().rev().take_while(|byte| byte.is_ascii_digit() || **byte == b';').count();
+                let len = self.buffer.len() - params;
                 if len >= 2 && self.buffer[len - 1] == b'P' && self.buffer[len - 2] == 0x1b {
                     // Found start of sixel
                     self.in_sequence = true;
@@ -71,136 +80,58 @@ impl SixelImageParser {
     }
 
     fn parse_sequence(&self) -> Option<DynamicImage> {
-        // Simplified sixel parser
-        // Full implementation would be quite complex
-        // For now, we'll create a placeholder
-
-        let seq_str = String::from_utf8_lossy(&self.buffer);
-
-        log::debug!("Parsing sixel sequence, length: {}", seq_str.len());
-
-        // Parse sixel data (simplified)
-        // Sixel format:
-        // - "#<color>;<mode>;<r>;<g>;<b>" - define color
-        // - "<data>" - sixel data (6 vertical pixels per byte)
-        // - "$" - carriage return
-        // - "-" - newline
-
-        let mut width = 0;
-        let mut height = 0;
-        let mut x = 0;
-        let mut y = 0;
-
-        // Color palette (256 colors max for simplicity)
-        let mut palette: Vec<[u8; 3]> = vec![[0, 0, 0]; 256];
-        // Initialize with default VT340 palette
-        for i in 0..16 {
-            palette[i] = Self::default_color(i);
-        }
+        log::debug!("Parsing sixel sequence, length: {}", self.buffer.len());
 
-        let mut current_color = 0;
-
-        // Parse character by character
-        let chars: Vec<char> = seq_str.chars().collect();
-        let mut i = 0;
-
-        while i < chars.len() {
-            let c = chars[i];
-
-            match c {
-                '#' => {
-                    // Color definition: #<Pc>;<Pu>;<Px>;<Py>;<Pz>
-                    i += 1;
-                    let mut params = Vec::new();
-                    let mut num_str = String::new();
-
-                    while i < chars.len() {
-                        let ch = chars[i];
-                        if ch.is_ascii_digit() {
-                            num_str.push(ch);
-                        } else if ch == ';' {
-                            if let Ok(n) = num_str.parse::<u8>() {
-                                params.push(n);
-                            }
-                            num_str.clear();
-                        } else {
-                            if !num_str.is_empty() {
-                                if let Ok(n) = num_str.parse::<u8>() {
-                                    params.push(n);
-                                }
-                            }
-                            i -= 1; // Back up to process this char in main loop
-                            break;
-                        }
-                        i += 1;
+        let mut decoder = SixelDecoder::new();
+        let mut bytes = self.buffer.iter().copied().peekable();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                b'"' => {
+                    // Raster attributes: "Pan;Pad;Ph;Pv (aspect ratio, then the image size)
+                    let params = read_params(&mut bytes);
+                    if let [_, _, width, height, ..] = params[..] {
+                        decoder.raster_size = (width as usize, height as usize);
                     }
-
-                    // Apply color definition
-                    if params.len() >= 5 {
-                        let color_idx = params[0] as usize;
-                        // params[1] is color coordination system (2=RGB)
-                        let r = (params[2] as f32 / 100.0 * 255.0) as u8;
-                        let g = (params[3] as f32 / 100.0 * 255.0) as u8;
-                        let b = (params[4] as f32 / 100.0 * 255.0) as u8;
-                        if color_idx < palette.len() {
-                            palette[color_idx] = [r, g, b];
-                        }
-                        current_color = color_idx;
-                    } else if !params.is_empty() {
-                        // Just color selection
-                        current_color = params[0] as usize;
-                    }
-                }
-                '$' => {
-                    // Carriage return
-                    x = 0;
-                }
-                '-' => {
-                    // Newline
-                    x = 0;
-                    y += 6; // Sixel row is 6 pixels high
                 }
-                '?' | '@'..='~' => {
-                    // Sixel data byte
-                    // Each byte represents 6 vertical pixels
-                    x += 1;
-                    if x > width {
-                        width = x;
+                b'#' => {
+                    // Color: #Pc selects a register, #Pc;Pu;Px;Py;Pz also defines it
+                    // (Pu 1 = HLS, 2 = RGB, in degrees and percent)
+                    let params = read_params(&mut bytes);
+                    if let Some(&register) = params.first() {
+                        decoder.color = register as usize % PALETTE_SIZE;
                     }
-                    if y + 6 > height {
-                        height = y + 6;
+                    match params[..] {
+                        [_, 1, h, l, s, ..] => decoder.palette[decoder.color] = hls_to_rgb(h, l, s),
+                        [_, 2, r, g, b, ..] => decoder.palette[decoder.color] = [percent(r), percent(g), percent(b)],
+                        _ => {}
                     }
                 }
-                _ => {
-                    // Ignore other characters
+                b'!' => {
+                    // Repeat the following sixel Pn times
+                    let count = read_params(&mut bytes).first().copied().unwrap_or(1).max(1);
+                    if let Some(sixel) = bytes.next_if(|byte| (0x3f..=0x7e).contains(byte)) {
+                        decoder.draw(sixel - 0x3f, count as usize);
+                    }
+                }
+                b'$' => decoder.x = 0,
+                b'-' => {
+                    decoder.x = 0;
+                    decoder.y += 6;
                 }
+                0x3f..=0x7e => decoder.draw(byte - 0x3f, 1),
+                _ => {}
             }
-
-            i += 1;
         }
 
-        // For now, create a placeholder image
-        // Full sixel rendering would require pixel-by-pixel rendering
-        if width > 0 && height > 0 {
-            log::info!("Sixel image parsed: estimated {}x{}", width, height);
-
-            // Create a simple placeholder image
-            let img = RgbaImage::from_fn(width.min(800) as u32, height.min(600) as u32, |x, y| {
-                // Simple gradient pattern as placeholder
-                let r = ((x % 256) as u8).wrapping_add((y % 256) as u8);
-                let g = ((y % 256) as u8);
-                let b = ((x % 128) as u8).wrapping_mul(2);
-                image::Rgba([r, g, b, 255])
-            });
-
-            Some(DynamicImage::ImageRgba8(img))
-        } else {
-            log::warn!("Failed to parse sixel: invalid dimensions");
-            None
+        let image = decoder.finish();
+        match &image {
+            Some(image) => log::info!("Sixel image decoded: {}x{}", image.width(), image.height()),
+            None => log::warn!("Failed to parse sixel: no image data"),
         }
+        image
     }
 
-    fn default_color(index: usize) -> [u8; 3] {
+    pub(crate) fn default_color(index: usize) -> [u8; 3] {
         // Default VT340 color palette
         match index {
             0 => [0, 0, 0],       // Black
@@ -235,3 +166,167 @@ impl Default for SixelImageParser {
         Self::new()
     }
 }
+
+/// Pixels drawn so far by a sixel sequence
+struct SixelDecoder {
+    palette: [[u8; 3]; PALETTE_SIZE],
+    /// Selected color register
+    color: usize,
+    /// Position of the next sixel: column and top row of the current band
+    x: usize,
+    y: usize,
+    /// Size announced by the raster attributes, (0, 0) without them
+    raster_size: (usize, usize),
+    /// Right and bottom edges of the sixels drawn (also blank ones)
+    extent: (usize, usize),
+    /// Drawn pixels, row by row; each row is only as long as needed
+    pixels: Vec<Vec<Option<[u8; 3]>>>,
+}
+
+impl SixelDecoder {
+    fn new() -> Self {
+        Self {
+            palette: std::array::from_fn(SixelImageParser::default_color),
+            color: 0,
+            x: 0,
+            y: 0,
+            raster_size: (0, 0),
+            extent: (0, 0),
+            pixels: Vec::new(),
+        }
+    }
+
+    /// Draw `count` copies of a sixel (bit 0 is the top pixel) and advance past them
+    fn draw(&mut self, bits: u8, count: usize) {
+        let start = self.x.min(MAX_SIXEL_SIZE);
+        let end = self.x.saturating_add(count).min(MAX_SIXEL_SIZE);
+        self.x = self.x.saturating_add(count);
+        if start == end || self.y >= MAX_SIXEL_SIZE {
+            return;
+        }
+        self.extent = (self.extent.0.max(end), self.extent.1.max((self.y + 6).min(MAX_SIXEL_SIZE)));
+
+        let color = self.palette[self.color];
+        for bit in 0..6 {
+            let y = self.y + bit;
+            if bits & (1 << bit) == 0 || y >= MAX_SIXEL_SIZE {
+                continue;
+            }
+            if self.pixels.len() <= y {
+                self.pixels.resize_with(y + 1, Vec::new);
+            }
+            let row = &mut self.pixels[y];
+            if row.len() < end {
+                row.resize(end, None);
+            }
+            row[start..end].fill(Some(color));
+        }
+    }
+
+    /// The decoded image; pixels no sixel set are transparent
+    fn finish(self) -> Option<DynamicImage> {
+        let width = self.raster_size.0.max(self.extent.0).min(MAX_SIXEL_SIZE);
+        let height = self.raster_size.1.max(self.extent.1).min(MAX_SIXEL_SIZE);
+        if width == 0 || height == 0 || self.extent == (0, 0) {
+            return None;
+        }
+
+        let image = RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+            let pixel = self.pixels.get(y as usize).and_then(|row| row.get(x as usize)).copied().flatten();
+            match pixel {
+                Some([r, g, b]) => Rgba([r, g, b, 255]),
+                None => Rgba([0, 0, 0, 0]),
+            }
+        });
+        Some(DynamicImage::ImageRgba8(image))
+    }
+}
+
+/// Read `;`-separated numbers (missing ones are 0) up to the next other byte
+fn read_params(bytes: &mut Peekable<impl Iterator<Item = u8>>) -> Vec<u32> {
+    let mut params = vec![0u32];
+    while let Some(byte) = bytes.next_if(|byte| byte.is_ascii_digit() || *byte == b';') {
+        let last = params.last_mut().unwrap();
+        match byte {
+            b';' => params.push(0),
+            digit => *last = last.saturating_mul(10).saturating_add((digit - b'0') as u32),
+        }
+    }
+    params
+}
+
+/// Color component given in percent
+fn percent(value: u32) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+/// Convert a sixel HLS color (hue in degrees with blue at 0, lightness and saturation in percent)
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 3] {
+    // DEC hues are rotated against the usual HSL ones, where red is at 0
+    let hue = ((hue + 240) % 360) as f32;
+    let lightness = lightness.min(100) as f32 / 100.0;
+    let saturation = saturation.min(100) as f32 / 100.0;
+
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let second = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, second, 0.0),
+        1 => (second, chroma, 0.0),
+        2 => (0.0, chroma, second),
+        3 => (0.0, second, chroma),
+        4 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let base = lightness - chroma / 2.0;
+    let channel = |value: f32| ((value + base) * 255.0).round() as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> RgbaImage {
+        let mut parser = SixelImageParser::new();
+        let image = [b"\x1bPq".as_slice(), data, b"\x1b\\"]
+            .concat()
+            .into_iter()
+            .find_map(|byte| parser.process_byte(byte))
+            .expect("no image decoded");
+        image.to_rgba8()
+    }
+
+    #[test]
+    fn test_decode_sixel_pixels() {
+        const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+        const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+        const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+        // Two full red columns, then (after $) two full blue ones beside them,
+        // then (after -) the top pixel of the next band in red three times
+        let image = decode(b"#1;2;100;0;0#2;2;0;0;100#1~~$#2??~~-#1!3@");
+        assert_eq!(image.dimensions(), (4, 12));
+        assert_eq!(*image.get_pixel(0, 0), RED);
+        assert_eq!(*image.get_pixel(1, 5), RED);
+        assert_eq!(*image.get_pixel(2, 0), BLUE);
+        assert_eq!(*image.get_pixel(3, 5), BLUE);
+        assert_eq!(*image.get_pixel(0, 6), RED);
+        assert_eq!(*image.get_pixel(2, 6), RED);
+        assert_eq!(*image.get_pixel(3, 6), TRANSPARENT);
+        assert_eq!(*image.get_pixel(0, 7), TRANSPARENT);
+    }
+
+    #[test]
+    fn test_raster_attributes_and_hls() {
+        // The raster attributes set the size even where nothing is drawn
+        let image = decode(b"\"1;1;8;10#3;1;120;50;100A");
+        assert_eq!(image.dimensions(), (8, 10));
+        assert_eq!(*image.get_pixel(0, 1), Rgba([255, 0, 0, 255])); // 'A' sets the second pixel
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+
+        // Default registers and an introducer with parameters
+        let mut parser = SixelImageParser::new();
+        let image = b"\x1bP0;1;0q#2~\x1b\\".iter().find_map(|&byte| parser.process_byte(byte)).unwrap();
+        assert_eq!(*image.to_rgba8().get_pixel(0, 0), Rgba([204, 51, 51, 255]));
+    }
+}
@@ -1,9 +1,14 @@
+pub mod charset;
 pub mod grid;
 pub mod emulator;
 pub mod image;
+pub mod palette;
 pub mod pty;
 
+#[cfg(test)]
+mod fuzz_tests;
+
 pub use grid::Grid;
-pub use emulator::TerminalEmulator;
+pub use emulator::{TerminalEmulator, TerminalEvent};
 pub use image::{TerminalImage, KittyImageParser, SixelImageParser};
 pub use pty::PtyController;
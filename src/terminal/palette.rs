@@ -0,0 +1,119 @@
+use crate::renderer::backend::{Color, CURSOR_COLOR};
+
+/// Colors an application can redefine at runtime (OSC 4 / OSC 10 / OSC 11), and the
+/// configured cursor color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// The 256 indexed colors
+    colors: [Color; 256],
+    /// Default foreground color
+    pub foreground: Color,
+    /// Default background color
+    pub background: Color,
+    /// Color of the cursor
+    pub cursor: Color,
+}
+
+impl Palette {
+    /// Built-in indexed colors with the given default colors
+    pub fn new(foreground: Color, background: Color) -> Self {
+        Self {
+            colors: std::array::from_fn(|index| Color::from_ansi_256(index as u8)),
+            foreground,
+            background,
+            cursor: CURSOR_COLOR,
+        }
+    }
+
+    /// Color for an indexed (SGR 30-37, 90-97, 38;5;n ...) color
+    pub fn get(&self, index: u8) -> Color {
+        self.colors[index as usize]
+    }
+
+    pub fn set(&mut self, index: u8, color: Color) {
+        self.colors[index as usize] = color;
+    }
+
+    /// Indexed colors that differ from the built-in palette
+    pub fn changed_colors(&self) -> impl Iterator<Item = (u8, Color)> + '_ {
+        (0..=255u8)
+            .map(|index| (index, self.get(index)))
+            .filter(|&(index, color)| color != Color::from_ansi_256(index))
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new(Color::WHITE, Color::BLACK)
+    }
+}
+
+/// Parse an X11 color specification as used by OSC 4/10/11.
+///
+/// Supports `rgb:R/G/B` with 1-4 hex digits per component (scaled to 8 bits)
+/// and `#RRGGBB`.
+pub fn parse_color_spec(spec: &[u8]) -> Option<Color> {
+    let spec = std::str::from_utf8(spec).ok()?;
+
+    if let Some(rgb) = spec.strip_prefix("rgb:") {
+        let mut components = rgb.split('/').map(|component| {
+            if component.is_empty() || component.len() > 4 {
+                return None;
+            }
+            let value = u32::from_str_radix(component, 16).ok()?;
+            let max = (1u32 << (4 * component.len())) - 1;
+            Some((value * 255 / max) as u8)
+        });
+        let r = components.next()??;
+        let g = components.next()??;
+        let b = components.next()??;
+        if components.next().is_some() {
+            return None;
+        }
+        return Some(Color::rgb(r, g, b));
+    }
+
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        return Some(Color::rgb((value >> 16) as u8, (value >> 8) as u8, value as u8));
+    }
+
+    None
+}
+
+/// Format a color as `#rrggbb`
+pub fn to_hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_spec() {
+        assert_eq!(parse_color_spec(b"rgb:ff/80/00"), Some(Color::rgb(255, 128, 0)));
+        assert_eq!(parse_color_spec(b"rgb:ffff/0000/8080"), Some(Color::rgb(255, 0, 128)));
+        assert_eq!(parse_color_spec(b"rgb:f/0/8"), Some(Color::rgb(255, 0, 136)));
+        assert_eq!(parse_color_spec(b"#1d2021"), Some(Color::rgb(0x1d, 0x20, 0x21)));
+
+        assert_eq!(parse_color_spec(b"?"), None);
+        assert_eq!(parse_color_spec(b"rgb:ff/80"), None);
+        assert_eq!(parse_color_spec(b"rgb:ff/80/00/00"), None);
+        assert_eq!(parse_color_spec(b"#12345"), None);
+        assert_eq!(parse_color_spec(b"red"), None);
+    }
+
+    #[test]
+    fn test_changed_colors() {
+        let mut palette = Palette::default();
+        assert_eq!(palette.changed_colors().count(), 0);
+
+        palette.set(1, Color::rgb(0xcc, 0x24, 0x1d));
+        assert_eq!(palette.changed_colors().collect::<Vec<_>>(), vec![(1, Color::rgb(0xcc, 0x24, 0x1d))]);
+        assert_eq!(to_hex(palette.get(1)), "#cc241d");
+    }
+}
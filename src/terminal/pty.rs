@@ -1,20 +1,117 @@
 use crate::utils::{Result, TerbulatorError};
-use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use portable_pty::{native_pty_system, Child, CommandBuilder, ExitStatus, MasterPty, PtySize};
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
-use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError, TrySendError};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// Maximum number of 4KB chunks buffered between the reader thread and the main loop (~1MB)
+const OUTPUT_CHANNEL_CAPACITY: usize = 256;
+
+/// How long the reader thread waits for the main loop to drain a full channel
+/// before dropping the chunk.
+///
+/// While the channel is full the reader stops reading, so the kernel PTY buffer fills
+/// up and the child process blocks on write (lossless backpressure). Only if the UI
+/// stays stalled past this timeout is output dropped, which bounds memory under an
+/// extreme flood at the cost of losing (and possibly garbling) some output.
+const OUTPUT_BACKPRESSURE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The shell a PTY runs and the environment it starts in
+#[derive(Debug, Clone, Default)]
+pub struct ShellCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// TERM value (COLORTERM is always "truecolor")
+    pub term: String,
+    /// Starting directory; None for portable-pty's default (the home directory)
+    pub working_directory: Option<PathBuf>,
+    /// Extra environment variables, applied after TERM and COLORTERM
+    pub env: BTreeMap<String, String>,
+}
+
+/// Command that runs the shell with the environment terminal applications look at
+fn shell_command(shell: &ShellCommand) -> CommandBuilder {
+    let mut cmd = CommandBuilder::new(&shell.program);
+    cmd.args(&shell.args);
+    cmd.env("TERM", &shell.term);
+    // 24-bit SGR colors (38;2 / 48;2) are supported
+    cmd.env("COLORTERM", "truecolor");
+    for (name, value) in &shell.env {
+        cmd.env(name, value);
+    }
+    match &shell.working_directory {
+        Some(dir) if dir.is_dir() => cmd.cwd(dir),
+        Some(dir) => log::warn!("Working directory {} does not exist, starting in the default one", dir.display()),
+        None => {}
+    }
+    cmd
+}
+
+/// Called from the reader thread whenever output arrives or the process closes the PTY,
+/// so an idle event loop can wake up for it
+pub type OutputWaker = Arc<dyn Fn() + Send + Sync>;
+
+/// Wakeups from the reader thread to the main loop, coalesced so a flood of output
+/// sends one wakeup per drain of the channel rather than one per chunk
+#[derive(Default)]
+struct WakeSignal {
+    /// Set by `set_output_waker`; the reader thread may start before it is
+    waker: Mutex<Option<OutputWaker>>,
+    /// A wakeup was sent and the main loop has not read since
+    pending: AtomicBool,
+}
+
+impl WakeSignal {
+    /// Call the waker unless a wakeup is already pending
+    fn notify(&self) {
+        if let Some(waker) = self.waker.lock().unwrap().as_ref()
+            && !self.pending.swap(true, Ordering::AcqRel)
+        {
+            waker();
+        }
+    }
+
+    /// The main loop is reading; output arriving from now on needs a new wakeup
+    fn acknowledge(&self) {
+        self.pending.store(false, Ordering::Release);
+    }
+}
 
 pub struct PtyController {
     master: Box<dyn MasterPty + Send>,
     child: Box<dyn Child + Send>,
     rx: Receiver<Vec<u8>>,
+    /// Chunk received by `has_pending_output`, returned by the next `read`.
+    /// `Some(empty)` records that the channel was disconnected.
+    peeked: Option<Vec<u8>>,
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    dropped_bytes: Arc<AtomicUsize>,
+    exit_status: Option<ExitStatus>,
+    wake_signal: Arc<WakeSignal>,
+}
+
+impl ShellCommand {
+    /// The same environment running `argv` (program first) instead
+    pub fn with_argv(&self, argv: &[String]) -> Self {
+        let (program, args) = match argv.split_first() {
+            Some((program, args)) => (program.clone(), args.to_vec()),
+            None => (String::new(), Vec::new()),
+        };
+        Self { program, args, ..self.clone() }
+    }
 }
 
 impl PtyController {
-    pub fn new(cols: u16, rows: u16, shell: &str) -> Result<Self> {
-        log::info!("PtyController::new() called with cols={}, rows={}, shell={}", cols, rows, shell);
+    pub fn new(cols: u16, rows: u16, shell: &ShellCommand) -> Result<Self> {
+        log::info!(
+            "PtyController::new() called with cols={}, rows={}, shell={}, TERM={}, cwd={:?}",
+            cols, rows, shell.program, shell.term, shell.working_directory
+        );
         let pty_system = native_pty_system();
 
         let pty_size = PtySize {
@@ -29,14 +126,11 @@ impl PtyController {
             .openpty(pty_size)
             .map_err(|e| TerbulatorError::pty(format!("Failed to open PTY: {}", e)))?;
 
-        log::debug!("Spawning shell: {}", shell);
-        let mut cmd = CommandBuilder::new(&shell);
-        cmd.env("TERM", "xterm-256color");
-
+        log::debug!("Spawning shell: {}", shell.program);
         let child = pair
             .slave
-            .spawn_command(cmd)
-            .map_err(|e| TerbulatorError::pty(format!("Failed to spawn shell '{}': {}", shell, e)))?;
+            .spawn_command(shell_command(shell))
+            .map_err(|e| TerbulatorError::pty(format!("Failed to spawn shell '{}': {}", shell.program, e)))?;
 
         log::debug!("Shell spawned successfully, PID: {:?}", child.process_id());
 
@@ -50,50 +144,58 @@ impl PtyController {
             .take_writer()
             .map_err(|e| TerbulatorError::pty(format!("Failed to take writer: {}", e)))?;
 
-        // Create a channel for sending data from PTY reader thread
-        let (tx, rx) = channel();
+        // Create a bounded channel for sending data from PTY reader thread
+        let (tx, rx) = sync_channel(OUTPUT_CHANNEL_CAPACITY);
+        let dropped_bytes = Arc::new(AtomicUsize::new(0));
 
         // Spawn a thread to read from PTY
+        let thread_dropped_bytes = Arc::clone(&dropped_bytes);
+        let wake_signal = Arc::new(WakeSignal::default());
+        let thread_wake_signal = Arc::clone(&wake_signal);
         thread::spawn(move || {
-            let mut buf = [0u8; 4096];
-            loop {
-                match reader.read(&mut buf) {
-                    Ok(n) if n > 0 => {
-                        let data = buf[..n].to_vec();
-                        if tx.send(data).is_err() {
-                            log::error!("Failed to send PTY data, channel closed");
-                            break;
-                        }
-                    }
-                    Ok(_) => {
-                        // EOF
-                        log::info!("PTY reader reached EOF");
-                        break;
-                    }
-                    Err(e) => {
-                        log::error!("PTY read error: {}", e);
-                        break;
-                    }
-                }
-            }
+            let wake = || thread_wake_signal.notify();
+            pump_output(&mut reader, &tx, &thread_dropped_bytes, OUTPUT_BACKPRESSURE_TIMEOUT, &wake);
+            // EOF: the main loop has to notice that the process exited
+            thread_wake_signal.acknowledge();
+            wake();
             log::info!("PTY reader thread exiting");
         });
 
-        log::info!("PTY initialized: {}x{} shell={}", cols, rows, shell);
+        log::info!("PTY initialized: {}x{} shell={}", cols, rows, shell.program);
 
         Ok(Self {
             master: pair.master,
             child,
             rx,
+            peeked: None,
             writer: Arc::new(Mutex::new(writer)),
+            dropped_bytes,
+            exit_status: None,
+            wake_signal,
         })
     }
 
+    /// Total number of output bytes dropped because the main loop fell behind
+    pub fn dropped_bytes(&self) -> usize {
+        self.dropped_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Wake the event loop through `waker` whenever output arrives
+    pub fn set_output_waker(&self, waker: OutputWaker) {
+        *self.wake_signal.waker.lock().unwrap() = Some(waker);
+    }
+
+    /// How the child process exited, once `is_alive` has seen it exit
+    pub fn exit_status(&self) -> Option<&ExitStatus> {
+        self.exit_status.as_ref()
+    }
+
     /// Check if the child process is still alive
     pub fn is_alive(&mut self) -> bool {
         match self.child.try_wait() {
-            Ok(Some(_exit_status)) => {
-                log::info!("Child process has exited");
+            Ok(Some(exit_status)) => {
+                log::info!("Child process has exited: {}", exit_status);
+                self.exit_status = Some(exit_status);
                 false
             }
             Ok(None) => {
@@ -122,7 +224,26 @@ impl PtyController {
         Ok(())
     }
 
+    /// Whether `read` has output (or the EOF of an exited process) to return
+    pub fn has_pending_output(&mut self) -> bool {
+        if self.peeked.is_none() {
+            self.peeked = match self.rx.try_recv() {
+                Ok(data) => Some(data),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Vec::new()),
+            };
+        }
+        self.peeked.is_some()
+    }
+
     pub fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.wake_signal.acknowledge();
+        if let Some(data) = self.peeked.take() {
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            return Ok(len);
+        }
+
         match self.rx.try_recv() {
             Ok(data) => {
                 let len = data.len().min(buf.len());
@@ -164,3 +285,161 @@ impl PtyController {
         Arc::clone(&self.writer)
     }
 }
+
+/// Read PTY output and forward it over the bounded channel until EOF or error.
+/// A chunk that cannot be delivered within `backpressure_timeout` is dropped and counted.
+fn pump_output(
+    reader: &mut impl Read,
+    tx: &SyncSender<Vec<u8>>,
+    dropped_bytes: &AtomicUsize,
+    backpressure_timeout: Duration,
+    on_output: &dyn Fn(),
+) {
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(n) if n > 0 => {
+                let mut data = buf[..n].to_vec();
+                let deadline = Instant::now() + backpressure_timeout;
+                loop {
+                    match tx.try_send(data) {
+                        Ok(()) => {
+                            on_output();
+                            break;
+                        }
+                        Err(TrySendError::Full(pending)) => {
+                            if Instant::now() >= deadline {
+                                dropped_bytes.fetch_add(pending.len(), Ordering::Relaxed);
+                                log::debug!("PTY output channel full, dropped {} bytes", pending.len());
+                                break;
+                            }
+                            data = pending;
+                            thread::sleep(Duration::from_millis(1));
+                        }
+                        Err(TrySendError::Disconnected(_)) => {
+                            log::error!("Failed to send PTY data, channel closed");
+                            return;
+                        }
+                    }
+                }
+            }
+            Ok(_) => {
+                // EOF
+                log::info!("PTY reader reached EOF");
+                break;
+            }
+            Err(e) => {
+                log::error!("PTY read error: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_shell_command_environment() {
+        let shell = ShellCommand {
+            program: "/bin/sh".to_string(),
+            args: vec!["-l".to_string()],
+            term: "terbulator-test".to_string(),
+            env: BTreeMap::from([("EDITOR".to_string(), "vi".to_string()), ("COLORTERM".to_string(), "24bit".to_string())]),
+            ..ShellCommand::default()
+        };
+        let cmd = shell_command(&shell);
+        assert_eq!(cmd.get_argv(), &["/bin/sh", "-l"]);
+        assert_eq!(cmd.get_env("TERM").and_then(|term| term.to_str()), Some("terbulator-test"));
+        assert_eq!(cmd.get_env("EDITOR").and_then(|editor| editor.to_str()), Some("vi"));
+        // Configured variables win over the built-in ones
+        assert_eq!(cmd.get_env("COLORTERM").and_then(|colorterm| colorterm.to_str()), Some("24bit"));
+    }
+
+    #[test]
+    fn test_pump_output_bounds_buffered_data_under_flood() {
+        let capacity = 4;
+        let (tx, rx) = sync_channel(capacity);
+        let dropped = AtomicUsize::new(0);
+        let flood = vec![b'y'; 64 * 4096];
+
+        // Nobody drains the channel while the reader floods it
+        pump_output(&mut Cursor::new(&flood), &tx, &dropped, Duration::from_millis(5), &|| {});
+
+        let buffered: Vec<Vec<u8>> = rx.try_iter().collect();
+        assert_eq!(buffered.len(), capacity);
+        let buffered_bytes: usize = buffered.iter().map(|chunk| chunk.len()).sum();
+        assert_eq!(buffered_bytes + dropped.load(Ordering::Relaxed), flood.len());
+    }
+
+    #[test]
+    fn test_pump_output_lossless_when_drained() {
+        let (tx, rx) = sync_channel::<Vec<u8>>(2);
+        let dropped = AtomicUsize::new(0);
+        let data = vec![b'x'; 32 * 4096];
+        let expected = data.len();
+
+        let wakes = AtomicUsize::new(0);
+        let consumer = thread::spawn(move || rx.iter().map(|chunk| chunk.len()).sum::<usize>());
+        pump_output(&mut Cursor::new(data), &tx, &dropped, Duration::from_secs(5), &|| {
+            wakes.fetch_add(1, Ordering::Relaxed);
+        });
+        drop(tx);
+
+        assert_eq!(consumer.join().unwrap(), expected);
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+        // on_output runs for every chunk handed over
+        assert_eq!(wakes.load(Ordering::Relaxed), 32);
+    }
+
+    #[test]
+    fn test_wakeups_coalesce_until_read() {
+        let signal = WakeSignal::default();
+        // Output before a waker is set is picked up by the first redraw
+        signal.notify();
+
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&wakes);
+        *signal.waker.lock().unwrap() = Some(Arc::new(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        // Chunks arriving before the main loop reads share one wakeup
+        signal.notify();
+        signal.notify();
+        assert_eq!(wakes.load(Ordering::Relaxed), 1);
+
+        signal.acknowledge();
+        signal.notify();
+        assert_eq!(wakes.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_shell_starts_in_working_directory() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let shell = ShellCommand {
+            program: "/bin/sh".to_string(),
+            args: Vec::new(),
+            term: "dumb".to_string(),
+            working_directory: Some(dir.clone()),
+            env: BTreeMap::from([("TERBULATOR_TEST".to_string(), "from-config".to_string())]),
+        };
+        let mut pty = PtyController::new(80, 24, &shell).unwrap();
+        pty.write(b"echo \"cwd=$(pwd) var=$TERBULATOR_TEST\"; exit\n").unwrap();
+
+        let expected = format!("cwd={} var=from-config", dir.display());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = String::new();
+        let mut buf = [0u8; 4096];
+        while !output.contains(&expected) && Instant::now() < deadline {
+            match pty.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => output.push_str(&String::from_utf8_lossy(&buf[..len])),
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        assert!(output.contains(&expected), "shell printed {:?}", output);
+    }
+}
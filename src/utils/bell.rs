@@ -0,0 +1,65 @@
+use crate::utils::Result;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Players allowed to run at once; bells rung while this many are still playing
+/// stay silent, so a player that hangs cannot pile up processes
+const MAX_PLAYING: usize = 2;
+
+/// Players started and not yet exited
+static PLAYING: AtomicUsize = AtomicUsize::new(0);
+
+/// What a bell (BEL) does besides asking for attention in an unfocused window (`terminal.bell`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BellMode {
+    /// Nothing visible or audible
+    None,
+    /// Briefly flash the pane that rang
+    #[default]
+    Visual,
+    /// Play the system's alert sound
+    Audible,
+}
+
+impl BellMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "visual" => Some(Self::Visual),
+            "audible" => Some(Self::Audible),
+            _ => None,
+        }
+    }
+}
+
+/// Play the platform's alert sound with its own player, so no audio library is needed.
+/// The player is waited for on its own thread; where it is missing the error says so.
+pub fn play_sound() -> Result<()> {
+    if PLAYING.load(Ordering::Acquire) >= MAX_PLAYING {
+        log::debug!("Skipped the bell sound: {} players still running", MAX_PLAYING);
+        return Ok(());
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.args(["-e", "beep"]);
+        command
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", "[System.Media.SystemSounds]::Beep.Play()"]);
+        command
+    } else {
+        // libcanberra's player, which plays the desktop theme's bell
+        let mut command = Command::new("canberra-gtk-play");
+        command.args(["--id", "bell"]);
+        command
+    };
+    let mut child = command.spawn()?;
+    PLAYING.fetch_add(1, Ordering::AcqRel);
+    std::thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            log::warn!("Failed to wait for the bell sound player: {}", e);
+        }
+        PLAYING.fetch_sub(1, Ordering::AcqRel);
+    });
+    Ok(())
+}
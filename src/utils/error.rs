@@ -30,6 +30,9 @@ pub enum TerbulatorError {
 
     #[error("UTF-8 error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
+
+    #[error("Pane limit reached: at most {0} panes (pane.max_panes)")]
+    PaneLimit(usize),
 }
 
 // Convenience constructors
@@ -0,0 +1,122 @@
+use crate::utils::Result;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Hook commands allowed to run at once; sequences arriving while this many are
+/// still running are dropped, so output repeating a sequence cannot start
+/// processes without limit
+const MAX_RUNNING: usize = 4;
+
+/// Command that receives the OSC/DCS sequences the emulator does not handle
+/// (`terminal.sequence_hook`), so users can implement custom protocols
+#[derive(Debug, Clone)]
+pub struct SequenceHook {
+    argv: Vec<String>,
+    /// Payload prefixes forwarded (e.g. `1337;`)
+    prefixes: Vec<String>,
+    /// Commands started and not yet exited
+    running: Arc<AtomicUsize>,
+}
+
+impl SequenceHook {
+    /// `argv` must not be empty
+    pub fn new(argv: Vec<String>, prefixes: Vec<String>) -> Self {
+        Self { argv, prefixes, running: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Whether a payload is forwarded to the command
+    pub fn accepts(&self, payload: &[u8]) -> bool {
+        self.prefixes.iter().any(|prefix| payload.starts_with(prefix.as_bytes()))
+    }
+
+    /// Run the command with the payload on its stdin. `TERBULATOR_SEQUENCE` (the `kind`,
+    /// `osc` or `dcs`) and `TERBULATOR_PANE` tell where it came from. The payload never
+    /// reaches the command line, and the command is waited for on its own thread.
+    /// Returns None when the sequence was dropped because `MAX_RUNNING` commands
+    /// are still running.
+    pub fn run(&self, pane_id: usize, kind: &str, payload: Vec<u8>) -> Result<Option<JoinHandle<()>>> {
+        if self.running.load(Ordering::Acquire) >= MAX_RUNNING {
+            log::warn!("Dropped {} bytes of {} from pane {}: {} sequence hooks still running", payload.len(), kind, pane_id, MAX_RUNNING);
+            return Ok(None);
+        }
+        let mut child = Command::new(&self.argv[0])
+            .args(&self.argv[1..])
+            .env("TERBULATOR_SEQUENCE", kind)
+            .env("TERBULATOR_PANE", pane_id.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        log::debug!("Forwarded {} bytes of {} to {}", payload.len(), kind, self.argv[0]);
+
+        let mut stdin = child.stdin.take();
+        let running = Arc::clone(&self.running);
+        running.fetch_add(1, Ordering::AcqRel);
+        Ok(Some(std::thread::spawn(move || {
+            if let Some(stdin) = &mut stdin
+                && let Err(e) = stdin.write_all(&payload)
+            {
+                log::warn!("Failed to write to the sequence hook: {}", e);
+            }
+            // Close stdin so the command sees the end of the payload
+            drop(stdin);
+            if let Err(e) = child.wait() {
+                log::warn!("Failed to wait for the sequence hook: {}", e);
+            }
+            running.fetch_sub(1, Ordering::AcqRel);
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::{TerminalEmulator, TerminalEvent};
+
+    #[test]
+    fn test_hook_receives_unhandled_osc() {
+        let output = std::env::temp_dir().join(format!("terbulator-hook-{}", std::process::id()));
+        let script = "printf '%s %s ' \"$TERBULATOR_SEQUENCE\" \"$TERBULATOR_PANE\" > \"$1\"; cat >> \"$1\"";
+        let hook = SequenceHook::new(
+            ["/bin/sh", "-c", script, "sh", output.to_str().unwrap()].map(String::from).to_vec(),
+            vec!["1337;".to_string()],
+        );
+
+        let mut term = TerminalEmulator::new(80, 24, 100);
+        term.set_forward_unhandled(true);
+        term.process_bytes(b"\x1b]1337;CustomThing=1\x07\x1b]777;notify\x07");
+        let forwarded: Vec<_> = term
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::UnhandledSequence { kind, payload } if hook.accepts(&payload) => Some((kind, payload)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(forwarded.len(), 1);
+
+        let (kind, payload) = forwarded.into_iter().next().unwrap();
+        hook.run(3, kind.name(), payload).unwrap().unwrap().join().unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "osc 3 1337;CustomThing=1");
+        std::fs::remove_file(&output).unwrap();
+    }
+
+    #[test]
+    fn test_running_hooks_are_capped() {
+        let hook = SequenceHook::new(["/bin/sh", "-c", "sleep 1"].map(String::from).to_vec(), vec!["7;".to_string()]);
+        assert!(!hook.accepts(b"1337;x"));
+
+        // A prompt printing OSC 7 faster than the hook exits
+        let running: Vec<_> = (0..MAX_RUNNING).map(|_| hook.run(0, "osc", b"7;file:///".to_vec()).unwrap()).collect();
+        assert!(running.iter().all(Option::is_some));
+        assert!(hook.run(0, "osc", b"7;file:///".to_vec()).unwrap().is_none());
+
+        // Once they exit, sequences are forwarded again
+        for handle in running.into_iter().flatten() {
+            handle.join().unwrap();
+        }
+        hook.run(0, "osc", b"7;file:///".to_vec()).unwrap().unwrap().join().unwrap();
+    }
+}
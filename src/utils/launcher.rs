@@ -0,0 +1,62 @@
+use crate::utils::{Result, TerbulatorError};
+use std::process::Command;
+
+/// URL schemes that may be handed to the platform launcher
+const OPENABLE_SCHEMES: &[&str] = &["http", "https", "ftp", "file", "mailto"];
+
+/// Whether `url` has a scheme that is safe to open. Links come from terminal
+/// output, so anything else (custom handlers, bare paths, options) is refused.
+pub fn is_openable(url: &str) -> bool {
+    url.split_once(':')
+        .is_some_and(|(scheme, _)| OPENABLE_SCHEMES.iter().any(|allowed| scheme.eq_ignore_ascii_case(allowed)))
+}
+
+/// Program and leading arguments that open a URL on `os`. The URL is passed as one
+/// more argument and never goes through a shell: `cmd /C start` would run whatever
+/// follows a `&` in it.
+fn launcher(os: &str) -> (&'static str, &'static [&'static str]) {
+    match os {
+        "macos" => ("open", &[]),
+        "windows" => ("rundll32", &["url.dll,FileProtocolHandler"]),
+        _ => ("xdg-open", &[]),
+    }
+}
+
+/// Open `url` with the platform's default handler
+pub fn open_url(url: &str) -> Result<()> {
+    if !is_openable(url) {
+        return Err(TerbulatorError::io(format!("Refusing to open link: {:?}", url)));
+    }
+
+    // The launcher runs detached; the handler reports its own errors
+    let (program, args) = launcher(std::env::consts::OS);
+    Command::new(program).args(args).arg(url).spawn()?;
+    log::info!("Opened link: {}", url);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_openable() {
+        assert!(is_openable("https://example.com/a;b"));
+        assert!(is_openable("HTTP://example.com"));
+        assert!(is_openable("file:///home/user/notes.txt"));
+
+        assert!(!is_openable("javascript:alert(1)"));
+        assert!(!is_openable("/etc/passwd"));
+        assert!(!is_openable("--help"));
+    }
+
+    #[test]
+    fn test_launcher_avoids_shells() {
+        for os in ["linux", "macos", "windows"] {
+            let (program, args) = launcher(os);
+            assert!(!["cmd", "sh"].contains(&program), "{} uses {}", os, program);
+            assert!(!args.contains(&"/C"));
+        }
+        assert_eq!(launcher("windows"), ("rundll32", &["url.dll,FileProtocolHandler"][..]));
+    }
+}
@@ -1,3 +1,8 @@
+pub mod bell;
 pub mod error;
+pub mod hook;
+pub mod launcher;
+pub mod path;
+pub mod url;
 
 pub use error::{Result, TerbulatorError};
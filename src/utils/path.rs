@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~` to the home directory and `$VAR` / `${VAR}` to environment
+/// variables. Unset variables expand to nothing.
+pub fn expand_path(path: &Path) -> PathBuf {
+    expand_with(&path.to_string_lossy(), |name| std::env::var(name).ok())
+}
+
+fn expand_with(path: &str, var: impl Fn(&str) -> Option<String>) -> PathBuf {
+    let mut expanded = String::new();
+    let mut rest = path;
+
+    if rest == "~" || rest.starts_with("~/") {
+        expanded.push_str(&var("HOME").unwrap_or_default());
+        rest = &rest[1..];
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_')).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() {
+            // Not a variable reference, keep the `$`
+            expanded.push('$');
+            rest = after;
+        } else {
+            expanded.push_str(&var(name).unwrap_or_default());
+            rest = remainder;
+        }
+    }
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(path: &str) -> String {
+        let var = |name: &str| match name {
+            "HOME" => Some("/home/user".to_string()),
+            "PROJECT" => Some("work".to_string()),
+            _ => None,
+        };
+        expand_with(path, var).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_expand_path() {
+        assert_eq!(expand("~"), "/home/user");
+        assert_eq!(expand("~/src/$PROJECT"), "/home/user/src/work");
+        assert_eq!(expand("${HOME}/${PROJECT}-old"), "/home/user/work-old");
+        assert_eq!(expand("/srv/$UNSET/x"), "/srv//x");
+        // Only a leading `~` means home, and a lone `$` is kept
+        assert_eq!(expand("/tmp/~user/$/${"), "/tmp/~user/$/${");
+    }
+}
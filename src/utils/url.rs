@@ -0,0 +1,143 @@
+use crate::terminal::grid::Cell;
+
+const SCHEMES: &[&str] = &["https://", "http://"];
+
+/// A URL found in a row of cells (end column exclusive)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlSpan {
+    pub start_col: usize,
+    pub end_col: usize,
+    pub url: String,
+}
+
+/// Characters that can appear in a URL found in plain text
+fn is_url_char(ch: char) -> bool {
+    ch.is_ascii_graphic() && !matches!(ch, '<' | '>' | '"' | '`' | '{' | '}' | '|' | '\\' | '^')
+}
+
+/// Length of `url` without trailing punctuation that more likely ends the sentence,
+/// e.g. `(see https://example.com).` keeps neither the `)` nor the `.`
+fn trimmed_len(url: &[char]) -> usize {
+    let mut len = url.len();
+    while len > 0 {
+        let unbalanced = |open: char, close: char| {
+            let url = &url[..len];
+            url.iter().filter(|&&ch| ch == close).count() > url.iter().filter(|&&ch| ch == open).count()
+        };
+        let trim = match url[len - 1] {
+            '.' | ',' | ':' | ';' | '!' | '?' | '\'' => true,
+            ')' => unbalanced('(', ')'),
+            ']' => unbalanced('[', ']'),
+            _ => false,
+        };
+        if !trim {
+            break;
+        }
+        len -= 1;
+    }
+    len
+}
+
+/// Find the `http(s)://` URLs in a row. URLs wrapped onto the next row are only
+/// detected up to the end of this one.
+pub fn find_urls(row: &[Cell]) -> Vec<UrlSpan> {
+    // Characters with the column each starts at; wide characters skip a column
+    let chars: Vec<(usize, char)> = row
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| !cell.is_continuation())
+        .map(|(col, cell)| (col, cell.ch))
+        .collect();
+
+    let mut spans = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let rest = chars[index..].iter().map(|&(_, ch)| ch);
+        let Some(scheme) = SCHEMES.iter().find(|scheme| rest.clone().take(scheme.len()).eq(scheme.chars())) else {
+            index += 1;
+            continue;
+        };
+
+        let url: Vec<char> = chars[index..].iter().map(|&(_, ch)| ch).take_while(|&ch| is_url_char(ch)).collect();
+        let len = trimmed_len(&url);
+        if len <= scheme.len() {
+            index += scheme.len();
+            continue;
+        }
+
+        // URL characters are ASCII, so each takes exactly one column
+        spans.push(UrlSpan {
+            start_col: chars[index].0,
+            end_col: chars[index + len - 1].0 + 1,
+            url: url[..len].iter().collect(),
+        });
+        index += len;
+    }
+    spans
+}
+
+/// The URL covering column `col` of a row, if any
+pub fn url_at(row: &[Cell], col: usize) -> Option<String> {
+    find_urls(row)
+        .into_iter()
+        .find(|span| (span.start_col..span.end_col).contains(&col))
+        .map(|span| span.url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(text: &str) -> Vec<Cell> {
+        text.chars().map(Cell::new).collect()
+    }
+
+    fn urls(text: &str) -> Vec<String> {
+        find_urls(&row(text)).into_iter().map(|span| span.url).collect()
+    }
+
+    #[test]
+    fn test_find_urls_columns() {
+        let spans = find_urls(&row("see https://example.com/a?b=1#c now"));
+        assert_eq!(
+            spans,
+            vec![UrlSpan { start_col: 4, end_col: 31, url: "https://example.com/a?b=1#c".to_string() }]
+        );
+        assert_eq!(urls("http://a.test http://b.test"), ["http://a.test", "http://b.test"]);
+    }
+
+    #[test]
+    fn test_trailing_punctuation() {
+        assert_eq!(urls("Go to https://example.com."), ["https://example.com"]);
+        assert_eq!(urls("(see https://example.com/x)!"), ["https://example.com/x"]);
+        assert_eq!(urls("https://en.wikipedia.org/wiki/Rust_(programming_language),"), [
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        ]);
+        assert_eq!(urls("'https://example.com/it's'"), ["https://example.com/it's"]);
+        assert_eq!(urls("<https://example.com/a>"), ["https://example.com/a"]);
+    }
+
+    #[test]
+    fn test_not_urls() {
+        assert!(urls("https:// nothing").is_empty());
+        assert!(urls("ftp://example.com mailto:me@example.com").is_empty());
+        assert!(urls("").is_empty());
+    }
+
+    #[test]
+    fn test_url_after_wide_characters() {
+        // Each wide character takes a cell and a continuation cell
+        let mut cells = Vec::new();
+        for ch in "漢字".chars() {
+            let cell = Cell { width: 2, ..Cell::new(ch) };
+            cells.push(cell);
+            cells.push(Cell::continuation(&cell));
+        }
+        cells.extend(row(" http://x.test"));
+
+        assert_eq!(url_at(&cells, 4), None);
+        assert_eq!(url_at(&cells, 5).as_deref(), Some("http://x.test"));
+        assert_eq!(url_at(&cells, 17).as_deref(), Some("http://x.test"));
+        assert_eq!(url_at(&cells, 18), None);
+    }
+}